@@ -0,0 +1,10 @@
+fn main() {
+    // cudart of the CUDA Toolkit, for the "cuda" feature
+    if std::env::var_os("CARGO_FEATURE_CUDA").is_some() {
+        println!("cargo:rerun-if-env-changed=CUDA_PATH");
+        if let Some(cuda_path) = std::env::var_os("CUDA_PATH") {
+            let lib = std::path::Path::new(&cuda_path).join("lib").join("x64");
+            println!("cargo:rustc-link-search=native={}", lib.display());
+        }
+    }
+}
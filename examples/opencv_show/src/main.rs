@@ -1,5 +1,5 @@
 fn main() {
-    let device = dxcapture::Device::default();
+    let device = dxcapture::Device::new_from_displays(None).unwrap();
     let capture = dxcapture::Capture::new(&device).unwrap();
 
     // show desktop example
@@ -0,0 +1,270 @@
+use std::{
+    sync::{
+        Arc,
+        Mutex,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::capture::{
+    RawFrameData,
+    Rect,
+};
+use crate::overlay::{
+    draw_text,
+    FrameFilter,
+    GLYPH_HEIGHT,
+    GLYPH_WIDTH,
+};
+
+
+/// Shape drawn onto frames. colors are BGRA, and the alpha blends the shape with the frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Annotation {
+    /// outline of `rect`, `thickness` pixels inside of it.
+    Rect {
+        rect: Rect,
+        color: [u8; 4],
+        thickness: i32,
+    },
+    FilledRect {
+        rect: Rect,
+        color: [u8; 4],
+    },
+    Line {
+        from: (i32, i32),
+        to: (i32, i32),
+        color: [u8; 4],
+        thickness: i32,
+    },
+    /// text of the built-in font with its top left at (`x`, `y`). lower case letters are drawn in upper case.
+    Label {
+        x: i32,
+        y: i32,
+        text: String,
+        color: [u8; 4],
+        /// box behind the text.
+        background: Option<[u8; 4]>,
+        font_size: u32,
+    },
+}
+impl Annotation {
+    /// Draw onto `frame`, clipped to it. the padded rows are packed first.
+    pub fn draw(&self, frame: &mut RawFrameData) {
+        frame.pack_rows();
+        match self {
+            Annotation::Rect{ rect, color, thickness } => {
+                let thickness = (*thickness).clamp(1, rect.width.min(rect.height).max(1));
+                let inner_height = rect.height - thickness * 2;
+                blend_rect(frame, Rect::new(rect.x, rect.y, rect.width, thickness), *color);
+                blend_rect(frame, Rect::new(rect.x, rect.bottom() - thickness, rect.width, thickness), *color);
+                blend_rect(frame, Rect::new(rect.x, rect.y + thickness, thickness, inner_height), *color);
+                blend_rect(frame, Rect::new(rect.right() - thickness, rect.y + thickness, thickness, inner_height), *color);
+            },
+            Annotation::FilledRect{ rect, color } => blend_rect(frame, *rect, *color),
+            Annotation::Line{ from, to, color, thickness } => draw_line(frame, *from, *to, *color, (*thickness).max(1)),
+            Annotation::Label{ x, y, text, color, background, font_size } => {
+                let scale = (*font_size as i32 / GLYPH_HEIGHT).max(1);
+                if let Some(background) = background {
+                    let padding = scale * 2;
+                    let width = text.chars().count() as i32 * (GLYPH_WIDTH + 1) * scale - scale + padding * 2;
+                    blend_rect(frame, Rect::new(*x - padding, *y - padding, width, GLYPH_HEIGHT * scale + padding * 2), *background);
+                }
+
+                if color[3] == 255 {
+                    draw_text(frame, text, *x, *y, scale, *color);
+                } else {
+                    // draw opaque on a copy of the area and blend it back
+                    let width = text.chars().count() as i32 * (GLYPH_WIDTH + 1) * scale;
+                    let area = Rect::new(*x, *y, width, GLYPH_HEIGHT * scale).intersect(&Rect::new(0, 0, frame.width, frame.height));
+                    let mut layer = RawFrameData{
+                        width: area.width,
+                        height: area.height,
+                        data: vec![0; (area.width * area.height * 4) as usize],
+                        ..Default::default()
+                    };
+                    draw_text(&mut layer, text, *x - area.x, *y - area.y, scale, [color[0], color[1], color[2], 255]);
+                    for (i, pixel) in layer.data.chunks_exact(4).enumerate() {
+                        if pixel[3] != 0 {
+                            let (px, py) = (area.x + i as i32 % area.width, area.y + i as i32 / area.width);
+                            blend_pixel(frame, px, py, *color);
+                        }
+                    }
+                }
+            },
+        }
+    }
+}
+
+
+fn blend_pixel(frame: &mut RawFrameData, x: i32, y: i32, color: [u8; 4]) {
+    if x < 0 || y < 0 || frame.width <= x || frame.height <= y {
+        return;
+    }
+
+    let offset = ((y * frame.width + x) * 4) as usize;
+    if let Some(pixel) = frame.data.get_mut(offset..offset + 3) {
+        let alpha = color[3] as u32;
+        for (value, color) in pixel.iter_mut().zip(color) {
+            *value = ((color as u32 * alpha + *value as u32 * (255 - alpha) + 127) / 255) as u8;
+        }
+    }
+}
+
+fn blend_rect(frame: &mut RawFrameData, rect: Rect, color: [u8; 4]) {
+    let rect = rect.intersect(&Rect::new(0, 0, frame.width, frame.height));
+    for y in rect.y..rect.bottom() {
+        for x in rect.x..rect.right() {
+            blend_pixel(frame, x, y, color);
+        }
+    }
+}
+
+/// Bresenham's line with a square pen. each pixel is blended once.
+fn draw_line(frame: &mut RawFrameData, from: (i32, i32), to: (i32, i32), color: [u8; 4], thickness: i32) {
+    let bounds = Rect::new(from.0.min(to.0), from.1.min(to.1), (from.0 - to.0).abs() + 1, (from.1 - to.1).abs() + 1);
+    let pen = Rect::new(bounds.x - thickness / 2, bounds.y - thickness / 2, bounds.width + thickness - 1, bounds.height + thickness - 1)
+        .intersect(&Rect::new(0, 0, frame.width, frame.height));
+    if pen.is_empty() {
+        return;
+    }
+    let mut covered = vec![false; (pen.width * pen.height) as usize];
+
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    loop {
+        let dot = Rect::new(x - thickness / 2, y - thickness / 2, thickness, thickness).intersect(&pen);
+        for py in dot.y..dot.bottom() {
+            for px in dot.x..dot.right() {
+                let i = ((py - pen.y) * pen.width + (px - pen.x)) as usize;
+                if !covered[i] {
+                    covered[i] = true;
+                    blend_pixel(frame, px, py, color);
+                }
+            }
+        }
+
+        if (x, y) == to {
+            break;
+        }
+        let doubled = error * 2;
+        if dy <= doubled {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+
+/// annotation with when to remove it.
+type TimedAnnotation = (Annotation, Option<Instant>);
+
+/// Annotations drawn onto each frame, shared between the [FrameFilter] and the code placing them.
+///
+/// clones share the same annotations, so an automation thread can highlight what it found
+/// while the recording thread draws them.
+/// # Examples
+/// ```
+/// use dxcapture::{ Annotations, FilteredSource, FrameSource, Rect };
+///
+/// let annotations = Annotations::new();
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(annotations.clone());
+///
+/// // found the button
+/// annotations.highlight(Rect::new(120, 80, 200, 40), "OK BUTTON", [0, 0, 255, 255]);
+/// annotations.add_for(
+///     dxcapture::Annotation::Line{ from: (0, 0), to: (120, 80), color: [0, 255, 255, 160], thickness: 3 },
+///     std::time::Duration::from_secs(2),
+/// );
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Annotations {
+    items: Arc<Mutex<Vec<TimedAnnotation>>>,
+}
+impl Annotations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep drawing `annotation` until removed by [clear](Self::clear).
+    pub fn add(&self, annotation: Annotation) {
+        self.items.lock().unwrap().push((annotation, None));
+    }
+
+    /// Draw `annotation` for `duration`.
+    pub fn add_for(&self, annotation: Annotation, duration: Duration) {
+        self.items.lock().unwrap().push((annotation, Some(Instant::now() + duration)));
+    }
+
+    /// Outline `rect` 2 pixels thick.
+    pub fn rect(&self, rect: Rect, color: [u8; 4]) {
+        self.add(Annotation::Rect{ rect, color, thickness: 2 });
+    }
+
+    pub fn line(&self, from: (i32, i32), to: (i32, i32), color: [u8; 4]) {
+        self.add(Annotation::Line{ from, to, color, thickness: 2 });
+    }
+
+    /// `text` in 14px, in `color` on translucent black.
+    pub fn label(&self, x: i32, y: i32, text: &str, color: [u8; 4]) {
+        self.add(Annotation::Label{ x, y, text: text.to_string(), color, background: Some([0, 0, 0, 160]), font_size: 14 });
+    }
+
+    /// Outline `rect` with `text` above it, or inside when there is no room above.
+    pub fn highlight(&self, rect: Rect, text: &str, color: [u8; 4]) {
+        let font_size = 14;
+        let label_height = (font_size as i32 / GLYPH_HEIGHT).max(1) * (GLYPH_HEIGHT + 4);
+        let y = if label_height <= rect.y { rect.y - label_height } else { rect.y + 4 };
+
+        let mut items = self.items.lock().unwrap();
+        items.push((Annotation::Rect{ rect, color, thickness: 2 }, None));
+        items.push((Annotation::Label{
+            x: rect.x + 4,
+            y: y + 4,
+            text: text.to_string(),
+            color: [255, 255, 255, 255],
+            background: Some(color),
+            font_size,
+        }, None));
+    }
+
+    pub fn clear(&self) {
+        self.items.lock().unwrap().clear();
+    }
+
+    /// Annotations drawn now.
+    pub fn annotations(&self) -> Vec<Annotation> {
+        let now = Instant::now();
+        self.items.lock().unwrap().iter()
+            .filter(|(_, until)| until.map_or(true, |until| now < until))
+            .map(|(annotation, _)| annotation.clone())
+            .collect()
+    }
+
+    /// Draw the annotations onto `frame` and drop the expired ones.
+    pub fn draw(&self, frame: &mut RawFrameData) {
+        let now = Instant::now();
+        let mut items = self.items.lock().unwrap();
+        items.retain(|(_, until)| until.map_or(true, |until| now < until));
+
+        for (annotation, _) in items.iter() {
+            annotation.draw(frame);
+        }
+    }
+}
+impl FrameFilter for Annotations {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        self.draw(frame);
+    }
+}
@@ -0,0 +1,227 @@
+//! Screenshot assertions for visual-regression tests.
+//!
+//! a frame is compared against a PNG baseline stored under the baseline directory.
+//! on failure the actual frame and a diff image are written to the output directory.
+//! set `DXCAPTURE_UPDATE_BASELINES=1` to (re)write baselines instead of comparing.
+//!
+//! Required features: *`"img"`*
+
+use std::path::{
+    Path,
+    PathBuf,
+};
+
+use image::{
+    Rgba,
+    RgbaImage,
+};
+
+use crate::capture::{
+    CaptureError,
+    ImgFrameData,
+    RawFrameData,
+};
+use crate::diff::{
+    diff_frames_with,
+    DiffOptions,
+    FrameDiff,
+};
+use crate::source::FrameSource;
+
+
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+#[derive(Debug, thiserror::Error)]
+pub enum FrameAssertionError {
+    #[error("Baseline {0} is not found. actual frame is written to {1}.")]
+    MissingBaseline(PathBuf, PathBuf),
+
+    #[error("Frame differs from {baseline} by {percent:.3}%. see {actual} and {diff}.")]
+    Mismatch {
+        percent: f64,
+        baseline: PathBuf,
+        actual: PathBuf,
+        diff: PathBuf,
+    },
+
+    #[error("Frame size {0}x{1} differs from baseline {2} ({3}x{4}). actual frame is written to {5}.")]
+    SizeMismatch(i32, i32, PathBuf, i32, i32, PathBuf),
+
+    #[error("Capture error: {0}")]
+    Capture(#[from] CaptureError),
+
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+
+/// Compare frames against stored baselines.
+///
+/// Required features: *`"img"`*
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+///
+/// let assertion = dxcapture::FrameAssertion::new("tests/baselines")
+///     .output_dir("target/visual")
+///     .tolerance(0.5);
+/// dxcapture::assert_frame_matches!(capture, "desktop", assertion);
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+#[derive(Clone, Debug)]
+pub struct FrameAssertion {
+    baseline_dir: PathBuf,
+    output_dir: PathBuf,
+    tolerance: f64,
+    options: DiffOptions,
+    update: bool,
+}
+impl FrameAssertion {
+    /// Baselines are read from `baseline_dir/<name>.png`.
+    pub fn new<P: AsRef<Path>>(baseline_dir: P) -> Self {
+        Self{
+            baseline_dir: baseline_dir.as_ref().to_path_buf(),
+            output_dir: PathBuf::from("target/dxcapture"),
+            tolerance: 0.0,
+            options: DiffOptions::default(),
+            update: std::env::var("DXCAPTURE_UPDATE_BASELINES").map(|v| v == "1").unwrap_or(false),
+        }
+    }
+
+    /// Where the actual and diff images of failed assertions are written. default is `target/dxcapture`.
+    pub fn output_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.output_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    /// Changed pixels in percent (0.0..=100.0) still regarded as matched. default is 0.0.
+    pub fn tolerance(mut self, percent: f64) -> Self {
+        self.tolerance = percent;
+        self
+    }
+
+    /// Per pixel comparison options.
+    pub fn diff_options(mut self, options: DiffOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Overwrite baselines with the actual frames instead of comparing.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Compare `frame` against the baseline `name`.
+    pub fn check(&self, name: &str, frame: &RawFrameData) -> anyhow::Result<FrameDiff, FrameAssertionError> {
+        let baseline_path = self.baseline_dir.join(format!("{}.png", name));
+        let actual = ImgFrameData::from_raw(frame.clone());
+
+        if self.update {
+            std::fs::create_dir_all(&self.baseline_dir)?;
+            actual.data.save(&baseline_path)?;
+            return Ok(FrameDiff::default());
+        }
+
+        if !baseline_path.exists() {
+            let actual_path = self.write_output(name, "actual", &actual.data)?;
+            return Err(FrameAssertionError::MissingBaseline(baseline_path, actual_path));
+        }
+
+        let baseline = image::open(&baseline_path)?.to_rgba8();
+        let (baseline_width, baseline_height) = (baseline.width() as i32, baseline.height() as i32);
+        let baseline = ImgFrameData::new(baseline_width, baseline_height, baseline).to_raw();
+
+        let diff = match diff_frames_with(&baseline, frame, &self.options) {
+            Ok(diff) => diff,
+            Err(CaptureError::SizeMismatch) => {
+                let actual_path = self.write_output(name, "actual", &actual.data)?;
+                return Err(FrameAssertionError::SizeMismatch(
+                    frame.width, frame.height, baseline_path, baseline_width, baseline_height, actual_path,
+                ));
+            },
+            Err(e) => return Err(e.into()),
+        };
+        if diff.changed_percent <= self.tolerance {
+            return Ok(diff);
+        }
+
+        let actual_path = self.write_output(name, "actual", &actual.data)?;
+        let diff_path = self.write_output(name, "diff", &self.diff_image(&baseline, frame))?;
+
+        Err(FrameAssertionError::Mismatch {
+            percent: diff.changed_percent,
+            baseline: baseline_path,
+            actual: actual_path,
+            diff: diff_path,
+        })
+    }
+
+    /// Take a frame from `source` and compare it against the baseline `name`.
+    pub fn check_source<S: FrameSource + ?Sized>(&self, name: &str, source: &S) -> anyhow::Result<FrameDiff, FrameAssertionError> {
+        let frame = source.wait_raw_frame()?;
+
+        self.check(name, &frame)
+    }
+
+    fn write_output(&self, name: &str, kind: &str, image: &RgbaImage) -> anyhow::Result<PathBuf, FrameAssertionError> {
+        std::fs::create_dir_all(&self.output_dir)?;
+
+        let path = self.output_dir.join(format!("{}.{}.png", name, kind));
+        image.save(&path)?;
+
+        Ok(path)
+    }
+
+    /// Changed pixels in red over the dimmed actual frame.
+    fn diff_image(&self, baseline: &RawFrameData, actual: &RawFrameData) -> RgbaImage {
+        RgbaImage::from_fn(actual.width as u32, actual.height as u32, |x, y| {
+            let (baseline_offset, offset) = (baseline.pixel_offset(x as usize, y as usize), actual.pixel_offset(x as usize, y as usize));
+            let b = &baseline.data[baseline_offset..baseline_offset + 3];
+            let a = &actual.data[offset..offset + 3];
+
+            let changed = (0..3).any(|channel| self.options.threshold < b[channel].abs_diff(a[channel]));
+            if changed {
+                Rgba([255, 0, 0, 255])
+            } else {
+                let gray = ((a[0] as u32 + a[1] as u32 + a[2] as u32) / 3 / 3) as u8;
+                Rgba([gray, gray, gray, 255])
+            }
+        })
+    }
+}
+impl Default for FrameAssertion {
+    /// Baselines in `DXCAPTURE_BASELINE_DIR`, or `tests/baselines`.
+    fn default() -> Self {
+        let baseline_dir = std::env::var("DXCAPTURE_BASELINE_DIR").unwrap_or_else(|_| "tests/baselines".to_string());
+        let assertion = Self::new(baseline_dir);
+
+        match std::env::var("DXCAPTURE_OUTPUT_DIR") {
+            Ok(dir) => assertion.output_dir(dir),
+            Err(_) => assertion,
+        }
+    }
+}
+
+
+/// Assert that a frame of a [FrameSource] matches the stored baseline, panicking with the paths of
+/// the actual and diff images if not.
+///
+/// the third argument is a [FrameAssertion], default is [FrameAssertion::default].
+///
+/// Required features: *`"img"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+#[macro_export]
+macro_rules! assert_frame_matches {
+    ($source:expr, $name:expr) => {
+        $crate::assert_frame_matches!($source, $name, $crate::FrameAssertion::default())
+    };
+    ($source:expr, $name:expr, $assertion:expr) => {
+        if let Err(e) = $assertion.check_source($name, &$source) {
+            panic!("assert_frame_matches!({}) failed: {}", $name, e);
+        }
+    };
+}
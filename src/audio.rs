@@ -0,0 +1,708 @@
+use std::{
+    collections::VecDeque,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use windows::{
+    core::{
+        Interface,
+        GUID,
+    },
+    Win32::{
+        Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
+        Foundation::PWSTR,
+        Media::Audio::{
+            eCapture,
+            eConsole,
+            eRender,
+            EDataFlow,
+            IAudioCaptureClient,
+            IAudioClient,
+            IMMDevice,
+            IMMDeviceEnumerator,
+            MMDeviceEnumerator,
+            AUDCLNT_BUFFERFLAGS_SILENT,
+            AUDCLNT_SHAREMODE_SHARED,
+            AUDCLNT_STREAMFLAGS_LOOPBACK,
+            DEVICE_STATE_ACTIVE,
+            WAVEFORMATEX,
+            WAVEFORMATEXTENSIBLE,
+        },
+        System::Com::{
+            CoCreateInstance,
+            CoInitializeEx,
+            CoTaskMemFree,
+            StructuredStorage::{
+                PropVariantClear,
+                STGM_READ,
+            },
+            CLSCTX_ALL,
+            COINIT_MULTITHREADED,
+        },
+    },
+};
+
+use crate::capture::CaptureError;
+
+
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_IEEE_FLOAT: u16 = 3;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+const KSDATAFORMAT_SUBTYPE_IEEE_FLOAT: GUID = GUID::from_u128(0x00000003_0000_0010_8000_00aa00389b71);
+
+/// 100ns units of WASAPI.
+const REFTIMES_PER_SEC: i64 = 10_000_000;
+
+fn directx_error(e: windows::core::Error) -> CaptureError {
+    CaptureError::DirectxError(e)
+}
+
+unsafe fn take_pwstr(value: PWSTR) -> String {
+    if value.is_null() {
+        return String::new();
+    }
+
+    let mut len = 0;
+    while *value.0.add(len) != 0 {
+        len += 1;
+    }
+    let text = String::from_utf16_lossy(std::slice::from_raw_parts(value.0, len));
+    CoTaskMemFree(value.0 as *const _);
+
+    text
+}
+
+fn device_enumerator() -> windows::core::Result<IMMDeviceEnumerator> {
+    unsafe {
+        // already initialized in another mode is fine
+        let _ = CoInitializeEx(std::ptr::null(), COINIT_MULTITHREADED);
+
+        CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+    }
+}
+
+
+/// Audio endpoint.
+///
+/// Required features: *`"audio"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AudioDeviceInfo {
+    /// endpoint id, to be passed to [AudioCapture].
+    pub id: String,
+    pub name: String,
+    pub is_default: bool,
+}
+impl AudioDeviceInfo {
+    fn from_device(device: &IMMDevice, default_id: &str) -> windows::core::Result<Self> {
+        unsafe {
+            let id = take_pwstr(device.GetId()?);
+
+            let store = device.OpenPropertyStore(STGM_READ)?;
+            let mut value = store.GetValue(&PKEY_Device_FriendlyName)?;
+            let name = {
+                let name = value.Anonymous.Anonymous.Anonymous.pwszVal;
+                let mut len = 0;
+                while !name.is_null() && *name.0.add(len) != 0 {
+                    len += 1;
+                }
+                if name.is_null() {
+                    String::new()
+                } else {
+                    String::from_utf16_lossy(std::slice::from_raw_parts(name.0, len))
+                }
+            };
+            let _ = PropVariantClear(&mut value);
+
+            Ok(Self{
+                is_default: id == default_id,
+                id,
+                name,
+            })
+        }
+    }
+}
+
+fn enumerate_audio_devices(flow: EDataFlow) -> anyhow::Result<Vec<AudioDeviceInfo>, CaptureError> {
+    let enumerator = device_enumerator().map_err(directx_error)?;
+
+    unsafe {
+        let default_id = match enumerator.GetDefaultAudioEndpoint(flow, eConsole) {
+            Ok(device) => take_pwstr(device.GetId().map_err(directx_error)?),
+            Err(_) => String::new(),
+        };
+
+        let collection = enumerator.EnumAudioEndpoints(flow, DEVICE_STATE_ACTIVE).map_err(directx_error)?;
+        let count = collection.GetCount().map_err(directx_error)?;
+
+        (0..count).map(|index| {
+            let device = collection.Item(index).map_err(directx_error)?;
+            AudioDeviceInfo::from_device(&device, &default_id).map_err(directx_error)
+        }).collect()
+    }
+}
+
+/// Get all active output (render) devices, which can be captured by [AudioCapture::loopback].
+///
+/// Required features: *`"audio"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+pub fn enumerate_audio_outputs() -> anyhow::Result<Vec<AudioDeviceInfo>, CaptureError> {
+    enumerate_audio_devices(eRender)
+}
+
+/// Get all active input (capture) devices such as microphones, for [AudioCapture::microphone].
+///
+/// Required features: *`"audio"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+pub fn enumerate_audio_inputs() -> anyhow::Result<Vec<AudioDeviceInfo>, CaptureError> {
+    enumerate_audio_devices(eCapture)
+}
+
+
+/// Format of the samples delivered by the device. [AudioBuffer] is always converted to `f32`.
+///
+/// Required features: *`"audio"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub is_float: bool,
+}
+impl AudioFormat {
+    fn from_wave_format(format: &WAVEFORMATEX) -> anyhow::Result<Self, CaptureError> {
+        let is_float = match format.wFormatTag {
+            WAVE_FORMAT_IEEE_FLOAT => true,
+            WAVE_FORMAT_PCM => false,
+            WAVE_FORMAT_EXTENSIBLE => unsafe {
+                // packed, so copy out before comparing
+                let sub_format = (*(format as *const WAVEFORMATEX as *const WAVEFORMATEXTENSIBLE)).SubFormat;
+                sub_format == KSDATAFORMAT_SUBTYPE_IEEE_FLOAT
+            },
+            tag => return Err(CaptureError::UnsupportedAudioFormat(tag as u32)),
+        };
+        match (is_float, format.wBitsPerSample) {
+            (true, 32) | (false, 16) | (false, 24) | (false, 32) => {},
+            _ => return Err(CaptureError::UnsupportedAudioFormat(format.wBitsPerSample as u32)),
+        }
+
+        Ok(Self{
+            sample_rate: format.nSamplesPerSec,
+            channels: format.nChannels,
+            bits_per_sample: format.wBitsPerSample,
+            is_float,
+        })
+    }
+
+    fn bytes_per_frame(&self) -> usize {
+        self.channels as usize * (self.bits_per_sample / 8) as usize
+    }
+
+    /// Convert one sample at the head of `bytes` to `f32` in -1.0..=1.0
+    fn read_sample(&self, bytes: &[u8]) -> f32 {
+        match (self.is_float, self.bits_per_sample) {
+            (true, _) => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            (false, 16) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            (false, 24) => (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / 8388608.0,
+            (false, _) => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 / 2147483648.0,
+        }
+    }
+}
+
+
+/// Interleaved samples read at once.
+///
+/// Required features: *`"audio"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+#[derive(Clone, Debug, Default)]
+pub struct AudioBuffer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// interleaved, -1.0..=1.0
+    pub samples: Vec<f32>,
+    /// QPC time of the first sample in 100ns units, same clock as the frame timestamps.
+    pub qpc_position: u64,
+}
+impl AudioBuffer {
+    /// Samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate.max(1) as f64)
+    }
+
+    /// Convert to 16 bit PCM.
+    pub fn to_i16(&self) -> Vec<i16> {
+        self.samples.iter().map(|&sample| (sample.clamp(-1.0, 1.0) * 32767.0) as i16).collect()
+    }
+}
+
+
+/// Audio capture through WASAPI.
+///
+/// Required features: *`"audio"`*
+/// # Examples
+/// ```
+/// let audio = dxcapture::AudioCapture::loopback(None).unwrap();
+/// audio.start().unwrap();
+///
+/// loop {
+///     if let Some(buffer) = audio.read().unwrap() {
+///         // hoge buffer.samples
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+/// }
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+pub struct AudioCapture {
+    client: IAudioClient,
+    capture_client: IAudioCaptureClient,
+    format: AudioFormat,
+}
+impl AudioCapture {
+    /// Capture what is played on an output device. (loopback)
+    /// ## Parameters
+    /// * device_id: [AudioDeviceInfo::id] of [enumerate_audio_outputs]. default is the default output device.
+    pub fn loopback(device_id: Option<&str>) -> anyhow::Result<Self, CaptureError> {
+        Self::new(eRender, device_id, AUDCLNT_STREAMFLAGS_LOOPBACK)
+    }
+
+    /// Capture an input device such as a microphone.
+    /// ## Parameters
+    /// * device_id: [AudioDeviceInfo::id] of [enumerate_audio_inputs]. default is the default input device.
+    pub fn microphone(device_id: Option<&str>) -> anyhow::Result<Self, CaptureError> {
+        Self::new(eCapture, device_id, 0)
+    }
+
+    fn new(flow: EDataFlow, device_id: Option<&str>, stream_flags: u32) -> anyhow::Result<Self, CaptureError> {
+        let enumerator = device_enumerator().map_err(directx_error)?;
+
+        unsafe {
+            let device = match device_id {
+                Some(id) => enumerator.GetDevice(id),
+                None => enumerator.GetDefaultAudioEndpoint(flow, eConsole),
+            }.map_err(directx_error)?;
+
+            let mut client: Option<IAudioClient> = None;
+            device.Activate(&IAudioClient::IID, CLSCTX_ALL, std::ptr::null(), &mut client as *mut _ as *mut _)
+                .map_err(directx_error)?;
+            let client = client.ok_or(CaptureError::NotActive)?;
+
+            let mix_format = client.GetMixFormat().map_err(directx_error)?;
+            let format = AudioFormat::from_wave_format(&*mix_format);
+            let result = match format {
+                Ok(_) => client.Initialize(
+                    AUDCLNT_SHAREMODE_SHARED, stream_flags, REFTIMES_PER_SEC / 5, 0, mix_format, std::ptr::null(),
+                ).map_err(directx_error),
+                Err(_) => Ok(()),
+            };
+            CoTaskMemFree(mix_format as *const _);
+            let format = format?;
+            result?;
+
+            let mut capture_client: Option<IAudioCaptureClient> = None;
+            client.GetService(&IAudioCaptureClient::IID, &mut capture_client as *mut _ as *mut _)
+                .map_err(directx_error)?;
+            let capture_client = capture_client.ok_or(CaptureError::NotActive)?;
+
+            Ok(Self{
+                client,
+                capture_client,
+                format,
+            })
+        }
+    }
+
+    pub fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    pub fn start(&self) -> anyhow::Result<(), CaptureError> {
+        unsafe { self.client.Start().map_err(directx_error) }
+    }
+
+    pub fn stop(&self) -> anyhow::Result<(), CaptureError> {
+        unsafe { self.client.Stop().map_err(directx_error) }
+    }
+
+    /// Read all samples arrived since the last read. `None` if nothing arrived.
+    ///
+    /// the device keeps about 200ms, so read more often than that.
+    pub fn read(&self) -> anyhow::Result<Option<AudioBuffer>, CaptureError> {
+        let mut buffer = AudioBuffer{
+            sample_rate: self.format.sample_rate,
+            channels: self.format.channels,
+            ..Default::default()
+        };
+        let sample_bytes = (self.format.bits_per_sample / 8) as usize;
+        let mut first = true;
+
+        unsafe {
+            while 0 < self.capture_client.GetNextPacketSize().map_err(directx_error)? {
+                let mut data = std::ptr::null_mut();
+                let mut frames = 0;
+                let mut flags = 0;
+                let mut qpc_position = 0;
+                self.capture_client.GetBuffer(&mut data, &mut frames, &mut flags, std::ptr::null_mut(), &mut qpc_position)
+                    .map_err(directx_error)?;
+
+                if first {
+                    buffer.qpc_position = qpc_position;
+                    first = false;
+                }
+
+                let sample_count = frames as usize * self.format.channels as usize;
+                if flags & AUDCLNT_BUFFERFLAGS_SILENT as u32 != 0 || data.is_null() {
+                    buffer.samples.extend(std::iter::repeat(0.0).take(sample_count));
+                } else {
+                    let bytes = std::slice::from_raw_parts(data, frames as usize * self.format.bytes_per_frame());
+                    buffer.samples.extend(bytes.chunks_exact(sample_bytes).map(|sample| self.format.read_sample(sample)));
+                }
+
+                self.capture_client.ReleaseBuffer(frames).map_err(directx_error)?;
+            }
+        }
+
+        Ok(if first { None } else { Some(buffer) })
+    }
+}
+impl Drop for AudioCapture {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+
+/// Convert channel count and sample rate of a stream, keeping the state between buffers.
+struct StreamConverter {
+    channels: u16,
+    /// input frames per output frame
+    ratio: f64,
+    position: f64,
+    pending: Vec<f32>,
+}
+impl StreamConverter {
+    fn new(format: &AudioFormat, sample_rate: u32, channels: u16) -> Self {
+        Self{
+            channels,
+            ratio: format.sample_rate as f64 / sample_rate as f64,
+            position: 0.0,
+            pending: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, buffer: &AudioBuffer, output: &mut VecDeque<f32>) {
+        let (from, to) = (buffer.channels.max(1) as usize, self.channels as usize);
+
+        for frame in buffer.samples.chunks_exact(from) {
+            match (from, to) {
+                (_, 1) => self.pending.push(frame.iter().sum::<f32>() / from as f32),
+                (1, _) => self.pending.extend(std::iter::repeat(frame[0]).take(to)),
+                // front left and right come first, the rest are dropped
+                _ => self.pending.extend((0..to).map(|channel| frame.get(channel).copied().unwrap_or(0.0))),
+            }
+        }
+
+        // linear interpolation
+        let frames = self.pending.len() / to;
+        while self.position + 1.0 < frames as f64 {
+            let index = self.position as usize;
+            let t = (self.position - index as f64) as f32;
+            for channel in 0..to {
+                let a = self.pending[index * to + channel];
+                let b = self.pending[(index + 1) * to + channel];
+                output.push_back(a + (b - a) * t);
+            }
+            self.position += self.ratio;
+        }
+
+        let consumed = (self.position as usize).min(frames);
+        self.pending.drain(..consumed * to);
+        self.position -= consumed as f64;
+    }
+}
+
+/// A source of [AudioMixer] converted to the mixer format, waiting to be mixed.
+struct MixerTrack {
+    gain: f32,
+    muted: bool,
+    converter: StreamConverter,
+    queue: VecDeque<f32>,
+    /// last time the source delivered anything.
+    last_delivery: Instant,
+}
+impl MixerTrack {
+    fn new(converter: StreamConverter, gain: f32, now: Instant) -> Self {
+        Self{
+            gain,
+            muted: false,
+            converter,
+            queue: VecDeque::new(),
+            last_delivery: now,
+        }
+    }
+
+    fn push(&mut self, buffer: &AudioBuffer, now: Instant) {
+        self.converter.push(buffer, &mut self.queue);
+        self.last_delivery = now;
+    }
+}
+
+/// Mix the samples all the delivering tracks have, and leave the rest queued.
+///
+/// a track a little behind is waited for instead of padded, so no gap is inserted into its stream.
+/// a track silent for `silence_timeout` is not waited for, and counts as silence until it delivers again.
+fn mix_tracks(tracks: &mut [MixerTrack], now: Instant, silence_timeout: Duration) -> Vec<f32> {
+    let len = tracks.iter()
+        .filter(|track| now.saturating_duration_since(track.last_delivery) < silence_timeout)
+        .map(|track| track.queue.len())
+        .min()
+        // all silent, so flush what is left
+        .unwrap_or_else(|| tracks.iter().map(|track| track.queue.len()).max().unwrap_or(0));
+
+    let mut samples = vec![0.0f32; len];
+    for track in tracks {
+        let take = track.queue.len().min(len);
+        let gain = if track.muted { 0.0 } else { track.gain };
+        for (mixed, sample) in samples.iter_mut().zip(track.queue.drain(..take)) {
+            *mixed += sample * gain;
+        }
+    }
+    for sample in &mut samples {
+        *sample = sample.clamp(-1.0, 1.0);
+    }
+    samples
+}
+
+
+/// Mix several [AudioCapture] into one track, with gain and mute per source.
+///
+/// each source is converted to the sample rate and channels of the mixer.
+/// the mix goes as far as all the delivering sources have arrived, so a source a few ms behind is waited for.
+/// sources delivering nothing for the [silence timeout](Self::set_silence_timeout)
+/// (e.g. a loopback while nothing is played) count as silence.
+///
+/// Required features: *`"audio"`*
+/// # Examples
+/// ```
+/// let mut mixer = dxcapture::AudioMixer::new(48000, 2);
+/// let system = mixer.add_source(dxcapture::AudioCapture::loopback(None).unwrap(), 1.0);
+/// let mic = mixer.add_source(dxcapture::AudioCapture::microphone(None).unwrap(), 0.8);
+/// mixer.set_gain(system, 0.5);
+/// mixer.set_muted(mic, false);
+/// mixer.start().unwrap();
+///
+/// loop {
+///     if let Some(buffer) = mixer.read().unwrap() {
+///         // hoge buffer.samples
+///     }
+///     std::thread::sleep(std::time::Duration::from_millis(10));
+/// }
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+pub struct AudioMixer {
+    sample_rate: u32,
+    channels: u16,
+    captures: Vec<AudioCapture>,
+    tracks: Vec<MixerTrack>,
+    silence_timeout: Duration,
+    /// QPC time of the next mixed sample.
+    qpc_position: Option<u64>,
+}
+impl AudioMixer {
+    /// Default of [set_silence_timeout](Self::set_silence_timeout), about the buffer the devices keep.
+    pub const DEFAULT_SILENCE_TIMEOUT: Duration = Duration::from_millis(200);
+
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self{
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+            captures: Vec::new(),
+            tracks: Vec::new(),
+            silence_timeout: Self::DEFAULT_SILENCE_TIMEOUT,
+            qpc_position: None,
+        }
+    }
+
+    /// Add a source and return its index for [set_gain](Self::set_gain) and [set_muted](Self::set_muted).
+    pub fn add_source(&mut self, capture: AudioCapture, gain: f32) -> usize {
+        let converter = StreamConverter::new(&capture.format(), self.sample_rate, self.channels);
+        self.captures.push(capture);
+        self.tracks.push(MixerTrack::new(converter, gain, Instant::now()));
+
+        self.tracks.len() - 1
+    }
+
+    pub fn set_gain(&mut self, source: usize, gain: f32) {
+        if let Some(track) = self.tracks.get_mut(source) {
+            track.gain = gain;
+        }
+    }
+
+    /// Muted sources are still read, so they stay in sync when unmuted.
+    pub fn set_muted(&mut self, source: usize, muted: bool) {
+        if let Some(track) = self.tracks.get_mut(source) {
+            track.muted = muted;
+        }
+    }
+
+    /// How long a source delivers nothing before it's mixed as silence instead of waited for.
+    /// default is [DEFAULT_SILENCE_TIMEOUT](Self::DEFAULT_SILENCE_TIMEOUT).
+    pub fn set_silence_timeout(&mut self, timeout: Duration) {
+        self.silence_timeout = timeout;
+    }
+
+    pub fn start(&self) -> anyhow::Result<(), CaptureError> {
+        self.captures.iter().try_for_each(|capture| capture.start())
+    }
+
+    pub fn stop(&self) -> anyhow::Result<(), CaptureError> {
+        self.captures.iter().try_for_each(|capture| capture.stop())
+    }
+
+    /// Read all sources and return the mixed samples. `None` if nothing is ready to mix.
+    pub fn read(&mut self) -> anyhow::Result<Option<AudioBuffer>, CaptureError> {
+        let now = Instant::now();
+        for (capture, track) in self.captures.iter().zip(&mut self.tracks) {
+            if let Some(buffer) = capture.read()? {
+                self.qpc_position.get_or_insert(buffer.qpc_position);
+                track.push(&buffer, now);
+            }
+        }
+
+        let samples = mix_tracks(&mut self.tracks, now, self.silence_timeout);
+        if samples.is_empty() {
+            return Ok(None);
+        }
+
+        let qpc_position = self.qpc_position.unwrap_or(0);
+        let frames = (samples.len() / self.channels as usize) as u64;
+        self.qpc_position = Some(qpc_position + frames * REFTIMES_PER_SEC as u64 / self.sample_rate as u64);
+
+        Ok(Some(AudioBuffer{
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            samples,
+            qpc_position,
+        }))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converter(from_rate: u32, from_channels: u16, to_rate: u32, to_channels: u16) -> StreamConverter {
+        let format = AudioFormat{ sample_rate: from_rate, channels: from_channels, bits_per_sample: 32, is_float: true };
+
+        StreamConverter::new(&format, to_rate, to_channels)
+    }
+
+    fn convert(converter: &mut StreamConverter, sample_rate: u32, channels: u16, samples: &[f32]) -> Vec<f32> {
+        let buffer = AudioBuffer{ sample_rate, channels, samples: samples.to_vec(), qpc_position: 0 };
+        let mut output = VecDeque::new();
+        converter.push(&buffer, &mut output);
+
+        output.into()
+    }
+
+    #[test]
+    fn stereo_to_mono() {
+        let mut stereo = converter(48000, 2, 48000, 1);
+
+        // the last frame waits for the next buffer to interpolate with
+        assert_eq!(convert(&mut stereo, 48000, 2, &[0.25, 0.75, -0.5, 0.0, 1.0, 0.0]), vec![0.5, -0.25]);
+        assert_eq!(convert(&mut stereo, 48000, 2, &[0.0, 0.0]), vec![0.5]);
+    }
+
+    #[test]
+    fn mono_to_stereo() {
+        let mut mono = converter(48000, 1, 48000, 2);
+
+        assert_eq!(convert(&mut mono, 48000, 1, &[0.25, -0.5, 0.0]), vec![0.25, 0.25, -0.5, -0.5]);
+    }
+
+    #[test]
+    fn downsample() {
+        let mut half = converter(96000, 1, 48000, 1);
+        let samples: Vec<f32> = (0..8).map(|i| i as f32 / 8.0).collect();
+
+        assert_eq!(convert(&mut half, 96000, 1, &samples), vec![0.0, 0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn upsample_across_buffers() {
+        let mut double = converter(24000, 1, 48000, 1);
+
+        assert_eq!(convert(&mut double, 24000, 1, &[0.0, 0.5]), vec![0.0, 0.25]);
+        // interpolated from the last sample of the buffer before
+        assert_eq!(convert(&mut double, 24000, 1, &[1.0]), vec![0.5, 0.75]);
+    }
+
+    #[test]
+    fn resampled_length() {
+        let mut converter = converter(44100, 1, 48000, 1);
+        let mut frames = 0;
+        // a second in buffers of 10ms
+        for _ in 0..100 {
+            frames += convert(&mut converter, 44100, 1, &[0.0; 441]).len();
+        }
+
+        assert!((47990..=48000).contains(&frames), "{} frames", frames);
+    }
+
+    fn track(samples: &[f32], gain: f32, last_delivery: Instant) -> MixerTrack {
+        let mut track = MixerTrack::new(converter(48000, 1, 48000, 1), gain, last_delivery);
+        track.queue.extend(samples);
+
+        track
+    }
+
+    #[test]
+    fn mix_waits_for_delivering_tracks() {
+        let now = Instant::now();
+        let mut tracks = [track(&[0.5, 0.5, 0.5, 0.5], 1.0, now), track(&[0.25, 0.25], 0.5, now)];
+
+        assert_eq!(mix_tracks(&mut tracks, now, AudioMixer::DEFAULT_SILENCE_TIMEOUT), vec![0.625, 0.625]);
+        // the rest waits for the second track
+        assert_eq!(tracks[0].queue.len(), 2);
+        assert!(tracks[1].queue.is_empty());
+    }
+
+    #[test]
+    fn mix_skips_silent_tracks() {
+        let start = Instant::now();
+        let now = start + Duration::from_secs(1);
+        let mut tracks = [track(&[0.5, 0.5, 0.5], 1.0, now), track(&[], 1.0, start)];
+
+        // silent for longer than the timeout, so not waited for
+        assert_eq!(mix_tracks(&mut tracks, now, AudioMixer::DEFAULT_SILENCE_TIMEOUT), vec![0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn mix_flushes_when_all_silent() {
+        let start = Instant::now();
+        let now = start + Duration::from_secs(1);
+        let mut tracks = [track(&[0.5, 0.5, 0.5], 1.0, start), track(&[0.25], 1.0, start)];
+
+        // padded with silence
+        assert_eq!(mix_tracks(&mut tracks, now, AudioMixer::DEFAULT_SILENCE_TIMEOUT), vec![0.75, 0.5, 0.5]);
+        assert!(tracks.iter().all(|track| track.queue.is_empty()));
+    }
+
+    #[test]
+    fn mix_mutes_and_clamps() {
+        let now = Instant::now();
+        let mut tracks = [track(&[0.75, -0.75], 1.0, now), track(&[0.75, -0.75], 1.0, now), track(&[0.5, 0.5], 1.0, now)];
+        tracks[2].muted = true;
+
+        assert_eq!(mix_tracks(&mut tracks, now, AudioMixer::DEFAULT_SILENCE_TIMEOUT), vec![1.0, -1.0]);
+    }
+}
@@ -0,0 +1,233 @@
+//! Benchmark of the capture on this machine.
+//!
+//! measure what a target gets with a configuration, to choose between the formats and the GPU paths
+//! by the numbers of the machine rather than guesses.
+//! ```
+//! let report = dxcapture::bench::run(&dxcapture::bench::BenchConfig::default()).unwrap();
+//! println!("{:.1} fps, readback p99 {:?}, {:.0}% of a core", report.fps, report.readback.p99, report.cpu_usage * 100.0);
+//! ```
+
+use std::time::{
+    Duration,
+    Instant,
+};
+use winapi::{
+    shared::minwindef::FILETIME,
+    um::processthreadsapi::{
+        GetCurrentProcess,
+        GetProcessTimes,
+    },
+};
+use windows::Graphics::DirectX::DirectXPixelFormat;
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+};
+use crate::clock::CaptureClock;
+use crate::d3d::Device;
+
+
+/// What [run] captures.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum BenchTarget {
+    #[default]
+    Primary,
+    /// same as [Device::new_from_displays]. range is [1..=len].
+    Display(usize),
+    /// caption query, same as [Device::new_from_window].
+    Window(String),
+}
+
+/// Configuration measured by [run].
+#[derive(Clone, Debug)]
+pub struct BenchConfig {
+    pub target: BenchTarget,
+    /// default is `B8G8R8A8UIntNormalized`.
+    pub pixel_format: DirectXPixelFormat,
+    /// how long it is measured. default is 5 seconds.
+    pub duration: Duration,
+    /// frames before this are not measured, while the session starts up. default is 1 second.
+    pub warmup: Duration,
+    /// read back each frame by [get_raw_frame](Capture::get_raw_frame). `false` measures the frame arrivals only.
+    pub readback: bool,
+    /// also copy each frame into this many shared textures, as by [share_frames](Capture::share_frames).
+    #[cfg(feature = "interop")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub share_frames: Option<usize>,
+    /// in the [low latency mode](Capture::set_low_latency).
+    #[cfg(feature = "latency")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "latency")))]
+    pub low_latency: bool,
+}
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self{
+            target: BenchTarget::Primary,
+            pixel_format: DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            duration: Duration::from_secs(5),
+            warmup: Duration::from_secs(1),
+            readback: true,
+            #[cfg(feature = "interop")]
+            share_frames: None,
+            #[cfg(feature = "latency")]
+            low_latency: false,
+        }
+    }
+}
+
+
+/// Percentiles of the measured times. all zero without samples.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min: Duration,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+impl LatencyStats {
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort();
+
+        // nearest rank
+        let percentile = |p: usize| samples[((samples.len() * p + 99) / 100).clamp(1, samples.len()) - 1];
+        Self{
+            samples: samples.len(),
+            min: samples[0],
+            mean: samples.iter().sum::<Duration>() / samples.len() as u32,
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples[samples.len() - 1],
+        }
+    }
+}
+
+/// Result of [run].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BenchReport {
+    /// size of the last frame.
+    pub width: u32,
+    pub height: u32,
+    /// new frames seen while measured.
+    pub frames: u64,
+    /// measured time, without the warmup.
+    pub duration: Duration,
+    /// new frames a second. no more than the refresh rate, and less while the target doesn't change.
+    pub fps: f64,
+    /// time of [get_raw_frame](Capture::get_raw_frame), the map and the copy of the staging texture.
+    pub readback: LatencyStats,
+    /// from the frame time (`SystemRelativeTime`) to the end of the readback, or to when the frame was seen without it.
+    pub latency: LatencyStats,
+    /// CPU time of the whole process over the measured time. `1.0` is a core fully busy.
+    pub cpu_usage: f64,
+}
+
+
+/// CPU time of the process, kernel and user.
+fn process_cpu_time() -> Duration {
+    let to_100ns = |time: FILETIME| (time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64;
+
+    unsafe {
+        let (mut creation, mut exit, mut kernel, mut user) = std::mem::zeroed::<(FILETIME, FILETIME, FILETIME, FILETIME)>();
+        if GetProcessTimes(GetCurrentProcess(), &mut creation, &mut exit, &mut kernel, &mut user) == 0 {
+            return Duration::ZERO;
+        }
+        CaptureClock::from_100ns(to_100ns(kernel) + to_100ns(user))
+    }
+}
+
+fn to_capture_error(e: anyhow::Error) -> CaptureError {
+    match e.downcast::<CaptureError>() {
+        Ok(e) => e,
+        Err(e) => CaptureError::SourceError(e.to_string()),
+    }
+}
+
+/// Capture `config.target` as configured, and measure the frame rate, the readback and latency percentiles,
+/// and the CPU usage.
+///
+/// blocks for the warmup and the duration. the capture runs on this thread, so other work of the process
+/// counts in the CPU usage.
+/// ## Errors
+/// * [SourceError](CaptureError::SourceError): failed to capture the target.
+/// * the errors of [get_raw_frame](Capture::get_raw_frame).
+pub fn run(config: &BenchConfig) -> anyhow::Result<BenchReport, CaptureError> {
+    let device = match &config.target {
+        BenchTarget::Primary => Device::new_from_displays(None),
+        BenchTarget::Display(display_id) => Device::new_from_displays(Some(*display_id)),
+        BenchTarget::Window(query) => Device::new_from_window(query.clone()),
+    }.map_err(to_capture_error)?;
+    let capture = Capture::new_with_format(&device, config.pixel_format).map_err(to_capture_error)?;
+
+    #[cfg(feature = "interop")]
+    if let Some(buffers) = config.share_frames {
+        capture.share_frames(buffers)?;
+    }
+    #[cfg(feature = "latency")]
+    capture.set_low_latency(config.low_latency)?;
+
+    let mut report = BenchReport::default();
+    let mut readback = Vec::new();
+    let mut latency = Vec::new();
+    let mut last_frame_time = None;
+
+    let warmup_end = Instant::now() + config.warmup;
+    let mut measuring = None;
+    loop {
+        let now = Instant::now();
+        if measuring.is_none() && warmup_end <= now {
+            measuring = Some((now, process_cpu_time()));
+            report.frames = 0;
+            readback.clear();
+            latency.clear();
+        }
+        if measuring.is_some_and(|(start, _)| config.duration <= now.duration_since(start)) {
+            break;
+        }
+
+        let frame_time = capture.get_frame_time();
+        if frame_time.is_none() || frame_time == last_frame_time {
+            std::thread::sleep(Duration::from_micros(500));
+            continue;
+        }
+
+        if config.readback {
+            let started = Instant::now();
+            let raw = match capture.get_raw_frame() {
+                Ok(raw) => raw,
+                Err(CaptureError::NoTexture) => continue,
+                Err(e) => return Err(e),
+            };
+            readback.push(started.elapsed());
+            report.width = raw.width as u32;
+            report.height = raw.height as u32;
+        } else if let Some(size) = capture.get_content_size() {
+            report.width = size.texture_width as u32;
+            report.height = size.texture_height as u32;
+        }
+
+        // the handler may have replaced the frame during the readback
+        let frame_time = capture.get_frame_time();
+        if let Some(frame_time) = frame_time {
+            latency.push(CaptureClock::qpc_now().saturating_sub(frame_time));
+        }
+        last_frame_time = frame_time;
+        report.frames += 1;
+    }
+
+    let (start, cpu_start) = measuring.unwrap();
+    report.duration = start.elapsed();
+    report.fps = report.frames as f64 / report.duration.as_secs_f64().max(f64::EPSILON);
+    report.readback = LatencyStats::from_samples(readback);
+    report.latency = LatencyStats::from_samples(latency);
+    report.cpu_usage = process_cpu_time().saturating_sub(cpu_start).as_secs_f64() / report.duration.as_secs_f64().max(f64::EPSILON);
+
+    Ok(report)
+}
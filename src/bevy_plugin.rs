@@ -0,0 +1,268 @@
+use std::{
+    marker::PhantomData,
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use bevy::{
+    app::{
+        App,
+        CoreStage,
+        Plugin,
+        StartupStage,
+    },
+    asset::{
+        Assets,
+        Handle,
+    },
+    ecs::system::{
+        Commands,
+        Res,
+        ResMut,
+    },
+    render::{
+        render_resource::{
+            Extent3d,
+            TextureDimension,
+            TextureFormat,
+        },
+        texture::Image,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::source::FrameSource;
+
+
+type SourceFactory<S> = Box<dyn FnOnce() -> anyhow::Result<S> + Send>;
+
+/// Bevy plugin showing a [FrameSource] as an `Image` asset, updated every frame of the app.
+///
+/// the source is made by `source_factory` on a capture thread, as [ReplayBuffer](crate::ReplayBuffer),
+/// so [Capture](crate::Capture) works though it is not `Send`. the latest frame is copied into the image
+/// in `CoreStage::PreUpdate`, and the image is resized with the frame.
+///
+/// add after `DefaultPlugins`, and use [CaptureImage::handle] for sprites and materials.
+///
+/// Required features: *`"bevy"`*
+/// # Examples
+/// ```
+/// use bevy::prelude::*;
+///
+/// fn setup(mut commands: Commands, capture: Res<dxcapture::CaptureImage>) {
+///     commands.spawn_bundle(OrthographicCameraBundle::new_2d());
+///     commands.spawn_bundle(SpriteBundle{
+///         texture: capture.handle.clone(),
+///         ..Default::default()
+///     });
+/// }
+///
+/// App::new()
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(dxcapture::CapturePlugin::new(|| {
+///         let device = dxcapture::Device::new_from_displays(None)?;
+///         dxcapture::Capture::new(&device)
+///     }))
+///     .add_startup_system(setup)
+///     .run();
+/// ```
+pub struct CapturePlugin<S: FrameSource + 'static> {
+    source_factory: Mutex<Option<SourceFactory<S>>>,
+    fps: u32,
+    _source: PhantomData<fn() -> S>,
+}
+impl<S: FrameSource + 'static> CapturePlugin<S> {
+    pub fn new<F>(source_factory: F) -> Self
+    where
+        F: FnOnce() -> anyhow::Result<S> + Send + 'static,
+    {
+        Self{
+            source_factory: Mutex::new(Some(Box::new(source_factory))),
+            fps: 60,
+            _source: PhantomData,
+        }
+    }
+
+    /// Frames taken from the source a second. default is 60.
+    pub fn fps(mut self, fps: u32) -> Self {
+        self.fps = fps.max(1);
+        self
+    }
+}
+impl<S: FrameSource + 'static> Plugin for CapturePlugin<S> {
+    fn build(&self, app: &mut App) {
+        let source_factory = self.source_factory.lock().unwrap().take()
+            .expect("CapturePlugin is added twice.");
+
+        app.insert_resource(CaptureFeed::start(source_factory, self.fps))
+            // before the startup systems of the app, to use the handle
+            .add_startup_system_to_stage(StartupStage::PreStartup, setup_capture_image)
+            .add_system_to_stage(CoreStage::PreUpdate, update_capture_image);
+    }
+}
+
+
+/// Resource of the image updated by [CapturePlugin].
+#[derive(Clone, Debug)]
+pub struct CaptureImage {
+    /// BGRA8 sRGB image. 1x1 until the first frame.
+    pub handle: Handle<Image>,
+    pub width: u32,
+    pub height: u32,
+    /// frames copied into the image.
+    pub frame_count: u64,
+    /// why the capture thread stopped. the image keeps the last frame.
+    pub error: Option<String>,
+}
+
+
+#[derive(Default)]
+struct FeedState {
+    stopped: bool,
+    latest: Option<RawFrameData>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct FeedShared {
+    state: Mutex<FeedState>,
+    stop_requested: Condvar,
+}
+
+/// Capture thread of [CapturePlugin]. stopped when the app drops its resources.
+struct CaptureFeed {
+    shared: Arc<FeedShared>,
+    thread: Option<JoinHandle<()>>,
+}
+impl CaptureFeed {
+    fn start<S: FrameSource>(source_factory: SourceFactory<S>, fps: u32) -> Self {
+        let shared = Arc::new(FeedShared::default());
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+
+            move || {
+                if let Err(e) = Self::run(&shared, source_factory, fps) {
+                    shared.state.lock().unwrap().error = Some(e.to_string());
+                }
+            }
+        });
+
+        Self{
+            shared,
+            thread: Some(thread),
+        }
+    }
+
+    fn run<S: FrameSource>(shared: &FeedShared, source_factory: SourceFactory<S>, fps: u32) -> anyhow::Result<(), CaptureError> {
+        let source = source_factory().map_err(|e| match e.downcast::<CaptureError>() {
+            Ok(e) => e,
+            Err(e) => CaptureError::SourceError(e.to_string()),
+        })?;
+
+        let interval = Duration::from_secs(1) / fps;
+        let mut next_tick = Instant::now();
+
+        loop {
+            // sleep until the next tick, waking up on stop
+            {
+                let mut state = shared.state.lock().unwrap();
+                while !state.stopped {
+                    let wait = match next_tick.checked_duration_since(Instant::now()) {
+                        Some(wait) => wait,
+                        None => break,
+                    };
+                    state = shared.stop_requested.wait_timeout(state, wait).unwrap().0;
+                }
+                if state.stopped {
+                    break;
+                }
+            }
+            // skip the ticks already passed
+            while next_tick <= Instant::now() {
+                next_tick += interval;
+            }
+
+            let frame = match source.get_raw_frame() {
+                Ok(frame) => frame,
+                Err(CaptureError::NoTexture) => continue,
+                Err(e) => return Err(e),
+            };
+            // the app takes only the latest one
+            shared.state.lock().unwrap().latest = Some(frame);
+        }
+
+        Ok(())
+    }
+}
+impl Drop for CaptureFeed {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().stopped = true;
+        self.shared.stop_requested.notify_all();
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+
+fn setup_capture_image(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = Image::new(
+        Extent3d{ width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        vec![0; 4],
+        TextureFormat::Bgra8UnormSrgb,
+    );
+
+    commands.insert_resource(CaptureImage{
+        handle: images.add(image),
+        width: 1,
+        height: 1,
+        frame_count: 0,
+        error: None,
+    });
+}
+
+fn update_capture_image(feed: Res<CaptureFeed>, capture_image: Option<ResMut<CaptureImage>>, mut images: ResMut<Assets<Image>>) {
+    let mut capture_image = match capture_image {
+        Some(capture_image) => capture_image,
+        None => return,
+    };
+
+    let (frame, error) = {
+        let mut state = feed.shared.state.lock().unwrap();
+        (state.latest.take(), state.error.take())
+    };
+    if error.is_some() {
+        capture_image.error = error;
+    }
+    let frame = match frame {
+        Some(frame) => frame,
+        None => return,
+    };
+
+    // modified, so uploaded again by the renderer
+    if let Some(image) = images.get_mut(&capture_image.handle) {
+        image.texture_descriptor.size = Extent3d{
+            width: frame.width as u32,
+            height: frame.height as u32,
+            depth_or_array_layers: 1,
+        };
+        image.data = frame.data;
+    }
+
+    capture_image.width = frame.width as u32;
+    capture_image.height = frame.height as u32;
+    capture_image.frame_count += 1;
+}
@@ -0,0 +1,244 @@
+//! `dxcapture` command line tool.
+//!
+//! Required features: *`"cli"`*
+
+use std::{
+    path::PathBuf,
+    time::Duration,
+};
+
+use dxcapture::{
+    Capture,
+    Device,
+    DxcapMuxer,
+    ImageMetadata,
+    ImageSequenceMuxer,
+    RawEncoder,
+    Recorder,
+    RecorderOptions,
+    RecorderState,
+};
+
+
+const USAGE: &str = "\
+dxcapture - capture displays and windows with Direct3D 11
+
+USAGE:
+    dxcapture list <windows|displays>
+    dxcapture screenshot [TARGET] [--metadata] -o <FILE>
+    dxcapture record [TARGET] [--fps <N>] [--duration <TIME>] [--format <dxcap|png>] -o <PATH>
+    dxcapture help
+
+TARGET (the primary display by default):
+    --window <QUERY>    first window whose caption contains QUERY
+    --display <N>       display N, as numbered by `list displays` (from 1)
+    --all               all displays composed into one (screenshot only)
+
+OPTIONS:
+    -o, --output <PATH>      file to write. the image format is chosen by the extension
+    --metadata               embed the capture time and target into PNG/JPEG files
+    --fps <N>                frames per second of the recording [default: 30]
+    --duration <TIME>        stop after TIME, like `10`, `10s`, `500ms` or `2m`. otherwise press Enter to stop
+    --format <FORMAT>        `dxcap` (replayable raw frames) or `png` (numbered images in a directory) [default: dxcap]
+";
+
+
+#[derive(Clone, Debug, PartialEq)]
+enum Target {
+    Primary,
+    Display(usize),
+    Window(String),
+    AllDisplays,
+}
+impl Target {
+    fn device(&self) -> anyhow::Result<Device> {
+        match self {
+            Target::Primary => Device::new_from_displays(None),
+            Target::Display(display_id) => Device::new_from_displays(Some(*display_id)),
+            Target::Window(query) => Device::new_from_window(query.clone()),
+            Target::AllDisplays => Err(anyhow::anyhow!("--all is only for screenshots")),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Options {
+    target: Target,
+    output: Option<PathBuf>,
+    metadata: bool,
+    fps: u32,
+    duration: Option<Duration>,
+    format: String,
+}
+impl Default for Options {
+    fn default() -> Self {
+        Self{
+            target: Target::Primary,
+            output: None,
+            metadata: false,
+            fps: 30,
+            duration: None,
+            format: "dxcap".to_string(),
+        }
+    }
+}
+impl Options {
+    fn parse(args: &[String]) -> anyhow::Result<Self> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| anyhow::anyhow!("{} needs a value", arg));
+            match arg.as_str() {
+                "--window" => options.target = Target::Window(value()?.clone()),
+                "--display" => options.target = Target::Display(value()?.parse()?),
+                "--all" => options.target = Target::AllDisplays,
+                "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+                "--metadata" => options.metadata = true,
+                "--fps" => options.fps = value()?.parse()?,
+                "--duration" => options.duration = Some(parse_duration(value()?)?),
+                "--format" => options.format = value()?.to_ascii_lowercase(),
+                _ => return Err(anyhow::anyhow!("unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+
+    fn output(&self) -> anyhow::Result<&PathBuf> {
+        self.output.as_ref().ok_or_else(|| anyhow::anyhow!("-o <PATH> is required"))
+    }
+}
+
+/// `10`, `10s`, `500ms` or `2m`.
+fn parse_duration(text: &str) -> anyhow::Result<Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let number: f64 = number.parse().map_err(|_| anyhow::anyhow!("invalid duration: {}", text))?;
+
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(anyhow::anyhow!("invalid duration: {}", text)),
+    };
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+
+fn list(args: &[String]) -> anyhow::Result<()> {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("windows") => {
+            for window in dxcapture::enumerate_windows() {
+                println!("{}\t{}", window.title, window.class_name);
+            }
+        },
+        Some("displays") => {
+            for (index, display) in dxcapture::enumerate_displays()?.iter().enumerate() {
+                let rect = display.rect;
+                println!("{}\t{}\t{}x{}+{}+{}", index + 1, display.display_name, rect.width, rect.height, rect.x, rect.y);
+            }
+        },
+        _ => return Err(anyhow::anyhow!("list windows or displays?")),
+    }
+
+    Ok(())
+}
+
+fn screenshot(options: &Options) -> anyhow::Result<()> {
+    let output = options.output()?;
+    let image = match &options.target {
+        Target::Primary => dxcapture::screenshot_primary_img()?,
+        Target::Display(display_id) => dxcapture::screenshot_display_img(*display_id)?,
+        Target::Window(query) => dxcapture::screenshot_window_img(query)?,
+        Target::AllDisplays => dxcapture::screenshot_all_displays_img()?,
+    };
+
+    if options.metadata {
+        let mut metadata = ImageMetadata::now();
+        match &options.target {
+            Target::Window(query) => metadata = metadata.window_title(query),
+            Target::Display(display_id) => {
+                if let Some(display) = display_id.checked_sub(1).and_then(|index| dxcapture::enumerate_displays().ok()?.get(index).cloned()) {
+                    metadata = metadata.monitor_name(&display.display_name);
+                }
+            },
+            _ => {},
+        }
+        image.save_with_metadata(output, &metadata)?;
+    } else {
+        image.data.save(output)?;
+    }
+
+    eprintln!("saved {}x{} to {}", image.width, image.height, output.display());
+    Ok(())
+}
+
+fn record(options: &Options) -> anyhow::Result<()> {
+    let output = options.output()?.clone();
+    // fail early, not on the recording thread
+    let _ = options.target.device()?;
+
+    let target = options.target.clone();
+    let source_factory = move || {
+        let device = target.device()?;
+        Ok(Capture::new(&device)?)
+    };
+    let recorder_options = RecorderOptions{
+        fps: options.fps,
+        max_duration: options.duration,
+        ..Default::default()
+    };
+    let recorder = match options.format.as_str() {
+        "dxcap" => Recorder::start(source_factory, RawEncoder, DxcapMuxer::create(&output)?, recorder_options)?,
+        "png" => Recorder::start(source_factory, RawEncoder, ImageSequenceMuxer::create(&output)?, recorder_options)?,
+        format => return Err(anyhow::anyhow!("unknown format: {}", format)),
+    };
+
+    if options.duration.is_none() {
+        eprintln!("recording to {}. press Enter to stop.", output.display());
+        // EOF (no console, as in CI) doesn't stop, only a line does
+        let mut line = String::new();
+        while let Ok(0) = std::io::stdin().read_line(&mut line) {
+            if recorder.state() == RecorderState::Stopped {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    } else {
+        eprintln!("recording to {} for {:?}.", output.display(), options.duration.unwrap());
+        while recorder.state() != RecorderState::Stopped {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    let progress = recorder.stop()?;
+    eprintln!("recorded {} frames ({} dropped) in {:?}.", progress.frames, progress.dropped, progress.duration);
+    Ok(())
+}
+
+
+fn run(args: &[String]) -> anyhow::Result<()> {
+    match args.first().map(|arg| arg.as_str()) {
+        Some("list") => list(&args[1..]),
+        Some("screenshot") => screenshot(&Options::parse(&args[1..])?),
+        Some("record") => record(&Options::parse(&args[1..])?),
+        Some("help") | Some("-h") | Some("--help") | None => {
+            print!("{}", USAGE);
+            Ok(())
+        },
+        Some(command) => Err(anyhow::anyhow!("unknown command: {}", command)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        eprintln!("see `dxcapture help`.");
+        std::process::exit(1);
+    }
+}
@@ -1,14 +1,32 @@
-use std::sync::{
-    Arc,
-    Mutex
+use std::{
+    sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+        Condvar,
+        Mutex
+    },
+    time::{
+        Duration,
+        Instant,
+    },
 };
 use winapi::{
     shared::dxgiformat::{
         DXGI_FORMAT_B8G8R8A8_UNORM,
+        DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+        DXGI_FORMAT_R8G8B8A8_UNORM,
+        DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        DXGI_FORMAT_R10G10B10A2_UNORM,
+        DXGI_FORMAT_R16G16B16A16_FLOAT,
     },
     um::d3d11::{
+        D3D11_BIND_SHADER_RESOURCE,
         D3D11_CPU_ACCESS_READ,
         D3D11_MAP_READ,
+        D3D11_USAGE_DEFAULT,
         D3D11_USAGE_STAGING,
     }
 };
@@ -16,6 +34,7 @@ use windows::{
     Graphics::{
         Capture::{
             Direct3D11CaptureFramePool,
+            GraphicsCaptureItem,
             GraphicsCaptureSession,
         },
         DirectX::{
@@ -37,10 +56,24 @@ use windows::{
     }
 };
 
+#[cfg(feature = "latency")]
+use windows::Win32::Graphics::{
+    Direct3D11::D3D11_MAP_FLAG_DO_NOT_WAIT,
+    Dxgi::DXGI_ERROR_WAS_STILL_DRAWING,
+};
+
 type FrameArrivedHandler =
     windows::Foundation::TypedEventHandler<Direct3D11CaptureFramePool, windows::core::IInspectable>;
+type ItemClosedHandler =
+    windows::Foundation::TypedEventHandler<GraphicsCaptureItem, windows::core::IInspectable>;
 
 use crate::d3d::*;
+use crate::overlay::{
+    CropFilter,
+    FrameFilter,
+};
+use crate::priority::ThreadOptions;
+use crate::source::FrameSource;
 
 
 #[derive(Debug, PartialEq, thiserror::Error)]
@@ -67,6 +100,56 @@ pub enum CaptureError {
 
     #[error("Unsupported pixel format.")]
     UnsupportedPixelFormat(u32),
+
+    #[error("IO error: {0}")]
+    IoError(String),
+
+    #[error("Frame sizes differ.")]
+    SizeMismatch,
+
+    #[error("Timed out.")]
+    Timeout,
+
+    // session 0 service, locked or disconnected session.
+    #[error("No interactive desktop session.")]
+    NoDesktopSession,
+
+    #[error("No displays attached.")]
+    NoDisplaysAttached,
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    // failed to create a frame source.
+    #[error("Source error: {0}")]
+    SourceError(String),
+
+    #[error("Unsupported audio format.")]
+    UnsupportedAudioFormat(u32),
+
+    // modifiers and virtual-key code.
+    #[error("Hotkey is already registered.")]
+    HotkeyInUse(u32, u32),
+
+    #[error("Vulkan error: {0}")]
+    VulkanError(String),
+
+    #[error("OpenGL error: {0}")]
+    OpenglError(String),
+
+    #[error("CUDA error: {0}")]
+    CudaError(String),
+
+    #[error("Torch error: {0}")]
+    TorchError(String),
+
+    // the captured window or display is gone.
+    #[error("Capture item is closed.")]
+    ItemClosed,
+
+    // more than one window matched, with the titles of them.
+    #[error("Window is ambiguous: {0:?}")]
+    AmbiguousWindow(Vec<String>),
 }
 
 
@@ -75,189 +158,1730 @@ pub struct RawFrameData {
     pub width: i32,
     pub height: i32,
     pub data: Vec<u8>,
+    /// default for the frames not from a [Capture].
+    pub info: FrameInfo,
+    /// bytes from a row to the next in `data`. 0 for `width * 4`, the tightly packed rows all but
+    /// [get_raw_frame_strided](Capture::get_raw_frame_strided) give.
+    pub row_pitch: usize,
+}
+impl RawFrameData {
+    /// Bytes from a row to the next in `data`.
+    pub fn stride(&self) -> usize {
+        match self.row_pitch {
+            0 => self.width.max(0) as usize * 4,
+            row_pitch => row_pitch,
+        }
+    }
+
+    /// Drop the padding of the rows, for the APIs taking only the tightly packed rows.
+    pub fn pack_rows(&mut self) {
+        let (stride, row_len) = (self.stride(), self.width.max(0) as usize * 4);
+        if stride != row_len {
+            for row in 1..self.height.max(0) as usize {
+                self.data.copy_within(row * stride..row * stride + row_len, row * row_len);
+            }
+            self.data.truncate(row_len * self.height.max(0) as usize);
+        }
+        self.row_pitch = 0;
+    }
+
+    /// Offset of the pixel at (`x`, `y`) in `data`, with the [stride](Self::stride).
+    pub fn pixel_offset(&self, x: usize, y: usize) -> usize {
+        y * self.stride() + x * 4
+    }
+
+    /// The pixels of the row `y`, without the padding.
+    pub fn row(&self, y: usize) -> &[u8] {
+        let start = y * self.stride();
+        &self.data[start..start + self.width.max(0) as usize * 4]
+    }
+
+    /// `data` holds all the rows of the size.
+    pub(crate) fn has_all_rows(&self) -> bool {
+        let (width, height) = (self.width.max(0) as usize, self.height.max(0) as usize);
+        height == 0 || (height - 1) * self.stride() + width * 4 <= self.data.len()
+    }
+}
+
+/// Frame of `R10G10B10A2` kept in 10 bits. [Read more](Capture::get_raw10_frame)
+#[derive(Clone, Debug, Default)]
+pub struct Raw10FrameData {
+    pub width: i32,
+    pub height: i32,
+    /// R, G, B, A of each pixel in `0..=1023`. the 2 bits alpha is scaled to the same range.
+    pub data: Vec<u16>,
+    pub info: FrameInfo,
+}
+
+/// Frame of `R16G16B16A16Float` kept in float, the scRGB of HDR displays. [Read more](Capture::get_float_frame)
+#[derive(Clone, Debug, Default)]
+pub struct FloatFrameData {
+    pub width: i32,
+    pub height: i32,
+    /// R, G, B, A of each pixel in linear scRGB. `1.0` is the SDR white of 80 nits, and HDR highlights go above it.
+    pub data: Vec<f32>,
+    pub info: FrameInfo,
+}
+
+/// How `R10G10B10A2` frames are reduced to the 8 bits BGRA of [RawFrameData]. [Read more](Capture::set_ten_bit_conversion)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TenBitConversion {
+    /// Round to the nearest 8 bits value. gradients show banding.
+    #[default]
+    Round,
+    /// Ordered dithering by a 4x4 Bayer matrix, keeping the gradients smooth.
+    Dither,
+}
+impl TenBitConversion {
+    /// Thresholds of the 4x4 Bayer matrix, in 16ths.
+    const BAYER: [[u32; 4]; 4] = [
+        [0, 8, 2, 10],
+        [12, 4, 14, 6],
+        [3, 11, 1, 9],
+        [15, 7, 13, 5],
+    ];
+
+    /// Reduce a 10 bits value at `x`, `y` to 8 bits.
+    fn reduce(&self, value: u32, x: u32, y: u32) -> u8 {
+        match self {
+            Self::Round => ((value * 255 + 511) / 1023) as u8,
+            Self::Dither => {
+                // 16ths of a step of 8 bits, shifted by the threshold of the pixel
+                let scaled = value * 255 * 16 / 1023 + Self::BAYER[(y % 4) as usize][(x % 4) as usize];
+                (scaled / 16).min(255) as u8
+            },
+        }
+    }
 }
 
 
+/// Rectangle in pixels. `x`, `y` is the top left.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self{
+            x, y, width, height
+        }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Smallest rectangle containing both.
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect::new(x, y, self.right().max(other.right()) - x, self.bottom().max(other.bottom()) - y)
+    }
+
+    /// Overlapping area of both. empty if they don't overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if right <= x || bottom <= y {
+            return Rect::default();
+        }
+
+        Rect::new(x, y, right - x, bottom - y)
+    }
+}
+
+
+/// Copy the pixels of a staging texture, without the row padding.
+fn read_texture(d3d_context: &ID3D11DeviceContext, d3d_texture: &ID3D11Texture2D, map_flags: u32) -> anyhow::Result<(u32, u32, u32, Vec<u8>), CaptureError> {
+    let mut data = Vec::new();
+    let (width, height, format) = read_texture_into(d3d_context, d3d_texture, map_flags, &mut data)?;
+
+    Ok((width, height, format, data))
+}
+
+/// [read_texture] into `data`, reusing its allocation. returns the width, height and format.
+fn read_texture_into(d3d_context: &ID3D11DeviceContext, d3d_texture: &ID3D11Texture2D, map_flags: u32, data: &mut Vec<u8>) -> anyhow::Result<(u32, u32, u32), CaptureError> {
+    let mapped = MappedTexture::map(d3d_context, d3d_texture, map_flags)?;
+    let (width, height, row_len) = (mapped.desc.Width, mapped.desc.Height, mapped.row_len());
+    let slice = mapped.as_slice();
+
+    // Make a copy of the data
+    data.clear();
+    if mapped.row_pitch as usize == row_len {
+        // no padding, the rows are already packed
+        data.extend_from_slice(slice);
+    } else {
+        data.reserve(row_len * height as usize);
+        for row in slice.chunks(mapped.row_pitch as usize) {
+            data.extend_from_slice(&row[..row_len]);
+        }
+    }
+
+    Ok((width, height, mapped.desc.Format))
+}
+
+/// [read_texture] keeping the row padding, in a single copy. returns the width, height, format and row pitch.
+fn read_texture_strided_into(d3d_context: &ID3D11DeviceContext, d3d_texture: &ID3D11Texture2D, map_flags: u32, data: &mut Vec<u8>) -> anyhow::Result<(u32, u32, u32, u32), CaptureError> {
+    let mapped = MappedTexture::map(d3d_context, d3d_texture, map_flags)?;
+
+    data.clear();
+    data.extend_from_slice(mapped.as_slice());
+
+    Ok((mapped.desc.Width, mapped.desc.Height, mapped.desc.Format, mapped.row_pitch))
+}
+
+/// Bytes of a pixel of the formats read back. `None` for the others.
+fn bytes_per_pixel(format: u32) -> Option<u32> {
+    match format {
+        DXGI_FORMAT_B8G8R8A8_UNORM | DXGI_FORMAT_B8G8R8A8_UNORM_SRGB |
+        DXGI_FORMAT_R8G8B8A8_UNORM | DXGI_FORMAT_R8G8B8A8_UNORM_SRGB |
+        DXGI_FORMAT_R10G10B10A2_UNORM => Some(4),
+        DXGI_FORMAT_R16G16B16A16_FLOAT => Some(8),
+        _ => None,
+    }
+}
+
+/// Staging texture mapped for reading, unmapped on drop.
+struct MappedTexture<'a> {
+    d3d_context: &'a ID3D11DeviceContext,
+    d3d_texture: &'a ID3D11Texture2D,
+    desc: D3D11_TEXTURE2D_DESC,
+    bytes_per_pixel: u32,
+    data: *const u8,
+    row_pitch: u32,
+}
+impl<'a> MappedTexture<'a> {
+    fn map(d3d_context: &'a ID3D11DeviceContext, d3d_texture: &'a ID3D11Texture2D, map_flags: u32) -> anyhow::Result<Self, CaptureError> {
+        // Make sure the surface is a pixel format we support
+        let desc = unsafe {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            d3d_texture.GetDesc(&mut desc);
+
+            desc
+        };
+        let bytes_per_pixel = bytes_per_pixel(desc.Format)
+            .ok_or(CaptureError::UnsupportedPixelFormat(desc.Format))?;
+
+        // TODO: If the texture isn't marked for staging, make a copy
+        if desc.Usage as u32 != D3D11_USAGE_STAGING {
+            return Err(CaptureError::UnsupportedBufferType);
+        }
+        if (desc.CPUAccessFlags & D3D11_CPU_ACCESS_READ) != D3D11_CPU_ACCESS_READ {
+            return Err(CaptureError::DeniedAccessCpuRead);
+        }
+
+        // Map the texture
+        let mapped = unsafe {
+            d3d_context.Map(d3d_texture, 0, D3D11_MAP_READ as i32, map_flags)
+                .map_err(|e| {
+                    #[cfg(feature = "latency")]
+                    if windows::core::HRESULT::from(e.clone()) == DXGI_ERROR_WAS_STILL_DRAWING {
+                        return CaptureError::NoTexture;
+                    }
+                    CaptureError::DirectxError(e)
+                })?
+        };
+
+        Ok(Self{
+            d3d_context,
+            d3d_texture,
+            desc,
+            bytes_per_pixel,
+            data: mapped.pData as *const u8,
+            row_pitch: mapped.RowPitch,
+        })
+    }
+
+    /// Bytes of the pixels in a row, without the padding.
+    fn row_len(&self) -> usize {
+        (self.desc.Width * self.bytes_per_pixel) as usize
+    }
+
+    /// The mapped rows, `row_pitch` apart. the last one without the padding.
+    fn as_slice(&self) -> &[u8] {
+        let len = match self.desc.Height {
+            0 => 0,
+            height => (height as usize - 1) * self.row_pitch as usize + self.row_len(),
+        };
+
+        unsafe {
+            std::slice::from_raw_parts(self.data, len)
+        }
+    }
+}
+impl Drop for MappedTexture<'_> {
+    fn drop(&mut self) {
+        // Unmap the texture
+        unsafe {
+            self.d3d_context.Unmap(self.d3d_texture, 0);
+        }
+    }
+}
+
+/// Turn the pixels of [read_texture] into the BGRA of [RawFrameData].
+fn to_bgra(width: u32, height: u32, format: u32, mut data: Vec<u8>, conversion: TenBitConversion) -> RawFrameData {
+    convert_to_bgra(width * bytes_per_pixel(format).unwrap_or(4), format, &mut data, conversion);
+
+    RawFrameData{
+        width: width as i32,
+        height: height as i32,
+        data,
+        info: FrameInfo::default(),
+        row_pitch: 0,
+    }
+}
+
+/// [to_bgra] in place, of the rows `row_pitch` bytes apart. returns the row pitch of the BGRA.
+fn convert_to_bgra(row_pitch: u32, format: u32, data: &mut Vec<u8>, conversion: TenBitConversion) -> u32 {
+    match format {
+        // RGBA is swapped into BGRA. sRGB formats are the same bytes
+        DXGI_FORMAT_R8G8B8A8_UNORM | DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => {
+            for pixel in data.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        },
+        DXGI_FORMAT_R10G10B10A2_UNORM => {
+            for (i, pixel) in data.chunks_exact_mut(4).enumerate() {
+                let (x, y) = ((i as u32 * 4 % row_pitch) / 4, i as u32 * 4 / row_pitch);
+                let [r, g, b, a] = unpack_r10g10b10a2(pixel);
+                pixel.copy_from_slice(&[
+                    conversion.reduce(b, x, y),
+                    conversion.reduce(g, x, y),
+                    conversion.reduce(r, x, y),
+                    (a * 85) as u8,
+                ]);
+            }
+        },
+        // scRGB clipped to the SDR white, packed into the front half
+        DXGI_FORMAT_R16G16B16A16_FLOAT => {
+            let pixels = data.len() / 8;
+            for i in 0..pixels {
+                let [r, g, b, a] = unpack_r16g16b16a16_float(&data[i * 8..i * 8 + 8]);
+                data[i * 4..i * 4 + 4].copy_from_slice(
+                    &[linear_to_srgb8(b), linear_to_srgb8(g), linear_to_srgb8(r), (a.clamp(0.0, 1.0) * 255.0).round() as u8]
+                );
+            }
+            data.truncate(pixels * 4);
+
+            return row_pitch / 2;
+        },
+        _ => {},
+    }
+
+    row_pitch
+}
+
+
+/// R, G, B, A of a little endian `R16G16B16A16Float` pixel.
+fn unpack_r16g16b16a16_float(pixel: &[u8]) -> [f32; 4] {
+    let channel = |i: usize| f16_to_f32(u16::from_le_bytes([pixel[i * 2], pixel[i * 2 + 1]]));
+
+    [channel(0), channel(1), channel(2), channel(3)]
+}
+
+/// IEEE 754 half precision `bits`.
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as i32;
+    let mantissa = (bits & 0x3FF) as f32;
+
+    sign * match exponent {
+        // subnormal
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Linear `value` clipped to `0.0..=1.0` and encoded in 8 bits sRGB.
+pub(crate) fn linear_to_srgb8(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let encoded = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+
+    (encoded * 255.0).round() as u8
+}
+
+/// R, G, B and the 2 bits A of a little endian `R10G10B10A2` pixel.
+fn unpack_r10g10b10a2(pixel: &[u8]) -> [u32; 4] {
+    let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+
+    [value & 0x3FF, (value >> 10) & 0x3FF, (value >> 20) & 0x3FF, value >> 30]
+}
+
+
+/// `ContentSize` of a frame and the size of its texture. [Read more](Capture::get_content_size)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContentSize {
+    /// size of the captured window or display in the frame.
+    pub width: i32,
+    pub height: i32,
+    /// size of the frame texture, the size of the frame pool.
+    pub texture_width: i32,
+    pub texture_height: i32,
+}
+impl ContentSize {
+    /// The content doesn't fill the texture, or is cut off, as while the window is resized.
+    pub fn is_mismatched(&self) -> bool {
+        self.width != self.texture_width || self.height != self.texture_height
+    }
+
+    /// Area of the texture with the content, from the top left. the rest is garbage while mismatched.
+    pub fn valid_rect(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height).intersect(&Rect::new(0, 0, self.texture_width, self.texture_height))
+    }
+}
+
+/// Layout of a frame handed to [with_mapped_frame](Capture::with_mapped_frame).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameDesc {
+    pub width: u32,
+    pub height: u32,
+    /// bytes from a row to the next, the width with the padding.
+    pub row_pitch: u32,
+    pub bytes_per_pixel: u32,
+    /// `DXGI_FORMAT` of the pixels, as in the texture. not converted to BGRA.
+    pub format: u32,
+    pub info: FrameInfo,
+}
+
+/// When and how a frame was captured, taken with the frame in the frame arrived handler.
+///
+/// read from the same frame as the pixels, unlike [get_frame_time](Capture::get_frame_time) and
+/// [get_content_size](Capture::get_content_size) which may be of a newer frame by the time they're called.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// QPC time when the frame was captured (`SystemRelativeTime`), as [get_frame_time](Capture::get_frame_time).
+    pub time: Option<Duration>,
+    pub content_size: Option<ContentSize>,
+    /// number of the frame, counting up from 1 by each frame taken by the capture. 0 when unknown.
+    pub sequence: u64,
+    /// the [zoom](Capture::set_zoom) or the [output size](Capture::set_output_size) the frame was scaled by.
+    /// `None` when in the size of the whole frame.
+    pub scale: Option<FrameScale>,
+    /// the [region](Capture::set_region) cut out, in the frame after the scale. `None` for the whole frame.
+    pub region: Option<Rect>,
+}
+impl FrameInfo {
+    /// Map `rect` in the pixels of the whole frame to the delivered frame, through the scale and the region.
+    /// rounded outwards, so the mapped rect covers all of `rect`.
+    ///
+    /// for the positions on the screen or the window, offset by the top left of the captured item first.
+    pub fn map_rect(&self, rect: Rect) -> Rect {
+        let (left, top) = self.map(rect.x, rect.y, false);
+        let (right, bottom) = self.map(rect.right(), rect.bottom(), true);
+
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// Map the point (`x`, `y`) in the pixels of the whole frame to the delivered frame. [Read more](Self::map_rect)
+    pub fn map_point(&self, x: i32, y: i32) -> (i32, i32) {
+        self.map(x, y, false)
+    }
+
+    fn map(&self, x: i32, y: i32, round_up: bool) -> (i32, i32) {
+        let (mut x, mut y) = (x, y);
+        if let Some(scale) = &self.scale {
+            let stretch = |value: i32, origin: i32, from: i32, to: u32| {
+                let numerator = (value as i64 - origin as i64) * to as i64;
+                let from = from.max(1) as i64;
+                let value = if round_up { -(-numerator).div_euclid(from) } else { numerator.div_euclid(from) };
+                value.clamp(i32::MIN as i64, i32::MAX as i64) as i32
+            };
+            x = stretch(x, scale.source.x, scale.source.width, scale.width);
+            y = stretch(y, scale.source.y, scale.source.height, scale.height);
+        }
+        if let Some(region) = &self.region {
+            x = x.saturating_sub(region.x);
+            y = y.saturating_sub(region.y);
+        }
+
+        (x, y)
+    }
+}
+
+/// Area of the whole frame stretched to `width` x `height`. [Read more](FrameInfo::scale)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameScale {
+    /// in the pixels of the whole frame.
+    pub source: Rect,
+    pub width: u32,
+    pub height: u32,
+}
+
+
+/// Frames arrived so far, notified by the frame arrived handler.
+#[derive(Debug, Default)]
+struct FrameSignal {
+    count: Mutex<u64>,
+    arrived: Condvar,
+}
+impl FrameSignal {
+    fn notify(&self) {
+        *self.count.lock().unwrap() += 1;
+        self.arrived.notify_all();
+    }
+}
+
+
+/// Where the frames of a [Capture] come from.
+#[derive(Debug)]
+enum Backend {
+    /// the frame pool of `Windows.Graphics.Capture`.
+    GraphicsCapture{
+        frame_pool: Direct3D11CaptureFramePool,
+        session: GraphicsCaptureSession,
+        item: GraphicsCaptureItem,
+        closed_token: windows::Foundation::EventRegistrationToken,
+        _on_frame_arrived: FrameArrivedHandler,
+    },
+    /// copied by GDI on a thread of its own, running while active. [Read more](crate::gdi)
+    #[cfg(feature = "gdi")]
+    Gdi,
+}
+
+
+/// Session and frame pool shared by the clones of a [Capture], or the GDI thread in their place.
+///
+/// closed by [stop](Capture::stop) of any clone, or when the last clone is dropped.
+#[derive(Debug)]
+struct SessionGuard {
+    backend: Backend,
+    /// cleared once closed, seen by all the clones and the GDI thread.
+    active: Arc<AtomicBool>,
+    signal: Arc<FrameSignal>,
+}
+impl SessionGuard {
+    fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    /// The session of `Windows.Graphics.Capture`, `None` while copied by GDI.
+    fn graphics_session(&self) -> Option<&GraphicsCaptureSession> {
+        match &self.backend {
+            Backend::GraphicsCapture{ session, .. } => Some(session),
+            #[cfg(feature = "gdi")]
+            Backend::Gdi => None,
+        }
+    }
+
+    fn release(&self) -> anyhow::Result<(), CaptureError> {
+        if !self.active.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        // the waits of all the clones return NotActive
+        self.signal.notify();
+
+        match &self.backend {
+            Backend::GraphicsCapture{ frame_pool, session, item, closed_token, .. } => {
+                // End the capture, the frame pool even if the session failed
+                let _ = item.RemoveClosed(closed_token);
+                let session = session.Close();
+                let frame_pool = frame_pool.Close();
+                session.and(frame_pool).map_err(|e| CaptureError::DirectxError(e))
+            },
+            // the thread ends before its next frame
+            #[cfg(feature = "gdi")]
+            Backend::Gdi => Ok(()),
+        }
+    }
+}
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        // never panics, maybe while unwinding. use stop to see the failures
+        let _ = self.release();
+    }
+}
+
+
+/// The clones share the session and the frames. the capture ends when any clone is [stopped](Self::stop),
+/// or when the last clone is dropped.
 #[derive(Clone, Debug)]
 pub struct Capture {
     _d3d_device: ID3D11Device,
     d3d_context: ID3D11DeviceContext,
-    frame_pool: Direct3D11CaptureFramePool,
-    session: GraphicsCaptureSession,
-    _on_frame_arrived: FrameArrivedHandler,
+    session: Arc<SessionGuard>,
+    /// the item was closed, for good.
+    closed: Arc<Mutex<bool>>,
     texture: Arc<Mutex<Option<ID3D11Texture2D>>>,
-    active: bool,
+    /// staging textures taken by the readers, not written again until read back.
+    reading: ReadingTextures,
+    /// copy on the GPU for [get_texture](Self::get_texture), `Some` once asked.
+    gpu_texture: Arc<Mutex<Option<Option<ID3D11Texture2D>>>>,
+    signal: Arc<FrameSignal>,
+    info: Arc<Mutex<FrameInfo>>,
+    /// sequence number of the last frame read back.
+    last_read: Arc<Mutex<u64>>,
+    ten_bit: Arc<Mutex<TenBitConversion>>,
+    callbacks: Arc<Mutex<FrameCallbacks>>,
+    zoom: Arc<Mutex<ZoomState>>,
+    region: Arc<Mutex<Option<Rect>>>,
+    history: Arc<Mutex<FrameHistory>>,
+    power: Arc<Mutex<PowerState>>,
+    limiter: Arc<Mutex<FrameLimiter>>,
+    thread: Arc<Mutex<Option<ThreadOptions>>>,
+    paused: Arc<Mutex<bool>>,
+    #[cfg(feature = "interop")]
+    shared: Arc<Mutex<SharedState>>,
+    #[cfg(feature = "latency")]
+    latency: Arc<Mutex<Option<LatencyState>>>,
+    #[cfg(feature = "dedup")]
+    dedup: Arc<Mutex<Option<DedupState>>>,
+    #[cfg(feature = "watermark")]
+    watermark: Arc<Mutex<Option<WatermarkState>>>,
+    /// made on the first NV12 frame.
+    #[cfg(feature = "color")]
+    nv12: Arc<Mutex<Option<ColorConverter>>>,
 }
 impl Capture {
     pub fn new(device: &Device) -> anyhow::Result<Self> {
+        Self::new_with_format(device, DirectXPixelFormat::B8G8R8A8UIntNormalized)
+    }
+
+    /// Capture in `pixel_format`, like `R16G16B16A16Float` for scRGB of HDR displays.
+    ///
+    /// [get_raw_frame](Self::get_raw_frame) supports only the 8 bits BGRA and RGBA formats, `R10G10B10A2UIntNormalized`
+    /// reduced to 8 bits (or kept by [get_raw10_frame](Self::get_raw10_frame)), and `R16G16B16A16Float` clipped
+    /// to SDR (or kept by [get_float_frame](Self::get_float_frame)). convert the others on the GPU,
+    /// as by [ColorConverter](crate::ColorConverter).
+    ///
+    /// the other options are set by [builder](Self::builder).
+    pub fn new_with_format(device: &Device, pixel_format: DirectXPixelFormat) -> anyhow::Result<Self> {
+        Self::builder(device).pixel_format(pixel_format).build()
+    }
+
+    fn start(builder: &CaptureBuilder) -> anyhow::Result<Self> {
+        let device = builder.device;
         let d3d_context = Device::get_immediate_context(&device.d3d_device)?;
-        let item_size = device.item.Size()?;
 
         // Initialize the capture
-        let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
-            &device.device,
-            DirectXPixelFormat::B8G8R8A8UIntNormalized,
-            1,
-            item_size,
-        )?;
-        let session = frame_pool.CreateCaptureSession(&device.item)?;
+        let graphics_capture = device.item.Size().and_then(|item_size| {
+            let frame_pool = Direct3D11CaptureFramePool::CreateFreeThreaded(
+                &device.device,
+                builder.pixel_format,
+                builder.buffer_count,
+                item_size,
+            )?;
+            let session = frame_pool.CreateCaptureSession(&device.item)?;
+
+            Ok((frame_pool, session, item_size))
+        });
 
         // to thread safety
         let texture = Arc::new(Mutex::new(None));
+        let reading = ReadingTextures::default();
+        let gpu_texture = Arc::new(Mutex::new(None));
+        let signal = Arc::new(FrameSignal::default());
+        let info = Arc::new(Mutex::new(FrameInfo::default()));
+        let ten_bit = Arc::new(Mutex::new(TenBitConversion::default()));
+        let callbacks = Arc::new(Mutex::new(FrameCallbacks::default()));
+        let zoom = Arc::new(Mutex::new(ZoomState::new(builder.output_size)));
+        let region = Arc::new(Mutex::new(builder.region));
+        let history = Arc::new(Mutex::new(FrameHistory::new(builder.history)));
+        let power = Arc::new(Mutex::new(PowerState::default()));
+        let limiter = Arc::new(Mutex::new(FrameLimiter::new(builder.max_fps)));
+        let thread = Arc::new(Mutex::new(None::<ThreadOptions>));
+        let paused = Arc::new(Mutex::new(false));
+        let closed = Arc::new(Mutex::new(false));
+        let active = Arc::new(AtomicBool::new(true));
+        #[cfg(feature = "interop")]
+        let shared = Arc::new(Mutex::new(SharedState::default()));
+        #[cfg(feature = "latency")]
+        let latency = Arc::new(Mutex::new(None::<LatencyState>));
+        #[cfg(feature = "dedup")]
+        let dedup = Arc::new(Mutex::new(None::<DedupState>));
+        #[cfg(feature = "watermark")]
+        let watermark = Arc::new(Mutex::new(None::<WatermarkState>));
 
-        let on_frame_arrived = FrameArrivedHandler::new({
+        // a frame of the session or of GDI, not paused, saving power nor over the max fps.
+        // the size is of the captured window or display
+        let deliver = {
             let d3d_device = device.d3d_device.clone();
             let d3d_context = d3d_context.clone();
             let texture = texture.clone();
-            
-            move |frame_pool, _| {
-                let frame = frame_pool.as_ref().unwrap().TryGetNextFrame()?;
-                let surface = frame.Surface()?;
+            let reading = reading.clone();
+            let gpu_texture = gpu_texture.clone();
+            let signal = signal.clone();
+            let info = info.clone();
+            let ten_bit = ten_bit.clone();
+            let callbacks = callbacks.clone();
+            let zoom = zoom.clone();
+            let region = region.clone();
+            let history = history.clone();
+            let mut staging = StagingRing::default();
+            #[cfg(feature = "interop")]
+            let shared = shared.clone();
+            #[cfg(feature = "latency")]
+            let latency = latency.clone();
+            #[cfg(feature = "dedup")]
+            let dedup = dedup.clone();
+            #[cfg(feature = "watermark")]
+            let watermark = watermark.clone();
 
-                let frame_texture = Device::from_direct3d_surface(&surface)?;
+            move |frame_texture: ID3D11Texture2D, time: Duration, (content_width, content_height): (i32, i32)| -> windows::core::Result<()> {
+                // cut out and scaled on the GPU while zoomed
+                let (frame_texture, scale) = zoom.lock().unwrap().apply(&d3d_device, &d3d_context, frame_texture);
 
                 // Make a copy of the texture
                 let mut desc = D3D11_TEXTURE2D_DESC::default();
                 unsafe {
                     frame_texture.GetDesc(&mut desc);
                 }
+                // only the region is copied and read back
+                let region = region.lock().unwrap().and_then(|region| region::region_box(region, desc.Width, desc.Height));
+                if let Some(region) = &region {
+                    desc.Width = region.right - region.left;
+                    desc.Height = region.bottom - region.top;
+                }
+                // the same frame as the last one is dropped before any copy
+                #[cfg(feature = "dedup")]
+                if let Some(dedup) = dedup.lock().unwrap().as_mut() {
+                    if dedup.is_duplicate(&d3d_device, &d3d_context, &frame_texture, region.as_ref()) {
+                        return Ok(());
+                    }
+                }
+                // blended before any copy, so all the readers get it
+                #[cfg(feature = "watermark")]
+                let frame_texture = match watermark.lock().unwrap().as_mut() {
+                    Some(watermark) => watermark.composite(&d3d_device, &d3d_context, frame_texture, region.as_ref())?,
+                    None => frame_texture,
+                };
+                let copy_frame = |copy_texture: &ID3D11Texture2D| unsafe {
+                    match &region {
+                        Some(region) => d3d_context.CopySubresourceRegion(copy_texture, 0, 0, 0, 0, &frame_texture, 0, region),
+                        None => d3d_context.CopyResource(copy_texture, &frame_texture),
+                    }
+                };
+                // kept on the GPU too, only once asked
+                if let Some(gpu_texture) = gpu_texture.lock().unwrap().as_mut() {
+                    let mut gpu_desc = desc;
+                    gpu_desc.Usage = D3D11_USAGE_DEFAULT as i32;
+                    gpu_desc.BindFlags = D3D11_BIND_SHADER_RESOURCE;
+                    gpu_desc.CPUAccessFlags = 0;
+                    gpu_desc.MiscFlags = 0;
+                    *gpu_texture = unsafe {
+                        let copy_texture = d3d_device.CreateTexture2D( &gpu_desc, std::ptr::null() )?;
+                        copy_frame(&copy_texture);
+
+                        Some(copy_texture)
+                    };
+                }
                 // Make this a staging texture
                 desc.Usage = D3D11_USAGE_STAGING as i32;
                 desc.BindFlags = 0;
                 desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
                 desc.MiscFlags = 0;
-                let copy_texture = unsafe {
-                    let copy_texture = d3d_device.CreateTexture2D( &desc, std::ptr::null() )?;
-                    d3d_context.CopyResource(&copy_texture, &frame_texture);
-
-                    copy_texture
+                let copy_texture = if history.lock().unwrap().is_enabled() {
+                    // the kept frames are never written again
+                    staging.clear();
+                    unsafe { d3d_device.CreateTexture2D( &desc, std::ptr::null() )? }
+                } else {
+                    staging.next(&d3d_device, &desc, &reading)?
                 };
+                copy_frame(&copy_texture);
+                // signaled as soon as the copy is done
+                #[cfg(feature = "latency")]
+                if let Some(latency) = latency.lock().unwrap().as_mut() {
+                    latency.signal(&d3d_context)?;
+                }
 
-                *texture.lock().unwrap() = Some(copy_texture);
+                let content_size = match &region {
+                    Some(region) => region::region_content_size(content_width, content_height, region),
+                    None => ContentSize{
+                        width: content_width,
+                        height: content_height,
+                        texture_width: desc.Width as i32,
+                        texture_height: desc.Height as i32,
+                    },
+                };
+                let frame_info = {
+                    // replaced together, so a frame is always read with its own info
+                    let mut texture = texture.lock().unwrap();
+                    let mut info = info.lock().unwrap();
+                    *info = FrameInfo{
+                        time: Some(time),
+                        content_size: Some(content_size),
+                        sequence: info.sequence + 1,
+                        scale,
+                        region: region.as_ref().map(|region| Rect::new(
+                            region.left as i32,
+                            region.top as i32,
+                            (region.right - region.left) as i32,
+                            (region.bottom - region.top) as i32,
+                        )),
+                    };
+                    *texture = Some(copy_texture.clone());
+                    *info
+                };
+                history.lock().unwrap().push(&copy_texture, frame_info);
+                // for the other devices, only while shared
+                #[cfg(feature = "interop")]
+                shared.lock().unwrap().publish(&d3d_device, &d3d_context, &frame_texture)?;
+                // read back only for the callbacks
+                let mut callbacks = callbacks.lock().unwrap();
+                if !callbacks.is_empty() {
+                    if let Ok((width, height, format, data)) = read_texture(&d3d_context, &copy_texture, 0) {
+                        let mut raw = to_bgra(width, height, format, data, *ten_bit.lock().unwrap());
+                        raw.info = frame_info;
+                        callbacks.call(raw);
+                    }
+                }
+                // wake up the waits
+                signal.notify();
 
                 Ok(())
             }
-        });
+        };
+
+        let backend = match graphics_capture {
+            Ok((frame_pool, session, item_size)) => {
+                // left to the system unless set
+                if let Some(cursor) = builder.cursor {
+                    session.SetIsCursorCaptureEnabled(cursor)?;
+                }
+                if let Some(border) = builder.border {
+                    border::apply_border(&session, border)?;
+                }
+
+                let on_frame_arrived = FrameArrivedHandler::new({
+                    let direct3d_device = device.device.clone();
+                    let (pixel_format, buffer_count, resize_with_content) = (builder.pixel_format, builder.buffer_count, builder.resize_with_content);
+                    let mut pool_size = item_size;
+                    let power = power.clone();
+                    let limiter = limiter.clone();
+                    let thread = thread.clone();
+                    let paused = paused.clone();
+                    let mut deliver = deliver;
+
+                    move |frame_pool, _| {
+                        // a thread of the pool, restored for the others on return
+                        let options = *thread.lock().unwrap();
+                        let _thread = options.and_then(|options| options.apply().ok());
+
+                        // taken out even while paused, to free the buffers of the pool. with more buffers, the frames
+                        // arrived while the last one was copied are waiting too, so only the newest is copied
+                        let frame_pool = frame_pool.as_ref().unwrap();
+                        let mut frame = frame_pool.TryGetNextFrame()?;
+                        while let Ok(next) = frame_pool.TryGetNextFrame() {
+                            let _ = frame.Close();
+                            frame = next;
+                        }
+                        if *paused.lock().unwrap() {
+                            return Ok(());
+                        }
+                        // the next frames come in the new size
+                        let size = frame.ContentSize()?;
+                        if resize_with_content && 0 < size.Width && 0 < size.Height
+                            && (size.Width != pool_size.Width || size.Height != pool_size.Height) {
+                            frame_pool.Recreate(&direct3d_device, pixel_format, buffer_count, &size)?;
+                            pool_size = size;
+                        }
+                        // 100ns units on the QPC clock
+                        let system_relative_time = frame.SystemRelativeTime()?.Duration.max(0) as u64;
+                        let time = Duration::from_nanos(system_relative_time * 100);
+                        // dropped before any copy while saving power
+                        if !power.lock().unwrap().should_copy(time) {
+                            return Ok(());
+                        }
+                        // and over the max fps
+                        if !limiter.lock().unwrap().should_copy(time) {
+                            return Ok(());
+                        }
+                        let surface = frame.Surface()?;
+
+                        deliver(Device::from_direct3d_surface(&surface)?, time, (size.Width, size.Height))
+                    }
+                });
+
+                // the window was closed or the display detached
+                let closed_token = device.item.Closed(ItemClosedHandler::new({
+                    let closed = closed.clone();
+                    let signal = signal.clone();
 
-        // Start the capture
-        frame_pool.FrameArrived(on_frame_arrived.clone())?;
-        session.StartCapture()?;
+                    move |_, _| {
+                        *closed.lock().unwrap() = true;
+                        // the waits return ItemClosed
+                        signal.notify();
+                        Ok(())
+                    }
+                }))?;
+
+                // Start the capture
+                frame_pool.FrameArrived(on_frame_arrived.clone())?;
+                session.StartCapture()?;
+
+                Backend::GraphicsCapture{
+                    frame_pool,
+                    session,
+                    item: device.item.clone(),
+                    closed_token,
+                    _on_frame_arrived: on_frame_arrived,
+                }
+            },
+            // copied by GDI on the Windows without the frame pool
+            #[cfg(feature = "gdi")]
+            Err(e) if !crate::gdi::is_graphics_capture_supported() => match device.gdi_target {
+                Some(target) => {
+                    // both of the failures, when GDI can't copy it either
+                    if let Err(gdi) = crate::gdi::probe_target(target) {
+                        return Err(CaptureError::SourceError(
+                            format!("Windows.Graphics.Capture failed: {}, GDI failed: {}", e, gdi)
+                        ).into());
+                    }
+
+                    std::thread::spawn({
+                        let d3d_device = device.d3d_device.clone();
+                        let active = active.clone();
+                        let closed = closed.clone();
+                        let signal = signal.clone();
+                        let power = power.clone();
+                        let limiter = limiter.clone();
+                        let thread = thread.clone();
+                        let paused = paused.clone();
+                        let mut deliver = deliver;
+
+                        move || {
+                            while active.load(Ordering::Acquire) {
+                                let next = Instant::now() + crate::gdi::FRAME_INTERVAL;
+                                // changed while capturing too, as for the frame arrived handler
+                                let options = *thread.lock().unwrap();
+                                let _thread = options.and_then(|options| options.apply().ok());
+
+                                // checked before copying, GDI is the slow part
+                                let time = crate::clock::CaptureClock::qpc_now();
+                                if !*paused.lock().unwrap()
+                                    && power.lock().unwrap().should_copy(time)
+                                    && limiter.lock().unwrap().should_copy(time) {
+                                    match crate::gdi::grab(&d3d_device, target) {
+                                        Ok((frame_texture, size)) => {
+                                            let _ = deliver(frame_texture, time, size);
+                                        },
+                                        Err(CaptureError::ItemClosed) => {
+                                            *closed.lock().unwrap() = true;
+                                            // the waits return ItemClosed
+                                            signal.notify();
+                                            break;
+                                        },
+                                        // minimized, or failed only this time
+                                        Err(_) => {},
+                                    }
+                                }
+
+                                std::thread::sleep(next.saturating_duration_since(Instant::now()));
+                            }
+                        }
+                    });
+
+                    Backend::Gdi
+                },
+                None => return Err(e.into()),
+            },
+            Err(e) => return Err(e.into()),
+        };
 
         Ok(Self {
             _d3d_device: device.d3d_device.clone(),
             d3d_context,
-            frame_pool,
-            session,
-            _on_frame_arrived: on_frame_arrived,
+            session: Arc::new(SessionGuard{
+                backend,
+                active,
+                signal: signal.clone(),
+            }),
+            closed,
             texture,
-            active: true,
+            reading,
+            gpu_texture,
+            signal,
+            info,
+            last_read: Arc::new(Mutex::new(0)),
+            ten_bit,
+            callbacks,
+            zoom,
+            region,
+            history,
+            power,
+            limiter,
+            thread,
+            paused,
+            #[cfg(feature = "interop")]
+            shared,
+            #[cfg(feature = "latency")]
+            latency,
+            #[cfg(feature = "dedup")]
+            dedup,
+            #[cfg(feature = "watermark")]
+            watermark,
+            #[cfg(feature = "color")]
+            nv12: Arc::new(Mutex::new(None)),
         })
     }
 
-    fn release(&mut self) -> anyhow::Result<()> {
-        self.active = false;
-    
-        // End the capture
-        self.session.Close()?;
-        self.frame_pool.Close()?;
+    /// End the capture, reporting the failures to close the session and the frame pool.
+    ///
+    /// ends it for all the clones, which return [NotActive](CaptureError::NotActive) from then on.
+    /// dropping the last clone does the same and ignores the failures.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let raw = capture.wait_raw_frame().unwrap();
+    /// capture.stop().unwrap();
+    /// ```
+    /// ## Errors
+    /// * [DirectxError](CaptureError::DirectxError): failed to close the session or the frame pool.
+    pub fn stop(self) -> anyhow::Result<(), CaptureError> {
+        self.session.release()
+    }
 
-        Ok(())
+    /// Not stopped, by this or any clone.
+    fn is_running(&self) -> bool {
+        self.session.is_active()
+    }
+
+    /// Copied by GDI, as on the Windows without `Windows.Graphics.Capture`. [Read more](crate::gdi)
+    ///
+    /// Required features: *`"gdi"`*
+    #[cfg(feature = "gdi")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "gdi")))]
+    pub fn is_gdi(&self) -> bool {
+        self.session.graphics_session().is_none()
     }
 
-    fn take(&self) -> anyhow::Result<IDirect3DSurface, CaptureError> {
-        if !self.active {
+    /// The current frame and its info. the frame arrived handler doesn't write the texture until the guard is dropped,
+    /// so keep it until the texture is read back.
+    fn take(&self) -> anyhow::Result<(IDirect3DSurface, FrameInfo, ReadingGuard), CaptureError> {
+        if !self.is_running() {
             return Err(CaptureError::NotActive);
         }
-        if self.texture.lock().unwrap().is_none() {
-            return Err(CaptureError::NoTexture);
+        if self.is_closed() {
+            return Err(CaptureError::ItemClosed);
         }
+        let (texture, guard) = self.reading.take(&self.texture).ok_or(CaptureError::NoTexture)?;
 
         // Wait for our texture to come
-        let surface = Device::to_direct3d_surface(
-            self.texture.lock().unwrap().as_ref().unwrap()
-        ).map_err(|e| CaptureError::DirectxError(e))?;
+        let surface = Device::to_direct3d_surface(&texture).map_err(|e| CaptureError::DirectxError(e))?;
+
+        Ok((surface, *self.info.lock().unwrap(), guard))
+    }
+
+    /// Copy the pixels of a staging surface, without the row padding.
+    fn read_surface(&self, surface: &IDirect3DSurface) -> anyhow::Result<(u32, u32, u32, Vec<u8>), CaptureError> {
+        let mut data = Vec::new();
+        let (width, height, format) = self.read_surface_into(surface, &mut data)?;
+
+        Ok((width, height, format, data))
+    }
+
+    /// [read_surface](Self::read_surface) into `data`, reusing its allocation.
+    fn read_surface_into(&self, surface: &IDirect3DSurface, data: &mut Vec<u8>) -> anyhow::Result<(u32, u32, u32), CaptureError> {
+        let d3d_texture = Device::from_direct3d_surface(surface).map_err(|e| CaptureError::DirectxError(e))?;
+
+        read_texture_into(&self.d3d_context, &d3d_texture, self.map_flags(), data)
+    }
+
+    /// Flags to map the staging textures with.
+    fn map_flags(&self) -> u32 {
+        // without blocking on the copy in the low latency mode
+        #[cfg(feature = "latency")]
+        let map_flags = if self.is_low_latency() { D3D11_MAP_FLAG_DO_NOT_WAIT as u32 } else { 0 };
+        #[cfg(not(feature = "latency"))]
+        let map_flags = 0;
 
-        Ok(surface)
+        map_flags
     }
 
     /// rap surface to [RawFrameData]
     fn surface_to_data(&self, surface: &IDirect3DSurface) -> anyhow::Result<RawFrameData, CaptureError> {
-        let d3d_texture = Device::from_direct3d_surface(surface).map_err(|e| CaptureError::DirectxError(e))?;
+        let (width, height, format, data) = self.read_surface(surface)?;
 
-        // Make sure the surface is a pixel format we support
-        let desc = unsafe {
-            let mut desc = D3D11_TEXTURE2D_DESC::default();
-            d3d_texture.GetDesc(&mut desc);
+        Ok(to_bgra(width, height, format, data, *self.ten_bit.lock().unwrap()))
+    }
 
-            desc
-        };
-        let width = desc.Width;
-        let height = desc.Height;
-        let bytes_per_pixel = match desc.Format {
-            DXGI_FORMAT_B8G8R8A8_UNORM => 4,
-            _ => return Err(CaptureError::UnsupportedPixelFormat(desc.Format)),
-        };
+    /// Return rapped current frame with [RawFrameData]
+    ///
+    /// the [info](RawFrameData::info) is of the same frame, for the time and the sequence number.
+    pub fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let (surface, info, _reading) = self.take()?;
 
-        // TODO: If the texture isn't marked for staging, make a copy
-        let d3d_texture = if desc.Usage as u32 == D3D11_USAGE_STAGING {
-            if (desc.CPUAccessFlags & D3D11_CPU_ACCESS_READ) == D3D11_CPU_ACCESS_READ {
-                d3d_texture
-            } else {
-                return Err(CaptureError::DeniedAccessCpuRead);
-            }
-        } else {
-            return Err(CaptureError::UnsupportedBufferType);
+        let mut raw = self.surface_to_data(&surface)?;
+        raw.info = info;
+        *self.last_read.lock().unwrap() = info.sequence;
+        Ok(raw)
+    }
+
+    /// [get_raw_frame](Self::get_raw_frame) into `raw`, reusing the allocation of its data instead of a new one per frame.
+    ///
+    /// the data grows only when a frame is larger than any before. `raw` is left as it was on errors.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let mut raw = dxcapture::RawFrameData::default();
+    /// loop {
+    ///     capture.wait_raw_frame_into(&mut raw).unwrap();
+    ///     println!("frame {} in {}x{}", raw.info.sequence, raw.width, raw.height);
+    /// }
+    /// ```
+    pub fn get_raw_frame_into(&self, raw: &mut RawFrameData) -> anyhow::Result<(), CaptureError> {
+        let (surface, info, _reading) = self.take()?;
+
+        let mut data = std::mem::take(&mut raw.data);
+        let (width, height, format) = match self.read_surface_into(&surface, &mut data) {
+            Ok(read) => read,
+            Err(e) => {
+                raw.data = data;
+                return Err(e);
+            },
         };
+        convert_to_bgra(width * bytes_per_pixel(format).unwrap_or(4), format, &mut data, *self.ten_bit.lock().unwrap());
 
-        // Map the texture
-        let mapped = unsafe {
-            self.d3d_context.Map(&d3d_texture, 0, D3D11_MAP_READ as i32, 0)
-                .map_err(|e| CaptureError::DirectxError(e))?
+        *raw = RawFrameData{
+            width: width as i32,
+            height: height as i32,
+            data,
+            info,
+            row_pitch: 0,
         };
+        *self.last_read.lock().unwrap() = info.sequence;
+        Ok(())
+    }
+
+    /// [get_raw_frame](Self::get_raw_frame) keeping the rows [row_pitch](RawFrameData::row_pitch) apart as mapped,
+    /// so the whole frame is read back in a single copy without repacking the rows.
+    ///
+    /// for the APIs taking a stride, as `Mat` with a step or the Media Foundation buffers. the functions of this crate
+    /// taking a [RawFrameData] follow the [stride](RawFrameData::stride), or pack the rows where they need them packed.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let raw = capture.wait_raw_frame_strided().unwrap();
+    /// let top_left_of_second_row = &raw.data[raw.stride()..raw.stride() + 4];
+    /// ```
+    pub fn get_raw_frame_strided(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let (surface, info, _reading) = self.take()?;
+        let d3d_texture = Device::from_direct3d_surface(&surface).map_err(|e| CaptureError::DirectxError(e))?;
+
+        let mut data = Vec::new();
+        let (width, height, format, row_pitch) = read_texture_strided_into(&self.d3d_context, &d3d_texture, self.map_flags(), &mut data)?;
+        let row_pitch = convert_to_bgra(row_pitch, format, &mut data, *self.ten_bit.lock().unwrap());
+
+        *self.last_read.lock().unwrap() = info.sequence;
+        Ok(RawFrameData{
+            width: width as i32,
+            height: height as i32,
+            data,
+            info,
+            row_pitch: row_pitch as usize,
+        })
+    }
+
+    /// [get_raw_frame_strided](Self::get_raw_frame_strided). with throught NoTexture
+    pub fn wait_raw_frame_strided(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        self.wait_frame_with(None, Self::get_raw_frame_strided)
+    }
+
+    /// Call `f` with the pixels of the current frame right in the mapped staging texture, without copying them out.
+    ///
+    /// for the consumers taking strided input, like the encoders. the rows are [row_pitch](FrameDesc::row_pitch)
+    /// apart and in the [format](FrameDesc::format) of the capture, RGBA or 10 bits as they are. the texture is
+    /// unmapped when `f` returns, so don't keep the slice. the frame arrived handler doesn't write the texture
+    /// while `f` runs, but copies the frames meanwhile into new textures once the others are taken too, so keep `f` short.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let sum = capture.wait_mapped_frame(|data, desc| {
+    ///     (0..desc.height as usize)
+    ///         .map(|y| data[y * desc.row_pitch as usize] as u64)
+    ///         .sum::<u64>()
+    /// }).unwrap();
+    /// ```
+    pub fn with_mapped_frame<T, F>(&self, f: F) -> anyhow::Result<T, CaptureError>
+    where
+        F: FnOnce(&[u8], &FrameDesc) -> T,
+    {
+        self.map_frame(&mut Some(f))
+    }
+
+    /// [with_mapped_frame](Self::with_mapped_frame). with throught NoTexture
+    pub fn wait_mapped_frame<T, F>(&self, f: F) -> anyhow::Result<T, CaptureError>
+    where
+        F: FnOnce(&[u8], &FrameDesc) -> T,
+    {
+        let mut f = Some(f);
+        self.wait_frame_with(None, |capture| capture.map_frame(&mut f))
+    }
 
-        // Get a slice of bytes
-        let slice: &[u8] = unsafe {
-            std::slice::from_raw_parts(
-                mapped.pData as *const _,
-                (height * mapped.RowPitch) as usize,
-            )
+    /// Map the current frame and call `f`, taken out only once mapped.
+    fn map_frame<T, F>(&self, f: &mut Option<F>) -> anyhow::Result<T, CaptureError>
+    where
+        F: FnOnce(&[u8], &FrameDesc) -> T,
+    {
+        let (surface, info, _reading) = self.take()?;
+        let d3d_texture = Device::from_direct3d_surface(&surface).map_err(|e| CaptureError::DirectxError(e))?;
+
+        let mapped = MappedTexture::map(&self.d3d_context, &d3d_texture, self.map_flags())?;
+        let desc = FrameDesc{
+            width: mapped.desc.Width,
+            height: mapped.desc.Height,
+            row_pitch: mapped.row_pitch,
+            bytes_per_pixel: mapped.bytes_per_pixel,
+            format: mapped.desc.Format,
+            info,
         };
+        let f = f.take().ok_or(CaptureError::NotActive)?;
+        let result = f(mapped.as_slice(), &desc);
+        drop(mapped);
+
+        *self.last_read.lock().unwrap() = info.sequence;
+        Ok(result)
+    }
 
-        // Make a copy of the data
-        let mut data = vec![0u8; ((width * height) * bytes_per_pixel) as usize];
-        for row in 0..height {
-            let data_begin = (row * (width * bytes_per_pixel)) as usize;
-            let data_end = ((row + 1) * (width * bytes_per_pixel)) as usize;
-            let slice_begin = (row * mapped.RowPitch) as usize;
-            let slice_end = slice_begin + (width * bytes_per_pixel) as usize;
-            data[data_begin..data_end].copy_from_slice(&slice[slice_begin..slice_end]);
+    /// [get_raw_frame_into](Self::get_raw_frame_into). with throught NoTexture
+    pub fn wait_raw_frame_into(&self, raw: &mut RawFrameData) -> anyhow::Result<(), CaptureError> {
+        self.wait_frame_with(None, |capture| capture.get_raw_frame_into(raw))
+    }
+
+    /// [get_raw_frame](Self::get_raw_frame) only when a new frame arrived since the last frame read back,
+    /// else `Ok(None)` without reading the texture.
+    ///
+    /// the frames read by the others (`get_img_frame`, `wait_raw_frame`, ...) count as read, also on the clones.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// loop {
+    ///     match capture.get_raw_frame_if_new() {
+    ///         Ok(Some(raw)) => println!("frame {}", raw.info.sequence),
+    ///         Ok(None) | Err(dxcapture::CaptureError::NoTexture) => std::thread::sleep(std::time::Duration::from_millis(1)),
+    ///         Err(e) => panic!("{}", e),
+    ///     }
+    /// }
+    /// ```
+    pub fn get_raw_frame_if_new(&self) -> anyhow::Result<Option<RawFrameData>, CaptureError> {
+        if self.is_running() && !self.is_closed() && self.info.lock().unwrap().sequence == *self.last_read.lock().unwrap() {
+            return Ok(None);
         }
 
-        // Unmap the texture
-        unsafe {
-            self.d3d_context.Unmap(&d3d_texture, 0);
+        self.get_raw_frame().map(Some)
+    }
+
+    /// The current frame as a texture on the GPU, for a D3D11 pipeline of the same device without reading it back.
+    ///
+    /// a `D3D11_USAGE_DEFAULT` texture bound as a shader resource, copied from the frame pool on the GPU from the first call,
+    /// so the first calls return NoTexture until a frame arrives. each frame gets a new texture, so a returned one
+    /// is never written again. use it on [d3d_device](crate::Device::d3d_device) of the capture device.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let texture = capture.wait_texture().unwrap();
+    /// // device.d3d_device.CreateShaderResourceView(&texture, ...)
+    /// ```
+    pub fn get_texture(&self) -> anyhow::Result<ID3D11Texture2D, CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+        if self.is_closed() {
+            return Err(CaptureError::ItemClosed);
         }
 
-        Ok(RawFrameData{
+        self.gpu_texture.lock().unwrap()
+            .get_or_insert(None)
+            .clone()
+            .ok_or(CaptureError::NoTexture)
+    }
+
+    /// The current frame as a texture on the GPU. with throught NoTexture
+    pub fn wait_texture(&self) -> anyhow::Result<ID3D11Texture2D, CaptureError> {
+        self.wait_frame_with(None, Self::get_texture)
+    }
+
+    /// Return current frame of `R10G10B10A2UIntNormalized` kept in 10 bits, for 10 bits SDR and HDR10 displays.
+    ///
+    /// capture by [new_with_format](Self::new_with_format) in `R10G10B10A2UIntNormalized`.
+    /// [get_raw_frame](Self::get_raw_frame) returns the same frames in 8 bits as [set_ten_bit_conversion](Self::set_ten_bit_conversion).
+    /// ## Errors
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): the frames are not `R10G10B10A2`.
+    /// # Examples
+    /// ```
+    /// use windows::Graphics::DirectX::DirectXPixelFormat;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new_with_format(&device, DirectXPixelFormat::R10G10B10A2UIntNormalized).unwrap();
+    ///
+    /// let raw10 = capture.wait_raw10_frame().unwrap();
+    /// let max = raw10.data.chunks_exact(4).map(|pixel| pixel[0].max(pixel[1]).max(pixel[2])).max();
+    /// ```
+    pub fn get_raw10_frame(&self) -> anyhow::Result<Raw10FrameData, CaptureError> {
+        let (surface, info, _reading) = self.take()?;
+
+        let (width, height, format, data) = self.read_surface(&surface)?;
+        if format != DXGI_FORMAT_R10G10B10A2_UNORM {
+            return Err(CaptureError::UnsupportedPixelFormat(format));
+        }
+
+        let data = data.chunks_exact(4)
+            .flat_map(|pixel| {
+                let [r, g, b, a] = unpack_r10g10b10a2(pixel);
+                [r as u16, g as u16, b as u16, (a * 341) as u16]
+            })
+            .collect();
+
+        Ok(Raw10FrameData{
             width: width as i32,
             height: height as i32,
-            data
+            data,
+            info,
         })
     }
 
-    /// Return rapped current frame with [RawFrameData]
-    pub fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
-        let surface = self.take()?;
+    /// Return current frame of `R10G10B10A2` kept in 10 bits. with throught NoTexture
+    pub fn wait_raw10_frame(&self) -> anyhow::Result<Raw10FrameData, CaptureError> {
+        self.wait_frame_with(None, Self::get_raw10_frame)
+    }
 
-        self.surface_to_data(&surface)
+    /// Return current frame of `R16G16B16A16Float` kept in float, for HDR displays.
+    ///
+    /// capture by [new_with_format](Self::new_with_format) in `R16G16B16A16Float`. [get_raw_frame](Self::get_raw_frame)
+    /// returns the same frames in 8 bits sRGB, clipped at the SDR white.
+    /// ## Errors
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): the frames are not `R16G16B16A16Float`.
+    /// # Examples
+    /// ```
+    /// use windows::Graphics::DirectX::DirectXPixelFormat;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new_with_format(&device, DirectXPixelFormat::R16G16B16A16Float).unwrap();
+    ///
+    /// let hdr = capture.wait_float_frame().unwrap();
+    /// // scRGB 1.0 is 80 nits
+    /// let peak_nits = hdr.data.chunks_exact(4).map(|pixel| pixel[0].max(pixel[1]).max(pixel[2])).fold(0.0, f32::max) * 80.0;
+    /// ```
+    pub fn get_float_frame(&self) -> anyhow::Result<FloatFrameData, CaptureError> {
+        let (surface, info, _reading) = self.take()?;
+
+        let (width, height, format, data) = self.read_surface(&surface)?;
+        if format != DXGI_FORMAT_R16G16B16A16_FLOAT {
+            return Err(CaptureError::UnsupportedPixelFormat(format));
+        }
+
+        Ok(FloatFrameData{
+            width: width as i32,
+            height: height as i32,
+            data: data.chunks_exact(8).flat_map(unpack_r16g16b16a16_float).collect(),
+            info,
+        })
+    }
+
+    /// Return current frame of `R16G16B16A16Float` kept in float. with throught NoTexture
+    pub fn wait_float_frame(&self) -> anyhow::Result<FloatFrameData, CaptureError> {
+        self.wait_frame_with(None, Self::get_float_frame)
+    }
+
+    /// How `R10G10B10A2` frames are reduced to 8 bits by [get_raw_frame](Self::get_raw_frame) and the frames made from it.
+    /// default is [Round](TenBitConversion::Round).
+    /// # Examples
+    /// ```
+    /// use windows::Graphics::DirectX::DirectXPixelFormat;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new_with_format(&device, DirectXPixelFormat::R10G10B10A2UIntNormalized).unwrap();
+    ///
+    /// capture.set_ten_bit_conversion(dxcapture::TenBitConversion::Dither);
+    /// let raw = capture.wait_raw_frame().unwrap();
+    /// ```
+    pub fn set_ten_bit_conversion(&self, conversion: TenBitConversion) {
+        *self.ten_bit.lock().unwrap() = conversion;
+    }
+
+    /// QPC time when the current frame was captured (`SystemRelativeTime`). `None` until the first frame.
+    ///
+    /// use [CaptureClock](crate::CaptureClock) to line it up with audio.
+    pub fn get_frame_time(&self) -> Option<Duration> {
+        self.info.lock().unwrap().time
+    }
+
+    /// Priority and affinity of the frame arrived handler, which copies each frame out of the frame pool.
+    /// `None` leaves the threads as they are.
+    ///
+    /// the handler runs on the threads of the system thread pool, so the options are applied on each frame
+    /// and restored before the thread goes back to the pool. failures to apply are ignored.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Game".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// capture.set_thread_options(Some(dxcapture::ThreadOptions::capture()));
+    /// ```
+    pub fn set_thread_options(&self, options: Option<ThreadOptions>) {
+        *self.thread.lock().unwrap() = options;
+    }
+
+    pub fn thread_options(&self) -> Option<ThreadOptions> {
+        *self.thread.lock().unwrap()
+    }
+
+    /// Stop taking the frames, keeping the session and the frame pool for [resume](Self::resume).
+    ///
+    /// a session can't be started again once closed, so it keeps running and the arriving frames are dropped
+    /// before any copy. the last frame stays readable, and the waits time out or block until resumed.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// capture.pause();
+    /// assert!(!capture.is_active());
+    /// capture.resume();
+    /// ```
+    pub fn pause(&self) {
+        *self.paused.lock().unwrap() = true;
+    }
+
+    /// Take the frames again after [pause](Self::pause).
+    pub fn resume(&self) {
+        *self.paused.lock().unwrap() = false;
+    }
+
+    /// Taking the frames, neither paused, stopped nor [closed](Self::is_closed).
+    pub fn is_active(&self) -> bool {
+        self.is_running() && !self.is_closed() && !*self.paused.lock().unwrap()
+    }
+
+    /// The captured window was closed, or the display detached. the capture ends for good, and the frame getters
+    /// return [ItemClosed](CaptureError::ItemClosed) instead of the last frame.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Untitled - Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// match capture.wait_raw_frame() {
+    ///     Err(dxcapture::CaptureError::ItemClosed) => println!("notepad is gone"),
+    ///     result => println!("{:?}", result.map(|raw| raw.info)),
+    /// }
+    /// ```
+    pub fn is_closed(&self) -> bool {
+        *self.closed.lock().unwrap()
+    }
+
+    /// `ContentSize` of the current frame. `None` until the first frame.
+    ///
+    /// the frame pool is made again in the new size when the content is resized, so the frame right after
+    /// a resize is cut off or leaves garbage borders, as all of them do with [resize_with_content](CaptureBuilder::resize_with_content)
+    /// off. check [is_mismatched](ContentSize::is_mismatched) and use [valid_rect](ContentSize::valid_rect),
+    /// or [get_content_frame](Self::get_content_frame). the texture is the zoomed or scaled one while zoomed or scaled,
+    /// and the content is seen from the [region](Self::set_region) while set.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Untitled - Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let raw = capture.wait_raw_frame().unwrap();
+    /// if let Some(size) = capture.get_content_size().filter(|size| size.is_mismatched()) {
+    ///     println!("resized, valid in {:?}", size.valid_rect());
+    /// }
+    /// ```
+    pub fn get_content_size(&self) -> Option<ContentSize> {
+        self.info.lock().unwrap().content_size
+    }
+
+    /// [get_raw_frame](Self::get_raw_frame) cut to the [valid_rect](ContentSize::valid_rect) of the content.
+    pub fn get_content_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let mut raw = self.get_raw_frame()?;
+
+        if let Some(size) = raw.info.content_size.filter(|size| size.is_mismatched()) {
+            CropFilter::new(size.valid_rect()).apply(&mut raw, 0);
+        }
+        Ok(raw)
+    }
+
+    /// Return rapped current frame with [RawFrameData]. with throught NoTexture
+    pub fn wait_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        FrameSource::wait_raw_frame(self)
+    }
+
+    /// Return rapped current frame with [RawFrameData], waiting for a frame up to `timeout`.
+    ///
+    /// the thread sleeps until the frame arrived handler signals a frame, rather than retrying on
+    /// [NoTexture](CaptureError::NoTexture).
+    /// ## Errors
+    /// * [Timeout](CaptureError::Timeout): no frame arrived in `timeout`.
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    /// * [ItemClosed](CaptureError::ItemClosed): the captured window is closed.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let device = dxcapture::Device::new_from_window("Untitled - Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// match capture.wait_raw_frame_timeout(Duration::from_secs(1)) {
+    ///     Ok(raw) => println!("{}x{}", raw.width, raw.height),
+    ///     Err(dxcapture::CaptureError::Timeout) => println!("minimized?"),
+    ///     Err(e) => panic!("{}", e),
+    /// }
+    /// ```
+    pub fn wait_raw_frame_timeout(&self, timeout: Duration) -> anyhow::Result<RawFrameData, CaptureError> {
+        self.wait_frame_with(Some(timeout), Self::get_raw_frame)
+    }
+
+    /// Call `get` until it returns other than [NoTexture](CaptureError::NoTexture), sleeping until the next frame
+    /// between the calls. [Timeout](CaptureError::Timeout) after `timeout`.
+    pub(crate) fn wait_frame_with<T, F>(&self, timeout: Option<Duration>, mut get: F) -> anyhow::Result<T, CaptureError>
+    where
+        F: FnMut(&Self) -> anyhow::Result<T, CaptureError>,
+    {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+
+        loop {
+            let seen = *self.signal.count.lock().unwrap();
+            match get(self) {
+                Err(CaptureError::NoTexture) => {},
+                result => return result,
+            }
+            // a frame is there but its copy is not done, as in the low latency mode
+            let in_flight = self.texture.lock().unwrap().is_some();
+
+            let mut count = self.signal.count.lock().unwrap();
+            while *count == seen {
+                // stopped by a clone, so no frame comes any more
+                if !self.is_running() {
+                    return Err(CaptureError::NotActive);
+                }
+                let wait = match deadline {
+                    Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                        Some(wait) if !wait.is_zero() => wait,
+                        _ => return Err(CaptureError::Timeout),
+                    },
+                    None => Duration::MAX,
+                };
+                if in_flight {
+                    count = self.signal.arrived.wait_timeout(count, wait.min(Duration::from_millis(1))).unwrap().0;
+                    break;
+                }
+                count = if wait == Duration::MAX {
+                    self.signal.arrived.wait(count).unwrap()
+                } else {
+                    self.signal.arrived.wait_timeout(count, wait).unwrap().0
+                };
+            }
+        }
+    }
+
+    /// Wait until the frames stop changing. [Read more](FrameSource::wait_for_stable)
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// 
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// 
+    /// // same for 500ms, allowing 0.1% noise, give up after 10s.
+    /// let raw = capture.wait_for_stable(Duration::from_millis(500), 0.1, Duration::from_secs(10)).unwrap();
+    /// ```
+    pub fn wait_for_stable(&self, duration: Duration, tolerance: f64, timeout: Duration) -> anyhow::Result<RawFrameData, CaptureError> {
+        FrameSource::wait_for_stable(self, duration, tolerance, timeout)
     }
 }
-impl Drop for Capture {
-    fn drop(&mut self) {
-        self.release().unwrap();
+impl FrameSource for Capture {
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        Capture::get_raw_frame(self)
+    }
+
+    /// Sleeps until a frame arrives, instead of retrying.
+    fn wait_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        self.wait_frame_with(None, Self::get_raw_frame)
     }
 }
 
+pub mod builder;
+pub use builder::{
+    CaptureBuilder,
+    MAX_BUFFER_COUNT,
+};
+
+mod border;
+
+pub mod zoom;
+pub use zoom::ZoomView;
+use zoom::ZoomState;
+
+mod region;
+
+mod history;
+use history::FrameHistory;
+
+mod staging;
+use staging::{
+    ReadingGuard,
+    ReadingTextures,
+    StagingRing,
+};
+
+pub mod callback;
+pub use callback::FrameSubscription;
+use callback::FrameCallbacks;
+
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "async")))]
+pub use stream::FrameStream;
+
+pub mod power;
+pub use power::PowerPolicy;
+use power::PowerState;
+
+mod limiter;
+use limiter::FrameLimiter;
+
+#[cfg(feature = "latency")]
+mod latency;
+#[cfg(feature = "latency")]
+use latency::LatencyState;
+
+#[cfg(feature = "dedup")]
+mod dedup;
+#[cfg(feature = "dedup")]
+use dedup::DedupState;
+
+#[cfg(feature = "watermark")]
+mod watermark;
+#[cfg(feature = "watermark")]
+use watermark::WatermarkState;
+
+#[cfg(feature = "interop")]
+pub mod shared;
+#[cfg(feature = "interop")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+pub use shared::{
+    KeyedFrame,
+    SharedFrame,
+};
+#[cfg(feature = "interop")]
+use shared::SharedState;
+
+#[cfg(feature = "gl")]
+pub mod gl;
+#[cfg(feature = "gl")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "gl")))]
+pub use gl::{
+    GlFrame,
+    GlInterop,
+};
+
+#[cfg(feature = "cuda")]
+pub mod cuda;
+#[cfg(feature = "cuda")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "cuda")))]
+pub use cuda::{
+    CudaArray,
+    CudaFrame,
+    CudaInterop,
+    CudaStream,
+};
+
+#[cfg(feature = "directml")]
+pub mod directml;
+#[cfg(feature = "directml")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "directml")))]
+pub use directml::DmlPreprocessor;
+
+#[cfg(feature = "preview")]
+mod preview;
+
+#[cfg(feature = "ndarray")]
+pub mod array;
+#[cfg(feature = "ndarray")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+pub use array::frame_to_array;
+
+#[cfg(feature = "tch")]
+mod torch;
+
+#[cfg(feature = "d3d11on12")]
+pub mod d3d12;
+#[cfg(feature = "d3d11on12")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+pub use d3d12::D3D12Frame;
+
+#[cfg(feature = "d2d")]
+mod d2d;
+
+#[cfg(feature = "media")]
+mod media;
+
+#[cfg(feature = "winml")]
+mod winml;
+
+#[cfg(feature = "color")]
+pub mod color;
+#[cfg(feature = "color")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "color")))]
+pub use color::{
+    ColorConverter,
+    ColorOptions,
+    ColorSpace,
+    ConvertFormat,
+    ConvertedFrame,
+    Nv12FrameData,
+};
+
+#[cfg(feature = "wgpu")]
+mod wgpu_texture;
+
+#[cfg(feature = "yuv")]
+pub mod yuv;
+#[cfg(feature = "yuv")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "yuv")))]
+pub use yuv::{
+    frame_to_i420,
+    I420FrameData,
+};
+
+#[cfg(feature = "tonemap")]
+pub mod tonemap;
+#[cfg(feature = "tonemap")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "tonemap")))]
+pub use tonemap::{
+    sdr_white_level,
+    tonemap_frame,
+    ToneMapOperator,
+    ToneMapOptions,
+};
+
 #[cfg(feature = "img")]
 pub mod img;
 #[cfg(feature = "img")]
@@ -269,3 +1893,128 @@ pub mod mat;
 #[cfg(feature = "mat")]
 #[cfg_attr(feature = "docs-features", doc(cfg(feature = "mat")))]
 pub use mat::MatFrameData;
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_intersect() {
+        let a = Rect::new(0, 0, 100, 50);
+
+        assert_eq!(a.intersect(&Rect::new(50, 25, 100, 100)), Rect::new(50, 25, 50, 25));
+        assert_eq!(a.intersect(&Rect::new(10, 10, 20, 20)), Rect::new(10, 10, 20, 20));
+        assert_eq!(a.intersect(&Rect::new(-10, -10, 20, 20)), Rect::new(0, 0, 10, 10));
+        // touching edges don't overlap
+        assert!(a.intersect(&Rect::new(100, 0, 10, 10)).is_empty());
+        assert!(a.intersect(&Rect::new(0, 60, 10, 10)).is_empty());
+    }
+
+    #[test]
+    fn rgba_to_bgra() {
+        let mut data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let row_pitch = convert_to_bgra(8, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB, &mut data, TenBitConversion::Round);
+
+        // the same swap for sRGB
+        assert_eq!(row_pitch, 8);
+        assert_eq!(data, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+
+    #[test]
+    fn ten_bit_unpack() {
+        let value: u32 = 1023 | (512 << 10) | (1 << 20) | (3 << 30);
+
+        assert_eq!(unpack_r10g10b10a2(&value.to_le_bytes()), [1023, 512, 1, 3]);
+    }
+
+    #[test]
+    fn ten_bit_round() {
+        let round = TenBitConversion::Round;
+
+        assert_eq!(round.reduce(0, 0, 0), 0);
+        assert_eq!(round.reduce(1023, 0, 0), 255);
+        assert_eq!(round.reduce(512, 0, 0), 128);
+    }
+
+    #[test]
+    fn ten_bit_dither() {
+        let dither = TenBitConversion::Dither;
+        let block = |value: u32| (0..4).flat_map(|y| (0..4).map(move |x| (x, y)))
+            .map(|(x, y)| dither.reduce(value, x, y) as f64)
+            .collect::<Vec<_>>();
+
+        // the ends stay exact
+        assert!(block(0).iter().all(|&value| value == 0.0));
+        assert!(block(1023).iter().all(|&value| value == 255.0));
+        // a 4x4 block averages to the 10 bits value, within a 16th of a step
+        for value in [300, 514, 700] {
+            let average = block(value).iter().sum::<f64>() / 16.0;
+            assert!((average - value as f64 * 255.0 / 1023.0).abs() <= 1.0 / 16.0, "{} averaged {}", value, average);
+        }
+    }
+
+    #[test]
+    fn ten_bit_to_bgra() {
+        let value: u32 = 1023 | (512 << 20) | (3 << 30);
+        let mut data = value.to_le_bytes().to_vec();
+        convert_to_bgra(4, DXGI_FORMAT_R10G10B10A2_UNORM, &mut data, TenBitConversion::Round);
+
+        assert_eq!(data, vec![128, 0, 255, 255]);
+    }
+
+    #[test]
+    fn f16_to_f32_values() {
+        assert_eq!(f16_to_f32(0x0000), 0.0);
+        assert_eq!(f16_to_f32(0x3C00), 1.0);
+        assert_eq!(f16_to_f32(0x3800), 0.5);
+        assert_eq!(f16_to_f32(0xC000), -2.0);
+        assert_eq!(f16_to_f32(0x7BFF), 65504.0);
+        // subnormal
+        assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+        assert_eq!(f16_to_f32(0x7C00), f32::INFINITY);
+        assert_eq!(f16_to_f32(0xFC00), f32::NEG_INFINITY);
+        assert!(f16_to_f32(0x7E00).is_nan());
+    }
+
+    #[test]
+    fn float_to_bgra() {
+        let mut data = [0x3C00u16, 0x0000, 0x3800, 0x3C00].iter().flat_map(|channel| channel.to_le_bytes()).collect();
+        let row_pitch = convert_to_bgra(8, DXGI_FORMAT_R16G16B16A16_FLOAT, &mut data, TenBitConversion::Round);
+
+        assert_eq!(row_pitch, 4);
+        assert_eq!(data, vec![188, 0, 255, 255]);
+    }
+
+    #[test]
+    fn frame_info_map_rect() {
+        let info = FrameInfo{
+            scale: Some(FrameScale{ source: Rect::new(100, 100, 400, 200), width: 200, height: 100 }),
+            region: Some(Rect::new(10, 10, 100, 50)),
+            ..Default::default()
+        };
+
+        // halved from the source, then offset by the region
+        assert_eq!(info.map_point(300, 200), (90, 40));
+        assert_eq!(info.map_rect(Rect::new(300, 200, 20, 20)), Rect::new(90, 40, 10, 10));
+        // rounded outwards
+        assert_eq!(info.map_rect(Rect::new(301, 201, 1, 1)), Rect::new(90, 40, 1, 1));
+        assert_eq!(FrameInfo::default().map_rect(Rect::new(1, 2, 3, 4)), Rect::new(1, 2, 3, 4));
+    }
+
+    #[test]
+    fn pack_rows_drops_padding() {
+        let mut frame = RawFrameData{
+            width: 1,
+            height: 2,
+            data: vec![1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 7, 8],
+            row_pitch: 8,
+            ..Default::default()
+        };
+        assert_eq!(frame.row(1), &[5, 6, 7, 8]);
+        frame.pack_rows();
+
+        assert_eq!(frame.data, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(frame.stride(), 4);
+    }
+}
@@ -0,0 +1,56 @@
+use ndarray::Array4;
+
+use crate::tensor::{
+    frame_to_tensor,
+    TensorLayout,
+    TensorOptions,
+};
+
+use super::*;
+
+
+/// [frame_to_tensor] as an `ndarray` array of [shape](TensorOptions::shape).
+///
+/// the input of `ort` as it is, and of `tract` by `tract_ndarray` of the same `ndarray`.
+///
+/// Required features: *`"ndarray"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+pub fn frame_to_array(frame: &RawFrameData, options: &TensorOptions, layout: TensorLayout) -> anyhow::Result<Array4<f32>, CaptureError> {
+    let [n, c_or_h, h_or_w, w_or_c] = options.shape(layout);
+    let tensor = frame_to_tensor(frame, options, layout)?;
+
+    Array4::from_shape_vec((n, c_or_h, h_or_w, w_or_c), tensor)
+        .map_err(|e| CaptureError::InvalidArgument(e.to_string()))
+}
+
+impl Capture {
+    /// Get the current frame as an input tensor of `ndarray`, for `ort` and `tract`.
+    ///
+    /// resized, cropped and normalized on the CPU as `options`. [Read more](frame_to_tensor)
+    ///
+    /// Required features: *`"ndarray"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let options = dxcapture::TensorOptions::imagenet(224, 224);
+    /// let input = capture.wait_array_frame(&options, dxcapture::TensorLayout::Nchw).expect("Failed to capture");
+    /// assert_eq!(input.shape(), &[1, 3, 224, 224]);
+    /// // session.run(vec![input.into()])
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+    pub fn get_array_frame(&self, options: &TensorOptions, layout: TensorLayout) -> anyhow::Result<Array4<f32>, CaptureError> {
+        let raw = self.get_raw_frame()?;
+
+        frame_to_array(&raw, options, layout)
+    }
+
+    /// Get the current frame as an input tensor of `ndarray`. with throught NoTexture
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "ndarray")))]
+    pub fn wait_array_frame(&self, options: &TensorOptions, layout: TensorLayout) -> anyhow::Result<Array4<f32>, CaptureError> {
+        let raw = self.wait_raw_frame()?;
+
+        frame_to_array(&raw, options, layout)
+    }
+}
@@ -0,0 +1,70 @@
+use windows::{
+    Foundation::Metadata::ApiInformation,
+    Graphics::Capture::{
+        GraphicsCaptureAccess,
+        GraphicsCaptureAccessKind,
+    },
+    Security::Authorization::AppCapabilityAccess::AppCapabilityAccessStatus,
+};
+
+use super::*;
+
+
+/// Set the border of `session`, asking for the borderless access before hiding it.
+///
+/// `false` without trying on the Windows before `IsBorderRequired`.
+pub(crate) fn apply_border(session: &GraphicsCaptureSession, border: bool) -> anyhow::Result<bool, CaptureError> {
+    if !Capture::is_border_configurable() {
+        return Ok(false);
+    }
+
+    if !border {
+        let status = GraphicsCaptureAccess::RequestAccessAsync(GraphicsCaptureAccessKind::Borderless)
+            .and_then(|operation| operation.get())
+            .map_err(|e| CaptureError::DirectxError(e))?;
+        if status != AppCapabilityAccessStatus::Allowed {
+            return Ok(false);
+        }
+    }
+
+    session.SetIsBorderRequired(border).map_err(|e| CaptureError::DirectxError(e))?;
+    Ok(true)
+}
+
+
+impl Capture {
+    /// This Windows lets the yellow border be hidden. (Windows 10 2104, build 20348, or later)
+    pub fn is_border_configurable() -> bool {
+        ApiInformation::IsPropertyPresent("Windows.Graphics.Capture.GraphicsCaptureSession", "IsBorderRequired")
+            .unwrap_or(false)
+    }
+
+    /// Draw or hide the yellow border around the target while capturing.
+    ///
+    /// hiding it asks for the borderless access first, allowed without a prompt for the desktop apps.
+    /// returns `false`, leaving the border as it is, when this Windows can't hide it or the access is denied.
+    /// GDI draws no border, so hiding it returns `true` and drawing it `false` while [copied by GDI](Self::is_gdi).
+    /// also set before the start by [CaptureBuilder::border].
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// if !capture.set_border(false).unwrap() {
+    ///     println!("the border stays on this Windows");
+    /// }
+    /// ```
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is stopped.
+    /// * [DirectxError](CaptureError::DirectxError): failed to ask for the access or to set the border.
+    pub fn set_border(&self, border: bool) -> anyhow::Result<bool, CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+
+        match self.session.graphics_session() {
+            Some(session) => apply_border(session, border),
+            None => Ok(!border),
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use super::*;
+
+
+/// Most frames of a frame pool taken by [buffer_count](CaptureBuilder::buffer_count).
+pub const MAX_BUFFER_COUNT: u32 = 8;
+
+/// Options of a [Capture] set before the capture starts. [Read more](Capture::builder)
+#[derive(Clone, Debug)]
+pub struct CaptureBuilder<'a> {
+    pub(crate) device: &'a Device,
+    pub(crate) pixel_format: DirectXPixelFormat,
+    pub(crate) buffer_count: i32,
+    pub(crate) cursor: Option<bool>,
+    pub(crate) border: Option<bool>,
+    pub(crate) resize_with_content: bool,
+    pub(crate) region: Option<Rect>,
+    pub(crate) output_size: Option<(u32, u32)>,
+    pub(crate) history: usize,
+    pub(crate) max_fps: Option<u32>,
+}
+impl<'a> CaptureBuilder<'a> {
+    pub fn new(device: &'a Device) -> Self {
+        Self{
+            device,
+            pixel_format: DirectXPixelFormat::B8G8R8A8UIntNormalized,
+            buffer_count: 1,
+            cursor: None,
+            border: None,
+            resize_with_content: true,
+            region: None,
+            output_size: None,
+            history: 0,
+            max_fps: None,
+        }
+    }
+
+    /// Format of the frames, as [new_with_format](Capture::new_with_format). default is `B8G8R8A8UIntNormalized`.
+    pub fn pixel_format(mut self, pixel_format: DirectXPixelFormat) -> Self {
+        self.pixel_format = pixel_format;
+        self
+    }
+
+    /// Frames of the frame pool, 1 to [MAX_BUFFER_COUNT]. default is 1.
+    ///
+    /// more buffers let the system render the next frames while the handler still copies one,
+    /// for a steadier frame rate at the cost of the video memory. 2 or 3 is enough for most high frame rates.
+    /// the handler takes all the frames waiting in the pool and copies the newest, so the frames never lag behind.
+    pub fn buffer_count(mut self, buffer_count: u32) -> Self {
+        self.buffer_count = buffer_count.min(i32::MAX as u32) as i32;
+        self
+    }
+
+    /// Capture the mouse cursor. default is the system default, captured.
+    pub fn cursor(mut self, cursor: bool) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Draw the yellow border around the target. default is the system default, drawn.
+    ///
+    /// left drawn where it can't be hidden, as by [set_border](Capture::set_border).
+    pub fn border(mut self, border: bool) -> Self {
+        self.border = Some(border);
+        self
+    }
+
+    /// Make the frame pool again in the new size when the window is resized, so the frames follow the window.
+    /// default is `true`.
+    ///
+    /// `false` keeps the frames in the size at the start, for the consumers of a fixed size like the encoders,
+    /// with the resized content cut off or in a corner. [Read more](Capture::get_content_size)
+    pub fn resize_with_content(mut self, resize_with_content: bool) -> Self {
+        self.resize_with_content = resize_with_content;
+        self
+    }
+
+    /// Deliver only `region` of the frames. default is the whole frames. [Read more](Capture::set_region)
+    pub fn region(mut self, region: Rect) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// Scale the frames to `width` x `height` on the GPU. default is the frames in their own size. [Read more](Capture::set_output_size)
+    pub fn output_size(mut self, width: u32, height: u32) -> Self {
+        self.output_size = Some((width, height));
+        self
+    }
+
+    /// Keep the last `capacity` frames. default is 0, none. [Read more](Capture::set_history)
+    pub fn history(mut self, capacity: usize) -> Self {
+        self.history = capacity;
+        self
+    }
+
+    /// Deliver at most `max_fps` frames a second. default is every frame. [Read more](Capture::set_max_fps)
+    pub fn max_fps(mut self, max_fps: u32) -> Self {
+        self.max_fps = Some(max_fps);
+        self
+    }
+
+    /// Create the session with the options and start the capture.
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): `buffer_count` is out of 1 to [MAX_BUFFER_COUNT], the region or the output size is empty,
+    ///   `max_fps` is 0.
+    /// * the errors of the system, as for an option this Windows doesn't support.
+    pub fn build(self) -> anyhow::Result<Capture> {
+        if !(1..=MAX_BUFFER_COUNT as i32).contains(&self.buffer_count) {
+            return Err(CaptureError::InvalidArgument(format!("buffer count must be in 1..={}.", MAX_BUFFER_COUNT)).into());
+        }
+        if self.region.is_some_and(|region| region.is_empty()) {
+            return Err(CaptureError::InvalidArgument("region must not be empty.".to_string()).into());
+        }
+        if self.output_size.is_some_and(|(width, height)| width == 0 || height == 0) {
+            return Err(CaptureError::InvalidArgument("output size must not be empty.".to_string()).into());
+        }
+
+        if self.max_fps == Some(0) {
+            return Err(CaptureError::InvalidArgument("max fps must be greater than 0.".to_string()).into());
+        }
+
+        Capture::start(&self)
+    }
+}
+
+
+impl Capture {
+    /// Configure a capture of `device` before it starts.
+    /// # Examples
+    /// ```
+    /// use windows::Graphics::DirectX::DirectXPixelFormat;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::builder(&device)
+    ///     .pixel_format(DirectXPixelFormat::R10G10B10A2UIntNormalized)
+    ///     .buffer_count(3)
+    ///     .cursor(false)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(device: &Device) -> CaptureBuilder<'_> {
+        CaptureBuilder::new(device)
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::{
+    mpsc::{
+        self,
+        Receiver,
+        TrySendError,
+    },
+    Weak,
+};
+
+use super::*;
+
+
+/// `false` to be removed.
+type FrameCallback = Box<dyn FnMut(RawFrameData) -> bool + Send>;
+
+/// Callbacks of [on_frame](Capture::on_frame), shared with the frame arrived handler.
+#[derive(Default)]
+pub(crate) struct FrameCallbacks {
+    next_id: u64,
+    callbacks: Vec<(u64, FrameCallback)>,
+}
+impl FrameCallbacks {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.callbacks.is_empty()
+    }
+
+    pub(crate) fn add(&mut self, callback: FrameCallback) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.callbacks.push((id, callback));
+        id
+    }
+
+    /// Call all the callbacks with `frame`, cloned for all but the last, and remove the finished ones.
+    pub(crate) fn call(&mut self, frame: RawFrameData) {
+        let mut finished = Vec::new();
+        if let Some(((last_id, last), rest)) = self.callbacks.split_last_mut() {
+            for (id, callback) in rest {
+                if !callback(frame.clone()) {
+                    finished.push(*id);
+                }
+            }
+            if !last(frame) {
+                finished.push(*last_id);
+            }
+        }
+
+        if !finished.is_empty() {
+            self.callbacks.retain(|(id, _)| !finished.contains(id));
+        }
+    }
+}
+impl std::fmt::Debug for FrameCallbacks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameCallbacks")
+            .field("next_id", &self.next_id)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+
+/// Registration of [on_frame](Capture::on_frame). the callback is removed when this is dropped.
+#[must_use = "the callback is removed when the subscription is dropped"]
+#[derive(Debug)]
+pub struct FrameSubscription {
+    id: u64,
+    callbacks: Weak<Mutex<FrameCallbacks>>,
+}
+impl FrameSubscription {
+    /// Remove the callback. the call running now finishes.
+    pub fn unsubscribe(self) {}
+
+    /// Keep the callback for the life of the capture.
+    pub fn detach(mut self) {
+        self.callbacks = Weak::new();
+    }
+
+    /// The capture is still there.
+    pub fn is_active(&self) -> bool {
+        self.callbacks.strong_count() != 0
+    }
+}
+impl Drop for FrameSubscription {
+    fn drop(&mut self) {
+        if let Some(callbacks) = self.callbacks.upgrade() {
+            callbacks.lock().unwrap().callbacks.retain(|(id, _)| *id != self.id);
+        }
+    }
+}
+
+
+impl Capture {
+    /// Call `callback` with every arriving frame, instead of polling [get_raw_frame](Self::get_raw_frame).
+    ///
+    /// the callback runs on the frame arrived handler, a thread of the system thread pool, right after the copy.
+    /// the frames are read back only while any callback is registered. keep the callbacks short, as the next
+    /// frame waits for them, and don't call `on_frame` nor drop a subscription from them.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let subscription = capture.on_frame(|raw| {
+    ///     println!("{}x{}", raw.width, raw.height);
+    /// });
+    /// std::thread::sleep(std::time::Duration::from_secs(1));
+    /// subscription.unsubscribe();
+    /// ```
+    pub fn on_frame<F>(&self, mut callback: F) -> FrameSubscription
+    where
+        F: FnMut(RawFrameData) + Send + 'static,
+    {
+        let id = self.callbacks.lock().unwrap().add(Box::new(move |frame| {
+            callback(frame);
+            true
+        }));
+
+        FrameSubscription{
+            id,
+            callbacks: Arc::downgrade(&self.callbacks),
+        }
+    }
+
+    /// Receive every arriving frame on another thread, with up to 4 frames buffered. [Read more](Self::frames_bounded)
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let frames = capture.frames();
+    /// std::thread::spawn(move || {
+    ///     for raw in frames {
+    ///         println!("{}x{}", raw.width, raw.height);
+    ///     }
+    /// });
+    /// ```
+    pub fn frames(&self) -> Receiver<RawFrameData> {
+        self.frames_bounded(4)
+    }
+
+    /// Receive every arriving frame, fed from the frame arrived handler as [on_frame](Self::on_frame).
+    ///
+    /// the frames arriving while `capacity` frames are waiting are dropped, so a slow receiver doesn't hold
+    /// the capture up nor fill the memory. the feed stops when the receiver is dropped, and the receiver
+    /// ends when the capture is dropped.
+    pub fn frames_bounded(&self, capacity: usize) -> Receiver<RawFrameData> {
+        let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+
+        self.callbacks.lock().unwrap().add(Box::new(move |frame| {
+            !matches!(sender.try_send(frame), Err(TrySendError::Disconnected(_)))
+        }));
+
+        receiver
+    }
+}
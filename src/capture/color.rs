@@ -0,0 +1,545 @@
+//! Color space and format conversion of the shared frames by the D3D11 video processor.
+//!
+//! Required features: *`"color"`*
+
+use std::collections::HashMap;
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::BOOL,
+        Graphics::{
+            Direct3D11::{
+                ID3D11Texture2D,
+                ID3D11VideoContext1,
+                ID3D11VideoDevice,
+                ID3D11VideoProcessor,
+                ID3D11VideoProcessorEnumerator,
+                ID3D11VideoProcessorEnumerator1,
+                ID3D11VideoProcessorInputView,
+                ID3D11VideoProcessorOutputView,
+                D3D11_BIND_RENDER_TARGET,
+                D3D11_CPU_ACCESS_READ,
+                D3D11_MAP_READ,
+                D3D11_TEX2D_VPIV,
+                D3D11_TEX2D_VPOV,
+                D3D11_USAGE_DEFAULT,
+                D3D11_USAGE_STAGING,
+                D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                D3D11_VIDEO_PROCESSOR_CONTENT_DESC,
+                D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC,
+                D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0,
+                D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC,
+                D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0,
+                D3D11_VIDEO_PROCESSOR_STREAM,
+                D3D11_VIDEO_USAGE_PLAYBACK_NORMAL,
+                D3D11_VPIV_DIMENSION_TEXTURE2D,
+                D3D11_VPOV_DIMENSION_TEXTURE2D,
+            },
+            Dxgi::Common::{
+                DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+                DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+                DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+                DXGI_COLOR_SPACE_TYPE,
+                DXGI_COLOR_SPACE_YCBCR_FULL_G22_LEFT_P709,
+                DXGI_COLOR_SPACE_YCBCR_STUDIO_G2084_LEFT_P2020,
+                DXGI_COLOR_SPACE_YCBCR_STUDIO_G22_LEFT_P709,
+                DXGI_FORMAT,
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                DXGI_FORMAT_NV12,
+                DXGI_FORMAT_P010,
+                DXGI_RATIONAL,
+                DXGI_SAMPLE_DESC,
+            },
+        },
+    },
+};
+
+use super::*;
+
+
+/// Color space of frames, a `DXGI_COLOR_SPACE_TYPE`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// sRGB, full range BT.709 primaries. the frames of SDR displays.
+    Srgb,
+    /// linear scRGB. the `R16G16B16A16Float` frames of HDR displays.
+    ScRgb,
+    /// RGB of PQ and BT.2020 primaries.
+    Hdr10,
+    /// YCbCr BT.709 of the limited (studio) range. most of the H.264 and HEVC SDR video.
+    Bt709Limited,
+    /// YCbCr BT.709 of the full range.
+    Bt709Full,
+    /// YCbCr BT.2020 PQ of the limited range. HDR10 video.
+    Bt2020Limited,
+}
+impl ColorSpace {
+    pub fn dxgi(&self) -> DXGI_COLOR_SPACE_TYPE {
+        match self {
+            Self::Srgb => DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+            Self::ScRgb => DXGI_COLOR_SPACE_RGB_FULL_G10_NONE_P709,
+            Self::Hdr10 => DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+            Self::Bt709Limited => DXGI_COLOR_SPACE_YCBCR_STUDIO_G22_LEFT_P709,
+            Self::Bt709Full => DXGI_COLOR_SPACE_YCBCR_FULL_G22_LEFT_P709,
+            Self::Bt2020Limited => DXGI_COLOR_SPACE_YCBCR_STUDIO_G2084_LEFT_P2020,
+        }
+    }
+}
+
+
+/// Pixel format of converted frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConvertFormat {
+    /// 8 bits BGRA, as [RawFrameData].
+    Bgra8,
+    /// 8 bits Y plane, then interleaved UV of the half size.
+    Nv12,
+    /// 16 bits little endian Y plane, then interleaved UV of the half size, the top 10 bits used.
+    P010,
+}
+impl ConvertFormat {
+    pub fn dxgi(&self) -> DXGI_FORMAT {
+        match self {
+            Self::Bgra8 => DXGI_FORMAT_B8G8R8A8_UNORM,
+            Self::Nv12 => DXGI_FORMAT_NV12,
+            Self::P010 => DXGI_FORMAT_P010,
+        }
+    }
+
+    /// Bytes of a row of the first plane.
+    fn row_bytes(&self, width: u32) -> usize {
+        match self {
+            Self::Bgra8 => width as usize * 4,
+            Self::Nv12 => width as usize,
+            Self::P010 => width as usize * 2,
+        }
+    }
+
+    fn is_yuv(&self) -> bool {
+        *self != Self::Bgra8
+    }
+}
+
+
+/// What [ColorConverter] makes from what.
+///
+/// the default takes sRGB frames into BT.709 limited range NV12, for H.264 and HEVC encoders.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorOptions {
+    /// color space of the captured frames.
+    pub input: ColorSpace,
+    pub output: ColorSpace,
+    pub format: ConvertFormat,
+}
+impl Default for ColorOptions {
+    fn default() -> Self {
+        Self{
+            input: ColorSpace::Srgb,
+            output: ColorSpace::Bt709Limited,
+            format: ConvertFormat::Nv12,
+        }
+    }
+}
+impl ColorOptions {
+    /// scRGB frames of HDR displays, captured by [new_with_format](Capture::new_with_format) with
+    /// `R16G16B16A16Float`, into BT.709 limited range NV12.
+    pub fn hdr_to_sdr() -> Self {
+        Self{
+            input: ColorSpace::ScRgb,
+            ..Default::default()
+        }
+    }
+
+    /// scRGB frames into BT.2020 PQ limited range P010, for HDR10 encoders.
+    pub fn hdr10() -> Self {
+        Self{
+            input: ColorSpace::ScRgb,
+            output: ColorSpace::Bt2020Limited,
+            format: ConvertFormat::P010,
+        }
+    }
+
+    pub fn input(mut self, input: ColorSpace) -> Self {
+        self.input = input;
+        self
+    }
+
+    pub fn output(mut self, output: ColorSpace, format: ConvertFormat) -> Self {
+        self.output = output;
+        self.format = format;
+        self
+    }
+}
+
+
+/// Frame converted by [ColorConverter::convert].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConvertedFrame {
+    /// even for the YUV formats. the frame is stretched to it.
+    pub width: u32,
+    pub height: u32,
+    /// to tag the encoded stream with.
+    pub color_space: ColorSpace,
+    pub format: ConvertFormat,
+    /// the planes without padding. [Read more](ConvertFormat)
+    pub data: Vec<u8>,
+    /// same as [SharedFrame::fence_value].
+    pub fence_value: u64,
+}
+
+
+/// NV12 frame of [get_nv12_frame](Capture::get_nv12_frame), for the hardware video encoders.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Nv12FrameData {
+    /// rounded up to even.
+    pub width: u32,
+    pub height: u32,
+    /// luma, `height` rows of `y_stride` bytes.
+    pub y: Vec<u8>,
+    pub y_stride: usize,
+    /// interleaved Cb and Cr of the half size, `height / 2` rows of `uv_stride` bytes.
+    pub uv: Vec<u8>,
+    pub uv_stride: usize,
+    /// same as [SharedFrame::fence_value].
+    pub fence_value: u64,
+}
+impl Nv12FrameData {
+    /// Split the planes of a converted NV12 `frame`: Y, then UV of the half height.
+    fn from_converted(frame: ConvertedFrame) -> Self {
+        let stride = ConvertFormat::Nv12.row_bytes(frame.width);
+        let mut y = frame.data;
+        let uv = y.split_off((stride * frame.height as usize).min(y.len()));
+
+        Self{
+            width: frame.width,
+            height: frame.height,
+            y,
+            y_stride: stride,
+            uv,
+            uv_stride: stride,
+            fence_value: frame.fence_value,
+        }
+    }
+}
+
+
+/// Pipeline for a size of frames.
+struct Pipeline {
+    enumerator: ID3D11VideoProcessorEnumerator,
+    processor: ID3D11VideoProcessor,
+    output_view: ID3D11VideoProcessorOutputView,
+    output: ID3D11Texture2D,
+    staging: ID3D11Texture2D,
+    input_size: (u32, u32),
+    output_size: (u32, u32),
+}
+
+
+/// Convert the frames shared by [Capture::share_frames] into another color space and format on the GPU,
+/// by the D3D11 video processor, and read them back.
+///
+/// for encoders taking NV12 or P010 of the tagged color space, as scRGB of HDR displays into BT.709 NV12.
+///
+/// Required features: *`"color"`*
+/// # Examples
+/// ```
+/// use windows::Graphics::DirectX::DirectXPixelFormat;
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new_with_format(&device, DirectXPixelFormat::R16G16B16A16Float).unwrap();
+/// capture.share_frames(2).unwrap();
+///
+/// let mut converter = dxcapture::ColorConverter::new(&capture, dxcapture::ColorOptions::hdr_to_sdr()).expect("No video processor");
+/// capture.wait_shared_frame().unwrap();
+/// let frame = converter.convert(&capture).expect("Failed to convert");
+/// // frame.data is NV12 of BT.709 limited range
+/// ```
+pub struct ColorConverter {
+    options: ColorOptions,
+    video_device: ID3D11VideoDevice,
+    video_context: ID3D11VideoContext1,
+    pipeline: Option<Pipeline>,
+    input_views: HashMap<u64, ID3D11VideoProcessorInputView>,
+}
+impl std::fmt::Debug for ColorConverter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ColorConverter")
+            .field("options", &self.options)
+            .field("output_size", &self.pipeline.as_ref().map(|pipeline| pipeline.output_size))
+            .finish()
+    }
+}
+impl ColorConverter {
+    /// ## Errors
+    /// * [DirectxError](CaptureError::DirectxError): the device has no video processor.
+    pub fn new(capture: &Capture, options: ColorOptions) -> anyhow::Result<Self, CaptureError> {
+        let video_device = capture._d3d_device.cast::<ID3D11VideoDevice>().map_err(|e| CaptureError::DirectxError(e))?;
+        let video_context = capture.d3d_context.cast::<ID3D11VideoContext1>().map_err(|e| CaptureError::DirectxError(e))?;
+
+        Ok(Self{
+            options,
+            video_device,
+            video_context,
+            pipeline: None,
+            input_views: HashMap::new(),
+        })
+    }
+
+    pub fn options(&self) -> ColorOptions {
+        self.options
+    }
+
+    /// Convert the latest shared frame and read it back.
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Capture::share_frames).
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): the video processor can't convert the frame format to [options](Self::options).
+    /// * [DirectxError](CaptureError::DirectxError): failed to convert.
+    pub fn convert(&mut self, capture: &Capture) -> anyhow::Result<ConvertedFrame, CaptureError> {
+        let (frame, texture) = {
+            // through shared_frame to check the state
+            let frame = capture.shared_frame()?;
+            capture.shared.lock().unwrap().latest_texture()
+                .filter(|(latest, _)| latest.texture_id == frame.texture_id)
+                .ok_or(CaptureError::NoTexture)?
+        };
+
+        // made again on resize
+        if self.pipeline.as_ref().map_or(true, |pipeline| pipeline.input_size != (frame.width, frame.height)) {
+            self.pipeline = None;
+            self.input_views.clear();
+            self.pipeline = Some(self.create_pipeline(capture, &frame)?);
+        }
+        let pipeline = self.pipeline.as_ref().unwrap();
+
+        if !self.input_views.contains_key(&frame.texture_id) {
+            let view = Self::create_input_view(&self.video_device, pipeline, &texture).map_err(|e| CaptureError::DirectxError(e))?;
+            self.input_views.insert(frame.texture_id, view);
+        }
+        let input_view = self.input_views[&frame.texture_id].clone();
+
+        unsafe {
+            let stream = D3D11_VIDEO_PROCESSOR_STREAM{
+                Enable: BOOL::from(true),
+                pInputSurface: Some(input_view),
+                ..Default::default()
+            };
+            self.video_context.VideoProcessorBlt(&pipeline.processor, &pipeline.output_view, 0, 1, &stream)
+                .map_err(|e| CaptureError::DirectxError(e))?;
+            capture.d3d_context.CopyResource(&pipeline.staging, &pipeline.output);
+        }
+
+        let data = self.read_back(capture, pipeline)?;
+        Ok(ConvertedFrame{
+            width: pipeline.output_size.0,
+            height: pipeline.output_size.1,
+            color_space: self.options.output,
+            format: self.options.format,
+            data,
+            fence_value: frame.fence_value,
+        })
+    }
+
+    fn create_pipeline(&self, capture: &Capture, frame: &SharedFrame) -> anyhow::Result<Pipeline, CaptureError> {
+        let format = self.options.format;
+        // 4:2:0 needs even sizes
+        let output_size = if format.is_yuv() {
+            ((frame.width + 1) & !1, (frame.height + 1) & !1)
+        } else {
+            (frame.width, frame.height)
+        };
+
+        unsafe {
+            let content = D3D11_VIDEO_PROCESSOR_CONTENT_DESC{
+                InputFrameFormat: D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                InputFrameRate: DXGI_RATIONAL{ Numerator: 60, Denominator: 1 },
+                InputWidth: frame.width,
+                InputHeight: frame.height,
+                OutputFrameRate: DXGI_RATIONAL{ Numerator: 60, Denominator: 1 },
+                OutputWidth: output_size.0,
+                OutputHeight: output_size.1,
+                Usage: D3D11_VIDEO_USAGE_PLAYBACK_NORMAL,
+            };
+            let enumerator = self.video_device.CreateVideoProcessorEnumerator(&content).map_err(|e| CaptureError::DirectxError(e))?;
+
+            // ID3D11VideoProcessorEnumerator1 tells the conversions of the color spaces
+            if let Ok(enumerator1) = enumerator.cast::<ID3D11VideoProcessorEnumerator1>() {
+                let supported = enumerator1.CheckVideoProcessorFormatConversion(
+                    frame.format, self.options.input.dxgi(),
+                    format.dxgi(), self.options.output.dxgi(),
+                ).map_err(|e| CaptureError::DirectxError(e))?;
+                if !supported.as_bool() {
+                    return Err(CaptureError::UnsupportedPixelFormat(frame.format));
+                }
+            }
+
+            let processor = self.video_device.CreateVideoProcessor(&enumerator, 0).map_err(|e| CaptureError::DirectxError(e))?;
+            self.video_context.VideoProcessorSetStreamFrameFormat(&processor, 0, D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE);
+            self.video_context.VideoProcessorSetStreamAutoProcessingMode(&processor, 0, BOOL::from(false));
+            self.video_context.VideoProcessorSetStreamColorSpace1(&processor, 0, self.options.input.dxgi());
+            self.video_context.VideoProcessorSetOutputColorSpace1(&processor, self.options.output.dxgi());
+
+            let mut desc = D3D11_TEXTURE2D_DESC{
+                Width: output_size.0,
+                Height: output_size.1,
+                MipLevels: 1,
+                ArraySize: 1,
+                Format: format.dxgi(),
+                SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_RENDER_TARGET,
+                CPUAccessFlags: 0,
+                MiscFlags: 0,
+            };
+            let output = capture._d3d_device.CreateTexture2D(&desc, std::ptr::null()).map_err(|e| CaptureError::DirectxError(e))?;
+            desc.Usage = D3D11_USAGE_STAGING;
+            desc.BindFlags = 0;
+            desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+            let staging = capture._d3d_device.CreateTexture2D(&desc, std::ptr::null()).map_err(|e| CaptureError::DirectxError(e))?;
+
+            let view_desc = D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC{
+                ViewDimension: D3D11_VPOV_DIMENSION_TEXTURE2D,
+                Anonymous: D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0{
+                    Texture2D: D3D11_TEX2D_VPOV{ MipSlice: 0 },
+                },
+            };
+            let output_view = self.video_device.CreateVideoProcessorOutputView(&output, &enumerator, &view_desc)
+                .map_err(|e| CaptureError::DirectxError(e))?;
+
+            Ok(Pipeline{
+                enumerator,
+                processor,
+                output_view,
+                output,
+                staging,
+                input_size: (frame.width, frame.height),
+                output_size,
+            })
+        }
+    }
+
+    fn create_input_view(video_device: &ID3D11VideoDevice, pipeline: &Pipeline, texture: &ID3D11Texture2D) -> windows::core::Result<ID3D11VideoProcessorInputView> {
+        let desc = D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC{
+            FourCC: 0,
+            ViewDimension: D3D11_VPIV_DIMENSION_TEXTURE2D,
+            Anonymous: D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0{
+                Texture2D: D3D11_TEX2D_VPIV{ MipSlice: 0, ArraySlice: 0 },
+            },
+        };
+
+        unsafe {
+            video_device.CreateVideoProcessorInputView(texture, &pipeline.enumerator, &desc)
+        }
+    }
+
+    /// The planes of the staging texture without the row padding.
+    fn read_back(&self, capture: &Capture, pipeline: &Pipeline) -> anyhow::Result<Vec<u8>, CaptureError> {
+        let format = self.options.format;
+        let (width, height) = pipeline.output_size;
+        let row_bytes = format.row_bytes(width);
+        // Y and the half height of UV
+        let rows = if format.is_yuv() { height as usize * 3 / 2 } else { height as usize };
+
+        unsafe {
+            let mapped = capture.d3d_context.Map(&pipeline.staging, 0, D3D11_MAP_READ, 0)
+                .map_err(|e| CaptureError::DirectxError(e))?;
+
+            // UV follows Y in the same mapping
+            let pitch = mapped.RowPitch as usize;
+            let slice = std::slice::from_raw_parts(mapped.pData as *const u8, pitch * (rows - 1) + row_bytes);
+            let data = pack_rows(slice, pitch, row_bytes, rows);
+
+            capture.d3d_context.Unmap(&pipeline.staging, 0);
+            Ok(data)
+        }
+    }
+}
+
+/// `rows` rows of `row_bytes` from `slice` of the rows `pitch` bytes apart, without the padding.
+fn pack_rows(slice: &[u8], pitch: usize, row_bytes: usize, rows: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(row_bytes * rows);
+    for row in 0..rows {
+        data.extend_from_slice(&slice[row * pitch..row * pitch + row_bytes]);
+    }
+
+    data
+}
+
+
+impl Capture {
+    /// Convert the latest shared frame into BT.709 limited range NV12 on the GPU, for the hardware H.264 and HEVC encoders.
+    ///
+    /// the same as a [ColorConverter] of the default [ColorOptions], kept by the capture. share the frames by
+    /// [share_frames](Self::share_frames) first. the planes are read back without the row padding.
+    ///
+    /// Required features: *`"color"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(2).unwrap();
+    ///
+    /// let nv12 = capture.wait_nv12_frame().expect("No video processor");
+    /// // encoder.encode(&nv12.y, nv12.y_stride, &nv12.uv, nv12.uv_stride)
+    /// ```
+    /// ## Errors
+    /// * the errors of [ColorConverter::new] and [ColorConverter::convert].
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "color")))]
+    pub fn get_nv12_frame(&self) -> anyhow::Result<Nv12FrameData, CaptureError> {
+        let mut converter = self.nv12.lock().unwrap();
+        if converter.is_none() {
+            *converter = Some(ColorConverter::new(self, ColorOptions::default())?);
+        }
+        let frame = converter.as_mut().unwrap().convert(self)?;
+
+        Ok(Nv12FrameData::from_converted(frame))
+    }
+
+    /// [get_nv12_frame](Self::get_nv12_frame), waiting through NoTexture.
+    ///
+    /// Required features: *`"color"`*
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "color")))]
+    pub fn wait_nv12_frame(&self) -> anyhow::Result<Nv12FrameData, CaptureError> {
+        self.wait_frame_with(None, Self::get_nv12_frame)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_bytes() {
+        assert_eq!(ConvertFormat::Bgra8.row_bytes(6), 24);
+        assert_eq!(ConvertFormat::Nv12.row_bytes(6), 6);
+        assert_eq!(ConvertFormat::P010.row_bytes(6), 12);
+    }
+
+    #[test]
+    fn padding_is_dropped() {
+        // 3 rows of 2 bytes, 4 bytes apart. the last row has no padding
+        let slice = [1, 2, 0, 0, 3, 4, 0, 0, 5, 6];
+
+        assert_eq!(pack_rows(&slice, 4, 2, 3), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn nv12_planes() {
+        // 4x2: 2 rows of Y, then 1 row of UV
+        let frame = ConvertedFrame{
+            width: 4,
+            height: 2,
+            color_space: ColorSpace::Bt709Limited,
+            format: ConvertFormat::Nv12,
+            data: vec![16, 17, 18, 19, 20, 21, 22, 23, 128, 129, 130, 131],
+            fence_value: 7,
+        };
+        let nv12 = Nv12FrameData::from_converted(frame);
+
+        assert_eq!((nv12.y_stride, nv12.uv_stride), (4, 4));
+        assert_eq!(nv12.y, vec![16, 17, 18, 19, 20, 21, 22, 23]);
+        assert_eq!(nv12.uv, vec![128, 129, 130, 131]);
+        assert_eq!(nv12.fence_value, 7);
+    }
+}
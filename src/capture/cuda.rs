@@ -0,0 +1,225 @@
+//! CUDA arrays of the shared frames by the CUDA runtime's D3D11 interop.
+//!
+//! Required features: *`"cuda"`*
+
+use std::{
+    collections::HashMap,
+    ffi::{
+        c_void,
+        CStr,
+    },
+    marker::PhantomData,
+    os::raw::{
+        c_char,
+        c_int,
+        c_uint,
+    },
+};
+use windows::{
+    core::Interface,
+    Win32::Graphics::Direct3D11::ID3D11Resource,
+};
+
+use super::*;
+
+
+/// `cudaStream_t`. null is the default stream.
+pub type CudaStream = *mut c_void;
+/// `cudaArray_t`.
+pub type CudaArray = *mut c_void;
+
+type CudaGraphicsResource = *mut c_void;
+
+const CUDA_SUCCESS: c_int = 0;
+const CUDA_GRAPHICS_REGISTER_FLAGS_NONE: c_uint = 0;
+const CUDA_GRAPHICS_MAP_FLAGS_READ_ONLY: c_uint = 1;
+const CUDA_MEMCPY_DEVICE_TO_DEVICE: c_int = 3;
+
+#[link(name = "cudart")]
+extern "C" {
+    fn cudaGraphicsD3D11RegisterResource(resource: *mut CudaGraphicsResource, d3d_resource: *mut c_void, flags: c_uint) -> c_int;
+    fn cudaGraphicsResourceSetMapFlags(resource: CudaGraphicsResource, flags: c_uint) -> c_int;
+    fn cudaGraphicsUnregisterResource(resource: CudaGraphicsResource) -> c_int;
+    fn cudaGraphicsMapResources(count: c_int, resources: *mut CudaGraphicsResource, stream: CudaStream) -> c_int;
+    fn cudaGraphicsUnmapResources(count: c_int, resources: *mut CudaGraphicsResource, stream: CudaStream) -> c_int;
+    fn cudaGraphicsSubResourceGetMappedArray(array: *mut CudaArray, resource: CudaGraphicsResource, array_index: c_uint, mip_level: c_uint) -> c_int;
+    fn cudaMemcpy2DFromArrayAsync(
+        dst: *mut c_void, dst_pitch: usize,
+        src: CudaArray, offset_x: usize, offset_y: usize,
+        width: usize, height: usize,
+        kind: c_int, stream: CudaStream,
+    ) -> c_int;
+    fn cudaGetErrorString(error: c_int) -> *const c_char;
+}
+
+fn check(name: &str, error: c_int) -> anyhow::Result<(), CaptureError> {
+    if error == CUDA_SUCCESS {
+        return Ok(());
+    }
+
+    let message = unsafe { CStr::from_ptr(cudaGetErrorString(error)) };
+    Err(CaptureError::CudaError(format!("{}: {} ({})", name, message.to_string_lossy(), error)))
+}
+
+
+#[derive(Debug)]
+struct RegisteredResource {
+    resource: CudaGraphicsResource,
+    width: u32,
+    height: u32,
+}
+
+
+/// Use the frames shared by [Capture::share_frames] as CUDA arrays, for inference and custom kernels on the GPU.
+///
+/// the shared textures of the [Capture] are registered by `cudaGraphicsD3D11RegisterResource`, each once,
+/// and kept until the frame size changes. the current CUDA device must be on the same adapter as the captured [Device].
+///
+/// links `cudart`. the `lib/x64` of `CUDA_PATH` is searched by the build script.
+///
+/// Required features: *`"cuda"`*
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// capture.share_frames(3).unwrap();
+///
+/// let mut interop = dxcapture::CudaInterop::new();
+/// # let (input, pitch): (*mut std::ffi::c_void, usize) = (std::ptr::null_mut(), 0);
+/// {
+///     let frame = interop.map(&capture, std::ptr::null_mut()).expect("Failed to map");
+///     // BGRA8 rows into a cudaMallocPitch buffer for the model input
+///     frame.copy_to(input, pitch).unwrap();
+/// } // unmapped, back to the capture
+/// ```
+#[derive(Debug, Default)]
+pub struct CudaInterop {
+    resources: HashMap<u64, RegisteredResource>,
+}
+impl CudaInterop {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the texture of the latest shared frame on `stream`. unmapped by dropping the [CudaFrame].
+    ///
+    /// the work of D3D11 on the texture is done before the work on `stream` after this.
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Capture::share_frames).
+    /// * [CudaError](CaptureError::CudaError): failed to register or map, as on another adapter.
+    pub fn map(&mut self, capture: &Capture, stream: CudaStream) -> anyhow::Result<CudaFrame<'_>, CaptureError> {
+        let (frame, texture) = {
+            // through shared_frame to check the state
+            let frame = capture.shared_frame()?;
+            capture.shared.lock().unwrap().latest_texture()
+                .filter(|(latest, _)| latest.texture_id == frame.texture_id)
+                .ok_or(CaptureError::NoTexture)?
+        };
+
+        // the textures are made again on resize
+        if self.resources.values().any(|registered| registered.width != frame.width || registered.height != frame.height) {
+            self.clear();
+        }
+        if !self.resources.contains_key(&frame.texture_id) {
+            let registered = Self::register(&texture, &frame)?;
+            self.resources.insert(frame.texture_id, registered);
+        }
+
+        let mut resource = self.resources[&frame.texture_id].resource;
+        let mut array = std::ptr::null_mut();
+        unsafe {
+            check("cudaGraphicsMapResources", cudaGraphicsMapResources(1, &mut resource, stream))?;
+            if let Err(e) = check("cudaGraphicsSubResourceGetMappedArray", cudaGraphicsSubResourceGetMappedArray(&mut array, resource, 0, 0)) {
+                cudaGraphicsUnmapResources(1, &mut resource, stream);
+                return Err(e);
+            }
+        }
+
+        Ok(CudaFrame{
+            array,
+            width: frame.width,
+            height: frame.height,
+            fence_value: frame.fence_value,
+            resource,
+            stream,
+            _interop: PhantomData,
+        })
+    }
+
+    fn register(texture: &ID3D11Texture2D, frame: &SharedFrame) -> anyhow::Result<RegisteredResource, CaptureError> {
+        let texture: ID3D11Resource = texture.cast().map_err(|e| CaptureError::DirectxError(e))?;
+
+        let mut resource = std::ptr::null_mut();
+        unsafe {
+            check(
+                "cudaGraphicsD3D11RegisterResource",
+                cudaGraphicsD3D11RegisterResource(&mut resource, std::mem::transmute_copy(&texture), CUDA_GRAPHICS_REGISTER_FLAGS_NONE),
+            )?;
+            if let Err(e) = check("cudaGraphicsResourceSetMapFlags", cudaGraphicsResourceSetMapFlags(resource, CUDA_GRAPHICS_MAP_FLAGS_READ_ONLY)) {
+                cudaGraphicsUnregisterResource(resource);
+                return Err(e);
+            }
+        }
+
+        Ok(RegisteredResource{
+            resource,
+            width: frame.width,
+            height: frame.height,
+        })
+    }
+
+    /// Unregister the textures. registered again on the next [map](Self::map).
+    pub fn clear(&mut self) {
+        for (_, registered) in self.resources.drain() {
+            unsafe {
+                cudaGraphicsUnregisterResource(registered.resource);
+            }
+        }
+    }
+}
+impl Drop for CudaInterop {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+
+/// Shared frame mapped for CUDA. [Read more](CudaInterop::map)
+pub struct CudaFrame<'a> {
+    /// BGRA8 array, for texture and surface objects.
+    pub array: CudaArray,
+    pub width: u32,
+    pub height: u32,
+    /// same as [SharedFrame::fence_value].
+    pub fence_value: u64,
+    resource: CudaGraphicsResource,
+    stream: CudaStream,
+    _interop: PhantomData<&'a mut CudaInterop>,
+}
+impl CudaFrame<'_> {
+    /// Copy the frame into linear device memory on the stream of [map](CudaInterop::map).
+    /// `width * 4` bytes of each row go to `device_ptr + row * pitch`.
+    pub fn copy_to(&self, device_ptr: *mut c_void, pitch: usize) -> anyhow::Result<(), CaptureError> {
+        let row_bytes = self.width as usize * 4;
+        if pitch < row_bytes {
+            return Err(CaptureError::InvalidArgument(format!("pitch must be {} or more.", row_bytes)));
+        }
+
+        unsafe {
+            check("cudaMemcpy2DFromArrayAsync", cudaMemcpy2DFromArrayAsync(
+                device_ptr, pitch,
+                self.array, 0, 0,
+                row_bytes, self.height as usize,
+                CUDA_MEMCPY_DEVICE_TO_DEVICE, self.stream,
+            ))
+        }
+    }
+}
+impl Drop for CudaFrame<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            cudaGraphicsUnmapResources(1, &mut self.resource, self.stream);
+        }
+    }
+}
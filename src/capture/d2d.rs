@@ -0,0 +1,88 @@
+//! Direct2D bitmaps of the shared frames.
+//!
+//! Required features: *`"d2d"`*
+
+use windows::{
+    core::Interface,
+    Win32::Graphics::{
+        Direct2D::{
+            Common::{
+                D2D1_ALPHA_MODE_IGNORE,
+                D2D1_PIXEL_FORMAT,
+            },
+            D2D1CreateDevice,
+            ID2D1Bitmap1,
+            ID2D1DeviceContext,
+            D2D1_BITMAP_OPTIONS_NONE,
+            D2D1_BITMAP_PROPERTIES1,
+            D2D1_DEVICE_CONTEXT_OPTIONS_NONE,
+        },
+        Direct3D11::D3D11_BIND_SHADER_RESOURCE,
+        Dxgi::{
+            IDXGIDevice,
+            IDXGISurface,
+        },
+    },
+};
+
+use super::*;
+
+
+impl Capture {
+    /// Direct2D device context on the device of the capture, for [get_d2d_bitmap](Self::get_d2d_bitmap).
+    ///
+    /// Required features: *`"d2d"`*
+    /// ## Errors
+    /// * [DirectxError](CaptureError::DirectxError): failed to create the D2D device.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d2d")))]
+    pub fn create_d2d_context(&self) -> anyhow::Result<ID2D1DeviceContext, CaptureError> {
+        unsafe {
+            let dxgi_device = self._d3d_device.cast::<IDXGIDevice>().map_err(|e| CaptureError::DirectxError(e))?;
+
+            D2D1CreateDevice(dxgi_device, std::ptr::null())
+                .and_then(|device| device.CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE))
+                .map_err(|e| CaptureError::DirectxError(e))
+        }
+    }
+
+    /// Copy the latest shared frame into a new `ID2D1Bitmap1` of `context` on the GPU, to draw it without reading it back.
+    ///
+    /// the bitmap keeps the frame, the capture goes on with the shared textures.
+    /// `context` must be on the device of the capture, as by [create_d2d_context](Self::create_d2d_context).
+    ///
+    /// Required features: *`"d2d"`*
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing by [share_frames](Self::share_frames).
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Self::share_frames).
+    /// * [DirectxError](CaptureError::DirectxError): failed to copy, or `context` is on another device.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(2).unwrap();
+    ///
+    /// let context = capture.create_d2d_context().unwrap();
+    /// let bitmap = capture.get_d2d_bitmap(&context).expect("Failed to capture");
+    /// // context.SetTarget(...), BeginDraw(), DrawBitmap2(&bitmap, ...), EndDraw()
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d2d")))]
+    pub fn get_d2d_bitmap(&self, context: &ID2D1DeviceContext) -> anyhow::Result<ID2D1Bitmap1, CaptureError> {
+        let (frame, texture) = self.copy_latest_texture(D3D11_BIND_SHADER_RESOURCE)?;
+
+        unsafe {
+            let surface = texture.cast::<IDXGISurface>().map_err(|e| CaptureError::DirectxError(e))?;
+            let properties = D2D1_BITMAP_PROPERTIES1{
+                pixelFormat: D2D1_PIXEL_FORMAT{
+                    format: frame.format,
+                    alphaMode: D2D1_ALPHA_MODE_IGNORE,
+                },
+                dpiX: 96.0,
+                dpiY: 96.0,
+                bitmapOptions: D2D1_BITMAP_OPTIONS_NONE,
+                colorContext: None,
+            };
+
+            context.CreateBitmapFromDxgiSurface(surface, &properties).map_err(|e| CaptureError::DirectxError(e))
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Frames as D3D12 resources of a D3D11On12 device.
+//!
+//! Required features: *`"d3d11on12"`*
+
+use windows::{
+    core::Interface,
+    Win32::Graphics::{
+        Direct3D11on12::ID3D11On12Device2,
+        Direct3D12::{
+            ID3D12CommandQueue,
+            ID3D12Resource,
+        },
+    },
+};
+
+use super::*;
+
+
+/// Frame unwrapped into the D3D12 resource under it. [Read more](Capture::get_d3d12_frame)
+///
+/// the resource is given back to D3D11 on drop. finish the work on it before, or on the same queue.
+///
+/// Required features: *`"d3d11on12"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+#[derive(Debug)]
+pub struct D3D12Frame {
+    /// in `D3D12_RESOURCE_STATE_COMMON`.
+    pub resource: ID3D12Resource,
+    pub width: u32,
+    pub height: u32,
+    /// `DXGI_FORMAT` of the resource.
+    pub format: u32,
+    texture: ID3D11Texture2D,
+    device: ID3D11On12Device2,
+}
+impl Drop for D3D12Frame {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.device.ReturnUnderlyingResource(&self.texture, 0, std::ptr::null(), std::ptr::null());
+        }
+    }
+}
+
+
+impl Capture {
+    /// The current frame as a D3D12 resource of the device made by [on_d3d12](crate::Device::on_d3d12),
+    /// so the engines on D3D12 use it without a D3D11 device of their own, nor copies between devices.
+    ///
+    /// the frame is copied on the GPU as [get_texture](Self::get_texture), and unwrapped for `command_queue`,
+    /// which waits for the copy. the resource is never written by the capture again.
+    ///
+    /// Required features: *`"d3d11on12"`*
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the device is not made by `on_d3d12`, or the Windows is older than 10 2004.
+    /// * [DirectxError](CaptureError::DirectxError): failed to unwrap.
+    /// * the errors of [get_texture](Self::get_texture).
+    /// # Examples
+    /// ```
+    /// # fn app() -> (windows::Win32::Graphics::Direct3D12::ID3D12Device, windows::Win32::Graphics::Direct3D12::ID3D12CommandQueue) { unimplemented!() }
+    /// let (d3d12_device, command_queue) = app();
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap()
+    ///     .on_d3d12(&d3d12_device, &command_queue).expect("No D3D11On12");
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let frame = capture.wait_d3d12_frame(&command_queue).unwrap();
+    /// // record a copy from frame.resource, execute it on command_queue, then drop the frame
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+    pub fn get_d3d12_frame(&self, command_queue: &ID3D12CommandQueue) -> anyhow::Result<D3D12Frame, CaptureError> {
+        let device = self._d3d_device.cast::<ID3D11On12Device2>()
+            .map_err(|_| CaptureError::InvalidArgument("device is not on D3D12.".to_string()))?;
+        let texture = self.get_texture()?;
+
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        let resource = unsafe {
+            texture.GetDesc(&mut desc);
+            device.UnwrapUnderlyingResource::<_, _, ID3D12Resource>(&texture, command_queue)
+                .map_err(|e| CaptureError::DirectxError(e))?
+        };
+
+        Ok(D3D12Frame{
+            resource,
+            width: desc.Width,
+            height: desc.Height,
+            format: desc.Format,
+            texture,
+            device,
+        })
+    }
+
+    /// The current frame as a D3D12 resource. with throught NoTexture
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+    pub fn wait_d3d12_frame(&self, command_queue: &ID3D12CommandQueue) -> anyhow::Result<D3D12Frame, CaptureError> {
+        self.wait_frame_with(None, |capture| capture.get_d3d12_frame(command_queue))
+    }
+}
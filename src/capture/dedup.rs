@@ -0,0 +1,248 @@
+//! Suppression of the frames identical to the previous one, by a hash computed on the GPU.
+//!
+//! Required features: *`"dedup"`*
+
+use windows::Win32::Graphics::{
+    Direct3D::{
+        Fxc::D3DCompile,
+        ID3DBlob,
+    },
+    Direct3D11::{
+        ID3D11Buffer,
+        ID3D11ComputeShader,
+        ID3D11Device,
+        ID3D11DeviceContext,
+        ID3D11ShaderResourceView,
+        ID3D11Texture2D,
+        ID3D11UnorderedAccessView,
+        D3D11_BIND_UNORDERED_ACCESS,
+        D3D11_BOX,
+        D3D11_BUFFER_DESC,
+        D3D11_CPU_ACCESS_READ,
+        D3D11_MAP_READ,
+        D3D11_RESOURCE_MISC_BUFFER_STRUCTURED,
+        D3D11_USAGE_DEFAULT,
+        D3D11_USAGE_STAGING,
+    },
+};
+
+use super::*;
+
+
+/// Sum and xor of a mixed hash of every pixel with its position, reduced in each group first.
+const HASH_SHADER: &str = r#"
+Texture2D<float4> frame : register(t0);
+RWStructuredBuffer<uint> hash : register(u0);
+
+groupshared uint group_sum;
+groupshared uint group_xor;
+
+uint pcg(uint v) {
+    uint state = v * 747796405u + 2891336453u;
+    uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+[numthreads(16, 16, 1)]
+void main(uint3 id : SV_DispatchThreadID, uint index : SV_GroupIndex) {
+    if (index == 0) {
+        group_sum = 0;
+        group_xor = 0;
+    }
+    GroupMemoryBarrierWithGroupSync();
+
+    uint width, height;
+    frame.GetDimensions(width, height);
+    if (id.x < width && id.y < height) {
+        uint4 pixel = asuint(frame.Load(int3(id.xy, 0)));
+        uint h = pcg(id.y * width + id.x);
+        h = pcg(h ^ pixel.r);
+        h = pcg(h ^ pixel.g);
+        h = pcg(h ^ pixel.b);
+        h = pcg(h ^ pixel.a);
+        InterlockedAdd(group_sum, h);
+        InterlockedXor(group_xor, pcg(h));
+    }
+    GroupMemoryBarrierWithGroupSync();
+
+    if (index == 0) {
+        InterlockedAdd(hash[0], group_sum);
+        InterlockedXor(hash[1], group_xor);
+    }
+}
+"#;
+
+/// Threads of a group in each direction, as `numthreads` of the shader.
+const GROUP_SIZE: u32 = 16;
+
+
+/// Compute shader hashing the frames, shared with the frame arrived handler.
+#[derive(Debug)]
+pub(crate) struct DedupState {
+    shader: ID3D11ComputeShader,
+    hash: ID3D11Buffer,
+    hash_view: ID3D11UnorderedAccessView,
+    readback: ID3D11Buffer,
+    /// hash and region of the last frame delivered.
+    last: Option<(u64, Option<(u32, u32, u32, u32)>)>,
+    /// frames suppressed since enabled.
+    suppressed: u64,
+}
+impl DedupState {
+    fn new(d3d_device: &ID3D11Device) -> windows::core::Result<Self> {
+        unsafe {
+            let mut code: Option<ID3DBlob> = None;
+            let mut errors: Option<ID3DBlob> = None;
+            D3DCompile(
+                HASH_SHADER.as_ptr() as *const _,
+                HASH_SHADER.len(),
+                "dedup",
+                std::ptr::null(),
+                None,
+                "main",
+                "cs_5_0",
+                0,
+                0,
+                &mut code,
+                &mut errors,
+            )?;
+            let code = code.unwrap();
+            let shader = d3d_device.CreateComputeShader(code.GetBufferPointer(), code.GetBufferSize(), None)?;
+
+            let mut desc = D3D11_BUFFER_DESC{
+                ByteWidth: 8,
+                Usage: D3D11_USAGE_DEFAULT,
+                BindFlags: D3D11_BIND_UNORDERED_ACCESS,
+                CPUAccessFlags: 0,
+                MiscFlags: D3D11_RESOURCE_MISC_BUFFER_STRUCTURED,
+                StructureByteStride: 4,
+            };
+            let hash = d3d_device.CreateBuffer(&desc, std::ptr::null())?;
+            let hash_view = d3d_device.CreateUnorderedAccessView(&hash, std::ptr::null())?;
+
+            desc.Usage = D3D11_USAGE_STAGING;
+            desc.BindFlags = 0;
+            desc.CPUAccessFlags = D3D11_CPU_ACCESS_READ;
+            let readback = d3d_device.CreateBuffer(&desc, std::ptr::null())?;
+
+            Ok(Self{
+                shader,
+                hash,
+                hash_view,
+                readback,
+                last: None,
+                suppressed: 0,
+            })
+        }
+    }
+
+    /// Hash of the pixels of `texture`, waiting for the GPU. only 8 bytes are read back.
+    fn hash(&self, d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, texture: &ID3D11Texture2D) -> windows::core::Result<u64> {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut desc);
+        }
+
+        unsafe {
+            // fails on the textures not bound as shader resources
+            let view: ID3D11ShaderResourceView = d3d_device.CreateShaderResourceView(texture, std::ptr::null())?;
+
+            d3d_context.ClearUnorderedAccessViewUint(&self.hash_view, [0u32; 4].as_ptr());
+            d3d_context.CSSetShader(&self.shader, std::ptr::null(), 0);
+            d3d_context.CSSetShaderResources(0, 1, &Some(view));
+            d3d_context.CSSetUnorderedAccessViews(0, 1, &Some(self.hash_view.clone()), std::ptr::null());
+            d3d_context.Dispatch((desc.Width + GROUP_SIZE - 1) / GROUP_SIZE, (desc.Height + GROUP_SIZE - 1) / GROUP_SIZE, 1);
+            // unbound, so the frame is free for the copies
+            d3d_context.CSSetShaderResources(0, 1, &None);
+            d3d_context.CSSetUnorderedAccessViews(0, 1, &None, std::ptr::null());
+            d3d_context.CSSetShader(None, std::ptr::null(), 0);
+
+            d3d_context.CopyResource(&self.readback, &self.hash);
+            let mapped = d3d_context.Map(&self.readback, 0, D3D11_MAP_READ, 0)?;
+            let words = std::slice::from_raw_parts(mapped.pData as *const u32, 2);
+            let hash = ((words[0] as u64) << 32) | words[1] as u64;
+            d3d_context.Unmap(&self.readback, 0);
+
+            Ok(hash)
+        }
+    }
+
+    /// Whether the frame in `texture` cut to `region` is the same as the last one delivered.
+    /// a frame which can't be hashed is never a duplicate.
+    pub(crate) fn is_duplicate(&mut self, d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, texture: &ID3D11Texture2D, region: Option<&D3D11_BOX>) -> bool {
+        let hash = match self.hash(d3d_device, d3d_context, texture) {
+            Ok(hash) => hash,
+            Err(_) => {
+                self.last = None;
+                return false;
+            },
+        };
+        let key = (hash, region.map(|region| (region.left, region.top, region.right, region.bottom)));
+
+        if self.last == Some(key) {
+            self.suppressed += 1;
+            return true;
+        }
+        self.last = Some(key);
+        false
+    }
+}
+
+
+impl Capture {
+    /// Suppress the frames identical to the previous one, for the static desktops producing the same frame over and over.
+    ///
+    /// each frame is hashed by a compute shader and dropped in the frame arrived handler before the staging copy,
+    /// when the hash is the same as the last delivered one. so the waits keep waiting, [get_raw_frame_if_new](Self::get_raw_frame_if_new)
+    /// returns `Ok(None)`, and neither the callbacks, the streams nor the recorders get them. the frames of the zoom,
+    /// which are not bound as shader resources, are delivered as usual.
+    ///
+    /// hashing costs a small dispatch and a wait for the GPU on every frame. a collision of the 64 bits hash
+    /// drops a changed frame, so don't rely on it for the frames which must never be lost.
+    ///
+    /// Required features: *`"dedup"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.set_skip_duplicates(true).unwrap();
+    ///
+    /// loop {
+    ///     // only the frames with something changed
+    ///     let raw = capture.wait_raw_frame().unwrap();
+    /// }
+    /// ```
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    /// * [DirectxError](CaptureError::DirectxError): the shader failed to compile, as without `d3dcompiler_47.dll`.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "dedup")))]
+    pub fn set_skip_duplicates(&self, enabled: bool) -> anyhow::Result<(), CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+
+        let mut dedup = self.dedup.lock().unwrap();
+        if enabled == dedup.is_some() {
+            return Ok(());
+        }
+        *dedup = if enabled {
+            Some(DedupState::new(&self._d3d_device).map_err(|e| CaptureError::DirectxError(e))?)
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+
+    /// The duplicate frames are suppressed by [set_skip_duplicates](Self::set_skip_duplicates).
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "dedup")))]
+    pub fn is_skipping_duplicates(&self) -> bool {
+        self.dedup.lock().unwrap().is_some()
+    }
+
+    /// Count of the frames suppressed since [set_skip_duplicates](Self::set_skip_duplicates) enabled it.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "dedup")))]
+    pub fn skipped_duplicates(&self) -> u64 {
+        self.dedup.lock().unwrap().as_ref().map_or(0, |dedup| dedup.suppressed)
+    }
+}
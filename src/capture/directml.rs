@@ -0,0 +1,699 @@
+//! Preprocessing of the shared frames into model inputs with DirectML.
+//!
+//! Required features: *`"directml"`*
+
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+    mem::ManuallyDrop,
+};
+use windows::{
+    core::Interface,
+    Win32::{
+        AI::MachineLearning::DirectML::*,
+        Foundation::{
+            CloseHandle,
+            HANDLE,
+            PSTR,
+            PWSTR,
+        },
+        Graphics::{
+            Direct3D::D3D_FEATURE_LEVEL_11_0,
+            Direct3D12::*,
+            Dxgi::{
+                Common::{
+                    DXGI_FORMAT_B8G8R8A8_UNORM,
+                    DXGI_FORMAT_UNKNOWN,
+                    DXGI_SAMPLE_DESC,
+                },
+                IDXGIDevice,
+            },
+        },
+        System::{
+            Threading::{
+                CreateEventW,
+                WaitForSingleObject,
+            },
+            WindowsProgramming::INFINITE,
+        },
+    },
+};
+
+use super::*;
+use crate::tensor::TensorOptions;
+
+
+/// offsets of the constant inputs in the constant buffer. DirectML wants 16 bytes aligned.
+const INDICES_OFFSET: u64 = 0;
+const MEAN_OFFSET: u64 = 16;
+const VARIANCE_OFFSET: u64 = 32;
+const SCALE_OFFSET: u64 = 48;
+const CONSTANTS_SIZE: u64 = 64;
+/// 3 of `u32` or `f32`.
+const CONSTANT_SIZE: u64 = 12;
+
+
+fn create_buffer(device: &ID3D12Device, size: u64, heap_type: D3D12_HEAP_TYPE, state: D3D12_RESOURCE_STATES, flags: D3D12_RESOURCE_FLAGS) -> windows::core::Result<ID3D12Resource> {
+    let heap = D3D12_HEAP_PROPERTIES{
+        Type: heap_type,
+        ..Default::default()
+    };
+    let desc = D3D12_RESOURCE_DESC{
+        Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+        Alignment: 0,
+        Width: size,
+        Height: 1,
+        DepthOrArraySize: 1,
+        MipLevels: 1,
+        Format: DXGI_FORMAT_UNKNOWN,
+        SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+        Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+        Flags: flags,
+    };
+
+    unsafe {
+        let mut buffer: Option<ID3D12Resource> = None;
+        device.CreateCommittedResource(&heap, D3D12_HEAP_FLAG_NONE, &desc, state, std::ptr::null(), &mut buffer)?;
+
+        Ok(buffer.unwrap())
+    }
+}
+
+fn transition(resource: &ID3D12Resource, before: D3D12_RESOURCE_STATES, after: D3D12_RESOURCE_STATES) -> D3D12_RESOURCE_BARRIER {
+    D3D12_RESOURCE_BARRIER{
+        Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0{
+            Transition: ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER{
+                // borrowed, never released
+                pResource: unsafe { std::mem::transmute_copy(resource) },
+                Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+                StateBefore: before,
+                StateAfter: after,
+            }),
+        },
+    }
+}
+
+fn buffer_binding(buffer: &ID3D12Resource, offset: u64, size: u64) -> DML_BUFFER_BINDING {
+    DML_BUFFER_BINDING{
+        Buffer: Some(buffer.clone()),
+        Offset: offset,
+        SizeInBytes: size,
+    }
+}
+
+fn binding_desc(binding: &DML_BUFFER_BINDING) -> DML_BINDING_DESC {
+    DML_BINDING_DESC{
+        Type: DML_BINDING_TYPE_BUFFER,
+        Desc: binding as *const _ as *mut c_void,
+    }
+}
+
+
+/// 4D buffer tensor. `sizes` and `strides` are kept to be pointed by the desc.
+struct Tensor {
+    data_type: DML_TENSOR_DATA_TYPE,
+    sizes: [u32; 4],
+    strides: Option<[u32; 4]>,
+    buffer: DML_BUFFER_TENSOR_DESC,
+}
+impl Tensor {
+    fn new(data_type: DML_TENSOR_DATA_TYPE, sizes: [u32; 4], strides: Option<[u32; 4]>) -> Box<Self> {
+        let element_size = if data_type == DML_TENSOR_DATA_TYPE_UINT8 { 1 } else { 4 };
+        let strides_or_packed = strides.unwrap_or([sizes[1] * sizes[2] * sizes[3], sizes[2] * sizes[3], sizes[3], 1]);
+        let last_index: u64 = sizes.iter().zip(strides_or_packed.iter()).map(|(&size, &stride)| (size as u64 - 1) * stride as u64).sum();
+        // in 4 bytes
+        let total_size = ((last_index + 1) * element_size + 3) & !3;
+
+        let mut tensor = Box::new(Self{
+            data_type,
+            sizes,
+            strides,
+            buffer: DML_BUFFER_TENSOR_DESC::default(),
+        });
+        tensor.buffer = DML_BUFFER_TENSOR_DESC{
+            DataType: tensor.data_type,
+            Flags: DML_TENSOR_FLAG_NONE,
+            DimensionCount: 4,
+            Sizes: tensor.sizes.as_mut_ptr(),
+            Strides: tensor.strides.as_mut().map_or(std::ptr::null_mut(), |strides| strides.as_mut_ptr()),
+            TotalTensorSizeInBytes: total_size,
+            GuaranteedBaseOffsetAlignment: 0,
+        };
+
+        tensor
+    }
+
+    fn desc(&mut self) -> DML_TENSOR_DESC {
+        DML_TENSOR_DESC{
+            Type: DML_TENSOR_TYPE_BUFFER,
+            Desc: &mut self.buffer as *mut _ as *mut c_void,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        self.buffer.TotalTensorSizeInBytes
+    }
+}
+
+fn create_operator<T>(device: &IDMLDevice1, operator_type: DML_OPERATOR_TYPE, desc: &mut T) -> windows::core::Result<IDMLOperator> {
+    let desc = DML_OPERATOR_DESC{
+        Type: operator_type,
+        Desc: desc as *mut T as *mut c_void,
+    };
+
+    unsafe {
+        let mut operator: Option<IDMLOperator> = None;
+        device.CreateOperator(&desc, &mut operator)?;
+
+        Ok(operator.unwrap())
+    }
+}
+
+
+/// Compiled preprocessing for one input size.
+struct DmlPipeline {
+    source: Rect,
+    row_pitch: u32,
+    /// BGRA8 rows of the source, copied from the shared texture.
+    input: ID3D12Resource,
+    compiled: IDMLCompiledOperator,
+    descriptor_heap: ID3D12DescriptorHeap,
+    binding_table: IDMLBindingTable,
+    _temporary: Option<ID3D12Resource>,
+    _persistent: Option<ID3D12Resource>,
+}
+
+
+/// Resize, crop, convert to NCHW `f32` and normalize the frames on the GPU with DirectML,
+/// into tensors ready for ONNX models.
+///
+/// the frames shared by [Capture::share_frames] are opened on a D3D12 device made on the adapter of the [Capture],
+/// and go through a DirectML graph (cast, BGR to RGB, bilinear resample, per channel normalization).
+/// nothing is read back until [read_tensor](Self::read_tensor): give [device](Self::device),
+/// [command_queue](Self::command_queue) and [output](Self::output) to the DirectML execution provider
+/// of ONNX Runtime to keep the tensors on the GPU.
+///
+/// needs DirectML 1.1 (Windows 10 2004, or the redistributable `DirectML.dll`).
+///
+/// Required features: *`"directml"`*
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// capture.share_frames(3).unwrap();
+///
+/// let mut preprocessor = dxcapture::DmlPreprocessor::new(&capture, dxcapture::TensorOptions::imagenet(224, 224)).unwrap();
+/// preprocessor.process(&capture).expect("Failed to preprocess");
+///
+/// // [1, 3, 224, 224]
+/// let tensor = preprocessor.read_tensor().unwrap();
+/// ```
+pub struct DmlPreprocessor {
+    options: TensorOptions,
+    device: ID3D12Device,
+    dml_device: IDMLDevice1,
+    queue: ID3D12CommandQueue,
+    allocator: ID3D12CommandAllocator,
+    list: ID3D12GraphicsCommandList,
+    recorder: IDMLCommandRecorder,
+    fence: ID3D12Fence,
+    fence_value: u64,
+    event: HANDLE,
+    constants: ID3D12Resource,
+    output: ID3D12Resource,
+    output_size: u64,
+    readback: ID3D12Resource,
+    pipeline: Option<DmlPipeline>,
+    /// opened shared textures by id, and their size.
+    textures: HashMap<u64, ID3D12Resource>,
+    texture_size: (u32, u32),
+    /// fence handle of the capture and the opened fence.
+    capture_fence: Option<(isize, ID3D12Fence)>,
+}
+impl DmlPreprocessor {
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): empty size, or 0 in `std`.
+    /// * [DirectxError](CaptureError::DirectxError): no D3D12 or DirectML on the adapter.
+    pub fn new(capture: &Capture, options: TensorOptions) -> anyhow::Result<Self, CaptureError> {
+        // validate all but the crop, which depends on the frame
+        options.source_rect(i32::MAX, i32::MAX)?;
+
+        Self::create(capture, options).map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    fn create(capture: &Capture, options: TensorOptions) -> windows::core::Result<Self> {
+        unsafe {
+            // the same adapter as the capture
+            let adapter = capture._d3d_device.cast::<IDXGIDevice>()?.GetAdapter()?;
+            let mut device: Option<ID3D12Device> = None;
+            D3D12CreateDevice(&adapter, D3D_FEATURE_LEVEL_11_0, &mut device)?;
+            let device = device.unwrap();
+
+            let mut dml_device: Option<IDMLDevice1> = None;
+            DMLCreateDevice(&device, DML_CREATE_DEVICE_FLAG_NONE, &mut dml_device)?;
+            let dml_device = dml_device.unwrap();
+
+            let queue: ID3D12CommandQueue = device.CreateCommandQueue(&D3D12_COMMAND_QUEUE_DESC{
+                Type: D3D12_COMMAND_LIST_TYPE_DIRECT,
+                Priority: 0,
+                Flags: D3D12_COMMAND_QUEUE_FLAG_NONE,
+                NodeMask: 0,
+            })?;
+            let allocator: ID3D12CommandAllocator = device.CreateCommandAllocator(D3D12_COMMAND_LIST_TYPE_DIRECT)?;
+            let list: ID3D12GraphicsCommandList = device.CreateCommandList(0, D3D12_COMMAND_LIST_TYPE_DIRECT, &allocator, None::<ID3D12PipelineState>)?;
+            list.Close()?;
+            let recorder: IDMLCommandRecorder = dml_device.CreateCommandRecorder()?;
+            let fence: ID3D12Fence = device.CreateFence(0, D3D12_FENCE_FLAG_NONE)?;
+            let event = CreateEventW(std::ptr::null(), false, false, PWSTR::default());
+
+            let output_size = 3 * options.width as u64 * options.height as u64 * 4;
+            let output = create_buffer(&device, output_size, D3D12_HEAP_TYPE_DEFAULT, D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS)?;
+            let readback = create_buffer(&device, output_size, D3D12_HEAP_TYPE_READBACK, D3D12_RESOURCE_STATE_COPY_DEST, D3D12_RESOURCE_FLAG_NONE)?;
+            let constants = create_buffer(&device, CONSTANTS_SIZE, D3D12_HEAP_TYPE_DEFAULT, D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS)?;
+
+            let mut preprocessor = Self{
+                options,
+                device,
+                dml_device,
+                queue,
+                allocator,
+                list,
+                recorder,
+                fence,
+                fence_value: 0,
+                event,
+                constants,
+                output,
+                output_size,
+                readback,
+                pipeline: None,
+                textures: HashMap::new(),
+                texture_size: (0, 0),
+                capture_fence: None,
+            };
+            preprocessor.upload_constants()?;
+
+            Ok(preprocessor)
+        }
+    }
+
+    /// Gather indices of BGR to RGB, and the normalization on the 0..255 scale.
+    fn upload_constants(&mut self) -> windows::core::Result<()> {
+        let mean = self.options.mean.map(|mean| mean * 255.0);
+        let variance = self.options.std.map(|std| (std * 255.0).powi(2));
+
+        let mut data = [0u8; CONSTANTS_SIZE as usize];
+        let mut write = |offset: u64, values: [[u8; 4]; 3]| {
+            for (i, value) in values.iter().enumerate() {
+                let begin = offset as usize + i * 4;
+                data[begin..begin + 4].copy_from_slice(value);
+            }
+        };
+        write(INDICES_OFFSET, [2u32, 1, 0].map(u32::to_le_bytes));
+        write(MEAN_OFFSET, mean.map(f32::to_le_bytes));
+        write(VARIANCE_OFFSET, variance.map(f32::to_le_bytes));
+        write(SCALE_OFFSET, [1f32; 3].map(f32::to_le_bytes));
+
+        let upload = create_buffer(&self.device, CONSTANTS_SIZE, D3D12_HEAP_TYPE_UPLOAD, D3D12_RESOURCE_STATE_GENERIC_READ, D3D12_RESOURCE_FLAG_NONE)?;
+        unsafe {
+            let mut mapped = std::ptr::null_mut();
+            upload.Map(0, std::ptr::null(), &mut mapped)?;
+            std::ptr::copy_nonoverlapping(data.as_ptr(), mapped as *mut u8, data.len());
+            upload.Unmap(0, std::ptr::null());
+
+            self.begin()?;
+            self.list.CopyBufferRegion(&self.constants, 0, &upload, 0, CONSTANTS_SIZE);
+            self.execute()
+        }
+    }
+
+    fn begin(&self) -> windows::core::Result<()> {
+        unsafe {
+            self.allocator.Reset()?;
+            self.list.Reset(&self.allocator, None::<ID3D12PipelineState>)
+        }
+    }
+
+    /// Close the list, execute it and wait for it.
+    fn execute(&mut self) -> windows::core::Result<()> {
+        unsafe {
+            self.list.Close()?;
+            self.queue.ExecuteCommandLists(1, &Some(self.list.cast::<ID3D12CommandList>()?));
+
+            self.fence_value += 1;
+            self.queue.Signal(&self.fence, self.fence_value)?;
+            if self.fence.GetCompletedValue() < self.fence_value {
+                self.fence.SetEventOnCompletion(self.fence_value, self.event)?;
+                WaitForSingleObject(self.event, INFINITE);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build the graph for `source` of the frames and initialize it.
+    fn create_pipeline(&mut self, source: Rect) -> windows::core::Result<DmlPipeline> {
+        let (width, height) = (source.width as u32, source.height as u32);
+        let (out_width, out_height) = (self.options.width, self.options.height);
+        let row_pitch = (width * 4 + D3D12_TEXTURE_DATA_PITCH_ALIGNMENT - 1) / D3D12_TEXTURE_DATA_PITCH_ALIGNMENT * D3D12_TEXTURE_DATA_PITCH_ALIGNMENT;
+
+        // BGRA8 rows seen as [1, 3, H, W] bytes, alpha skipped
+        let mut input = Tensor::new(DML_TENSOR_DATA_TYPE_UINT8, [1, 3, height, width], Some([height * row_pitch, 1, row_pitch, 4]));
+        let mut cast = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, height, width], None);
+        let mut indices = Tensor::new(DML_TENSOR_DATA_TYPE_UINT32, [1, 1, 1, 3], None);
+        let mut rgb = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, height, width], None);
+        let mut resampled = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, out_height, out_width], None);
+        let mut mean = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, 1, 1], None);
+        let mut variance = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, 1, 1], None);
+        let mut scale = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, 1, 1], None);
+        let mut output = Tensor::new(DML_TENSOR_DATA_TYPE_FLOAT32, [1, 3, out_height, out_width], None);
+        let input_size = input.size();
+
+        let (mut input_desc, mut cast_desc, mut indices_desc, mut rgb_desc, mut resampled_desc) =
+            (input.desc(), cast.desc(), indices.desc(), rgb.desc(), resampled.desc());
+        let (mut mean_desc, mut variance_desc, mut scale_desc, mut output_desc) =
+            (mean.desc(), variance.desc(), scale.desc(), output.desc());
+        let mut scales = [1.0, 1.0, out_height as f32 / height as f32, out_width as f32 / width as f32];
+
+        let operators = [
+            create_operator(&self.dml_device, DML_OPERATOR_CAST, &mut DML_CAST_OPERATOR_DESC{
+                InputTensor: &mut input_desc,
+                OutputTensor: &mut cast_desc,
+            })?,
+            create_operator(&self.dml_device, DML_OPERATOR_GATHER, &mut DML_GATHER_OPERATOR_DESC{
+                InputTensor: &mut cast_desc,
+                IndicesTensor: &mut indices_desc,
+                OutputTensor: &mut rgb_desc,
+                Axis: 1,
+                IndexDimensions: 1,
+            })?,
+            create_operator(&self.dml_device, DML_OPERATOR_RESAMPLE, &mut DML_RESAMPLE_OPERATOR_DESC{
+                InputTensor: &mut rgb_desc,
+                OutputTensor: &mut resampled_desc,
+                InterpolationMode: DML_INTERPOLATION_MODE_LINEAR,
+                ScaleCount: scales.len() as u32,
+                Scales: scales.as_mut_ptr(),
+            })?,
+            create_operator(&self.dml_device, DML_OPERATOR_BATCH_NORMALIZATION, &mut DML_BATCH_NORMALIZATION_OPERATOR_DESC{
+                InputTensor: &mut resampled_desc,
+                MeanTensor: &mut mean_desc,
+                VarianceTensor: &mut variance_desc,
+                ScaleTensor: &mut scale_desc,
+                BiasTensor: std::ptr::null_mut(),
+                OutputTensor: &mut output_desc,
+                Spatial: true.into(),
+                Epsilon: 0.0,
+                FusedActivation: std::ptr::null_mut(),
+            })?,
+        ];
+
+        // graph inputs: 0 frame, 1 indices, 2 mean, 3 variance, 4 scale
+        let mut operator_nodes: Vec<DML_OPERATOR_GRAPH_NODE_DESC> = operators.iter()
+            .map(|operator| DML_OPERATOR_GRAPH_NODE_DESC{ Operator: Some(operator.clone()), Name: PSTR::default() })
+            .collect();
+        let mut nodes: Vec<DML_GRAPH_NODE_DESC> = operator_nodes.iter_mut()
+            .map(|node| DML_GRAPH_NODE_DESC{ Type: DML_GRAPH_NODE_TYPE_OPERATOR, Desc: node as *mut _ as *mut c_void })
+            .collect();
+
+        let input_edge = |graph_input: u32, node: u32, node_input: u32| DML_INPUT_GRAPH_EDGE_DESC{
+            GraphInputIndex: graph_input,
+            ToNodeIndex: node,
+            ToNodeInputIndex: node_input,
+            Name: PSTR::default(),
+        };
+        let mut input_edge_descs = [
+            input_edge(0, 0, 0),
+            input_edge(1, 1, 1),
+            input_edge(2, 3, 1),
+            input_edge(3, 3, 2),
+            input_edge(4, 3, 3),
+        ];
+        let intermediate_edge = |from: u32, to: u32| DML_INTERMEDIATE_GRAPH_EDGE_DESC{
+            FromNodeIndex: from,
+            FromNodeOutputIndex: 0,
+            ToNodeIndex: to,
+            ToNodeInputIndex: 0,
+            Name: PSTR::default(),
+        };
+        let mut intermediate_edge_descs = [
+            intermediate_edge(0, 1),
+            intermediate_edge(1, 2),
+            intermediate_edge(2, 3),
+        ];
+        let mut output_edge_desc = DML_OUTPUT_GRAPH_EDGE_DESC{
+            FromNodeIndex: 3,
+            FromNodeOutputIndex: 0,
+            GraphOutputIndex: 0,
+            Name: PSTR::default(),
+        };
+
+        let mut input_edges: Vec<DML_GRAPH_EDGE_DESC> = input_edge_descs.iter_mut()
+            .map(|edge| DML_GRAPH_EDGE_DESC{ Type: DML_GRAPH_EDGE_TYPE_INPUT, Desc: edge as *mut _ as *mut c_void })
+            .collect();
+        let mut intermediate_edges: Vec<DML_GRAPH_EDGE_DESC> = intermediate_edge_descs.iter_mut()
+            .map(|edge| DML_GRAPH_EDGE_DESC{ Type: DML_GRAPH_EDGE_TYPE_INTERMEDIATE, Desc: edge as *mut _ as *mut c_void })
+            .collect();
+        let mut output_edges = [DML_GRAPH_EDGE_DESC{ Type: DML_GRAPH_EDGE_TYPE_OUTPUT, Desc: &mut output_edge_desc as *mut _ as *mut c_void }];
+
+        let graph = DML_GRAPH_DESC{
+            InputCount: input_edges.len() as u32,
+            OutputCount: 1,
+            NodeCount: nodes.len() as u32,
+            Nodes: nodes.as_mut_ptr(),
+            InputEdgeCount: input_edges.len() as u32,
+            InputEdges: input_edges.as_mut_ptr(),
+            OutputEdgeCount: output_edges.len() as u32,
+            OutputEdges: output_edges.as_mut_ptr(),
+            IntermediateEdgeCount: intermediate_edges.len() as u32,
+            IntermediateEdges: intermediate_edges.as_mut_ptr(),
+        };
+
+        unsafe {
+            let mut compiled: Option<IDMLCompiledOperator> = None;
+            self.dml_device.CompileGraph(&graph, DML_EXECUTION_FLAG_NONE, &mut compiled)?;
+            let compiled = compiled.unwrap();
+
+            let initializer: IDMLOperatorInitializer = self.dml_device.CreateOperatorInitializer(1, &Some(compiled.clone()))?;
+            let init_properties = initializer.GetBindingProperties();
+            let execute_properties = compiled.GetBindingProperties();
+            let descriptor_count = init_properties.RequiredDescriptorCount.max(execute_properties.RequiredDescriptorCount).max(1);
+            let temporary_size = init_properties.TemporaryResourceSize.max(execute_properties.TemporaryResourceSize);
+            let persistent_size = execute_properties.PersistentResourceSize;
+
+            let descriptor_heap: ID3D12DescriptorHeap = self.device.CreateDescriptorHeap(&D3D12_DESCRIPTOR_HEAP_DESC{
+                Type: D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+                NumDescriptors: descriptor_count,
+                Flags: D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE,
+                NodeMask: 0,
+            })?;
+            let mut table_desc = DML_BINDING_TABLE_DESC{
+                Dispatchable: Some(initializer.cast()?),
+                CPUDescriptorHandle: descriptor_heap.GetCPUDescriptorHandleForHeapStart(),
+                GPUDescriptorHandle: descriptor_heap.GetGPUDescriptorHandleForHeapStart(),
+                SizeInDescriptors: descriptor_count,
+            };
+            let binding_table: IDMLBindingTable = self.dml_device.CreateBindingTable(&table_desc)?;
+
+            let create_scratch = |size: u64| match size {
+                0 => Ok(None),
+                size => create_buffer(&self.device, size, D3D12_HEAP_TYPE_DEFAULT, D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS).map(Some),
+            };
+            let temporary = create_scratch(temporary_size)?;
+            let persistent = create_scratch(persistent_size)?;
+            let temporary_binding = temporary.as_ref().map(|buffer| buffer_binding(buffer, 0, temporary_size));
+            let persistent_binding = persistent.as_ref().map(|buffer| buffer_binding(buffer, 0, persistent_size));
+            let none = || DML_BINDING_DESC{ Type: DML_BINDING_TYPE_NONE, Desc: std::ptr::null_mut() };
+
+            // initialize, the persistent resource is its output
+            if let Some(binding) = &temporary_binding {
+                binding_table.BindTemporaryResource(&binding_desc(binding));
+            }
+            if let Some(binding) = &persistent_binding {
+                binding_table.BindOutputs(1, &binding_desc(binding));
+            }
+            self.begin()?;
+            self.list.SetDescriptorHeaps(1, &Some(descriptor_heap.clone()));
+            self.recorder.RecordDispatch(&self.list, &initializer, &binding_table);
+            self.execute()?;
+
+            // then bind for the execution, the same every frame
+            let input_buffer = create_buffer(&self.device, input_size, D3D12_HEAP_TYPE_DEFAULT, D3D12_RESOURCE_STATE_COMMON, D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS)?;
+            table_desc.Dispatchable = Some(compiled.cast()?);
+            binding_table.Reset(&table_desc)?;
+
+            let input_bindings = [
+                buffer_binding(&input_buffer, 0, input_size),
+                buffer_binding(&self.constants, INDICES_OFFSET, CONSTANT_SIZE),
+                buffer_binding(&self.constants, MEAN_OFFSET, CONSTANT_SIZE),
+                buffer_binding(&self.constants, VARIANCE_OFFSET, CONSTANT_SIZE),
+                buffer_binding(&self.constants, SCALE_OFFSET, CONSTANT_SIZE),
+            ];
+            let input_descs: Vec<DML_BINDING_DESC> = input_bindings.iter().map(binding_desc).collect();
+            binding_table.BindInputs(input_descs.len() as u32, input_descs.as_ptr());
+            let output_binding = buffer_binding(&self.output, 0, self.output_size);
+            binding_table.BindOutputs(1, &binding_desc(&output_binding));
+            binding_table.BindTemporaryResource(&temporary_binding.as_ref().map_or_else(none, binding_desc));
+            binding_table.BindPersistentResource(&persistent_binding.as_ref().map_or_else(none, binding_desc));
+
+            Ok(DmlPipeline{
+                source,
+                row_pitch,
+                input: input_buffer,
+                compiled,
+                descriptor_heap,
+                binding_table,
+                _temporary: temporary,
+                _persistent: persistent,
+            })
+        }
+    }
+
+    /// Preprocess the latest shared frame of `capture` into [output](Self::output), and wait for it.
+    ///
+    /// the graph is built again when the frame (or the crop) size changes.
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Capture::share_frames).
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): not a `B8G8R8A8_UNORM` frame.
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the crop is out of the frame.
+    pub fn process(&mut self, capture: &Capture) -> anyhow::Result<(), CaptureError> {
+        let frame = capture.shared_frame()?;
+        if frame.format != DXGI_FORMAT_B8G8R8A8_UNORM {
+            return Err(CaptureError::UnsupportedPixelFormat(frame.format));
+        }
+        let source = self.options.source_rect(frame.width as i32, frame.height as i32)?;
+
+        self.process_frame(&frame, source).map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    fn process_frame(&mut self, frame: &SharedFrame, source: Rect) -> windows::core::Result<()> {
+        if self.pipeline.as_ref().map_or(true, |pipeline| pipeline.source.width != source.width || pipeline.source.height != source.height) {
+            self.pipeline = None;
+            self.pipeline = Some(self.create_pipeline(source)?);
+        }
+        if let Some(pipeline) = self.pipeline.as_mut() {
+            pipeline.source = source;
+        }
+
+        unsafe {
+            // open the shared texture and the fence once. the textures are made again on resize
+            if self.texture_size != (frame.width, frame.height) {
+                self.textures.clear();
+                self.texture_size = (frame.width, frame.height);
+            }
+            if !self.textures.contains_key(&frame.texture_id) {
+                let mut texture: Option<ID3D12Resource> = None;
+                self.device.OpenSharedHandle(frame.texture, &mut texture)?;
+                self.textures.insert(frame.texture_id, texture.unwrap());
+            }
+            if self.capture_fence.as_ref().map_or(true, |(handle, _)| *handle != frame.fence.0) {
+                let mut fence: Option<ID3D12Fence> = None;
+                self.device.OpenSharedHandle(frame.fence, &mut fence)?;
+                self.capture_fence = Some((frame.fence.0, fence.unwrap()));
+            }
+            // until the capture is done copying
+            self.queue.Wait(&self.capture_fence.as_ref().unwrap().1, frame.fence_value)?;
+
+            let pipeline = self.pipeline.as_ref().unwrap();
+            let texture = &self.textures[&frame.texture_id];
+            self.begin()?;
+
+            let destination = D3D12_TEXTURE_COPY_LOCATION{
+                pResource: Some(pipeline.input.clone()),
+                Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+                Anonymous: D3D12_TEXTURE_COPY_LOCATION_0{
+                    PlacedFootprint: D3D12_PLACED_SUBRESOURCE_FOOTPRINT{
+                        Offset: 0,
+                        Footprint: D3D12_SUBRESOURCE_FOOTPRINT{
+                            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                            Width: source.width as u32,
+                            Height: source.height as u32,
+                            Depth: 1,
+                            RowPitch: pipeline.row_pitch,
+                        },
+                    },
+                },
+            };
+            let from = D3D12_TEXTURE_COPY_LOCATION{
+                pResource: Some(texture.clone()),
+                Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+                Anonymous: D3D12_TEXTURE_COPY_LOCATION_0{ SubresourceIndex: 0 },
+            };
+            let crop = D3D12_BOX{
+                left: source.x as u32,
+                top: source.y as u32,
+                front: 0,
+                right: source.right() as u32,
+                bottom: source.bottom() as u32,
+                back: 1,
+            };
+            self.list.CopyTextureRegion(&destination, 0, 0, 0, &from, &crop);
+            self.list.ResourceBarrier(1, &transition(&pipeline.input, D3D12_RESOURCE_STATE_COPY_DEST, D3D12_RESOURCE_STATE_UNORDERED_ACCESS));
+
+            self.list.SetDescriptorHeaps(1, &Some(pipeline.descriptor_heap.clone()));
+            self.recorder.RecordDispatch(&self.list, &pipeline.compiled, &pipeline.binding_table);
+        }
+
+        self.execute()
+    }
+
+    /// Read [output](Self::output) back, `[1, 3, height, width]` in NCHW order.
+    pub fn read_tensor(&mut self) -> anyhow::Result<Vec<f32>, CaptureError> {
+        self.read().map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    fn read(&mut self) -> windows::core::Result<Vec<f32>> {
+        unsafe {
+            self.begin()?;
+            self.list.CopyBufferRegion(&self.readback, 0, &self.output, 0, self.output_size);
+            self.execute()?;
+
+            let mut mapped = std::ptr::null_mut();
+            let range = D3D12_RANGE{ Begin: 0, End: self.output_size as usize };
+            self.readback.Map(0, &range, &mut mapped)?;
+            let tensor = std::slice::from_raw_parts(mapped as *const f32, (self.output_size / 4) as usize).to_vec();
+            let written = D3D12_RANGE::default();
+            self.readback.Unmap(0, &written);
+
+            Ok(tensor)
+        }
+    }
+
+    /// Shape of the tensors, `[1, 3, height, width]`.
+    pub fn shape(&self) -> [u32; 4] {
+        [1, 3, self.options.height, self.options.width]
+    }
+
+    pub fn options(&self) -> &TensorOptions {
+        &self.options
+    }
+
+    /// D3D12 device on the adapter of the capture.
+    pub fn device(&self) -> &ID3D12Device {
+        &self.device
+    }
+
+    /// DirectML device of [device](Self::device).
+    pub fn dml_device(&self) -> &IDMLDevice1 {
+        &self.dml_device
+    }
+
+    /// Queue executing the preprocessing. [output](Self::output) is ready when [process](Self::process) returns.
+    pub fn command_queue(&self) -> &ID3D12CommandQueue {
+        &self.queue
+    }
+
+    /// Buffer of the tensor, `f32` in NCHW order, in the common state.
+    pub fn output(&self) -> &ID3D12Resource {
+        &self.output
+    }
+}
+impl Drop for DmlPreprocessor {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.event);
+        }
+    }
+}
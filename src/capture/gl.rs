@@ -0,0 +1,229 @@
+//! OpenGL textures of the shared frames by `WGL_NV_DX_interop2`.
+//!
+//! Required features: *`"gl"`*
+
+use std::{
+    collections::HashMap,
+    ffi::c_void,
+};
+use windows::Win32::Graphics::OpenGL::{
+    glDeleteTextures,
+    glGenTextures,
+    wglGetCurrentContext,
+    wglGetProcAddress,
+    GL_TEXTURE_2D,
+};
+
+use super::*;
+
+
+const WGL_ACCESS_READ_ONLY_NV: u32 = 0x0000;
+
+type WglDxOpenDeviceNv = unsafe extern "system" fn(dx_device: *mut c_void) -> isize;
+type WglDxCloseDeviceNv = unsafe extern "system" fn(device: isize) -> i32;
+type WglDxRegisterObjectNv = unsafe extern "system" fn(device: isize, dx_object: *mut c_void, name: u32, object_type: u32, access: u32) -> isize;
+type WglDxUnregisterObjectNv = unsafe extern "system" fn(device: isize, object: isize) -> i32;
+type WglDxLockObjectsNv = unsafe extern "system" fn(device: isize, count: i32, objects: *mut isize) -> i32;
+
+/// `WGL_NV_DX_interop` entry points of the current context.
+#[derive(Clone, Copy)]
+struct WglDxInterop {
+    open_device: WglDxOpenDeviceNv,
+    close_device: WglDxCloseDeviceNv,
+    register_object: WglDxRegisterObjectNv,
+    unregister_object: WglDxUnregisterObjectNv,
+    lock_objects: WglDxLockObjectsNv,
+    unlock_objects: WglDxLockObjectsNv,
+}
+impl WglDxInterop {
+    unsafe fn load() -> anyhow::Result<Self, CaptureError> {
+        unsafe fn proc<T>(name: &str) -> anyhow::Result<T, CaptureError> {
+            match wglGetProcAddress(name) {
+                Some(proc) => Ok(std::mem::transmute_copy(&proc)),
+                None => Err(CaptureError::OpenglError(format!("{} is not supported.", name))),
+            }
+        }
+
+        Ok(Self{
+            open_device: proc("wglDXOpenDeviceNV")?,
+            close_device: proc("wglDXCloseDeviceNV")?,
+            register_object: proc("wglDXRegisterObjectNV")?,
+            unregister_object: proc("wglDXUnregisterObjectNV")?,
+            lock_objects: proc("wglDXLockObjectsNV")?,
+            unlock_objects: proc("wglDXUnlockObjectsNV")?,
+        })
+    }
+}
+
+
+#[derive(Debug)]
+struct RegisteredTexture {
+    name: u32,
+    object: isize,
+    width: u32,
+    height: u32,
+}
+
+
+/// Use the frames shared by [Capture::share_frames] as OpenGL textures, without reading them back.
+///
+/// the shared textures of the [Capture] are registered to the current GL context by `WGL_NV_DX_interop2`,
+/// each once, and kept until the frame size changes. the GL context must be current on the calling thread
+/// from [new](Self::new) to drop, and made on the same adapter as the captured [Device].
+///
+/// Required features: *`"gl"`*
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// capture.share_frames(3).unwrap();
+///
+/// // with a GL context current
+/// let mut interop = dxcapture::GlInterop::new(&capture).expect("No WGL_NV_DX_interop2");
+/// {
+///     let frame = interop.lock(&capture).expect("Failed to lock");
+///     // glBindTexture(frame.target, frame.texture) and draw it
+/// } // unlocked, back to the capture
+/// ```
+pub struct GlInterop {
+    wgl: WglDxInterop,
+    device: isize,
+    _d3d_device: ID3D11Device,
+    textures: HashMap<u64, RegisteredTexture>,
+}
+impl GlInterop {
+    /// Open the D3D11 device of `capture` for the current GL context.
+    /// ## Errors
+    /// * [OpenglError](CaptureError::OpenglError): no current context, or the driver has no `WGL_NV_DX_interop2`.
+    pub fn new(capture: &Capture) -> anyhow::Result<Self, CaptureError> {
+        unsafe {
+            if wglGetCurrentContext().is_invalid() {
+                return Err(CaptureError::OpenglError("No current GL context.".to_string()));
+            }
+            let wgl = WglDxInterop::load()?;
+
+            let d3d_device = capture._d3d_device.clone();
+            let device = (wgl.open_device)(std::mem::transmute_copy(&d3d_device));
+            if device == 0 {
+                return Err(CaptureError::OpenglError("wglDXOpenDeviceNV failed.".to_string()));
+            }
+
+            Ok(Self{
+                wgl,
+                device,
+                _d3d_device: d3d_device,
+                textures: HashMap::new(),
+            })
+        }
+    }
+
+    /// Lock the texture of the latest shared frame for GL. unlocked by dropping the [GlFrame].
+    ///
+    /// D3D11 doesn't write the texture while locked, and the other shared textures go on receiving frames.
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Capture::share_frames).
+    /// * [OpenglError](CaptureError::OpenglError): failed to register or lock.
+    pub fn lock(&mut self, capture: &Capture) -> anyhow::Result<GlFrame<'_>, CaptureError> {
+        let (frame, texture) = {
+            // through shared_frame to check the state
+            let frame = capture.shared_frame()?;
+            capture.shared.lock().unwrap().latest_texture()
+                .filter(|(latest, _)| latest.texture_id == frame.texture_id)
+                .ok_or(CaptureError::NoTexture)?
+        };
+
+        // the textures are made again on resize
+        if self.textures.values().any(|registered| registered.width != frame.width || registered.height != frame.height) {
+            self.clear();
+        }
+        if !self.textures.contains_key(&frame.texture_id) {
+            let registered = self.register(&texture, &frame)?;
+            self.textures.insert(frame.texture_id, registered);
+        }
+
+        let registered = &self.textures[&frame.texture_id];
+        let mut object = registered.object;
+        unsafe {
+            if (self.wgl.lock_objects)(self.device, 1, &mut object) == 0 {
+                return Err(CaptureError::OpenglError("wglDXLockObjectsNV failed.".to_string()));
+            }
+        }
+
+        Ok(GlFrame{
+            texture: registered.name,
+            target: GL_TEXTURE_2D,
+            width: frame.width,
+            height: frame.height,
+            fence_value: frame.fence_value,
+            object,
+            interop: self,
+        })
+    }
+
+    fn register(&self, texture: &ID3D11Texture2D, frame: &SharedFrame) -> anyhow::Result<RegisteredTexture, CaptureError> {
+        unsafe {
+            let mut name = 0;
+            glGenTextures(1, &mut name);
+
+            let object = (self.wgl.register_object)(
+                self.device,
+                std::mem::transmute_copy(texture),
+                name,
+                GL_TEXTURE_2D,
+                WGL_ACCESS_READ_ONLY_NV,
+            );
+            if object == 0 {
+                glDeleteTextures(1, &name);
+                return Err(CaptureError::OpenglError("wglDXRegisterObjectNV failed.".to_string()));
+            }
+
+            Ok(RegisteredTexture{
+                name,
+                object,
+                width: frame.width,
+                height: frame.height,
+            })
+        }
+    }
+
+    /// Unregister and delete the GL textures. registered again on the next [lock](Self::lock).
+    pub fn clear(&mut self) {
+        for (_, registered) in self.textures.drain() {
+            unsafe {
+                (self.wgl.unregister_object)(self.device, registered.object);
+                glDeleteTextures(1, &registered.name);
+            }
+        }
+    }
+}
+impl Drop for GlInterop {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe {
+            (self.wgl.close_device)(self.device);
+        }
+    }
+}
+
+
+/// Shared frame locked for GL. [Read more](GlInterop::lock)
+pub struct GlFrame<'a> {
+    /// GL texture name. sampled as RGBA.
+    pub texture: u32,
+    /// `GL_TEXTURE_2D`.
+    pub target: u32,
+    pub width: u32,
+    pub height: u32,
+    /// same as [SharedFrame::fence_value].
+    pub fence_value: u64,
+    object: isize,
+    interop: &'a GlInterop,
+}
+impl Drop for GlFrame<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            (self.interop.wgl.unlock_objects)(self.interop.device, 1, &mut self.object);
+        }
+    }
+}
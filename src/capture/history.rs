@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+
+/// The last frames kept on the GPU, shared with the frame arrived handler.
+#[derive(Debug, Default)]
+pub(crate) struct FrameHistory {
+    capacity: usize,
+    /// staging textures with their info, oldest first.
+    frames: VecDeque<(ID3D11Texture2D, FrameInfo)>,
+}
+impl FrameHistory {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self{
+            capacity,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Keep `texture`, dropping the oldest over the capacity. the texture must not be written again.
+    pub(crate) fn push(&mut self, texture: &ID3D11Texture2D, info: FrameInfo) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.capacity <= self.frames.len() {
+            self.frames.pop_front();
+        }
+        self.frames.push_back((texture.clone(), info));
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        0 < self.capacity
+    }
+}
+
+
+impl Capture {
+    /// Keep the last `capacity` frames, for replays of the moments just passed. 0 keeps none, the default.
+    ///
+    /// the frames are kept as staging textures on the GPU, and read back only by [drain_frames](Self::drain_frames)
+    /// or [get_frame_at](Self::get_frame_at), so keeping them costs video memory but no copies to the CPU.
+    /// each frame gets a staging texture of its own while any is kept, instead of the reused ones.
+    /// lowering the capacity drops the oldest ones.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::builder(&device)
+    ///     .history(300)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// std::thread::sleep(std::time::Duration::from_secs(10));
+    /// // up to the last 300 frames, oldest first
+    /// let frames = capture.drain_frames().unwrap();
+    /// ```
+    pub fn set_history(&self, capacity: usize) {
+        let mut history = self.history.lock().unwrap();
+        history.capacity = capacity;
+        while capacity < history.frames.len() {
+            history.frames.pop_front();
+        }
+    }
+
+    /// Count of the frames kept now. [Read more](Self::set_history)
+    pub fn history_len(&self) -> usize {
+        self.history.lock().unwrap().frames.len()
+    }
+
+    /// Read back all the kept frames, oldest first, and forget them. [Read more](Self::set_history)
+    pub fn drain_frames(&self) -> anyhow::Result<Vec<RawFrameData>, CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+        let frames: Vec<_> = self.history.lock().unwrap().frames.drain(..).collect();
+
+        frames.iter().map(|(texture, info)| self.read_history(texture, *info)).collect()
+    }
+
+    /// Read back the kept frame shown at `time`, the latest captured at or before it on the QPC clock
+    /// of [FrameInfo::time]. the frame is kept. [Read more](Self::set_history)
+    /// ## Errors
+    /// * [NoTexture](CaptureError::NoTexture): no kept frame is that old.
+    pub fn get_frame_at(&self, time: Duration) -> anyhow::Result<RawFrameData, CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+        let frame = self.history.lock().unwrap().frames.iter()
+            .rev()
+            .find(|(_, info)| info.time.is_some_and(|frame_time| frame_time <= time))
+            .cloned();
+
+        match frame {
+            Some((texture, info)) => self.read_history(&texture, info),
+            None => Err(CaptureError::NoTexture),
+        }
+    }
+
+    fn read_history(&self, texture: &ID3D11Texture2D, info: FrameInfo) -> anyhow::Result<RawFrameData, CaptureError> {
+        let (width, height, format, data) = read_texture(&self.d3d_context, texture, 0)?;
+
+        let mut raw = to_bgra(width, height, format, data, *self.ten_bit.lock().unwrap());
+        raw.info = info;
+        Ok(raw)
+    }
+}
@@ -5,6 +5,18 @@ use image::{
     Bgra,
 };
 
+use std::{
+    io::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
 use super::*;
 
 #[derive(Clone, Debug, Default)]
@@ -16,13 +28,49 @@ pub struct ImgFrameData {
     pub width: i32,
     pub height: i32,
     pub data: RgbaImage,
+    /// [info](RawFrameData::info) of the raw frame.
+    pub info: FrameInfo,
 }
 impl ImgFrameData {
     pub fn new(width: i32, height: i32, data: RgbaImage) -> Self {
         Self{
-            width, height, data
+            width, height, data, info: FrameInfo::default()
+        }
+    }
+
+    /// Convert BGRA [RawFrameData] to RGBA image.
+    pub fn from_raw(mut raw: RawFrameData) -> Self {
+        raw.pack_rows();
+        let image: ImageBuffer<Bgra<u8>, _> =
+            ImageBuffer::from_raw(raw.width as u32, raw.height as u32, raw.data).unwrap();
+        let dynamic_image = DynamicImage::ImageBgra8(image);
+
+        let mut image = Self::new( raw.width, raw.height, dynamic_image.to_rgba8() );
+        image.info = raw.info;
+        image
+    }
+
+    /// Convert back to BGRA [RawFrameData].
+    pub fn to_raw(&self) -> RawFrameData {
+        let mut data = self.data.as_raw().clone();
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+
+        RawFrameData{
+            width: self.width,
+            height: self.height,
+            data,
+            info: self.info,
+            row_pitch: 0,
         }
     }
+
+    /// Save as PNG, whatever the extension of `path` is.
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<(), CaptureError> {
+        self.data.save_with_format(path, image::ImageFormat::Png)
+            .map_err(|e| CaptureError::IoError(e.to_string()))
+    }
 }
 
 impl Capture {
@@ -33,7 +81,7 @@ impl Capture {
     /// Required features: *`"img"`*
     /// # Examples
     /// ```
-    /// let device = dxcapture::Device::default();
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
     /// let capture = dxcapture::Capture::new(&device).unwrap();
     /// 
     /// let image = capture.wait_img_frame().expect("Failed to capture");
@@ -44,26 +92,72 @@ impl Capture {
     pub fn get_img_frame(&self) -> anyhow::Result<ImgFrameData, CaptureError> {
         let raw = self.get_raw_frame()?;
 
-        let image: ImageBuffer<Bgra<u8>, _> =
-            ImageBuffer::from_raw(raw.width as u32, raw.height as u32, raw.data).unwrap();
-        let dynamic_image = DynamicImage::ImageBgra8(image);
-        let dynamic_image = dynamic_image.to_rgba8();
-
-        Ok(ImgFrameData::new( raw.width as i32, raw.height as i32, dynamic_image ))
+        Ok(ImgFrameData::from_raw(raw))
     }
 
     /// Get opencv image from a Direct3D surface. with throught NoTexture
     pub fn wait_img_frame(&self) -> anyhow::Result<ImgFrameData, CaptureError> {
-        loop {
-            match self.get_img_frame() {
-                Ok(mat) => return Ok(mat),
-                Err(e) => {
-                    if e == CaptureError::NoTexture {
-                        continue;
-                    }
-                    return Err(e);
-                },
+        self.wait_frame_with(None, Self::get_img_frame)
+    }
+
+    /// Get image RgbaImage from a Direct3D surface, waiting for a frame up to `timeout`. [Read more](Capture::wait_raw_frame_timeout)
+    pub fn wait_img_frame_timeout(&self, timeout: Duration) -> anyhow::Result<ImgFrameData, CaptureError> {
+        self.wait_frame_with(Some(timeout), Self::get_img_frame)
+    }
+
+    /// Save `count` frames into `dir` as zero-padded numbered PNGs, one every `interval`.
+    ///
+    /// files are named `frame_00000.png`, `frame_00001.png`, ... (`frame_%05d.png` for ffmpeg),
+    /// and `manifest.csv` is written beside them with the time of each frame since the start.
+    /// returns the paths of the saved frames.
+    /// 
+    /// Required features: *`"img"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// 
+    /// capture.save_sequence("frames", 10, std::time::Duration::from_millis(500)).expect("Failed to save");
+    /// ```
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the time of the last frame overflows a `Duration`.
+    /// * [IoError](CaptureError::IoError): failed to write a file.
+    pub fn save_sequence<P: AsRef<Path>>(&self, dir: P, count: usize, interval: Duration) -> anyhow::Result<Vec<PathBuf>, CaptureError> {
+        // checked before saving anything, so the later deadlines never overflow
+        let last = u32::try_from(count.saturating_sub(1)).ok().and_then(|last| interval.checked_mul(last));
+        if last.is_none() {
+            return Err(CaptureError::InvalidArgument("interval * count overflows.".to_string()));
+        }
+
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        let digits = count.to_string().len().max(5);
+        let mut manifest = String::from("file,timestamp_ms\n");
+        let mut paths = Vec::with_capacity(count);
+
+        let start = Instant::now();
+        for index in 0..count {
+            let deadline = interval * index as u32;
+            if let Some(wait) = deadline.checked_sub(start.elapsed()) {
+                std::thread::sleep(wait);
             }
+
+            let image = self.wait_img_frame()?;
+            let timestamp = start.elapsed();
+
+            let name = format!("frame_{:0digits$}.png", index, digits = digits);
+            let path = dir.join(&name);
+            image.data.save(&path).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+            manifest.push_str(&format!("{},{}\n", name, timestamp.as_millis()));
+            paths.push(path);
         }
+
+        std::fs::File::create(dir.join("manifest.csv"))
+            .and_then(|mut file| file.write_all(manifest.as_bytes()))
+            .map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        Ok(paths)
     }
 }
@@ -0,0 +1,172 @@
+//! Low latency readback, signaling an event when the copy of each frame is done.
+//!
+//! Required features: *`"latency"`*
+
+use std::time::Duration;
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{
+            CloseHandle,
+            HANDLE,
+            PWSTR,
+        },
+        Graphics::{
+            Direct3D11::{
+                ID3D11Device,
+                ID3D11Device5,
+                ID3D11DeviceContext,
+                ID3D11DeviceContext4,
+                ID3D11Fence,
+                D3D11_FENCE_FLAG_NONE,
+            },
+            Dxgi::IDXGIDevice1,
+        },
+        System::Threading::{
+            CreateEventW,
+            WaitForSingleObject,
+            WAIT_OBJECT_0,
+        },
+    },
+};
+
+use super::*;
+
+
+/// Fence and event of the low latency mode, shared with the frame arrived handler.
+#[derive(Debug)]
+pub(crate) struct LatencyState {
+    fence: ID3D11Fence,
+    context: ID3D11DeviceContext4,
+    value: u64,
+    /// auto reset, signaled by the fence.
+    event: HANDLE,
+}
+impl LatencyState {
+    fn new(d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext) -> windows::core::Result<Self> {
+        // D3D11.4, Windows 10 1703 or later
+        let device: ID3D11Device5 = d3d_device.cast()?;
+        let context: ID3D11DeviceContext4 = d3d_context.cast()?;
+
+        unsafe {
+            // the capture device presents nothing, but keep the driver from queueing ahead
+            d3d_device.cast::<IDXGIDevice1>()?.SetMaximumFrameLatency(1)?;
+
+            let mut fence: Option<ID3D11Fence> = None;
+            device.CreateFence(0, D3D11_FENCE_FLAG_NONE, &mut fence)?;
+            let event = CreateEventW(std::ptr::null(), false, false, PWSTR::default()).ok()?;
+
+            Ok(Self{
+                fence: fence.unwrap(),
+                context,
+                value: 0,
+                event,
+            })
+        }
+    }
+
+    /// Signal the event when the copies queued on `d3d_context` so far are done, and submit them now.
+    pub(crate) fn signal(&mut self, d3d_context: &ID3D11DeviceContext) -> windows::core::Result<()> {
+        self.value += 1;
+        unsafe {
+            self.context.Signal(&self.fence, self.value)?;
+            self.fence.SetEventOnCompletion(self.value, self.event)?;
+            // not batched with the next frame
+            d3d_context.Flush();
+        }
+
+        Ok(())
+    }
+}
+impl Drop for LatencyState {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.event);
+        }
+    }
+}
+
+
+impl Capture {
+    /// Switch the low latency mode, for remote control and the others reading each frame as soon as possible.
+    ///
+    /// while enabled:
+    /// * the copy of each frame is submitted to the GPU at once, instead of batched with the next commands.
+    /// * the event of [frame_ready_event](Self::frame_ready_event) is signaled when the copy is done,
+    ///   so [wait_frame_ready](Self::wait_frame_ready) wakes up without polling.
+    /// * [get_raw_frame](Self::get_raw_frame) maps with `D3D11_MAP_FLAG_DO_NOT_WAIT`, and returns
+    ///   [NoTexture](CaptureError::NoTexture) instead of blocking while the copy is not done.
+    ///
+    /// the frame pool has a single buffer, so the frames are never queued behind the old ones.
+    ///
+    /// Required features: *`"latency"`*
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    /// * [DirectxError](CaptureError::DirectxError): the device is older than D3D11.4.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.set_low_latency(true).unwrap();
+    ///
+    /// loop {
+    ///     if !capture.wait_frame_ready(Duration::from_millis(100)).unwrap() {
+    ///         continue;
+    ///     }
+    ///     match capture.get_raw_frame() {
+    ///         Ok(raw) => { /* send to the client */ },
+    ///         Err(dxcapture::CaptureError::NoTexture) => continue,
+    ///         Err(e) => panic!("{}", e),
+    ///     }
+    /// }
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "latency")))]
+    pub fn set_low_latency(&self, enabled: bool) -> anyhow::Result<(), CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+
+        let mut latency = self.latency.lock().unwrap();
+        if enabled == latency.is_some() {
+            return Ok(());
+        }
+        *latency = if enabled {
+            Some(LatencyState::new(&self._d3d_device, &self.d3d_context).map_err(|e| CaptureError::DirectxError(e))?)
+        } else {
+            None
+        };
+
+        Ok(())
+    }
+
+    /// The low latency mode is enabled by [set_low_latency](Self::set_low_latency).
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "latency")))]
+    pub fn is_low_latency(&self) -> bool {
+        self.latency.lock().unwrap().is_some()
+    }
+
+    /// Auto reset event signaled when the copy of a frame is done. `None` unless in the low latency mode.
+    ///
+    /// for `WaitForMultipleObjects` with the other events of the app. owned by the capture, and closed when
+    /// the low latency mode is disabled.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "latency")))]
+    pub fn frame_ready_event(&self) -> Option<HANDLE> {
+        self.latency.lock().unwrap().as_ref().map(|latency| latency.event)
+    }
+
+    /// Wait until the copy of the next frame is done, up to `timeout`. `false` on timeout.
+    ///
+    /// Required features: *`"latency"`*
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): not in the low latency mode.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "latency")))]
+    pub fn wait_frame_ready(&self, timeout: Duration) -> anyhow::Result<bool, CaptureError> {
+        let event = self.frame_ready_event()
+            .ok_or_else(|| CaptureError::InvalidArgument("not in the low latency mode".to_string()))?;
+
+        let millis = timeout.as_millis().min(u32::MAX as u128 - 1) as u32;
+        Ok(unsafe { WaitForSingleObject(event, millis) } == WAIT_OBJECT_0)
+    }
+}
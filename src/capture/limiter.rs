@@ -0,0 +1,125 @@
+use super::*;
+
+
+/// Frame rate limit of a [Capture], shared with the frame arrived handler.
+#[derive(Debug, Default)]
+pub(crate) struct FrameLimiter {
+    max_fps: Option<u32>,
+    /// time the next frame is due on the QPC clock.
+    next: Option<Duration>,
+}
+impl FrameLimiter {
+    pub(crate) fn new(max_fps: Option<u32>) -> Self {
+        Self{
+            max_fps,
+            next: None,
+        }
+    }
+
+    /// Whether the frame at `frame_time` is delivered.
+    ///
+    /// the frames are due every interval from the first one, so a source of a higher rate is thinned out evenly,
+    /// as 60fps into every other frame for 30fps. a frame a little early is taken for the vsync jitter.
+    pub(crate) fn should_copy(&mut self, frame_time: Duration) -> bool {
+        let interval = match self.max_fps {
+            Some(fps) => Duration::from_secs(1) / fps,
+            None => return true,
+        };
+
+        if self.next.is_some_and(|next| frame_time < next.saturating_sub(interval / 4)) {
+            return false;
+        }
+        self.next = Some(match self.next {
+            // fallen behind, as after a pause. due again from this frame
+            Some(next) if next + interval <= frame_time => frame_time + interval,
+            Some(next) => next + interval,
+            None => frame_time + interval,
+        });
+        true
+    }
+}
+
+
+impl Capture {
+    /// Deliver at most `max_fps` frames a second. `None` delivers every frame, the default.
+    ///
+    /// the frames over the rate are dropped in the frame arrived handler before the staging copy,
+    /// so the consumers see only the requested rate without paying the copies of the dropped frames.
+    /// the waits, the [callbacks](Self::on_frame) and the [history](Self::set_history) get only the delivered ones.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::builder(&device)
+    ///     .max_fps(30)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// capture.set_max_fps(Some(10)).unwrap();
+    /// ```
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): `max_fps` is 0.
+    pub fn set_max_fps(&self, max_fps: Option<u32>) -> anyhow::Result<(), CaptureError> {
+        if max_fps == Some(0) {
+            return Err(CaptureError::InvalidArgument("max fps must be greater than 0.".to_string()));
+        }
+
+        *self.limiter.lock().unwrap() = FrameLimiter::new(max_fps);
+        Ok(())
+    }
+
+    pub fn max_fps(&self) -> Option<u32> {
+        self.limiter.lock().unwrap().max_fps
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frames delivered of `count` frames at `fps`.
+    fn delivered(limiter: &mut FrameLimiter, fps: u32, count: u32) -> usize {
+        (0..count).filter(|&i| limiter.should_copy(Duration::from_secs(1) / fps * i)).count()
+    }
+
+    #[test]
+    fn unlimited() {
+        assert_eq!(delivered(&mut FrameLimiter::new(None), 60, 60), 60);
+    }
+
+    #[test]
+    fn thinned_out_evenly() {
+        let mut limiter = FrameLimiter::new(Some(30));
+        let copied: Vec<bool> = (0..6).map(|i| limiter.should_copy(Duration::from_secs(1) / 60 * i)).collect();
+
+        assert_eq!(copied, vec![true, false, true, false, true, false]);
+        assert_eq!(delivered(&mut FrameLimiter::new(Some(30)), 60, 60), 30);
+        assert_eq!(delivered(&mut FrameLimiter::new(Some(20)), 60, 60), 20);
+    }
+
+    #[test]
+    fn slower_source() {
+        assert_eq!(delivered(&mut FrameLimiter::new(Some(30)), 24, 24), 24);
+    }
+
+    #[test]
+    fn early_frames() {
+        let mut limiter = FrameLimiter::new(Some(30));
+        assert!(limiter.should_copy(Duration::ZERO));
+
+        // a vsync late or early is taken
+        assert!(limiter.should_copy(Duration::from_millis(31)));
+        assert!(!limiter.should_copy(Duration::from_millis(50)));
+    }
+
+    #[test]
+    fn after_a_pause() {
+        let mut limiter = FrameLimiter::new(Some(30));
+        assert!(limiter.should_copy(Duration::ZERO));
+        assert!(limiter.should_copy(Duration::from_secs(10)));
+
+        // due from the frame after the pause, not caught up from before it
+        assert!(!limiter.should_copy(Duration::from_secs(10) + Duration::from_millis(20)));
+        assert!(limiter.should_copy(Duration::from_secs(10) + Duration::from_millis(33)));
+    }
+}
@@ -14,12 +14,14 @@ pub struct MatFrameData {
     pub width: i32,
     pub height: i32,
     pub data: opencv::core::Mat,
+    /// [info](RawFrameData::info) of the raw frame.
+    pub info: FrameInfo,
     _raw_data: Vec<u8>,
 }
 impl MatFrameData {
     pub fn new(width: i32, height: i32, data: core::Mat, raw_data: Vec<u8>) -> Self {
         Self{
-            width, height, data, _raw_data: raw_data
+            width, height, data, info: FrameInfo::default(), _raw_data: raw_data
         }
     }
 }
@@ -35,7 +37,7 @@ impl Capture {
     /// use opencv::prelude::*;
     /// use opencv::imgcodecs::{ imwrite, IMWRITE_PNG_STRATEGY_DEFAULT };
     /// 
-    /// let device = dxcapture::Device::default();
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
     /// let capture = dxcapture::Capture::new(&device).unwrap();
     /// 
     /// let mat = capture.wait_mat_frame().expect("Failed to capture");
@@ -53,21 +55,18 @@ impl Capture {
             ).map_err(|err| CaptureError::OpencvError(err.to_string()))?
         };
 
-        Ok(MatFrameData::new( raw.width as i32, raw.height as i32, mat_data, raw.data ))
+        let mut mat = MatFrameData::new( raw.width as i32, raw.height as i32, mat_data, raw.data );
+        mat.info = raw.info;
+        Ok(mat)
     }
 
     /// Get opencv Mat from a Direct3D surface. with throught NoTexture
     pub fn wait_mat_frame(&self) -> anyhow::Result<MatFrameData, CaptureError> {
-        loop {
-            match self.get_mat_frame() {
-                Ok(mat) => return Ok(mat),
-                Err(e) => {
-                    if e == CaptureError::NoTexture {
-                        continue;
-                    }
-                    return Err(e);
-                },
-            }
-        }
+        self.wait_frame_with(None, Self::get_mat_frame)
+    }
+
+    /// Get opencv Mat from a Direct3D surface, waiting for a frame up to `timeout`. [Read more](Capture::wait_raw_frame_timeout)
+    pub fn wait_mat_frame_timeout(&self, timeout: Duration) -> anyhow::Result<MatFrameData, CaptureError> {
+        self.wait_frame_with(Some(timeout), Self::get_mat_frame)
     }
 }
@@ -0,0 +1,90 @@
+//! `Windows.Media.VideoFrame` and `SoftwareBitmap` of the frames.
+//!
+//! Required features: *`"media"`*
+
+use windows::{
+    Graphics::Imaging::{
+        BitmapAlphaMode,
+        BitmapPixelFormat,
+        SoftwareBitmap,
+    },
+    Media::VideoFrame,
+    Security::Cryptography::CryptographicBuffer,
+    Win32::Graphics::Direct3D11::{
+        D3D11_BIND_RENDER_TARGET,
+        D3D11_BIND_SHADER_RESOURCE,
+    },
+};
+
+use super::*;
+
+
+impl Capture {
+    /// Get the current frame as a `SoftwareBitmap` of BGRA8, for the WinRT imaging and media APIs.
+    ///
+    /// Required features: *`"media"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let bitmap = capture.wait_software_bitmap().expect("Failed to capture");
+    /// // FaceDetector::DetectFacesAsync needs Gray8 or Nv12
+    /// let gray = windows::Graphics::Imaging::SoftwareBitmap::Convert(&bitmap, windows::Graphics::Imaging::BitmapPixelFormat::Gray8).unwrap();
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "media")))]
+    pub fn get_software_bitmap(&self) -> anyhow::Result<SoftwareBitmap, CaptureError> {
+        let raw = self.get_raw_frame()?;
+
+        Self::raw_to_software_bitmap(&raw)
+    }
+
+    /// Get the current frame as a `SoftwareBitmap`. with throught NoTexture
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "media")))]
+    pub fn wait_software_bitmap(&self) -> anyhow::Result<SoftwareBitmap, CaptureError> {
+        let raw = self.wait_raw_frame()?;
+
+        Self::raw_to_software_bitmap(&raw)
+    }
+
+    /// Copy the latest shared frame into a new `VideoFrame` backed by a D3D11 surface, without reading it back.
+    ///
+    /// for `MediaComposition`, `MediaStreamSource` and the other media APIs taking surfaces on the same device.
+    /// the video frame keeps the frame, the capture goes on with the shared textures.
+    /// use [get_software_bitmap](Self::get_software_bitmap) for the APIs on the CPU.
+    ///
+    /// Required features: *`"media"`*
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing by [share_frames](Self::share_frames).
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Self::share_frames).
+    /// * [DirectxError](CaptureError::DirectxError): failed to copy or to create the video frame.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(2).unwrap();
+    ///
+    /// let frame = capture.get_video_frame().expect("Failed to capture");
+    /// let surface = frame.Direct3DSurface().unwrap();
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "media")))]
+    pub fn get_video_frame(&self) -> anyhow::Result<VideoFrame, CaptureError> {
+        let (_, texture) = self.copy_latest_texture(D3D11_BIND_SHADER_RESOURCE | D3D11_BIND_RENDER_TARGET)?;
+
+        Device::to_direct3d_surface(&texture)
+            .and_then(|surface| VideoFrame::CreateWithDirect3D11Surface(surface))
+            .map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    fn raw_to_software_bitmap(raw: &RawFrameData) -> anyhow::Result<SoftwareBitmap, CaptureError> {
+        CryptographicBuffer::CreateFromByteArray(&raw.data)
+            .and_then(|buffer| SoftwareBitmap::CreateCopyWithAlphaFromBuffer(
+                buffer,
+                BitmapPixelFormat::Bgra8,
+                raw.width,
+                raw.height,
+                BitmapAlphaMode::Ignore,
+            ))
+            .map_err(|e| CaptureError::DirectxError(e))
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Instant;
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        windef::HWND,
+    },
+    um::{
+        dwmapi::{
+            DwmGetWindowAttribute,
+            DWMWA_CLOAKED,
+        },
+        sysinfoapi::GetTickCount,
+        winbase::{
+            GetSystemPowerStatus,
+            SYSTEM_POWER_STATUS,
+        },
+        winuser::{
+            GetLastInputInfo,
+            IsIconic,
+            IsWindow,
+            IsWindowVisible,
+            LASTINPUTINFO,
+        },
+    },
+};
+
+use super::*;
+
+
+/// the conditions are checked at most this often, not on every frame.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Capture rate lowered while saving power. [Read more](Capture::set_power_policy)
+///
+/// each rate is frames a second, `0.0` pauses the copies, and `None` keeps the full rate on the condition.
+/// the lowest one of the conditions met is used.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerPolicy {
+    /// while the system runs on battery. default is 5fps.
+    pub on_battery: Option<f64>,
+    /// while the [window](Self::window) is minimized, hidden or cloaked. default is paused.
+    pub when_occluded: Option<f64>,
+    /// while the user has not touched the keyboard nor the mouse for [idle_after](Self::idle_after). default is 1fps.
+    pub when_idle: Option<f64>,
+    /// default is 60 seconds.
+    pub idle_after: Duration,
+    /// `HWND` of the captured window, for [when_occluded](Self::when_occluded).
+    window: Option<isize>,
+}
+impl PowerPolicy {
+    pub fn new() -> Self {
+        Self{
+            on_battery: Some(5.0),
+            when_occluded: Some(0.0),
+            when_idle: Some(1.0),
+            idle_after: Duration::from_secs(60),
+            window: None,
+        }
+    }
+
+    pub fn on_battery(mut self, fps: Option<f64>) -> Self {
+        self.on_battery = fps;
+        self
+    }
+
+    pub fn when_occluded(mut self, fps: Option<f64>) -> Self {
+        self.when_occluded = fps;
+        self
+    }
+
+    pub fn when_idle(mut self, fps: Option<f64>, idle_after: Duration) -> Self {
+        self.when_idle = fps;
+        self.idle_after = idle_after;
+        self
+    }
+
+    /// The captured window, checked for [when_occluded](Self::when_occluded). displays are never occluded.
+    ///
+    /// windows covered by the others still count as shown, only minimized, hidden and cloaked ones are occluded.
+    pub fn window(mut self, handle: HWND) -> Self {
+        self.window = Some(handle as isize);
+        self
+    }
+
+    /// The lowest rate of the conditions met now. `None` when none is met.
+    fn current_fps(&self) -> Option<f64> {
+        let on_battery = self.on_battery.filter(|_| is_on_battery());
+        let when_occluded = self.when_occluded.filter(|_| self.window.is_some_and(|window| is_occluded(window as HWND)));
+        let when_idle = self.when_idle.filter(|_| idle_time().is_some_and(|idle| self.idle_after <= idle));
+
+        [on_battery, when_occluded, when_idle].into_iter().flatten().reduce(f64::min)
+    }
+}
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+fn is_on_battery() -> bool {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    // ACLineStatus is 0 offline, 1 online and 255 unknown
+    unsafe { GetSystemPowerStatus(&mut status) != 0 && status.ACLineStatus == 0 }
+}
+
+fn is_occluded(handle: HWND) -> bool {
+    unsafe {
+        if IsWindow(handle) == 0 || IsWindowVisible(handle) == 0 || IsIconic(handle) != 0 {
+            return true;
+        }
+
+        let mut cloaked: DWORD = 0;
+        let result = DwmGetWindowAttribute(
+            handle,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut _ as *mut _,
+            std::mem::size_of::<DWORD>() as u32,
+        );
+        0 <= result && cloaked != 0
+    }
+}
+
+/// Time since the last input of the session.
+fn idle_time() -> Option<Duration> {
+    let mut info = LASTINPUTINFO{
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    unsafe {
+        if GetLastInputInfo(&mut info) == 0 {
+            return None;
+        }
+        // both wrap around after 49.7 days
+        Some(Duration::from_millis(GetTickCount().wrapping_sub(info.dwTime) as u64))
+    }
+}
+
+
+/// [PowerPolicy] of a [Capture], shared with the frame arrived handler.
+#[derive(Debug, Default)]
+pub(crate) struct PowerState {
+    policy: Option<PowerPolicy>,
+    checked_at: Option<Instant>,
+    fps: Option<f64>,
+    last_copy: Option<Duration>,
+}
+impl PowerState {
+    /// Whether the frame at `frame_time` is copied, checking the conditions when it's time.
+    pub(crate) fn should_copy(&mut self, frame_time: Duration) -> bool {
+        let policy = match self.policy {
+            Some(policy) => policy,
+            None => return true,
+        };
+
+        if self.checked_at.map_or(true, |checked_at| CHECK_INTERVAL <= checked_at.elapsed()) {
+            self.fps = policy.current_fps();
+            self.checked_at = Some(Instant::now());
+        }
+
+        let copy = match self.fps {
+            None => true,
+            Some(fps) if fps <= 0.0 => false,
+            Some(fps) => self.last_copy.map_or(true, |last_copy| {
+                Duration::from_secs_f64(1.0 / fps) <= frame_time.saturating_sub(last_copy)
+            }),
+        };
+        if copy {
+            self.last_copy = Some(frame_time);
+        }
+        copy
+    }
+}
+
+
+impl Capture {
+    /// Lower the capture rate on battery, while the window is occluded, or while the user is idle,
+    /// for monitoring tools running all day. `None` captures every frame again.
+    ///
+    /// the frames over the rate are dropped in the frame arrived handler before the staging copy,
+    /// so neither the GPU copy nor the readback is paid for them. [get_raw_frame](Self::get_raw_frame)
+    /// keeps returning the last copied frame meanwhile.
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// capture.set_power_policy(Some(dxcapture::PowerPolicy::new()
+    ///     .on_battery(Some(2.0))
+    ///     .when_idle(Some(0.2), Duration::from_secs(300))));
+    /// ```
+    pub fn set_power_policy(&self, policy: Option<PowerPolicy>) {
+        let mut power = self.power.lock().unwrap();
+        power.policy = policy;
+        power.checked_at = None;
+        power.fps = None;
+    }
+
+    pub fn power_policy(&self) -> Option<PowerPolicy> {
+        self.power.lock().unwrap().policy
+    }
+
+    /// The rate the [PowerPolicy] lowered the capture to, as of the last frame. `None` while at the full rate.
+    pub fn throttled_fps(&self) -> Option<f64> {
+        self.power.lock().unwrap().fps
+    }
+}
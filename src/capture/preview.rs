@@ -0,0 +1,286 @@
+//! Native window showing the live capture.
+//!
+//! Required features: *`"preview"`*
+
+use std::{
+    sync::Once,
+    time::Duration,
+};
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{
+            BOOL,
+            HWND,
+            LPARAM,
+            LRESULT,
+            PWSTR,
+            RECT,
+            WPARAM,
+        },
+        Graphics::{
+            Dxgi::{
+                Common::{
+                    DXGI_ALPHA_MODE_IGNORE,
+                    DXGI_FORMAT_B8G8R8A8_UNORM,
+                    DXGI_SAMPLE_DESC,
+                },
+                IDXGIDevice,
+                IDXGIFactory2,
+                IDXGISwapChain1,
+                DXGI_SCALING_STRETCH,
+                DXGI_SWAP_CHAIN_DESC1,
+                DXGI_SWAP_EFFECT_FLIP_DISCARD,
+                DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            },
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::{
+            AdjustWindowRect,
+            CreateWindowExW,
+            DefWindowProcW,
+            DestroyWindow,
+            DispatchMessageW,
+            IsWindow,
+            LoadCursorW,
+            PeekMessageW,
+            PostQuitMessage,
+            RegisterClassExW,
+            SetWindowPos,
+            ShowWindow,
+            TranslateMessage,
+            CS_HREDRAW,
+            CS_VREDRAW,
+            CW_USEDEFAULT,
+            IDC_ARROW,
+            MSG,
+            PM_REMOVE,
+            SWP_NOMOVE,
+            SWP_NOZORDER,
+            SW_SHOW,
+            WM_KEYDOWN,
+            WM_QUIT,
+            WNDCLASSEXW,
+            WS_OVERLAPPEDWINDOW,
+        },
+    },
+};
+
+use super::*;
+
+
+const CLASS_NAME: &str = "dxcapture_preview";
+const VK_ESCAPE: usize = 0x1B;
+/// largest client size at first, keeping the aspect ratio of the frame.
+const MAX_WIDTH: u32 = 1280;
+const MAX_HEIGHT: u32 = 720;
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if msg == WM_KEYDOWN && wparam.0 == VK_ESCAPE {
+        DestroyWindow(hwnd);
+        return LRESULT(0);
+    }
+
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
+
+fn register_class() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| unsafe {
+        let class_name: Vec<u16> = CLASS_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        let class = WNDCLASSEXW{
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(window_proc),
+            hInstance: GetModuleHandleW(None),
+            hCursor: LoadCursorW(None, IDC_ARROW),
+            // copied by RegisterClassExW
+            lpszClassName: PWSTR(class_name.as_ptr() as *mut u16),
+            ..Default::default()
+        };
+        RegisterClassExW(&class);
+    });
+}
+
+
+/// Window with a flip model swap chain on the device of the capture.
+struct PreviewWindow {
+    hwnd: HWND,
+    swap_chain: IDXGISwapChain1,
+    size: (u32, u32),
+}
+impl PreviewWindow {
+    fn new(d3d_device: &ID3D11Device, title: &str) -> anyhow::Result<Self, CaptureError> {
+        register_class();
+
+        unsafe {
+            let hwnd = CreateWindowExW(
+                0,
+                CLASS_NAME,
+                title,
+                WS_OVERLAPPEDWINDOW,
+                CW_USEDEFAULT, CW_USEDEFAULT, CW_USEDEFAULT, CW_USEDEFAULT,
+                None,
+                None,
+                GetModuleHandleW(None),
+                std::ptr::null(),
+            );
+            if hwnd.0 == 0 {
+                return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
+            }
+
+            let swap_chain = Self::create_swap_chain(d3d_device, hwnd).map_err(|e| {
+                DestroyWindow(hwnd);
+                CaptureError::DirectxError(e)
+            })?;
+            ShowWindow(hwnd, SW_SHOW);
+
+            Ok(Self{
+                hwnd,
+                swap_chain,
+                size: (1, 1),
+            })
+        }
+    }
+
+    unsafe fn create_swap_chain(d3d_device: &ID3D11Device, hwnd: HWND) -> windows::core::Result<IDXGISwapChain1> {
+        let factory: IDXGIFactory2 = d3d_device.cast::<IDXGIDevice>()?.GetAdapter()?.GetParent()?;
+
+        let desc = DXGI_SWAP_CHAIN_DESC1{
+            Width: 1,
+            Height: 1,
+            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+            BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: 2,
+            // the frame size, stretched to the window
+            Scaling: DXGI_SCALING_STRETCH,
+            SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+            AlphaMode: DXGI_ALPHA_MODE_IGNORE,
+            ..Default::default()
+        };
+        factory.CreateSwapChainForHwnd(d3d_device, hwnd, &desc, std::ptr::null(), None)
+    }
+
+    /// Handle the messages of the thread. `false` when the window is closed.
+    fn pump(&self) -> bool {
+        unsafe {
+            let mut msg = MSG::default();
+            while PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE).as_bool() {
+                if msg.message == WM_QUIT {
+                    // for the loop of the app
+                    PostQuitMessage(msg.wParam.0 as i32);
+                    return false;
+                }
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            IsWindow(self.hwnd).as_bool()
+        }
+    }
+
+    /// Copy `texture` into the back buffer and present it, resizing the buffers with the frame.
+    fn present(&mut self, d3d_context: &ID3D11DeviceContext, frame: &SharedFrame, texture: &ID3D11Texture2D) -> windows::core::Result<()> {
+        unsafe {
+            if self.size != (frame.width, frame.height) {
+                self.swap_chain.ResizeBuffers(2, frame.width, frame.height, frame.format, 0)?;
+                // fit the window to the first frame
+                if self.size == (1, 1) {
+                    self.fit_window(frame.width, frame.height);
+                }
+                self.size = (frame.width, frame.height);
+            }
+
+            let back_buffer: ID3D11Texture2D = self.swap_chain.GetBuffer(0)?;
+            d3d_context.CopyResource(&back_buffer, texture);
+
+            // paced by the vblank
+            self.swap_chain.Present(1, 0)
+        }
+    }
+
+    unsafe fn fit_window(&self, width: u32, height: u32) {
+        let scale = (MAX_WIDTH as f64 / width as f64).min(MAX_HEIGHT as f64 / height as f64).min(1.0);
+        let mut rect = RECT{
+            left: 0,
+            top: 0,
+            right: (width as f64 * scale) as i32,
+            bottom: (height as f64 * scale) as i32,
+        };
+        AdjustWindowRect(&mut rect, WS_OVERLAPPEDWINDOW, BOOL::from(false));
+
+        SetWindowPos(self.hwnd, None, 0, 0, rect.right - rect.left, rect.bottom - rect.top, SWP_NOMOVE | SWP_NOZORDER);
+    }
+}
+impl Drop for PreviewWindow {
+    fn drop(&mut self) {
+        unsafe {
+            if IsWindow(self.hwnd).as_bool() {
+                DestroyWindow(self.hwnd);
+            }
+        }
+    }
+}
+
+
+impl Capture {
+    /// Open a window showing the live capture, and block until it is closed (or Esc is pressed).
+    ///
+    /// the frames are copied on the GPU into the swap chain of the window, without reading them back,
+    /// so this is for checking what is captured without `opencv::highgui`. the window is fitted to the first frame
+    /// and the frames are stretched to it.
+    ///
+    /// shares the frames by [share_frames](Self::share_frames) while open, and stops sharing after,
+    /// unless it was sharing before.
+    ///
+    /// Required features: *`"preview"`*
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    /// * [DirectxError](CaptureError::DirectxError): failed to create the window or the swap chain, or to present.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Untitled - Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// capture.show_preview("dxcapture preview").unwrap();
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "preview")))]
+    pub fn show_preview(&self, title: &str) -> anyhow::Result<(), CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+
+        let was_sharing = self.shared.lock().unwrap().fence.is_some();
+        if !was_sharing {
+            self.share_frames(2)?;
+        }
+
+        let result = self.run_preview(title);
+
+        if !was_sharing {
+            self.stop_sharing();
+        }
+        result
+    }
+
+    fn run_preview(&self, title: &str) -> anyhow::Result<(), CaptureError> {
+        let mut window = PreviewWindow::new(&self._d3d_device, title)?;
+        let mut presented = None;
+
+        while window.pump() {
+            // the same device, so the copy into the shared texture is done before
+            let latest = self.shared.lock().unwrap().latest_texture();
+            match latest {
+                Some((frame, texture)) if presented != Some(frame.fence_value) => {
+                    window.present(&self.d3d_context, &frame, &texture).map_err(|e| CaptureError::DirectxError(e))?;
+                    presented = Some(frame.fence_value);
+                },
+                _ => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use windows::Win32::Graphics::Direct3D11::D3D11_BOX;
+
+use super::*;
+
+
+/// `region` clipped to a frame of `width` x `height`, as the box to copy. `None` when nothing is left.
+pub(crate) fn region_box(region: Rect, width: u32, height: u32) -> Option<D3D11_BOX> {
+    let clipped = region.intersect(&Rect::new(0, 0, width as i32, height as i32));
+    if clipped.is_empty() {
+        return None;
+    }
+
+    Some(D3D11_BOX{
+        left: clipped.x as u32,
+        top: clipped.y as u32,
+        front: 0,
+        right: clipped.right() as u32,
+        bottom: clipped.bottom() as u32,
+        back: 1,
+    })
+}
+
+/// Content of `width` x `height` in the whole frame, seen from the frame cut to `region`.
+pub(crate) fn region_content_size(width: i32, height: i32, region: &D3D11_BOX) -> ContentSize {
+    let texture_width = (region.right - region.left) as i32;
+    let texture_height = (region.bottom - region.top) as i32;
+
+    ContentSize{
+        width: (width - region.left as i32).clamp(0, texture_width),
+        height: (height - region.top as i32).clamp(0, texture_height),
+        texture_width,
+        texture_height,
+    }
+}
+
+
+impl Capture {
+    /// Copy and read back only `region` of the frames, such as the HUD of a game, instead of the whole frames.
+    ///
+    /// the region is cut out on the GPU by `CopySubresourceRegion`, so the frames of [get_raw_frame](Self::get_raw_frame)
+    /// and [get_texture](Self::get_texture) are in the size of the region. it's applied after the [zoom](Self::set_zoom),
+    /// and clipped to the frame. the whole frames are delivered while it's out of the frame. `None` goes back to the whole frames.
+    /// [FrameInfo::region] of each frame is the region cut out, and the filters on the screen positions map through it.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Game".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::builder(&device)
+    ///     .region(dxcapture::Rect::new(16, 16, 320, 64))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let hud = capture.wait_raw_frame().expect("Failed to capture");
+    /// assert!(hud.width <= 320);
+    /// ```
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the region is empty.
+    pub fn set_region(&self, region: Option<Rect>) -> anyhow::Result<(), CaptureError> {
+        if region.is_some_and(|region| region.is_empty()) {
+            return Err(CaptureError::InvalidArgument("region must not be empty.".to_string()));
+        }
+
+        *self.region.lock().unwrap() = region;
+        Ok(())
+    }
+
+    pub fn region(&self) -> Option<Rect> {
+        *self.region.lock().unwrap()
+    }
+}
@@ -0,0 +1,480 @@
+//! Frames shared with other devices by NT handles, or with other processes by keyed mutex textures.
+//!
+//! Required features: *`"interop"`*
+
+use windows::{
+    core::{
+        Interface,
+        HRESULT,
+    },
+    Win32::{
+        Foundation::{
+            CloseHandle,
+            HANDLE,
+            PWSTR,
+        },
+        Graphics::{
+            Direct3D11::{
+                ID3D11Device,
+                ID3D11Device5,
+                ID3D11DeviceContext,
+                ID3D11DeviceContext4,
+                ID3D11Fence,
+                ID3D11Texture2D,
+                D3D11_BIND_SHADER_RESOURCE,
+                D3D11_FENCE_FLAG_SHARED,
+                D3D11_RESOURCE_MISC_SHARED,
+                D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX,
+                D3D11_RESOURCE_MISC_SHARED_NTHANDLE,
+                D3D11_TEXTURE2D_DESC,
+                D3D11_USAGE_DEFAULT,
+            },
+            Dxgi::{
+                IDXGIKeyedMutex,
+                IDXGIResource,
+                IDXGIResource1,
+                DXGI_SHARED_RESOURCE_READ,
+                DXGI_SHARED_RESOURCE_WRITE,
+            },
+        },
+        System::SystemServices::GENERIC_ALL,
+    },
+};
+
+use super::*;
+
+
+/// Frame copied into a shared texture. [Read more](Capture::share_frames)
+///
+/// the handles stay owned by the [Capture]. open them on the other device, like
+/// `ID3D12Device::OpenSharedHandle`, and wait for [fence_value](Self::fence_value) on [fence](Self::fence)
+/// before reading the texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SharedFrame {
+    /// NT handle of the shared texture. the same handle comes back every [buffers](Capture::share_frames) frames.
+    pub texture: HANDLE,
+    /// changes when the textures are made again, as on resize. keep the opened textures by it.
+    pub texture_id: u64,
+    /// NT handle of the fence, the same while sharing.
+    pub fence: HANDLE,
+    /// signaled on [fence](Self::fence) when the copy is done.
+    pub fence_value: u64,
+    pub width: u32,
+    pub height: u32,
+    /// `DXGI_FORMAT`, `DXGI_FORMAT_B8G8R8A8_UNORM` unless zoomed by a driver with other formats.
+    pub format: u32,
+}
+
+
+/// Frame copied into the keyed mutex texture of [get_shared_handle](Capture::get_shared_handle).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyedFrame {
+    /// shared handle of the texture, not an NT handle. usable in other processes as it is, and not closed.
+    pub handle: HANDLE,
+    /// changes when the texture is made again, as on resize. open the handle again then.
+    pub texture_id: u64,
+    pub width: u32,
+    pub height: u32,
+    /// `DXGI_FORMAT` of the texture.
+    pub format: u32,
+}
+
+
+/// Owned NT handle.
+#[derive(Debug)]
+struct OwnedHandle(HANDLE);
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SharedTexture {
+    texture: ID3D11Texture2D,
+    handle: OwnedHandle,
+    id: u64,
+}
+
+/// Shared textures of one frame size, used in turn.
+#[derive(Debug)]
+struct SharedRing {
+    textures: Vec<SharedTexture>,
+    desc: D3D11_TEXTURE2D_DESC,
+    next: usize,
+}
+impl SharedRing {
+    fn new(d3d_device: &ID3D11Device, frame_desc: &D3D11_TEXTURE2D_DESC, buffers: usize, first_id: u64) -> windows::core::Result<Self> {
+        let desc = D3D11_TEXTURE2D_DESC{
+            Width: frame_desc.Width,
+            Height: frame_desc.Height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: frame_desc.Format,
+            SampleDesc: frame_desc.SampleDesc,
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE,
+            CPUAccessFlags: 0,
+            MiscFlags: D3D11_RESOURCE_MISC_SHARED | D3D11_RESOURCE_MISC_SHARED_NTHANDLE,
+        };
+
+        let textures = (0..buffers).map(|index| unsafe {
+            let texture = d3d_device.CreateTexture2D(&desc, std::ptr::null())?;
+            let handle = texture.cast::<IDXGIResource1>()?
+                .CreateSharedHandle(std::ptr::null(), DXGI_SHARED_RESOURCE_READ | DXGI_SHARED_RESOURCE_WRITE, PWSTR::default())?;
+
+            Ok(SharedTexture{
+                texture,
+                handle: OwnedHandle(handle),
+                id: first_id + index as u64,
+            })
+        }).collect::<windows::core::Result<Vec<_>>>()?;
+
+        Ok(Self{
+            textures,
+            desc,
+            next: 0,
+        })
+    }
+
+    fn fits(&self, frame_desc: &D3D11_TEXTURE2D_DESC) -> bool {
+        self.desc.Width == frame_desc.Width && self.desc.Height == frame_desc.Height && self.desc.Format == frame_desc.Format
+    }
+}
+
+#[derive(Debug)]
+struct SharedFence {
+    fence: ID3D11Fence,
+    handle: OwnedHandle,
+    context: ID3D11DeviceContext4,
+    value: u64,
+}
+impl SharedFence {
+    fn new(d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext) -> windows::core::Result<Self> {
+        // D3D11.4, Windows 10 1703 or later
+        let device: ID3D11Device5 = d3d_device.cast()?;
+        let context: ID3D11DeviceContext4 = d3d_context.cast()?;
+
+        unsafe {
+            let mut fence: Option<ID3D11Fence> = None;
+            device.CreateFence(0, D3D11_FENCE_FLAG_SHARED, &mut fence)?;
+            let fence = fence.unwrap();
+            let handle = fence.CreateSharedHandle(std::ptr::null(), GENERIC_ALL, PWSTR::default())?;
+
+            Ok(Self{
+                fence,
+                handle: OwnedHandle(handle),
+                context,
+                value: 0,
+            })
+        }
+    }
+}
+
+
+/// Texture of [KeyedFrame].
+#[derive(Debug)]
+struct KeyedTexture {
+    texture: ID3D11Texture2D,
+    mutex: IDXGIKeyedMutex,
+    handle: HANDLE,
+    desc: D3D11_TEXTURE2D_DESC,
+    id: u64,
+}
+impl KeyedTexture {
+    fn new(d3d_device: &ID3D11Device, frame_desc: &D3D11_TEXTURE2D_DESC, id: u64) -> windows::core::Result<Self> {
+        let desc = D3D11_TEXTURE2D_DESC{
+            Width: frame_desc.Width,
+            Height: frame_desc.Height,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: frame_desc.Format,
+            SampleDesc: frame_desc.SampleDesc,
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_SHADER_RESOURCE,
+            CPUAccessFlags: 0,
+            MiscFlags: D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX,
+        };
+
+        unsafe {
+            let texture = d3d_device.CreateTexture2D(&desc, std::ptr::null())?;
+            let mutex = texture.cast::<IDXGIKeyedMutex>()?;
+            let handle = texture.cast::<IDXGIResource>()?.GetSharedHandle()?;
+
+            Ok(Self{
+                texture,
+                mutex,
+                handle,
+                desc,
+                id,
+            })
+        }
+    }
+
+    /// Acquire `key`. `false` when timed out.
+    fn acquire(&self, key: u64, timeout: Duration) -> windows::core::Result<bool> {
+        // WAIT_TIMEOUT is a success code, taken for Ok by the wrapper
+        const WAIT_TIMEOUT: HRESULT = HRESULT(0x102);
+        let milliseconds = timeout.as_millis().min(u32::MAX as u128 - 1) as u32;
+
+        let result = unsafe {
+            (Interface::vtable(&self.mutex).8)(std::mem::transmute_copy(&self.mutex), key, milliseconds)
+        };
+        if result == WAIT_TIMEOUT {
+            return Ok(false);
+        }
+        result.ok().map(|_| true)
+    }
+}
+
+
+/// Sharing of a [Capture], shared with the frame arrived handler.
+#[derive(Debug, Default)]
+pub(crate) struct SharedState {
+    buffers: usize,
+    fence: Option<SharedFence>,
+    ring: Option<SharedRing>,
+    next_id: u64,
+    latest: Option<SharedFrame>,
+    keyed: Option<KeyedTexture>,
+}
+impl SharedState {
+    /// Copy `texture` into the next shared texture and signal the fence. nothing while not sharing.
+    pub(crate) fn publish(&mut self, d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, texture: &ID3D11Texture2D) -> windows::core::Result<()> {
+        let fence = match self.fence.as_mut() {
+            Some(fence) => fence,
+            None => return Ok(()),
+        };
+
+        let mut frame_desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut frame_desc);
+        }
+        // the frame size changes with the window
+        if !self.ring.as_ref().is_some_and(|ring| ring.fits(&frame_desc)) {
+            self.ring = None;
+            self.ring = Some(SharedRing::new(d3d_device, &frame_desc, self.buffers, self.next_id)?);
+            self.next_id += self.buffers as u64;
+        }
+        let ring = self.ring.as_mut().unwrap();
+        let shared = &ring.textures[ring.next];
+        ring.next = (ring.next + 1) % ring.textures.len();
+
+        fence.value += 1;
+        unsafe {
+            d3d_context.CopyResource(&shared.texture, texture);
+            fence.context.Signal(&fence.fence, fence.value)?;
+            // submit now, the other device may be waiting for it
+            d3d_context.Flush();
+        }
+
+        self.latest = Some(SharedFrame{
+            texture: shared.handle.0,
+            texture_id: shared.id,
+            fence: fence.handle.0,
+            fence_value: fence.value,
+            width: ring.desc.Width,
+            height: ring.desc.Height,
+            format: ring.desc.Format,
+        });
+
+        Ok(())
+    }
+
+    /// Block until the copy signaled by `fence_value` is done on the GPU.
+    #[cfg(feature = "wgpu")]
+    pub(crate) fn wait_copied(&self, fence_value: u64) {
+        if let Some(fence) = self.fence.as_ref() {
+            // a copy takes well under a millisecond
+            while unsafe { fence.fence.GetCompletedValue() } < fence_value {
+                std::thread::yield_now();
+            }
+        }
+    }
+
+    /// [SharedFrame] of the last frame and its texture.
+    #[cfg(any(feature = "gl", feature = "cuda", feature = "preview", feature = "d2d", feature = "media", feature = "color"))]
+    pub(crate) fn latest_texture(&self) -> Option<(SharedFrame, ID3D11Texture2D)> {
+        let latest = self.latest?;
+        let texture = self.ring.as_ref()?.textures.iter().find(|shared| shared.id == latest.texture_id)?;
+
+        Some((latest, texture.texture.clone()))
+    }
+}
+
+
+impl Capture {
+    /// Also copy the frames into textures shared by NT handles, for D3D12 renderers and encoders.
+    ///
+    /// each frame is copied on the GPU into the next of `buffers` shared textures, then a shared fence is signaled.
+    /// open the handles of [shared_frame](Self::shared_frame) on the other device
+    /// (`ID3D12Device::OpenSharedHandle` for both the texture and the fence),
+    /// and make its queue wait for [fence_value](SharedFrame::fence_value) before using the texture.
+    /// a texture is written again `buffers` frames later, so finish with it within `buffers - 1` frames.
+    ///
+    /// the frames are still read back for [get_raw_frame](Self::get_raw_frame). needs Windows 10 1703 or later.
+    ///
+    /// Required features: *`"interop"`*
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): `buffers` is 0.
+    /// * [DirectxError](CaptureError::DirectxError): the device has no shared fences (D3D11.4).
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(3).unwrap();
+    ///
+    /// let frame = capture.wait_shared_frame().expect("Failed to capture");
+    /// // d3d12_device.OpenSharedHandle(frame.texture) once per frame.texture_id,
+    /// // d3d12_device.OpenSharedHandle(frame.fence) once,
+    /// // command_queue.Wait(fence, frame.fence_value), then copy or sample the texture.
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub fn share_frames(&self, buffers: usize) -> anyhow::Result<(), CaptureError> {
+        if buffers == 0 {
+            return Err(CaptureError::InvalidArgument("buffers must be greater than 0.".to_string()));
+        }
+
+        let mut shared = self.shared.lock().unwrap();
+        if shared.fence.is_none() {
+            shared.fence = Some(SharedFence::new(&self._d3d_device, &self.d3d_context).map_err(|e| CaptureError::DirectxError(e))?);
+        }
+        if shared.buffers != buffers {
+            shared.ring = None;
+        }
+        shared.buffers = buffers;
+
+        Ok(())
+    }
+
+    /// Stop sharing and close the handles.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub fn stop_sharing(&self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.ring = None;
+        shared.fence = None;
+        shared.latest = None;
+    }
+
+    /// The latest shared frame. [Read more](Self::share_frames)
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Self::share_frames).
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub fn shared_frame(&self) -> anyhow::Result<SharedFrame, CaptureError> {
+        let shared = self.shared.lock().unwrap();
+        if !self.is_running() || shared.fence.is_none() {
+            return Err(CaptureError::NotActive);
+        }
+
+        shared.latest.ok_or(CaptureError::NoTexture)
+    }
+
+    /// [shared_frame](Self::shared_frame), waiting through NoTexture.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub fn wait_shared_frame(&self) -> anyhow::Result<SharedFrame, CaptureError> {
+        loop {
+            match self.shared_frame() {
+                Err(CaptureError::NoTexture) => std::thread::sleep(Duration::from_millis(1)),
+                result => return result,
+            }
+        }
+    }
+
+    /// Copy the latest shared frame into a new texture of `bind_flags` on the GPU, kept from the next frames.
+    #[cfg(any(feature = "d2d", feature = "media"))]
+    pub(crate) fn copy_latest_texture(&self, bind_flags: u32) -> anyhow::Result<(SharedFrame, ID3D11Texture2D), CaptureError> {
+        let (frame, texture) = {
+            // through shared_frame to check the state
+            let frame = self.shared_frame()?;
+            self.shared.lock().unwrap().latest_texture()
+                .filter(|(latest, _)| latest.texture_id == frame.texture_id)
+                .ok_or(CaptureError::NoTexture)?
+        };
+
+        unsafe {
+            let mut desc = D3D11_TEXTURE2D_DESC::default();
+            texture.GetDesc(&mut desc);
+            desc.Usage = D3D11_USAGE_DEFAULT;
+            desc.BindFlags = bind_flags;
+            desc.CPUAccessFlags = 0;
+            desc.MiscFlags = 0;
+
+            let copy_texture = self._d3d_device.CreateTexture2D(&desc, std::ptr::null()).map_err(|e| CaptureError::DirectxError(e))?;
+            self.d3d_context.CopyResource(&copy_texture, &texture);
+
+            Ok((frame, copy_texture))
+        }
+    }
+
+    /// Copy the current frame into a keyed mutex texture and return its shared handle, for the encoders and renderers
+    /// in other processes or on other APIs.
+    ///
+    /// the handle is a legacy shared handle, so the other process opens it as it is by `ID3D11Device::OpenSharedResource`
+    /// (or Vulkan `D3D11_TEXTURE_KMT`). the keyed mutex passes the texture back and forth: this copies under key 0 and
+    /// releases key 1, so the consumer does `AcquireSync(1)`, reads, then `ReleaseSync(0)`. waits up to `timeout` for
+    /// the consumer, and the same texture is used until the frame size changes.
+    ///
+    /// the frames are copied on the GPU, as [get_texture](Self::get_texture).
+    ///
+    /// Required features: *`"interop"`*
+    /// ## Errors
+    /// * [Timeout](CaptureError::Timeout): the consumer didn't release key 0 in `timeout`.
+    /// * the errors of [get_texture](Self::get_texture).
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let frame = loop {
+    ///     match capture.get_shared_handle(Duration::from_millis(100)) {
+    ///         Err(dxcapture::CaptureError::NoTexture) => continue,
+    ///         result => break result.unwrap(),
+    ///     }
+    /// };
+    /// // send frame.handle to the encoder process, which does
+    /// // OpenSharedResource(handle), AcquireSync(1, ..), encode, ReleaseSync(0)
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "interop")))]
+    pub fn get_shared_handle(&self, timeout: Duration) -> anyhow::Result<KeyedFrame, CaptureError> {
+        let texture = self.get_texture()?;
+        let mut frame_desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut frame_desc);
+        }
+
+        let mut shared = self.shared.lock().unwrap();
+        let fits = shared.keyed.as_ref().is_some_and(|keyed| {
+            keyed.desc.Width == frame_desc.Width && keyed.desc.Height == frame_desc.Height && keyed.desc.Format == frame_desc.Format
+        });
+        if !fits {
+            let id = shared.next_id;
+            shared.next_id += 1;
+            shared.keyed = None;
+            shared.keyed = Some(KeyedTexture::new(&self._d3d_device, &frame_desc, id).map_err(|e| CaptureError::DirectxError(e))?);
+        }
+        let keyed = shared.keyed.as_ref().unwrap();
+
+        if !keyed.acquire(0, timeout).map_err(|e| CaptureError::DirectxError(e))? {
+            return Err(CaptureError::Timeout);
+        }
+        unsafe {
+            self.d3d_context.CopyResource(&keyed.texture, &texture);
+            keyed.mutex.ReleaseSync(1).map_err(|e| CaptureError::DirectxError(e))?;
+            // submit now, the other process may be waiting for it
+            self.d3d_context.Flush();
+        }
+
+        Ok(KeyedFrame{
+            handle: keyed.handle,
+            texture_id: keyed.id,
+            width: keyed.desc.Width,
+            height: keyed.desc.Height,
+            format: keyed.desc.Format,
+        })
+    }
+}
@@ -0,0 +1,86 @@
+use super::*;
+
+
+/// Staging textures taken in turn by the frames.
+const STAGING_TEXTURES: usize = 3;
+
+/// Staging textures of the frame arrived handler, reused instead of created for every frame.
+///
+/// a texture is written again [STAGING_TEXTURES] frames after it was the current one, so the readers which took it
+/// as the current frame have a couple of frames to read it back. the textures still [read](ReadingTextures) are skipped,
+/// and a new texture not kept in the ring is made when all of them are. made again when the size or the format changes.
+#[derive(Debug, Default)]
+pub(crate) struct StagingRing {
+    desc: Option<D3D11_TEXTURE2D_DESC>,
+    textures: Vec<ID3D11Texture2D>,
+    next: usize,
+}
+impl StagingRing {
+    /// A staging texture of `desc` to copy the next frame into, not in `reading`.
+    pub(crate) fn next(&mut self, d3d_device: &ID3D11Device, desc: &D3D11_TEXTURE2D_DESC, reading: &ReadingTextures) -> windows::core::Result<ID3D11Texture2D> {
+        if self.desc.as_ref() != Some(desc) {
+            self.desc = Some(*desc);
+            self.textures.clear();
+            self.next = 0;
+        }
+
+        if self.textures.len() < STAGING_TEXTURES {
+            let texture = unsafe { d3d_device.CreateTexture2D( desc, std::ptr::null() )? };
+            self.textures.push(texture.clone());
+            return Ok(texture);
+        }
+
+        let reading = reading.0.lock().unwrap();
+        let free = (0..STAGING_TEXTURES)
+            .map(|offset| (self.next + offset) % STAGING_TEXTURES)
+            .find(|index| !reading.contains(&self.textures[*index]));
+        match free {
+            Some(index) => {
+                self.next = (index + 1) % STAGING_TEXTURES;
+                Ok(self.textures[index].clone())
+            },
+            // all still mapped by slow readers
+            None => unsafe { d3d_device.CreateTexture2D( desc, std::ptr::null() ) },
+        }
+    }
+
+    /// Release the textures, while the frames are kept by the others.
+    pub(crate) fn clear(&mut self) {
+        self.desc = None;
+        self.textures.clear();
+        self.next = 0;
+    }
+}
+
+
+/// Staging textures being read back by the readers of [Capture], which [StagingRing] doesn't write.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ReadingTextures(Arc<Mutex<Vec<ID3D11Texture2D>>>);
+impl ReadingTextures {
+    /// Take the current texture out of `current` and mark it read until the guard is dropped.
+    ///
+    /// marked under the same lock as the ring picks the next texture, so the ring never picks a texture
+    /// taken but not marked yet.
+    pub(crate) fn take(&self, current: &Mutex<Option<ID3D11Texture2D>>) -> Option<(ID3D11Texture2D, ReadingGuard)> {
+        let mut reading = self.0.lock().unwrap();
+        let texture = current.lock().unwrap().clone()?;
+        reading.push(texture.clone());
+
+        Some((texture.clone(), ReadingGuard{ reading: self.clone(), texture }))
+    }
+}
+
+/// Mark of a texture being read, removed when dropped.
+#[derive(Debug)]
+pub(crate) struct ReadingGuard {
+    reading: ReadingTextures,
+    texture: ID3D11Texture2D,
+}
+impl Drop for ReadingGuard {
+    fn drop(&mut self) {
+        let mut reading = self.reading.0.lock().unwrap();
+        if let Some(index) = reading.iter().position(|texture| *texture == self.texture) {
+            reading.swap_remove(index);
+        }
+    }
+}
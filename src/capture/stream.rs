@@ -0,0 +1,146 @@
+//! Frames as an async `Stream`.
+//!
+//! Required features: *`"async"`*
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+        Waker,
+    },
+};
+use futures_core::Stream;
+
+use super::*;
+
+
+#[derive(Debug, Default)]
+struct StreamState {
+    queue: VecDeque<RawFrameData>,
+    capacity: usize,
+    waker: Option<Waker>,
+    /// the capture is gone, or the stream is dropped.
+    closed: bool,
+    dropped: u64,
+}
+
+/// End of a [FrameStream] in the frame arrived handler. closes the stream when the handler is dropped with the capture.
+struct StreamFeed(Arc<Mutex<StreamState>>);
+impl StreamFeed {
+    /// `false` once the stream is dropped.
+    fn push(&self, frame: RawFrameData) -> bool {
+        let mut state = self.0.lock().unwrap();
+        if state.closed {
+            return false;
+        }
+
+        // drop the oldest, the newest is the most worth
+        if state.capacity <= state.queue.len() {
+            state.queue.pop_front();
+            state.dropped += 1;
+        }
+        state.queue.push_back(frame);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+}
+impl Drop for StreamFeed {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+
+/// `Stream` of the arriving frames. [Read more](Capture::frame_stream)
+///
+/// Required features: *`"async"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "async")))]
+#[derive(Debug)]
+pub struct FrameStream {
+    state: Arc<Mutex<StreamState>>,
+}
+impl FrameStream {
+    /// Frames dropped as the oldest while the consumer was behind.
+    pub fn dropped(&self) -> u64 {
+        self.state.lock().unwrap().dropped
+    }
+}
+impl Stream for FrameStream {
+    type Item = RawFrameData;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(frame) = state.queue.pop_front() {
+            return Poll::Ready(Some(frame));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+impl Drop for FrameStream {
+    fn drop(&mut self) {
+        // the feed is removed on the next frame
+        self.state.lock().unwrap().closed = true;
+    }
+}
+
+
+impl Capture {
+    /// Every arriving frame as a `futures::Stream`, keeping the latest 2 when the consumer is behind.
+    /// [Read more](Self::frame_stream_bounded)
+    ///
+    /// Required features: *`"async"`*
+    /// # Examples
+    /// ```
+    /// use futures_core::Stream;
+    ///
+    /// # async fn run() {
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let mut frames = capture.frame_stream();
+    /// // or `frames.next().await` with `futures::StreamExt`
+    /// while let Some(raw) = std::future::poll_fn(|cx| std::pin::Pin::new(&mut frames).poll_next(cx)).await {
+    ///     println!("{}x{}", raw.width, raw.height);
+    /// }
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "async")))]
+    pub fn frame_stream(&self) -> FrameStream {
+        self.frame_stream_bounded(2)
+    }
+
+    /// Every arriving frame as a `futures::Stream`, fed from the frame arrived handler as [on_frame](Self::on_frame).
+    ///
+    /// when `capacity` frames are waiting, the oldest is dropped for the new one, so the consumer always gets
+    /// the latest frames and never holds the capture up. no runtime is needed, the handler wakes the task.
+    /// the stream ends when the capture is dropped.
+    ///
+    /// Required features: *`"async"`*
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "async")))]
+    pub fn frame_stream_bounded(&self, capacity: usize) -> FrameStream {
+        let state = Arc::new(Mutex::new(StreamState{
+            capacity: capacity.max(1),
+            ..Default::default()
+        }));
+
+        let feed = StreamFeed(state.clone());
+        self.callbacks.lock().unwrap().add(Box::new(move |frame| feed.push(frame)));
+
+        FrameStream{
+            state,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Tone mapping of the HDR frames to SDR.
+//!
+//! Required features: *`"tonemap"`*
+
+use windows::{
+    core::HRESULT,
+    Win32::{
+        Devices::Display::{
+            DisplayConfigGetDeviceInfo,
+            GetDisplayConfigBufferSizes,
+            QueryDisplayConfig,
+            DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+            DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+            DISPLAYCONFIG_DEVICE_INFO_HEADER,
+            DISPLAYCONFIG_DEVICE_INFO_TYPE,
+            DISPLAYCONFIG_MODE_INFO,
+            DISPLAYCONFIG_PATH_INFO,
+            DISPLAYCONFIG_SDR_WHITE_LEVEL,
+            DISPLAYCONFIG_SOURCE_DEVICE_NAME,
+        },
+        Foundation::LUID,
+        Graphics::Gdi::QDC_ONLY_ACTIVE_PATHS,
+    },
+};
+
+use super::*;
+use crate::displays::DisplayInfo;
+
+
+/// How the highlights above the SDR white are brought into SDR. [Read more](ToneMapOptions)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// cut at the SDR white, as [get_raw_frame](Capture::get_raw_frame) does.
+    Clip,
+    /// extended Reinhard on the luminance, with the brightest pixel of the frame at white.
+    /// SDR content stays as it is, and the highlights roll off instead of clipping.
+    #[default]
+    Reinhard,
+}
+
+/// Tone mapping of [get_tonemapped_frame](Capture::get_tonemapped_frame).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMapOptions {
+    /// brightness of the SDR white on the display in nits, the "SDR content brightness" of the HDR settings.
+    /// default is 80, the white of scRGB.
+    pub sdr_white_nits: f32,
+    pub operator: ToneMapOperator,
+}
+impl Default for ToneMapOptions {
+    fn default() -> Self {
+        Self::new(80.0)
+    }
+}
+impl ToneMapOptions {
+    pub fn new(sdr_white_nits: f32) -> Self {
+        Self{
+            sdr_white_nits,
+            operator: ToneMapOperator::default(),
+        }
+    }
+
+    /// With the SDR white level of `display`. [Read more](sdr_white_level)
+    pub fn for_display(display: &DisplayInfo) -> anyhow::Result<Self, CaptureError> {
+        Ok(Self::new(sdr_white_level(display)?))
+    }
+
+    pub fn operator(mut self, operator: ToneMapOperator) -> Self {
+        self.operator = operator;
+        self
+    }
+}
+
+
+fn device_info_header(r#type: DISPLAYCONFIG_DEVICE_INFO_TYPE, size: usize, adapter_id: LUID, id: u32) -> DISPLAYCONFIG_DEVICE_INFO_HEADER {
+    DISPLAYCONFIG_DEVICE_INFO_HEADER{
+        r#type,
+        size: size as u32,
+        adapterId: adapter_id,
+        id,
+    }
+}
+
+/// SDR white level of `display` in nits, where the SDR content is shown on the HDR display.
+///
+/// 80 on SDR displays.
+/// ## Errors
+/// * [InvalidArgument](CaptureError::InvalidArgument): `display` is not attached anymore.
+/// * [DirectxError](CaptureError::DirectxError): failed to query the display configuration.
+pub fn sdr_white_level(display: &DisplayInfo) -> anyhow::Result<f32, CaptureError> {
+    let to_error = |code: i32| CaptureError::DirectxError(HRESULT::from_win32(code as u32).into());
+
+    unsafe {
+        let (mut path_count, mut mode_count) = (0, 0);
+        let result = GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count);
+        if result != 0 {
+            return Err(to_error(result));
+        }
+        let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+        let result = QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count, paths.as_mut_ptr(),
+            &mut mode_count, modes.as_mut_ptr(),
+            std::ptr::null_mut(),
+        );
+        if result != 0 {
+            return Err(to_error(result));
+        }
+        paths.truncate(path_count as usize);
+
+        for path in paths {
+            // the GDI name of the source, as the display name of the monitor
+            let mut source = DISPLAYCONFIG_SOURCE_DEVICE_NAME::default();
+            source.header = device_info_header(
+                DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>(),
+                path.sourceInfo.adapterId, path.sourceInfo.id,
+            );
+            if DisplayConfigGetDeviceInfo(&mut source.header) != 0 {
+                continue;
+            }
+            let name = String::from_utf16_lossy(&source.viewGdiDeviceName);
+            if name.trim_matches(char::from(0)) != display.display_name {
+                continue;
+            }
+
+            let mut white_level = DISPLAYCONFIG_SDR_WHITE_LEVEL::default();
+            white_level.header = device_info_header(
+                DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL, std::mem::size_of::<DISPLAYCONFIG_SDR_WHITE_LEVEL>(),
+                path.targetInfo.adapterId, path.targetInfo.id,
+            );
+            let result = DisplayConfigGetDeviceInfo(&mut white_level.header);
+            if result != 0 {
+                return Err(to_error(result));
+            }
+            // in thousandths of 80 nits
+            return Ok(white_level.SDRWhiteLevel as f32 / 1000.0 * 80.0);
+        }
+    }
+
+    Err(CaptureError::InvalidArgument(format!("display {} is not attached.", display.display_name)))
+}
+
+/// Map a scRGB `frame` into the 8 bits sRGB BGRA of [RawFrameData] by `options`.
+pub fn tonemap_frame(frame: &FloatFrameData, options: &ToneMapOptions) -> RawFrameData {
+    // SDR white at 1.0
+    let scale = 80.0 / options.sdr_white_nits.max(f32::EPSILON);
+    let luminance = |pixel: &[f32]| (0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2]) * scale;
+
+    // extended Reinhard keeps 0..=1 below a white of 1.0
+    let white = match options.operator {
+        ToneMapOperator::Clip => 1.0,
+        ToneMapOperator::Reinhard => frame.data.chunks_exact(4).map(luminance).fold(1.0, f32::max),
+    };
+
+    let data = frame.data.chunks_exact(4)
+        .flat_map(|pixel| {
+            let l = luminance(pixel);
+            let mapped = match options.operator {
+                ToneMapOperator::Clip => scale,
+                ToneMapOperator::Reinhard if 0.0 < l => {
+                    let l_mapped = l * (1.0 + l / (white * white)) / (1.0 + l);
+                    scale * l_mapped / l
+                },
+                ToneMapOperator::Reinhard => scale,
+            };
+            [
+                linear_to_srgb8(pixel[2] * mapped),
+                linear_to_srgb8(pixel[1] * mapped),
+                linear_to_srgb8(pixel[0] * mapped),
+                (pixel[3].clamp(0.0, 1.0) * 255.0).round() as u8,
+            ]
+        })
+        .collect();
+
+    RawFrameData{
+        width: frame.width,
+        height: frame.height,
+        data,
+        info: frame.info,
+        row_pitch: 0,
+    }
+}
+
+
+impl Capture {
+    /// Return current frame of `R16G16B16A16Float` tone mapped to 8 bits BGRA, so the screenshots of HDR content
+    /// are neither washed out nor clipped.
+    ///
+    /// capture by [new_with_format](Self::new_with_format) in `R16G16B16A16Float`. mapped on the CPU, as
+    /// [tonemap_frame]. for the GPU, see [ColorOptions::hdr_to_sdr](crate::ColorOptions::hdr_to_sdr) of the `color` feature.
+    ///
+    /// Required features: *`"tonemap"`*
+    /// # Examples
+    /// ```
+    /// use windows::Graphics::DirectX::DirectXPixelFormat;
+    ///
+    /// let display = &dxcapture::enumerate_displays().unwrap()[0];
+    /// let device = dxcapture::Device::new_from_displays(Some(1)).unwrap();
+    /// let capture = dxcapture::Capture::new_with_format(&device, DirectXPixelFormat::R16G16B16A16Float).unwrap();
+    ///
+    /// let options = dxcapture::ToneMapOptions::for_display(display).unwrap();
+    /// let raw = capture.wait_tonemapped_frame(&options).unwrap();
+    /// ```
+    /// ## Errors
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): the frames are not `R16G16B16A16Float`.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "tonemap")))]
+    pub fn get_tonemapped_frame(&self, options: &ToneMapOptions) -> anyhow::Result<RawFrameData, CaptureError> {
+        let frame = self.get_float_frame()?;
+
+        Ok(tonemap_frame(&frame, options))
+    }
+
+    /// Return current frame tone mapped to 8 bits BGRA. with throught NoTexture
+    ///
+    /// Required features: *`"tonemap"`*
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "tonemap")))]
+    pub fn wait_tonemapped_frame(&self, options: &ToneMapOptions) -> anyhow::Result<RawFrameData, CaptureError> {
+        self.wait_frame_with(None, |capture| capture.get_tonemapped_frame(options))
+    }
+}
@@ -0,0 +1,64 @@
+use tch::{
+    Cuda,
+    Tensor,
+};
+
+use crate::tensor::{
+    frame_to_tensor,
+    TensorLayout,
+    TensorOptions,
+};
+
+use super::*;
+
+
+impl Capture {
+    /// Get the current frame as a NCHW f32 `tch::Tensor` on `device`, for LibTorch models.
+    ///
+    /// resized, cropped and normalized on the CPU as `options`, then copied to `device`
+    /// (`tch::Device::Cuda(0)` for the first GPU). [Read more](frame_to_tensor)
+    ///
+    /// Required features: *`"tch"`*
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): as [source_rect](TensorOptions::source_rect).
+    /// * [TorchError](CaptureError::TorchError): CUDA is not available for `device`, or failed to make the tensor.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let options = dxcapture::TensorOptions::imagenet(224, 224);
+    /// let input = capture.wait_tensor_frame(&options, tch::Device::cuda_if_available()).expect("Failed to capture");
+    /// assert_eq!(input.size(), vec![1, 3, 224, 224]);
+    /// // model.forward_ts(&[input])
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "tch")))]
+    pub fn get_tensor_frame(&self, options: &TensorOptions, device: tch::Device) -> anyhow::Result<Tensor, CaptureError> {
+        let raw = self.get_raw_frame()?;
+
+        Self::raw_to_tensor(&raw, options, device)
+    }
+
+    /// Get the current frame as a `tch::Tensor`. with throught NoTexture
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "tch")))]
+    pub fn wait_tensor_frame(&self, options: &TensorOptions, device: tch::Device) -> anyhow::Result<Tensor, CaptureError> {
+        let raw = self.wait_raw_frame()?;
+
+        Self::raw_to_tensor(&raw, options, device)
+    }
+
+    fn raw_to_tensor(raw: &RawFrameData, options: &TensorOptions, device: tch::Device) -> anyhow::Result<Tensor, CaptureError> {
+        // to_device panics without CUDA
+        if device.is_cuda() && !Cuda::is_available() {
+            return Err(CaptureError::TorchError("CUDA is not available.".to_string()));
+        }
+
+        let shape: Vec<i64> = options.shape(TensorLayout::Nchw).iter().map(|&size| size as i64).collect();
+        let data = frame_to_tensor(raw, options, TensorLayout::Nchw)?;
+
+        let tensor = Tensor::of_slice(&data).f_reshape(&shape)
+            .map_err(|e| CaptureError::TorchError(e.to_string()))?;
+
+        Ok(tensor.to_device(device))
+    }
+}
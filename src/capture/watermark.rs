@@ -0,0 +1,274 @@
+//! Compositing of a [WatermarkOverlay] onto the frames on the GPU, before they are copied out.
+//!
+//! Required features: *`"watermark"`*
+
+use windows::Win32::Graphics::{
+    Direct3D::{
+        Fxc::D3DCompile,
+        ID3DBlob,
+        D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+    },
+    Direct3D11::{
+        ID3D11BlendState,
+        ID3D11PixelShader,
+        ID3D11RenderTargetView,
+        ID3D11ShaderResourceView,
+        ID3D11VertexShader,
+        D3D11_BIND_RENDER_TARGET,
+        D3D11_BLEND_DESC,
+        D3D11_BLEND_INV_SRC_ALPHA,
+        D3D11_BLEND_ONE,
+        D3D11_BLEND_OP_ADD,
+        D3D11_BLEND_SRC_ALPHA,
+        D3D11_BLEND_ZERO,
+        D3D11_BOX,
+        D3D11_COLOR_WRITE_ENABLE_BLUE,
+        D3D11_COLOR_WRITE_ENABLE_GREEN,
+        D3D11_COLOR_WRITE_ENABLE_RED,
+        D3D11_RENDER_TARGET_BLEND_DESC,
+        D3D11_SUBRESOURCE_DATA,
+        D3D11_USAGE_IMMUTABLE,
+        D3D11_VIEWPORT,
+    },
+    Dxgi::Common::DXGI_SAMPLE_DESC,
+};
+
+use crate::overlay::WatermarkOverlay;
+
+use super::*;
+
+
+/// A triangle covering the viewport, which is the image on the frame, reading the image pixel by pixel.
+/// blended by the output merger, so the frame is never read by the shader.
+const BLIT_SHADER: &str = r#"
+Texture2D<float4> image : register(t0);
+
+struct Vertex {
+    float4 position : SV_Position;
+    float2 uv : TEXCOORD0;
+};
+
+Vertex vs_main(uint id : SV_VertexID) {
+    Vertex vertex;
+    vertex.uv = float2((id << 1) & 2, id & 2);
+    vertex.position = float4(vertex.uv * float2(2, -2) + float2(-1, 1), 0, 1);
+    return vertex;
+}
+
+float4 ps_main(Vertex vertex) : SV_Target {
+    uint width, height;
+    image.GetDimensions(width, height);
+    return image.Load(int3(vertex.uv * float2(width, height), 0));
+}
+"#;
+
+fn compile(entry: &str, target: &str) -> windows::core::Result<ID3DBlob> {
+    unsafe {
+        let mut code: Option<ID3DBlob> = None;
+        let mut errors: Option<ID3DBlob> = None;
+        D3DCompile(
+            BLIT_SHADER.as_ptr() as *const _,
+            BLIT_SHADER.len(),
+            "watermark",
+            std::ptr::null(),
+            None,
+            entry,
+            target,
+            0,
+            0,
+            &mut code,
+            &mut errors,
+        )?;
+
+        Ok(code.unwrap())
+    }
+}
+
+
+/// Shaders and the image of [set_watermark](Capture::set_watermark), shared with the frame arrived handler.
+#[derive(Debug)]
+pub(crate) struct WatermarkState {
+    overlay: WatermarkOverlay,
+    vertex_shader: ID3D11VertexShader,
+    pixel_shader: ID3D11PixelShader,
+    blend: ID3D11BlendState,
+    /// the scaled image in BGRA, opacity folded into the alpha. `None` when scaled to nothing.
+    image: Option<(ID3D11ShaderResourceView, i32, i32)>,
+    /// copy of the frame drawn onto, made again when the size or the format changes.
+    target: Option<(D3D11_TEXTURE2D_DESC, ID3D11Texture2D, ID3D11RenderTargetView)>,
+}
+impl WatermarkState {
+    fn new(d3d_device: &ID3D11Device, mut overlay: WatermarkOverlay) -> windows::core::Result<Self> {
+        unsafe {
+            let code = compile("vs_main", "vs_4_0")?;
+            let vertex_shader = d3d_device.CreateVertexShader(code.GetBufferPointer(), code.GetBufferSize(), None)?;
+            let code = compile("ps_main", "ps_4_0")?;
+            let pixel_shader = d3d_device.CreatePixelShader(code.GetBufferPointer(), code.GetBufferSize(), None)?;
+
+            // over the frame by the alpha of the image, keeping the alpha of the frame
+            let mut blend_desc = D3D11_BLEND_DESC::default();
+            blend_desc.RenderTarget[0] = D3D11_RENDER_TARGET_BLEND_DESC{
+                BlendEnable: true.into(),
+                SrcBlend: D3D11_BLEND_SRC_ALPHA,
+                DestBlend: D3D11_BLEND_INV_SRC_ALPHA,
+                BlendOp: D3D11_BLEND_OP_ADD,
+                SrcBlendAlpha: D3D11_BLEND_ZERO,
+                DestBlendAlpha: D3D11_BLEND_ONE,
+                BlendOpAlpha: D3D11_BLEND_OP_ADD,
+                RenderTargetWriteMask: (D3D11_COLOR_WRITE_ENABLE_RED | D3D11_COLOR_WRITE_ENABLE_GREEN | D3D11_COLOR_WRITE_ENABLE_BLUE) as u8,
+            };
+            let blend = d3d_device.CreateBlendState(&blend_desc)?;
+
+            let scaled = overlay.scaled();
+            let image = if 0 < scaled.width && 0 < scaled.height {
+                let desc = D3D11_TEXTURE2D_DESC{
+                    Width: scaled.width as u32,
+                    Height: scaled.height as u32,
+                    MipLevels: 1,
+                    ArraySize: 1,
+                    Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                    SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+                    Usage: D3D11_USAGE_IMMUTABLE,
+                    BindFlags: D3D11_BIND_SHADER_RESOURCE,
+                    CPUAccessFlags: 0,
+                    MiscFlags: 0,
+                };
+                let initial_data = D3D11_SUBRESOURCE_DATA{
+                    pSysMem: scaled.data.as_ptr() as *const _,
+                    SysMemPitch: scaled.width as u32 * 4,
+                    SysMemSlicePitch: 0,
+                };
+                let texture = d3d_device.CreateTexture2D(&desc, &initial_data)?;
+                let view = d3d_device.CreateShaderResourceView(&texture, std::ptr::null())?;
+
+                Some((view, scaled.width, scaled.height))
+            } else {
+                None
+            };
+
+            Ok(Self{
+                overlay,
+                vertex_shader,
+                pixel_shader,
+                blend,
+                image,
+                target: None,
+            })
+        }
+    }
+
+    /// `texture` with the image blended onto it, placed on the area of `region` as on a frame of that size.
+    /// `texture` itself is left as it is, it belongs to the frame pool.
+    pub(crate) fn composite(&mut self, d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, texture: ID3D11Texture2D, region: Option<&D3D11_BOX>) -> windows::core::Result<ID3D11Texture2D> {
+        let (image, image_width, image_height) = match &self.image {
+            Some(image) => image.clone(),
+            None => return Ok(texture),
+        };
+
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut desc);
+        }
+        desc.Usage = D3D11_USAGE_DEFAULT as i32;
+        desc.BindFlags = D3D11_BIND_RENDER_TARGET;
+        desc.CPUAccessFlags = 0;
+        desc.MiscFlags = 0;
+        if self.target.as_ref().map_or(true, |(target_desc, _, _)| *target_desc != desc) {
+            unsafe {
+                let target = d3d_device.CreateTexture2D( &desc, std::ptr::null() )?;
+                let view = d3d_device.CreateRenderTargetView(&target, std::ptr::null())?;
+                self.target = Some((desc, target, view));
+            }
+        }
+        let (_, target, view) = self.target.as_ref().unwrap();
+
+        // placed on the frame as delivered, after the region is cut out
+        let (left, top, width, height) = match region {
+            Some(region) => (region.left as i32, region.top as i32, (region.right - region.left) as i32, (region.bottom - region.top) as i32),
+            None => (0, 0, desc.Width as i32, desc.Height as i32),
+        };
+        let (x, y) = self.overlay.placement(width, height, image_width, image_height);
+        let viewport = D3D11_VIEWPORT{
+            TopLeftX: (left + x) as f32,
+            TopLeftY: (top + y) as f32,
+            Width: image_width as f32,
+            Height: image_height as f32,
+            MinDepth: 0.0,
+            MaxDepth: 1.0,
+        };
+
+        unsafe {
+            d3d_context.CopyResource(target, &texture);
+
+            d3d_context.OMSetRenderTargets(1, &Some(view.clone()), None);
+            d3d_context.OMSetBlendState(&self.blend, std::ptr::null(), 0xffffffff);
+            d3d_context.RSSetViewports(1, &viewport);
+            d3d_context.IASetInputLayout(None);
+            d3d_context.IASetPrimitiveTopology(D3D11_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            d3d_context.VSSetShader(&self.vertex_shader, std::ptr::null(), 0);
+            d3d_context.PSSetShader(&self.pixel_shader, std::ptr::null(), 0);
+            d3d_context.PSSetShaderResources(0, 1, &Some(image));
+            d3d_context.Draw(3, 0);
+            // unbound, so the target is free for the copies
+            d3d_context.PSSetShaderResources(0, 1, &None);
+            d3d_context.OMSetRenderTargets(0, std::ptr::null(), None);
+            d3d_context.OMSetBlendState(None, std::ptr::null(), 0xffffffff);
+            d3d_context.VSSetShader(None, std::ptr::null(), 0);
+            d3d_context.PSSetShader(None, std::ptr::null(), 0);
+        }
+
+        Ok(target.clone())
+    }
+}
+
+
+impl Capture {
+    /// Blend `watermark` onto every frame on the GPU, or stop with `None`.
+    ///
+    /// the image is drawn onto a copy of each frame in the frame arrived handler, before the copies of the region,
+    /// the zoom and the readers, so the raw frames, the callbacks, the streams, [get_texture](Self::get_texture)
+    /// and the recorders all get it, and no post-processing pass is needed. the position, the scale and the opacity
+    /// are as set on `watermark`, placed on the frames as delivered. the image is taken as it is on float frames,
+    /// where 1.0 is the SDR white.
+    ///
+    /// Required features: *`"watermark"`*
+    /// # Examples
+    /// ```
+    /// use dxcapture::{ Corner, WatermarkOverlay };
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// // 2x2 red, scaled up
+    /// let stamp = WatermarkOverlay::new(2, 2, [255, 0, 0, 255].repeat(4)).unwrap()
+    ///     .scale(32.0)
+    ///     .opacity(0.6)
+    ///     .corner(Corner::TopRight);
+    /// capture.set_watermark(Some(stamp)).unwrap();
+    ///
+    /// let raw = capture.wait_raw_frame().unwrap();
+    /// ```
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    /// * [DirectxError](CaptureError::DirectxError): the shaders failed to compile, as without `d3dcompiler_47.dll`.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "watermark")))]
+    pub fn set_watermark(&self, watermark: Option<WatermarkOverlay>) -> anyhow::Result<(), CaptureError> {
+        if !self.is_running() {
+            return Err(CaptureError::NotActive);
+        }
+
+        let state = match watermark {
+            Some(watermark) => Some(WatermarkState::new(&self._d3d_device, watermark).map_err(|e| CaptureError::DirectxError(e))?),
+            None => None,
+        };
+        *self.watermark.lock().unwrap() = state;
+
+        Ok(())
+    }
+
+    /// A watermark is blended by [set_watermark](Self::set_watermark).
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "watermark")))]
+    pub fn has_watermark(&self) -> bool {
+        self.watermark.lock().unwrap().is_some()
+    }
+}
@@ -0,0 +1,111 @@
+//! Frames imported into `wgpu` on its D3D12 backend.
+//!
+//! Required features: *`"wgpu"`*
+
+use winapi::{
+    shared::winerror::FAILED,
+    um::d3d12::ID3D12Resource,
+    Interface as _,
+};
+use windows::Win32::Graphics::Dxgi::Common::{
+    DXGI_FORMAT_B8G8R8A8_UNORM,
+    DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+    DXGI_FORMAT_R10G10B10A2_UNORM,
+    DXGI_FORMAT_R16G16B16A16_FLOAT,
+    DXGI_FORMAT_R8G8B8A8_UNORM,
+    DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+};
+use wgpu_hal::api::Dx12;
+
+use super::*;
+
+
+/// `wgpu` format of a `DXGI_FORMAT` of the shared frames.
+fn texture_format(format: u32) -> Option<wgpu::TextureFormat> {
+    Some(match format {
+        DXGI_FORMAT_B8G8R8A8_UNORM => wgpu::TextureFormat::Bgra8Unorm,
+        DXGI_FORMAT_B8G8R8A8_UNORM_SRGB => wgpu::TextureFormat::Bgra8UnormSrgb,
+        DXGI_FORMAT_R8G8B8A8_UNORM => wgpu::TextureFormat::Rgba8Unorm,
+        DXGI_FORMAT_R8G8B8A8_UNORM_SRGB => wgpu::TextureFormat::Rgba8UnormSrgb,
+        DXGI_FORMAT_R10G10B10A2_UNORM => wgpu::TextureFormat::Rgb10a2Unorm,
+        DXGI_FORMAT_R16G16B16A16_FLOAT => wgpu::TextureFormat::Rgba16Float,
+        _ => return None,
+    })
+}
+
+impl Capture {
+    /// Open the latest shared frame as a `wgpu` texture of `device`, without reading it back.
+    ///
+    /// the shared texture of [share_frames](Self::share_frames) is opened on the D3D12 device under `device`,
+    /// so `device` must be of the DX12 backend on the same adapter as the captured [Device]. returns after the copy
+    /// into the shared texture is done on the GPU. the texture is written again [buffers](Self::share_frames) frames
+    /// later, so draw it before then and drop it.
+    ///
+    /// Required features: *`"wgpu"`*
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): closed, or not sharing.
+    /// * [NoTexture](CaptureError::NoTexture): no frame since [share_frames](Self::share_frames).
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): `wgpu` has no format for the frames.
+    /// * [InvalidArgument](CaptureError::InvalidArgument): `device` is not of the DX12 backend.
+    /// * [DirectxError](CaptureError::DirectxError): failed to open the texture, as on another adapter.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(3).unwrap();
+    ///
+    /// // wgpu_device of wgpu::Backends::DX12
+    /// let texture = capture.get_wgpu_texture(&wgpu_device).expect("Failed to open");
+    /// let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "wgpu")))]
+    pub fn get_wgpu_texture(&self, device: &wgpu::Device) -> anyhow::Result<wgpu::Texture, CaptureError> {
+        let frame = self.shared_frame()?;
+        let format = texture_format(frame.format).ok_or(CaptureError::UnsupportedPixelFormat(frame.format))?;
+        let size = wgpu::Extent3d{
+            width: frame.width,
+            height: frame.height,
+            depth_or_array_layers: 1,
+        };
+
+        let hal_texture = unsafe {
+            device.as_hal::<Dx12, _, _>(|hal_device| {
+                let hal_device = hal_device?;
+
+                let mut resource: *mut ID3D12Resource = std::ptr::null_mut();
+                let result = hal_device.raw_device().OpenSharedHandle(
+                    frame.texture.0 as _,
+                    &ID3D12Resource::uuidof(),
+                    &mut resource as *mut _ as *mut _,
+                );
+                if FAILED(result) {
+                    return Some(Err(CaptureError::DirectxError(windows::core::HRESULT(result).into())));
+                }
+
+                Some(Ok(wgpu_hal::dx12::Device::texture_from_raw(
+                    d3d12::Resource::from_raw(resource),
+                    format,
+                    wgpu::TextureDimension::D2,
+                    size,
+                    1,
+                    1,
+                )))
+            })
+        }.ok_or(CaptureError::InvalidArgument("device is not of the DX12 backend.".to_string()))??;
+
+        // the other device reads it after the copy
+        self.shared.lock().unwrap().wait_copied(frame.fence_value);
+
+        Ok(unsafe {
+            device.create_texture_from_hal::<Dx12>(hal_texture, &wgpu::TextureDescriptor{
+                label: Some("dxcapture frame"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_SRC,
+            })
+        })
+    }
+}
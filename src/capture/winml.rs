@@ -0,0 +1,74 @@
+//! Windows ML inputs of the shared frames.
+//!
+//! Required features: *`"winml"`*
+
+use windows::AI::MachineLearning::{
+    ImageFeatureValue,
+    LearningModelBinding,
+    LearningModelDevice,
+};
+
+use super::*;
+
+
+impl Capture {
+    /// `LearningModelDevice` on the device of the capture, so the frames stay on the GPU for the session.
+    ///
+    /// Required features: *`"winml"`*
+    /// # Examples
+    /// ```
+    /// use windows::AI::MachineLearning::{LearningModel, LearningModelSession};
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let model = LearningModel::LoadFromFilePath("model.onnx").unwrap();
+    /// let session = LearningModelSession::CreateFromModelOnDevice(&model, capture.create_learning_model_device().unwrap()).unwrap();
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "winml")))]
+    pub fn create_learning_model_device(&self) -> anyhow::Result<LearningModelDevice, CaptureError> {
+        D3D11Device::to_direct3d_device(&self._d3d_device)
+            .and_then(|device| LearningModelDevice::CreateFromDirect3D11Device(device))
+            .map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    /// Copy the latest shared frame into a new `ImageFeatureValue`, by [get_video_frame](Self::get_video_frame).
+    ///
+    /// Windows ML resizes and converts it to the image input of the model.
+    ///
+    /// Required features: *`"winml"`*
+    /// ## Errors
+    /// as [get_video_frame](Self::get_video_frame).
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "winml")))]
+    pub fn get_image_feature_value(&self) -> anyhow::Result<ImageFeatureValue, CaptureError> {
+        let frame = self.get_video_frame()?;
+
+        ImageFeatureValue::CreateFromVideoFrame(frame).map_err(|e| CaptureError::DirectxError(e))
+    }
+
+    /// Bind the latest shared frame to the input `name` of `binding`. [Read more](Self::get_image_feature_value)
+    ///
+    /// Required features: *`"winml"`*
+    /// # Examples
+    /// ```
+    /// use windows::AI::MachineLearning::{LearningModel, LearningModelBinding, LearningModelSession};
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// capture.share_frames(2).unwrap();
+    ///
+    /// let model = LearningModel::LoadFromFilePath("model.onnx").unwrap();
+    /// let session = LearningModelSession::CreateFromModelOnDevice(&model, capture.create_learning_model_device().unwrap()).unwrap();
+    /// let binding = LearningModelBinding::CreateFromSession(&session).unwrap();
+    ///
+    /// capture.wait_shared_frame().unwrap();
+    /// capture.bind_frame(&binding, "input").expect("Failed to bind");
+    /// let results = session.Evaluate(&binding, "").unwrap();
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "winml")))]
+    pub fn bind_frame(&self, binding: &LearningModelBinding, name: &str) -> anyhow::Result<(), CaptureError> {
+        let value = self.get_image_feature_value()?;
+
+        binding.Bind(name, &value).map_err(|e| CaptureError::DirectxError(e))
+    }
+}
@@ -0,0 +1,169 @@
+use super::*;
+
+
+/// Planar YUV 4:2:0 frame of [get_i420_frame](Capture::get_i420_frame), BT.601 limited range as libyuv makes it.
+///
+/// the input of libvpx, x264 and the most of the WebRTC stacks. the planes have no padding, so the strides are
+/// the widths of the planes.
+///
+/// Required features: *`"yuv"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "yuv")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct I420FrameData {
+    pub width: i32,
+    pub height: i32,
+    /// luma, `height` rows of `y_stride` bytes.
+    pub y: Vec<u8>,
+    pub y_stride: usize,
+    /// Cb, `(height + 1) / 2` rows of `u_stride` bytes.
+    pub u: Vec<u8>,
+    pub u_stride: usize,
+    /// Cr, the same size as `u`.
+    pub v: Vec<u8>,
+    pub v_stride: usize,
+    pub info: FrameInfo,
+}
+
+
+/// Convert a BGRA `frame` into I420, averaging the chroma of each 2x2 block.
+///
+/// Required features: *`"yuv"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "yuv")))]
+pub fn frame_to_i420(frame: &RawFrameData) -> I420FrameData {
+    let (width, height) = (frame.width.max(0) as usize, frame.height.max(0) as usize);
+    let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+    let pixel = |x: usize, y: usize| {
+        let offset = frame.pixel_offset(x, y);
+        // B, G, R
+        (frame.data[offset + 2] as i32, frame.data[offset + 1] as i32, frame.data[offset] as i32)
+    };
+
+    let mut y_plane = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = pixel(x, y);
+            y_plane.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+        }
+    }
+
+    let mut u_plane = Vec::with_capacity(chroma_width * chroma_height);
+    let mut v_plane = Vec::with_capacity(chroma_width * chroma_height);
+    for cy in 0..chroma_height {
+        for cx in 0..chroma_width {
+            // the odd edges average what's there
+            let (mut r, mut g, mut b, mut count) = (0, 0, 0, 0);
+            for y in cy * 2..(cy * 2 + 2).min(height) {
+                for x in cx * 2..(cx * 2 + 2).min(width) {
+                    let (pr, pg, pb) = pixel(x, y);
+                    r += pr;
+                    g += pg;
+                    b += pb;
+                    count += 1;
+                }
+            }
+            let (r, g, b) = (r / count, g / count, b / count);
+            u_plane.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+            v_plane.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+        }
+    }
+
+    I420FrameData{
+        width: width as i32,
+        height: height as i32,
+        y: y_plane,
+        y_stride: width,
+        u: u_plane,
+        u_stride: chroma_width,
+        v: v_plane,
+        v_stride: chroma_width,
+        info: frame.info,
+    }
+}
+
+impl Capture {
+    /// Get the current frame as I420, for the software encoders and WebRTC. converted on the CPU. [Read more](frame_to_i420)
+    ///
+    /// Required features: *`"yuv"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let i420 = capture.wait_i420_frame().expect("Failed to capture");
+    /// // vpx_img_wrap(.., VPX_IMG_FMT_I420, i420.width, i420.height, ..) with the planes and strides
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "yuv")))]
+    pub fn get_i420_frame(&self) -> anyhow::Result<I420FrameData, CaptureError> {
+        let raw = self.get_raw_frame()?;
+
+        Ok(frame_to_i420(&raw))
+    }
+
+    /// Get the current frame as I420. with throught NoTexture
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "yuv")))]
+    pub fn wait_i420_frame(&self) -> anyhow::Result<I420FrameData, CaptureError> {
+        let raw = self.wait_raw_frame()?;
+
+        Ok(frame_to_i420(&raw))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame of the BGRA `pixels`, row by row.
+    fn frame(width: i32, height: i32, pixels: &[[u8; 3]]) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: pixels.iter().flat_map(|&[b, g, r]| [b, g, r, 255]).collect(),
+            ..Default::default()
+        }
+    }
+
+    const WHITE: [u8; 3] = [255, 255, 255];
+    const BLACK: [u8; 3] = [0, 0, 0];
+    const RED: [u8; 3] = [0, 0, 255];
+    const BLUE: [u8; 3] = [255, 0, 0];
+
+    #[test]
+    fn limited_range() {
+        let i420 = frame_to_i420(&frame(2, 2, &[WHITE, WHITE, BLACK, BLACK]));
+
+        assert_eq!(i420.y, vec![235, 235, 16, 16]);
+        assert_eq!((i420.u[0], i420.v[0]), (128, 128));
+    }
+
+    #[test]
+    fn chroma_is_averaged() {
+        let i420 = frame_to_i420(&frame(2, 1, &[RED, BLACK]));
+
+        assert_eq!(i420.y, vec![82, 16]);
+        assert_eq!((i420.u, i420.v), (vec![109], vec![184]));
+    }
+
+    #[test]
+    fn odd_size() {
+        let mut pixels = [WHITE; 9];
+        pixels[8] = BLUE;
+        let i420 = frame_to_i420(&frame(3, 3, &pixels));
+
+        assert_eq!((i420.y_stride, i420.u_stride, i420.v_stride), (3, 2, 2));
+        assert_eq!(i420.y, vec![235, 235, 235, 235, 235, 235, 235, 235, 41]);
+        // the corner block has only the blue pixel
+        assert_eq!(i420.u, vec![128, 128, 128, 240]);
+        assert_eq!(i420.v, vec![128, 128, 128, 110]);
+    }
+
+    #[test]
+    fn strided_frame() {
+        let packed = frame(1, 2, &[RED, BLUE]);
+        let mut strided = RawFrameData{ row_pitch: 8, data: vec![0; 16], ..packed.clone() };
+        strided.data[..4].copy_from_slice(&packed.data[..4]);
+        strided.data[8..12].copy_from_slice(&packed.data[4..]);
+
+        assert_eq!(frame_to_i420(&strided), frame_to_i420(&packed));
+    }
+}
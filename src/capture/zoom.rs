@@ -0,0 +1,346 @@
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::RECT,
+        Graphics::{
+            Direct3D11::{
+                ID3D11Device,
+                ID3D11DeviceContext,
+                ID3D11Texture2D,
+                ID3D11VideoContext,
+                ID3D11VideoDevice,
+                ID3D11VideoProcessor,
+                ID3D11VideoProcessorEnumerator,
+                ID3D11VideoProcessorOutputView,
+                D3D11_BIND_RENDER_TARGET,
+                D3D11_TEX2D_VPIV,
+                D3D11_TEX2D_VPOV,
+                D3D11_TEXTURE2D_DESC,
+                D3D11_USAGE_DEFAULT,
+                D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+                D3D11_VIDEO_PROCESSOR_CONTENT_DESC,
+                D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC,
+                D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0,
+                D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC,
+                D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0,
+                D3D11_VIDEO_PROCESSOR_STREAM,
+                D3D11_VIDEO_USAGE_OPTIMAL_QUALITY,
+                D3D11_VPIV_DIMENSION_TEXTURE2D,
+                D3D11_VPOV_DIMENSION_TEXTURE2D,
+            },
+            Dxgi::Common::{
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                DXGI_SAMPLE_DESC,
+            },
+        },
+    },
+};
+
+use super::*;
+
+
+/// Zoomed view of a [Capture]. [Read more](Capture::set_zoom)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ZoomView {
+    /// area of the frame to show. moved inside the frame if it sticks out.
+    pub region: Rect,
+    /// size of the delivered frames. the region is stretched to it.
+    pub width: u32,
+    pub height: u32,
+}
+impl ZoomView {
+    pub fn new(region: Rect, width: u32, height: u32) -> Self {
+        Self{
+            region,
+            width,
+            height,
+        }
+    }
+
+    /// `factor` times zoomed view centered on (`x`, `y`), delivered in `width` x `height`.
+    pub fn centered(x: i32, y: i32, factor: f64, width: u32, height: u32) -> Self {
+        let factor = factor.max(f64::MIN_POSITIVE);
+        let region_width = ((width as f64 / factor).round() as i32).max(1);
+        let region_height = ((height as f64 / factor).round() as i32).max(1);
+
+        Self::new(Rect::new(x - region_width / 2, y - region_height / 2, region_width, region_height), width, height)
+    }
+
+    /// How many times the region is zoomed horizontally.
+    pub fn factor(&self) -> f64 {
+        self.width as f64 / self.region.width.max(1) as f64
+    }
+
+    /// Region moved inside a frame of `width` x `height`, as `RECT`.
+    fn source_rect(&self, width: u32, height: u32) -> RECT {
+        let region_width = self.region.width.clamp(1, width as i32);
+        let region_height = self.region.height.clamp(1, height as i32);
+        let left = self.region.x.clamp(0, width as i32 - region_width);
+        let top = self.region.y.clamp(0, height as i32 - region_height);
+
+        RECT{
+            left,
+            top,
+            right: left + region_width,
+            bottom: top + region_height,
+        }
+    }
+}
+
+
+/// Scaling with the D3D11 video processor.
+#[derive(Debug)]
+struct GpuScaler {
+    video_device: ID3D11VideoDevice,
+    video_context: ID3D11VideoContext,
+    enumerator: ID3D11VideoProcessorEnumerator,
+    processor: ID3D11VideoProcessor,
+    output: ID3D11Texture2D,
+    output_view: ID3D11VideoProcessorOutputView,
+    input_size: (u32, u32),
+    output_size: (u32, u32),
+}
+impl GpuScaler {
+    fn new(d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, input_size: (u32, u32), output_size: (u32, u32)) -> windows::core::Result<Self> {
+        let video_device: ID3D11VideoDevice = d3d_device.cast()?;
+        let video_context: ID3D11VideoContext = d3d_context.cast()?;
+
+        let content_desc = D3D11_VIDEO_PROCESSOR_CONTENT_DESC{
+            InputFrameFormat: D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE,
+            InputWidth: input_size.0,
+            InputHeight: input_size.1,
+            OutputWidth: output_size.0,
+            OutputHeight: output_size.1,
+            Usage: D3D11_VIDEO_USAGE_OPTIMAL_QUALITY,
+            ..Default::default()
+        };
+        let output_desc = D3D11_TEXTURE2D_DESC{
+            Width: output_size.0,
+            Height: output_size.1,
+            MipLevels: 1,
+            ArraySize: 1,
+            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+            Usage: D3D11_USAGE_DEFAULT,
+            BindFlags: D3D11_BIND_RENDER_TARGET,
+            CPUAccessFlags: 0,
+            MiscFlags: 0,
+        };
+        let output_view_desc = D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC{
+            ViewDimension: D3D11_VPOV_DIMENSION_TEXTURE2D,
+            Anonymous: D3D11_VIDEO_PROCESSOR_OUTPUT_VIEW_DESC_0{ Texture2D: D3D11_TEX2D_VPOV{ MipSlice: 0 } },
+        };
+
+        unsafe {
+            let enumerator = video_device.CreateVideoProcessorEnumerator(&content_desc)?;
+            let processor = video_device.CreateVideoProcessor(&enumerator, 0)?;
+            let output = d3d_device.CreateTexture2D(&output_desc, std::ptr::null())?;
+            let output_view = video_device.CreateVideoProcessorOutputView(&output, &enumerator, &output_view_desc)?;
+
+            // scale only, no denoise nor color adjustment by the driver
+            video_context.VideoProcessorSetStreamFrameFormat(&processor, 0, D3D11_VIDEO_FRAME_FORMAT_PROGRESSIVE);
+            video_context.VideoProcessorSetStreamAutoProcessingMode(&processor, 0, false);
+
+            Ok(Self{
+                video_device,
+                video_context,
+                enumerator,
+                processor,
+                output,
+                output_view,
+                input_size,
+                output_size,
+            })
+        }
+    }
+
+    /// Stretch `source` of `texture` to the whole output texture.
+    fn scale(&self, texture: &ID3D11Texture2D, source: &RECT) -> windows::core::Result<ID3D11Texture2D> {
+        let input_view_desc = D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC{
+            FourCC: 0,
+            ViewDimension: D3D11_VPIV_DIMENSION_TEXTURE2D,
+            Anonymous: D3D11_VIDEO_PROCESSOR_INPUT_VIEW_DESC_0{ Texture2D: D3D11_TEX2D_VPIV{ MipSlice: 0, ArraySlice: 0 } },
+        };
+        let destination = RECT{
+            left: 0,
+            top: 0,
+            right: self.output_size.0 as i32,
+            bottom: self.output_size.1 as i32,
+        };
+
+        unsafe {
+            let input_view = self.video_device.CreateVideoProcessorInputView(texture, &self.enumerator, &input_view_desc)?;
+
+            self.video_context.VideoProcessorSetStreamSourceRect(&self.processor, 0, true, source);
+            self.video_context.VideoProcessorSetStreamDestRect(&self.processor, 0, true, &destination);
+            self.video_context.VideoProcessorSetOutputTargetRect(&self.processor, true, &destination);
+
+            let stream = D3D11_VIDEO_PROCESSOR_STREAM{
+                Enable: true.into(),
+                pInputSurface: Some(input_view),
+                ..Default::default()
+            };
+            self.video_context.VideoProcessorBlt(&self.processor, &self.output_view, 0, 1, &stream)?;
+        }
+
+        Ok(self.output.clone())
+    }
+}
+
+
+/// Zoom of a [Capture], shared with the frame arrived handler.
+#[derive(Debug, Default)]
+pub(crate) struct ZoomState {
+    view: Option<ZoomView>,
+    /// size the whole frames are scaled to while not zoomed.
+    output_size: Option<(u32, u32)>,
+    scaler: Option<GpuScaler>,
+}
+impl ZoomState {
+    pub(crate) fn new(output_size: Option<(u32, u32)>) -> Self {
+        Self{
+            output_size,
+            ..Default::default()
+        }
+    }
+
+    /// `texture` zoomed into the view, or scaled to the output size, with the scale done. `texture` itself
+    /// while neither is set, or the GPU can't scale it.
+    pub(crate) fn apply(&mut self, d3d_device: &ID3D11Device, d3d_context: &ID3D11DeviceContext, texture: ID3D11Texture2D) -> (ID3D11Texture2D, Option<FrameScale>) {
+        let mut desc = D3D11_TEXTURE2D_DESC::default();
+        unsafe {
+            texture.GetDesc(&mut desc);
+        }
+
+        let view = match (self.view, self.output_size) {
+            (Some(view), _) => view,
+            (None, Some((width, height))) => ZoomView::new(Rect::new(0, 0, desc.Width as i32, desc.Height as i32), width, height),
+            (None, None) => return (texture, None),
+        };
+        let input_size = (desc.Width, desc.Height);
+        let output_size = (view.width, view.height);
+        // nothing to scale
+        if self.view.is_none() && input_size == output_size {
+            return (texture, None);
+        }
+
+        // the frame size changes with the window
+        let reusable = self.scaler.as_ref()
+            .is_some_and(|scaler| scaler.input_size == input_size && scaler.output_size == output_size);
+        if !reusable {
+            self.scaler = GpuScaler::new(d3d_device, d3d_context, input_size, output_size).ok();
+        }
+
+        let source = view.source_rect(desc.Width, desc.Height);
+        match self.scaler.as_ref().map(|scaler| scaler.scale(&texture, &source)) {
+            Some(Ok(scaled)) => (scaled, Some(FrameScale{
+                source: Rect::new(source.left, source.top, source.right - source.left, source.bottom - source.top),
+                width: output_size.0,
+                height: output_size.1,
+            })),
+            _ => (texture, None),
+        }
+    }
+}
+
+
+impl Capture {
+    /// Deliver a zoomed view of the frames, for magnifiers and detail inspection.
+    ///
+    /// the region is cut out and scaled on the GPU by the D3D11 video processor before the frames are read back,
+    /// so only `width` x `height` pixels are copied to the CPU. `None` goes back to the whole frames.
+    /// frames are delivered whole when the GPU has no video processor.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// // 4x around (640, 360), in 800x600
+    /// capture.set_zoom(Some(dxcapture::ZoomView::centered(640, 360, 4.0, 800, 600))).unwrap();
+    /// let zoomed = capture.wait_raw_frame().expect("Failed to capture");
+    ///
+    /// capture.pan(100, 0).unwrap();
+    /// let panned = capture.wait_raw_frame().expect("Failed to capture");
+    /// ```
+    pub fn set_zoom(&self, view: Option<ZoomView>) -> anyhow::Result<(), CaptureError> {
+        if let Some(view) = view {
+            if view.region.is_empty() || view.width == 0 || view.height == 0 {
+                return Err(CaptureError::InvalidArgument("zoom region and size must not be empty.".to_string()));
+            }
+        }
+
+        let mut zoom = self.zoom.lock().unwrap();
+        zoom.view = view;
+        // drop the textures of the last zoom
+        if view.is_none() && zoom.output_size.is_none() {
+            zoom.scaler = None;
+        }
+
+        Ok(())
+    }
+
+    /// Scale the whole frames to `width` x `height` on the GPU before they are read back, as thumbnails of 4K frames.
+    ///
+    /// stretched by the D3D11 video processor as [set_zoom](Self::set_zoom), which wins over this while zoomed.
+    /// keep the aspect ratio of the target to not distort. `None` goes back to the frames in their own size.
+    /// frames are delivered in their own size when the GPU has no video processor.
+    /// [FrameInfo::scale] of each frame tells how it was scaled, and the filters on the screen positions map through it.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::builder(&device)
+    ///     .output_size(1280, 720)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let thumbnail = capture.wait_raw_frame().expect("Failed to capture");
+    /// ```
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the size is 0.
+    pub fn set_output_size(&self, size: Option<(u32, u32)>) -> anyhow::Result<(), CaptureError> {
+        if size.is_some_and(|(width, height)| width == 0 || height == 0) {
+            return Err(CaptureError::InvalidArgument("output size must not be empty.".to_string()));
+        }
+
+        let mut zoom = self.zoom.lock().unwrap();
+        zoom.output_size = size;
+        if size.is_none() && zoom.view.is_none() {
+            zoom.scaler = None;
+        }
+
+        Ok(())
+    }
+
+    pub fn output_size(&self) -> Option<(u32, u32)> {
+        self.zoom.lock().unwrap().output_size
+    }
+
+    pub fn zoom(&self) -> Option<ZoomView> {
+        self.zoom.lock().unwrap().view
+    }
+
+    /// Move the zoomed region by (`dx`, `dy`) pixels of the frame.
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): not zoomed.
+    pub fn pan(&self, dx: i32, dy: i32) -> anyhow::Result<(), CaptureError> {
+        let mut zoom = self.zoom.lock().unwrap();
+        let view = zoom.view.as_mut().ok_or_else(|| CaptureError::InvalidArgument("not zoomed.".to_string()))?;
+        view.region.x += dx;
+        view.region.y += dy;
+
+        Ok(())
+    }
+
+    /// Zoom `factor` times around (`x`, `y`), keeping the delivered size. the whole frame size is used when not zoomed yet.
+    pub fn zoom_at(&self, x: i32, y: i32, factor: f64) -> anyhow::Result<(), CaptureError> {
+        let (width, height) = match self.zoom() {
+            Some(view) => (view.width, view.height),
+            None => {
+                let frame = self.wait_raw_frame()?;
+                (frame.width as u32, frame.height as u32)
+            },
+        };
+
+        self.set_zoom(Some(ZoomView::centered(x, y, factor, width, height)))
+    }
+}
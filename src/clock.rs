@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use winapi::um::{
+    profileapi::{
+        QueryPerformanceCounter,
+        QueryPerformanceFrequency,
+    },
+    winnt::LARGE_INTEGER,
+};
+
+use crate::capture::Capture;
+#[cfg(feature = "audio")]
+use crate::audio::AudioBuffer;
+
+
+/// Shared clock for stamping video frames and audio buffers.
+///
+/// frames (`SystemRelativeTime`) and WASAPI buffers (QPC position) are both on the QPC clock,
+/// this turns them into the time from one origin so the streams can be muxed together.
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// let clock = dxcapture::CaptureClock::new();
+///
+/// let raw = capture.wait_raw_frame().unwrap();
+/// let timestamp = clock.frame_time(&capture);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CaptureClock {
+    origin: Duration,
+}
+impl CaptureClock {
+    /// Start the clock from now.
+    pub fn new() -> Self {
+        Self::with_origin(Self::qpc_now())
+    }
+
+    /// Start the clock from a QPC time.
+    pub fn with_origin(origin: Duration) -> Self {
+        Self{ origin }
+    }
+
+    /// Current QPC time.
+    pub fn qpc_now() -> Duration {
+        unsafe {
+            let mut counter: LARGE_INTEGER = std::mem::zeroed();
+            let mut frequency: LARGE_INTEGER = std::mem::zeroed();
+            QueryPerformanceCounter(&mut counter);
+            QueryPerformanceFrequency(&mut frequency);
+
+            let (counter, frequency) = (*counter.QuadPart() as u128, (*frequency.QuadPart()).max(1) as u128);
+            Duration::from_nanos((counter * 1_000_000_000 / frequency) as u64)
+        }
+    }
+
+    /// Convert QPC time in 100ns units, as WASAPI and WinRT use.
+    pub fn from_100ns(units: u64) -> Duration {
+        Duration::from_nanos(units.saturating_mul(100))
+    }
+
+    pub fn origin(&self) -> Duration {
+        self.origin
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.stream_time(Self::qpc_now())
+    }
+
+    /// Time from the origin of a QPC time. zero if before the origin.
+    pub fn stream_time(&self, qpc: Duration) -> Duration {
+        qpc.saturating_sub(self.origin)
+    }
+
+    /// Time from the origin of the current frame of `capture`.
+    pub fn frame_time(&self, capture: &Capture) -> Option<Duration> {
+        capture.get_frame_time().map(|qpc| self.stream_time(qpc))
+    }
+
+    /// Time from the origin of the first sample of `buffer`.
+    ///
+    /// Required features: *`"audio"`*
+    #[cfg(feature = "audio")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+    pub fn audio_time(&self, buffer: &AudioBuffer) -> Duration {
+        self.stream_time(Self::from_100ns(buffer.qpc_position))
+    }
+}
+impl Default for CaptureClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+/// Keep an audio track aligned to the [CaptureClock].
+///
+/// sound cards run on their own crystal, and loopback delivers nothing while silent,
+/// so the sample count drifts away from the clock over long sessions.
+/// this pads silence or drops samples whenever the drift exceeds the tolerance.
+#[derive(Clone, Debug)]
+pub struct DriftCorrector {
+    sample_rate: u32,
+    channels: u16,
+    tolerance: Duration,
+    start: Option<Duration>,
+    written_frames: u64,
+}
+impl DriftCorrector {
+    /// ## Parameters
+    /// * tolerance: drift allowed before correcting. 20ms or so is not noticeable.
+    pub fn new(sample_rate: u32, channels: u16, tolerance: Duration) -> Self {
+        Self{
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+            tolerance,
+            start: None,
+            written_frames: 0,
+        }
+    }
+
+    /// Time of the samples written so far.
+    pub fn written_time(&self) -> Duration {
+        Duration::from_secs_f64(self.written_frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Seconds the written samples are ahead (+) or behind (-) the clock, for a buffer stamped at `timestamp`.
+    pub fn drift(&self, timestamp: Duration) -> f64 {
+        match self.start {
+            Some(start) => self.written_time().as_secs_f64() - timestamp.saturating_sub(start).as_secs_f64(),
+            None => 0.0,
+        }
+    }
+
+    /// Correct interleaved `samples` stamped at `timestamp` in place.
+    ///
+    /// returns frames inserted (+) or dropped (-).
+    pub fn correct(&mut self, timestamp: Duration, samples: &mut Vec<f32>) -> i64 {
+        if self.start.is_none() {
+            self.start = Some(timestamp);
+        }
+
+        let drift = self.drift(timestamp);
+        let channels = self.channels as usize;
+        let mut corrected = 0;
+        if self.tolerance.as_secs_f64() < drift.abs() {
+            let frames = (drift.abs() * self.sample_rate as f64) as usize;
+            if drift < 0.0 {
+                // behind: a gap in the input
+                let mut padded = vec![0.0; frames * channels];
+                padded.append(samples);
+                *samples = padded;
+                corrected = frames as i64;
+            } else {
+                // ahead: the device runs fast
+                let frames = frames.min(samples.len() / channels);
+                samples.drain(..frames * channels);
+                corrected = -(frames as i64);
+            }
+        }
+
+        self.written_frames += (samples.len() / channels) as u64;
+        corrected
+    }
+}
@@ -0,0 +1,368 @@
+//! Capture profiles loaded from TOML.
+//!
+//! ```toml
+//! [profile.notepad]
+//! window = "Notepad"          # or `display = 2`. the primary display when neither is given
+//! format = "dxcap"            # `dxcap`, or `png` with the "img" feature
+//! fps = 15
+//! duration = "30s"            # `500ms`, `30s`, `2m` or `1h`
+//! crop = { x = 0, y = 0, width = 800, height = 600 }
+//! overlays = ["timestamp", "frame_counter", "cursor"]
+//! output = "recordings/notepad.dxcap"
+//! ```
+//!
+//! Required features: *`"config"`*
+
+use std::{
+    collections::BTreeMap,
+    path::{
+        Path,
+        PathBuf,
+    },
+    time::Duration,
+};
+
+use serde::Deserialize;
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    Rect,
+};
+use crate::cursor::CursorOverlay;
+use crate::d3d::Device;
+use crate::overlay::{
+    CropFilter,
+    FilteredSource,
+    TimestampOverlay,
+};
+use crate::recorder::{
+    DxcapMuxer,
+    RawEncoder,
+    Recorder,
+    RecorderOptions,
+};
+
+#[cfg(feature = "img")]
+use crate::timelapse::ImageSequenceMuxer;
+
+
+/// What a [CaptureProfile] captures.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProfileTarget {
+    Primary,
+    /// same as [Device::new_from_displays]. range is [1..=len].
+    Display(usize),
+    /// caption query, same as [Device::new_from_window].
+    Window(String),
+}
+
+/// Overlay drawn by a [CaptureProfile].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProfileOverlay {
+    /// `"timestamp"`, [TimestampOverlay].
+    Timestamp,
+    /// `"frame_counter"`, the frame counter of [TimestampOverlay].
+    FrameCounter,
+    /// `"cursor"`, [CursorOverlay].
+    Cursor,
+}
+
+/// Named capture settings. [Read more](crate::config)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureProfile {
+    pub name: String,
+    pub target: ProfileTarget,
+    /// `dxcap` or `png`. default is `dxcap`.
+    pub format: String,
+    /// default is 30.
+    pub fps: u32,
+    pub duration: Option<Duration>,
+    /// in frame coordinates.
+    pub crop: Option<Rect>,
+    pub overlays: Vec<ProfileOverlay>,
+    pub output: Option<PathBuf>,
+}
+impl CaptureProfile {
+    pub fn device(&self) -> anyhow::Result<Device> {
+        match &self.target {
+            ProfileTarget::Primary => Device::new_from_displays(None),
+            ProfileTarget::Display(display_id) => Device::new_from_displays(Some(*display_id)),
+            ProfileTarget::Window(query) => Device::new_from_window(query.clone()),
+        }
+    }
+
+    pub fn capture(&self) -> anyhow::Result<Capture> {
+        Capture::new(&self.device()?)
+    }
+
+    /// [Capture] with the crop and the overlays applied.
+    pub fn source(&self) -> anyhow::Result<FilteredSource<Capture>> {
+        let source = FilteredSource::new(self.capture()?);
+        if let Some(crop) = self.crop {
+            source.add_filter(CropFilter::new(crop));
+        }
+
+        let timestamp = self.overlays.contains(&ProfileOverlay::Timestamp);
+        let frame_counter = self.overlays.contains(&ProfileOverlay::FrameCounter);
+        if timestamp || frame_counter {
+            source.add_filter(TimestampOverlay::new().timestamp(timestamp).frame_counter(frame_counter));
+        }
+        if self.overlays.contains(&ProfileOverlay::Cursor) {
+            // cropped frames start at the crop on the screen
+            let (x, y) = self.crop.map_or((0, 0), |crop| (crop.x, crop.y));
+            source.add_filter(CursorOverlay::new().origin(x, y));
+        }
+
+        Ok(source)
+    }
+
+    pub fn recorder_options(&self) -> RecorderOptions {
+        RecorderOptions{
+            fps: self.fps,
+            max_duration: self.duration,
+            ..Default::default()
+        }
+    }
+
+    /// Start recording [source](Self::source) into [output](Self::output) in [format](Self::format).
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): no output, or unknown format.
+    pub fn start_recorder(&self) -> anyhow::Result<Recorder, CaptureError> {
+        let output = self.output.as_ref()
+            .ok_or_else(|| CaptureError::InvalidArgument(format!("profile {} has no output.", self.name)))?;
+
+        let profile = self.clone();
+        let source_factory = move || profile.source();
+        match self.format.as_str() {
+            "dxcap" => Recorder::start(source_factory, RawEncoder, DxcapMuxer::create(output)?, self.recorder_options()),
+            #[cfg(feature = "img")]
+            "png" => Recorder::start(source_factory, RawEncoder, ImageSequenceMuxer::create(output)?, self.recorder_options()),
+            format => Err(CaptureError::InvalidArgument(format!("unknown format: {}", format))),
+        }
+    }
+}
+
+
+#[derive(Deserialize)]
+struct CropEntry {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileEntry {
+    window: Option<String>,
+    display: Option<usize>,
+    format: Option<String>,
+    fps: Option<u32>,
+    duration: Option<String>,
+    crop: Option<CropEntry>,
+    #[serde(default)]
+    overlays: Vec<String>,
+    output: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct ProfileFile {
+    #[serde(default)]
+    profile: BTreeMap<String, ProfileEntry>,
+}
+
+/// `500ms`, `30s`, `2m` or `1h`. seconds without a unit.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let number: f64 = number.parse().ok()?;
+
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Some(Duration::from_secs_f64(seconds))
+}
+
+impl ProfileEntry {
+    fn into_profile(self, name: &str) -> anyhow::Result<CaptureProfile, CaptureError> {
+        let invalid = |message: String| CaptureError::InvalidArgument(format!("profile {}: {}", name, message));
+
+        let target = match (self.window, self.display) {
+            (Some(_), Some(_)) => return Err(invalid("window and display are exclusive.".to_string())),
+            (Some(window), None) => ProfileTarget::Window(window),
+            (None, Some(display)) => ProfileTarget::Display(display),
+            (None, None) => ProfileTarget::Primary,
+        };
+
+        let format = self.format.unwrap_or_else(|| "dxcap".to_string()).to_ascii_lowercase();
+        let fps = self.fps.unwrap_or(30);
+        if fps == 0 {
+            return Err(invalid("fps must be greater than 0.".to_string()));
+        }
+        let duration = match self.duration {
+            Some(text) => Some(parse_duration(&text).ok_or_else(|| invalid(format!("invalid duration {}.", text)))?),
+            None => None,
+        };
+        let crop = self.crop.map(|crop| Rect::new(crop.x, crop.y, crop.width, crop.height));
+        if crop.is_some_and(|crop| crop.is_empty()) {
+            return Err(invalid("crop must not be empty.".to_string()));
+        }
+
+        let overlays = self.overlays.iter().map(|overlay| match overlay.as_str() {
+            "timestamp" => Ok(ProfileOverlay::Timestamp),
+            "frame_counter" => Ok(ProfileOverlay::FrameCounter),
+            "cursor" => Ok(ProfileOverlay::Cursor),
+            _ => Err(invalid(format!("unknown overlay {}.", overlay))),
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CaptureProfile{
+            name: name.to_string(),
+            target,
+            format,
+            fps,
+            duration,
+            crop,
+            overlays,
+            output: self.output,
+        })
+    }
+}
+
+
+/// Profiles of a TOML file, by name.
+///
+/// Required features: *`"config"`*
+/// # Examples
+/// ```
+/// let profiles = dxcapture::CaptureProfiles::load("capture.toml").expect("Failed to load");
+///
+/// let recorder = profiles.get("notepad").unwrap().start_recorder().expect("Failed to start");
+/// std::thread::sleep(std::time::Duration::from_secs(10));
+/// recorder.stop().unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureProfiles {
+    profiles: BTreeMap<String, CaptureProfile>,
+}
+impl CaptureProfiles {
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        let text = std::fs::read_to_string(path).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        Self::parse(&text)
+    }
+
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): not a TOML, or invalid values.
+    pub fn parse(text: &str) -> anyhow::Result<Self, CaptureError> {
+        let file: ProfileFile = toml::from_str(text).map_err(|e| CaptureError::InvalidArgument(e.to_string()))?;
+
+        let mut profiles = BTreeMap::new();
+        for (name, entry) in file.profile {
+            let profile = entry.into_profile(&name)?;
+            profiles.insert(name, profile);
+        }
+
+        Ok(Self{
+            profiles,
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CaptureProfile> {
+        self.profiles.get(name)
+    }
+
+    /// Names in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.profiles.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn profiles(&self) -> impl Iterator<Item = &CaptureProfile> {
+        self.profiles.values()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        match CaptureProfiles::parse(text) {
+            Err(CaptureError::InvalidArgument(message)) => message,
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn full_profile() {
+        let profiles = CaptureProfiles::parse(r#"
+            [profile.notepad]
+            window = "Notepad"
+            format = "PNG"
+            fps = 15
+            duration = "30s"
+            crop = { x = 0, y = 10, width = 800, height = 600 }
+            overlays = ["timestamp", "frame_counter", "cursor"]
+            output = "recordings/notepad"
+
+            [profile.display]
+            display = 2
+        "#).unwrap();
+
+        assert_eq!(profiles.names(), vec!["display", "notepad"]);
+        assert_eq!(profiles.get("notepad"), Some(&CaptureProfile{
+            name: "notepad".to_string(),
+            target: ProfileTarget::Window("Notepad".to_string()),
+            format: "png".to_string(),
+            fps: 15,
+            duration: Some(Duration::from_secs(30)),
+            crop: Some(Rect::new(0, 10, 800, 600)),
+            overlays: vec![ProfileOverlay::Timestamp, ProfileOverlay::FrameCounter, ProfileOverlay::Cursor],
+            output: Some(PathBuf::from("recordings/notepad")),
+        }));
+
+        let display = profiles.get("display").unwrap();
+        assert_eq!((&display.target, display.format.as_str(), display.fps), (&ProfileTarget::Display(2), "dxcap", 30));
+        assert_eq!(display.recorder_options().max_duration, None);
+    }
+
+    #[test]
+    fn defaults() {
+        let profiles = CaptureProfiles::parse("[profile.primary]").unwrap();
+
+        assert_eq!(profiles.get("primary").unwrap().target, ProfileTarget::Primary);
+        assert_eq!(CaptureProfiles::parse("").unwrap(), CaptureProfiles::default());
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration(" 1.5s "), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("10"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("10 s"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("10d"), None);
+        assert_eq!(parse_duration("s"), None);
+    }
+
+    #[test]
+    fn invalid_profiles() {
+        assert!(error("[profile.a]\nwindow = \"a\"\ndisplay = 1").contains("exclusive"));
+        assert!(error("[profile.a]\nfps = 0").contains("fps"));
+        assert!(error("[profile.a]\nduration = \"soon\"").contains("duration"));
+        assert!(error("[profile.a]\ncrop = { x = 0, y = 0, width = 0, height = 10 }").contains("crop"));
+        assert!(error("[profile.a]\noverlays = [\"clock\"]").contains("clock"));
+        // typos are not ignored
+        assert!(error("[profile.a]\nfsp = 30").contains("fsp"));
+        assert!(!error("profile = 1").is_empty());
+    }
+}
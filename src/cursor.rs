@@ -0,0 +1,230 @@
+use winapi::{
+    shared::{
+        minwindef::UINT,
+        windef::HCURSOR,
+    },
+    um::{
+        wingdi::{
+            CreateCompatibleDC,
+            CreateDIBSection,
+            DeleteDC,
+            DeleteObject,
+            GdiFlush,
+            GetObjectW,
+            SelectObject,
+            BITMAP,
+            BITMAPINFO,
+            BITMAPINFOHEADER,
+            BI_RGB,
+            DIB_RGB_COLORS,
+        },
+        winuser::{
+            DrawIconEx,
+            GetCursorInfo,
+            GetDC,
+            GetIconInfo,
+            ReleaseDC,
+            CURSORINFO,
+            CURSOR_SHOWING,
+            ICONINFO,
+        },
+    },
+};
+
+use crate::capture::{
+    RawFrameData,
+    Rect,
+};
+use crate::overlay::FrameFilter;
+
+
+// not defined by winapi
+const DI_NORMAL: UINT = 0x0003;
+
+
+/// Shape of a cursor.
+#[derive(Clone, Debug)]
+struct CursorImage {
+    width: i32,
+    height: i32,
+    hotspot_x: i32,
+    hotspot_y: i32,
+    /// BGRA, not premultiplied.
+    data: Vec<u8>,
+    /// pixels inverting the screen, as the I-beam does.
+    invert: Vec<bool>,
+}
+impl CursorImage {
+    /// Draw the cursor on black and on white, and tell the alpha from the difference.
+    fn load(cursor: HCURSOR) -> Option<Self> {
+        unsafe {
+            let mut info = ICONINFO::default();
+            if GetIconInfo(cursor, &mut info) == 0 {
+                return None;
+            }
+
+            // monochrome cursors have the AND and XOR masks stacked in hbmMask
+            let mut bitmap = BITMAP::default();
+            let source = if info.hbmColor.is_null() { info.hbmMask } else { info.hbmColor };
+            let got = GetObjectW(source as *mut _, std::mem::size_of::<BITMAP>() as i32, &mut bitmap as *mut _ as *mut _);
+            let width = bitmap.bmWidth;
+            let height = if info.hbmColor.is_null() { bitmap.bmHeight / 2 } else { bitmap.bmHeight };
+
+            if !info.hbmColor.is_null() {
+                DeleteObject(info.hbmColor as *mut _);
+            }
+            if !info.hbmMask.is_null() {
+                DeleteObject(info.hbmMask as *mut _);
+            }
+            if got == 0 || width <= 0 || height <= 0 {
+                return None;
+            }
+
+            let screen = GetDC(std::ptr::null_mut());
+            let dc = CreateCompatibleDC(screen);
+            let mut bitmap_info = BITMAPINFO::default();
+            bitmap_info.bmiHeader = BITMAPINFOHEADER{
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                // top-down
+                biHeight: -height,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB,
+                ..Default::default()
+            };
+            let mut bits = std::ptr::null_mut();
+            let dib = CreateDIBSection(dc, &bitmap_info, DIB_RGB_COLORS, &mut bits, std::ptr::null_mut(), 0);
+            if dib.is_null() || bits.is_null() {
+                DeleteDC(dc);
+                ReleaseDC(std::ptr::null_mut(), screen);
+                return None;
+            }
+            let previous = SelectObject(dc, dib as *mut _);
+
+            let len = (width * height * 4) as usize;
+            let draw_on = |background: u8| {
+                let pixels = std::slice::from_raw_parts_mut(bits as *mut u8, len);
+                pixels.fill(background);
+                DrawIconEx(dc, 0, 0, cursor, width, height, 0, std::ptr::null_mut(), DI_NORMAL);
+                GdiFlush();
+                std::slice::from_raw_parts(bits as *const u8, len).to_vec()
+            };
+            let on_black = draw_on(0);
+            let on_white = draw_on(255);
+
+            SelectObject(dc, previous);
+            DeleteObject(dib as *mut _);
+            DeleteDC(dc);
+            ReleaseDC(std::ptr::null_mut(), screen);
+
+            let mut data = vec![0; len];
+            let mut invert = vec![false; (width * height) as usize];
+            for i in 0..(width * height) as usize {
+                let (black, white) = (&on_black[i * 4..i * 4 + 3], &on_white[i * 4..i * 4 + 3]);
+                if black.iter().zip(white).all(|(b, w)| w < b) {
+                    invert[i] = true;
+                    continue;
+                }
+
+                // white - black = 255 - alpha
+                let difference = black.iter().zip(white).map(|(&b, &w)| w.saturating_sub(b) as u32).sum::<u32>() / 3;
+                let alpha = 255 - difference;
+                if alpha == 0 {
+                    continue;
+                }
+                for (channel, &value) in black.iter().enumerate() {
+                    data[i * 4 + channel] = (value as u32 * 255 / alpha).min(255) as u8;
+                }
+                data[i * 4 + 3] = alpha as u8;
+            }
+
+            Some(Self{
+                width,
+                height,
+                hotspot_x: info.xHotspot as i32,
+                hotspot_y: info.yHotspot as i32,
+                data,
+                invert,
+            })
+        }
+    }
+
+    fn draw(&self, frame: &mut RawFrameData, left: i32, top: i32) {
+        let area = Rect::new(left, top, self.width, self.height).intersect(&Rect::new(0, 0, frame.width, frame.height));
+        for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+                let i = ((y - top) * self.width + (x - left)) as usize;
+                let offset = ((y * frame.width + x) * 4) as usize;
+                let pixel = match frame.data.get_mut(offset..offset + 3) {
+                    Some(pixel) => pixel,
+                    None => continue,
+                };
+
+                if self.invert[i] {
+                    pixel.iter_mut().for_each(|value| *value = 255 - *value);
+                    continue;
+                }
+                let alpha = self.data[i * 4 + 3] as u32;
+                for (channel, value) in pixel.iter_mut().enumerate() {
+                    *value = ((self.data[i * 4 + channel] as u32 * alpha + *value as u32 * (255 - alpha) + 127) / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+
+/// [FrameFilter] drawing the mouse cursor onto each frame.
+///
+/// for captures excluding the hardware cursor. the shape and hotspot are read with `GetCursorInfo`,
+/// and the shapes are cached while the cursor doesn't change.
+/// # Examples
+/// ```
+/// use dxcapture::{ CursorOverlay, FilteredSource, FrameSource };
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(CursorOverlay::new());
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CursorOverlay {
+    origin: (i32, i32),
+    /// handle of the cached cursor. kept as a number so that the filter is `Send`.
+    cached: Option<(usize, CursorImage)>,
+}
+impl CursorOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Screen position of the top left of the frames. (the top left of the captured display or window)
+    /// default is (0, 0), the primary display.
+    pub fn origin(mut self, x: i32, y: i32) -> Self {
+        self.origin = (x, y);
+        self
+    }
+}
+impl FrameFilter for CursorOverlay {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        frame.pack_rows();
+        let mut info = CURSORINFO{
+            cbSize: std::mem::size_of::<CURSORINFO>() as u32,
+            ..Default::default()
+        };
+        if unsafe { GetCursorInfo(&mut info) } == 0 || info.flags & CURSOR_SHOWING == 0 || info.hCursor.is_null() {
+            return;
+        }
+
+        let handle = info.hCursor as usize;
+        if self.cached.as_ref().map(|(cached, _)| *cached) != Some(handle) {
+            self.cached = CursorImage::load(info.hCursor).map(|image| (handle, image));
+        }
+
+        if let Some((_, image)) = &self.cached {
+            // the hotspot stays on the point of the scaled frames, the image isn't scaled
+            let (x, y) = frame.info.map_point(info.ptScreenPos.x - self.origin.0, info.ptScreenPos.y - self.origin.1);
+            image.draw(frame, x - image.hotspot_x, y - image.hotspot_y);
+        }
+    }
+}
@@ -24,7 +24,10 @@ use windows::{
         },
     },
     Win32::{
-        Foundation::HWND,
+        Foundation::{
+            HWND,
+            POINT,
+        },
         Graphics::{
             Direct3D11::{
                 ID3D11Device,
@@ -36,12 +39,15 @@ use windows::{
                 IDXGISurface,
             },
             Gdi::{
+                MonitorFromPoint,
                 MonitorFromWindow,
                 HMONITOR,
+                MONITOR_DEFAULTTONEAREST,
                 MONITOR_DEFAULTTOPRIMARY,
             },
         },
         UI::WindowsAndMessaging::{
+            GetCursorPos,
             GetDesktopWindow,
         },
         System::WinRT::{
@@ -56,8 +62,27 @@ use windows::{
         },
     }
 };
+#[cfg(feature = "d3d11on12")]
+use windows::{
+    core::IUnknown,
+    Win32::{
+        Foundation::E_POINTER,
+        Graphics::{
+            Direct3D11on12::{
+                D3D11On12CreateDevice,
+                ID3D11On12Device,
+            },
+            Direct3D12::{
+                ID3D12CommandQueue,
+                ID3D12Device,
+            },
+        },
+    },
+};
 use winrt::AbiTransferable;
 
+use crate::capture::CaptureError;
+
 pub struct D3D11Device;
 impl D3D11Device {
     fn new_of_type() -> winrt::Result<ID3D11Device> {
@@ -82,7 +107,31 @@ impl D3D11Device {
         })
     }
 
-    fn to_direct3d_device(device: &ID3D11Device) -> windows::core::Result<IDirect3DDevice> {
+    /// D3D11On12 device on `d3d12_device`, submitting to `command_queue`.
+    #[cfg(feature = "d3d11on12")]
+    fn new_on_12(d3d12_device: &ID3D12Device, command_queue: &ID3D12CommandQueue) -> windows::core::Result<ID3D11Device> {
+        let queues = [Some(command_queue.cast::<IUnknown>()?)];
+        let mut device = None;
+
+        unsafe {
+            D3D11On12CreateDevice(
+                d3d12_device,
+                D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                std::ptr::null(),
+                0,
+                queues.as_ptr(),
+                queues.len() as u32,
+                0,
+                &mut device,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )?;
+        }
+
+        device.ok_or_else(|| E_POINTER.into())
+    }
+
+    pub(crate) fn to_direct3d_device(device: &ID3D11Device) -> windows::core::Result<IDirect3DDevice> {
         unsafe {
             let dxgi_device: IDXGIDevice = device.cast::<IDXGIDevice>()?;
             let result: IDirect3DDevice = CreateDirect3D11DeviceFromDXGIDevice(dxgi_device)?.cast::<IDirect3DDevice>()?;
@@ -98,6 +147,9 @@ pub struct Device {
     pub d3d_device: ID3D11Device,
     pub device: IDirect3DDevice,
     pub item: GraphicsCaptureItem,
+    /// the display or window to copy by GDI, when the capture falls back to it. [Read more](crate::gdi)
+    #[cfg(feature = "gdi")]
+    pub(crate) gdi_target: Option<crate::gdi::GdiTarget>,
 }
 impl Device {
     /// Create a new Device.
@@ -115,6 +167,8 @@ impl Device {
             d3d_device,
             device,
             item,
+            #[cfg(feature = "gdi")]
+            gdi_target: None,
         }
     }
 
@@ -122,39 +176,228 @@ impl Device {
     /// ## Parameters
     /// * display_id: id of the target display. default is created by [MONITOR_DEFAULTTOPRIMARY](winapi::um::winuser::MONITOR_DEFAULTTOPRIMARY).
     /// display_id range is [1..=len].
+    ///
+    /// fails with [CaptureError] from [probe_environment](crate::probe_environment) when there is nothing to capture.
     pub fn new_from_displays(display_id: Option<usize>) -> anyhow::Result<Self> {
+        crate::environment::probe_environment()?;
+
         let monitor_handle = if let Some(display_id) = display_id {
-            let displays = crate::displays::enumerate_displays();
+            let displays = crate::displays::enumerate_displays()?;
+            if displays.is_empty() {
+                return Err(CaptureError::NoDisplaysAttached.into());
+            }
             if display_id <= 0 || displays.len() <= display_id - 1 {
                 return Err(anyhow::anyhow!("DisplayId is out of range"));
             }
 
-            HMONITOR{ 0: displays[display_id].handle as isize }
+            displays[display_id - 1].handle as isize
         } else {
-            unsafe{ MonitorFromWindow(GetDesktopWindow(), MONITOR_DEFAULTTOPRIMARY) }
+            unsafe{ MonitorFromWindow(GetDesktopWindow(), MONITOR_DEFAULTTOPRIMARY) }.0
         };
 
+        Self::from_hmonitor(monitor_handle)
+    }
+
+    /// Create Device from a monitor handle, as resolved by `MonitorFromPoint`, instead of the index of the displays.
+    /// ## Parameters
+    /// * handle: `HMONITOR` of the target display, as an integer.
+    /// # Examples
+    /// ```
+    /// let displays = dxcapture::enumerate_displays().unwrap();
+    /// let device = dxcapture::Device::new_from_hmonitor(displays[0].handle as isize).unwrap();
+    /// ```
+    /// ## Errors
+    /// fails with the error of `CreateForMonitor` when `handle` is not an attached display.
+    pub fn new_from_hmonitor(handle: isize) -> anyhow::Result<Self> {
+        crate::environment::probe_environment()?;
+
+        Self::from_hmonitor(handle)
+    }
+
+    /// [new_from_hmonitor](Self::new_from_hmonitor) without the probe, for the callers which did it.
+    fn from_hmonitor(handle: isize) -> anyhow::Result<Self> {
         let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
-        let item: GraphicsCaptureItem = unsafe{ interop.CreateForMonitor(monitor_handle)? };
-        Ok(Self::new( item ))
+        let item: GraphicsCaptureItem = unsafe{ interop.CreateForMonitor(HMONITOR{ 0: handle })? };
+        let device = Self::new( item );
+        // copied by GDI where the capture falls back to it
+        #[cfg(feature = "gdi")]
+        let device = Self{ gdi_target: Some(crate::gdi::GdiTarget::Monitor(handle)), ..device };
+        Ok(device)
+    }
+
+    /// Create Device from the display under the mouse cursor, for "capture where I'm pointing".
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_cursor_monitor().unwrap();
+    /// ```
+    pub fn new_from_cursor_monitor() -> anyhow::Result<Self> {
+        let monitor_handle = unsafe {
+            let mut cursor = POINT::default();
+            GetCursorPos(&mut cursor).ok()?;
+
+            MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST)
+        };
+
+        Self::new_from_hmonitor(monitor_handle.0)
+    }
+
+    /// Create Device from the window under the mouse cursor. the top level window of the control pointed at.
+    /// # Examples
+    /// ```
+    /// std::thread::sleep(std::time::Duration::from_secs(3));
+    /// let device = dxcapture::Device::new_from_cursor_window().expect("No window under the cursor");
+    /// ```
+    /// ## Errors
+    /// fails over the desktop, the taskbar and the other windows not capturable.
+    pub fn new_from_cursor_window() -> anyhow::Result<Self> {
+        match crate::window_finder::get_window_under_cursor() {
+            Some(window) => Self::new_from_hwnd(window.handle as isize),
+            None => {
+                crate::environment::probe_environment()?;
+                anyhow::bail!("Window is not found");
+            },
+        }
     }
 
     /// Create Device from window caption.
     /// ## Parameters
     /// * window_caption: Window caption of the target window. default is created by [GetDesktopWindow].
     pub fn new_from_window(window_caption: String) -> anyhow::Result<Self> {
-        let window_handle = {
-            let windows = crate::window_finder::find_window(&window_caption);
-            if windows.len() == 0 {
+        let windows = crate::window_finder::find_window(&window_caption);
+        if windows.len() == 0 {
+            crate::environment::probe_environment()?;
+            anyhow::bail!("Window is not found");
+        }
+
+        Self::new_from_hwnd(windows[0].handle as isize)
+    }
+
+    /// Create Device from the only window with the title `window_match` matches, instead of the first one
+    /// as [new_from_window](Self::new_from_window) takes.
+    /// # Examples
+    /// ```
+    /// use dxcapture::WindowMatch;
+    ///
+    /// match dxcapture::Device::new_from_window_matching(&WindowMatch::Prefix("Untitled - ".to_string())) {
+    ///     Ok(device) => { /* capture */ },
+    ///     Err(e) => match e.downcast_ref::<dxcapture::CaptureError>() {
+    ///         Some(dxcapture::CaptureError::AmbiguousWindow(titles)) => println!("which one? {:?}", titles),
+    ///         _ => panic!("{}", e),
+    ///     },
+    /// }
+    /// ```
+    /// ## Errors
+    /// * [AmbiguousWindow](CaptureError::AmbiguousWindow): more than one window matched, with the titles of all of them.
+    pub fn new_from_window_matching(window_match: &crate::window_finder::WindowMatch) -> anyhow::Result<Self> {
+        let windows = crate::window_finder::find_windows_matching(window_match);
+        match windows.len() {
+            0 => {
+                crate::environment::probe_environment()?;
                 anyhow::bail!("Window is not found");
-            }
+            },
+            1 => Self::new_from_hwnd(windows[0].handle as isize),
+            _ => Err(CaptureError::AmbiguousWindow(windows.into_iter().map(|window| window.title).collect()).into()),
+        }
+    }
 
-            HWND { 0: windows[0].handle as isize }
+    /// Create Device from the main window of the process `process_id`, for the apps with the titles changing.
+    ///
+    /// the window without owner comes first, then the largest one of the process.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_pid(std::process::id()).unwrap();
+    /// ```
+    pub fn new_from_pid(process_id: u32) -> anyhow::Result<Self> {
+        match crate::window_finder::find_process_window(|id| id == process_id) {
+            Some(window) => Self::new_from_hwnd(window.handle as isize),
+            None => {
+                crate::environment::probe_environment()?;
+                anyhow::bail!("Window is not found");
+            },
+        }
+    }
+
+    /// Create Device from the main window of the processes of the executable `process_name`, as `game.exe`.
+    ///
+    /// the name is compared without case, and without `.exe` if `process_name` has none.
+    /// the main window is chosen among all the processes of the name, as for [new_from_pid](Self::new_from_pid).
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_process_name("notepad.exe").unwrap();
+    /// ```
+    pub fn new_from_process_name(process_name: &str) -> anyhow::Result<Self> {
+        let target = process_name.to_lowercase();
+        let matches = |name: String| {
+            let name = name.to_lowercase();
+            name == target || (!target.contains('.') && name.strip_suffix(".exe") == Some(target.as_str()))
         };
 
+        match crate::window_finder::find_process_window(|id| crate::window_finder::get_process_name(id).is_some_and(matches)) {
+            Some(window) => Self::new_from_hwnd(window.handle as isize),
+            None => {
+                crate::environment::probe_environment()?;
+                anyhow::bail!("Window is not found");
+            },
+        }
+    }
+
+    /// Create Device from a window handle, for the tools already tracking the windows (UI Automation, hooks, ...).
+    /// ## Parameters
+    /// * hwnd: `HWND` of the target window, as an integer.
+    /// # Examples
+    /// ```
+    /// let windows = dxcapture::enumerate_windows();
+    /// let hwnd = windows.iter().find(|window| window.class_name == "Notepad").unwrap().handle;
+    /// let device = dxcapture::Device::new_from_hwnd(hwnd as isize).unwrap();
+    /// ```
+    /// ## Errors
+    /// fails with the error of `CreateForWindow` when `hwnd` is not a capturable window.
+    pub fn new_from_hwnd(hwnd: isize) -> anyhow::Result<Self> {
+        crate::environment::probe_environment()?;
+
         let interop = windows::core::factory::<GraphicsCaptureItem, IGraphicsCaptureItemInterop>()?;
-        let item: GraphicsCaptureItem = unsafe{ interop.CreateForWindow(window_handle)? };
-        Ok(Self::new( item ))
+        let item: GraphicsCaptureItem = unsafe{ interop.CreateForWindow(HWND { 0: hwnd })? };
+        let device = Self::new( item );
+        // copied by GDI where the capture falls back to it
+        #[cfg(feature = "gdi")]
+        let device = Self{ gdi_target: Some(crate::gdi::GdiTarget::Window(hwnd)), ..device };
+        Ok(device)
+    }
+
+    /// Capture with a D3D11On12 device on the D3D12 device and queue of the app,
+    /// instead of a D3D11 device of its own. keeps the item.
+    ///
+    /// the frames are D3D11 textures on the D3D12 resources of `d3d12_device`, so they are used there without
+    /// copies between devices (get them by [d3d11on12_device](Self::d3d11on12_device)). the D3D11 work is submitted
+    /// to `command_queue` on the flushes of the capture, so the work after them on the same queue sees the frames.
+    ///
+    /// Required features: *`"d3d11on12"`*
+    /// # Examples
+    /// ```
+    /// # fn app() -> (windows::Win32::Graphics::Direct3D12::ID3D12Device, windows::Win32::Graphics::Direct3D12::ID3D12CommandQueue) { unimplemented!() }
+    /// let (d3d12_device, command_queue) = app();
+    ///
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap()
+    ///     .on_d3d12(&d3d12_device, &command_queue).expect("No D3D11On12");
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// ```
+    #[cfg(feature = "d3d11on12")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+    pub fn on_d3d12(mut self, d3d12_device: &ID3D12Device, command_queue: &ID3D12CommandQueue) -> anyhow::Result<Self> {
+        self.d3d_device = D3D11Device::new_on_12(d3d12_device, command_queue)?;
+        self.device = D3D11Device::to_direct3d_device(&self.d3d_device)?;
+
+        Ok(self)
+    }
+
+    /// `ID3D11On12Device` of a Device made by [on_d3d12](Self::on_d3d12), for `CreateWrappedResource`
+    /// and `ID3D11On12Device2::UnwrapUnderlyingResource` of the frames. `None` for the other devices.
+    ///
+    /// Required features: *`"d3d11on12"`*
+    #[cfg(feature = "d3d11on12")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "d3d11on12")))]
+    pub fn d3d11on12_device(&self) -> Option<ID3D11On12Device> {
+        self.d3d_device.cast().ok()
     }
 
     pub fn get_immediate_context(d3d_device: &ID3D11Device) -> windows::core::Result<ID3D11DeviceContext> {
@@ -183,11 +426,4 @@ impl Device {
             access.GetInterface::<ID3D11Texture2D>()
         }
     }
-}
-
-impl Default for Device {
-    /// Create a new Device with primary monitor.
-    fn default() -> Self {
-        Self::new_from_displays(None).expect("Not found primary monitor")
-    }
 }
\ No newline at end of file
@@ -0,0 +1,363 @@
+use crate::capture::{
+    Capture,
+    CaptureError,
+    RawFrameData,
+    Rect,
+};
+
+
+/// Options for [diff_frames_with].
+#[derive(Clone, Copy, Debug)]
+pub struct DiffOptions {
+    /// A pixel is changed when any of B, G, R differs more than this.
+    pub threshold: u8,
+    /// The frame is split into `grid_cols` x `grid_rows` regions for counting.
+    pub grid_cols: usize,
+    pub grid_rows: usize,
+}
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self{
+            threshold: 16,
+            grid_cols: 16,
+            grid_rows: 16,
+        }
+    }
+}
+
+
+/// Changed pixel count of a region of the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RegionChange {
+    pub rect: Rect,
+    pub changed_pixels: usize,
+}
+
+
+/// Result of [diff_frames].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameDiff {
+    pub changed_pixels: usize,
+    pub total_pixels: usize,
+    /// `changed_pixels / total_pixels` in 0.0..=100.0
+    pub changed_percent: f64,
+    /// all regions of the grid, row by row.
+    pub regions: Vec<RegionChange>,
+    /// tight bounds of each group of neighbouring changed regions.
+    pub bounding_boxes: Vec<Rect>,
+}
+impl FrameDiff {
+    pub fn is_changed(&self) -> bool {
+        0 < self.changed_pixels
+    }
+
+    /// Bounds of all the changed pixels. `None` when nothing changed.
+    pub fn changed_rect(&self) -> Option<Rect> {
+        self.bounding_boxes.iter().copied().reduce(|a, b| a.union(&b))
+    }
+
+    /// The whole frame of `width` x `height` changed, as the first frame.
+    fn whole(width: i32, height: i32) -> Self {
+        let rect = Rect::new(0, 0, width, height);
+        let total_pixels = (width.max(0) * height.max(0)) as usize;
+
+        Self{
+            changed_pixels: total_pixels,
+            total_pixels,
+            changed_percent: 100.0,
+            regions: vec![RegionChange{ rect, changed_pixels: total_pixels }],
+            bounding_boxes: vec![rect],
+        }
+    }
+}
+
+
+/// Compares each frame with the one before, for the tools sending the frames only when the screen changed.
+/// [Read more](Capture::get_changed_region)
+#[derive(Clone, Debug, Default)]
+pub struct ChangeDetector {
+    options: DiffOptions,
+    previous: Option<RawFrameData>,
+}
+impl ChangeDetector {
+    pub fn new(options: DiffOptions) -> Self {
+        Self{
+            options,
+            previous: None,
+        }
+    }
+
+    /// Compare `frame` with the last one given, and keep it for the next.
+    /// the first frame and the frames of another size are changed as a whole.
+    pub fn update(&mut self, frame: &RawFrameData) -> anyhow::Result<FrameDiff, CaptureError> {
+        let diff = match &self.previous {
+            Some(previous) if previous.width == frame.width && previous.height == frame.height => {
+                diff_frames_with(previous, frame, &self.options)?
+            },
+            _ => FrameDiff::whole(frame.width, frame.height),
+        };
+
+        match &mut self.previous {
+            // the buffer is reused
+            Some(previous) => previous.clone_from(frame),
+            None => self.previous = Some(frame.clone()),
+        }
+        Ok(diff)
+    }
+
+    /// Forget the last frame, so the next one is changed as a whole.
+    pub fn reset(&mut self) {
+        self.previous = None;
+    }
+}
+
+
+impl Capture {
+    /// Read back the current frame, and compare it with the frame `detector` saw before.
+    ///
+    /// [changed_rect](FrameDiff::changed_rect) of the diff is the region to send again,
+    /// and [changed_percent](FrameDiff::changed_percent) tells a small change from a whole new screen.
+    /// the comparison is on the CPU, so use [DiffOptions] of a coarser grid for large displays.
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    /// let mut detector = dxcapture::ChangeDetector::default();
+    ///
+    /// loop {
+    ///     let (frame, diff) = capture.get_changed_region(&mut detector).unwrap();
+    ///     if let Some(rect) = diff.changed_rect() {
+    ///         println!("send {:?} of {}x{}", rect, frame.width, frame.height);
+    ///     }
+    ///     std::thread::sleep(std::time::Duration::from_millis(100));
+    /// }
+    /// ```
+    /// ## Errors
+    /// the errors of [get_raw_frame](Self::get_raw_frame).
+    pub fn get_changed_region(&self, detector: &mut ChangeDetector) -> anyhow::Result<(RawFrameData, FrameDiff), CaptureError> {
+        let frame = self.get_raw_frame()?;
+        let diff = detector.update(&frame)?;
+
+        Ok((frame, diff))
+    }
+}
+
+
+/// Compare two frames of the same size with [DiffOptions::default].
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+///
+/// let a = capture.wait_raw_frame().unwrap();
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// let b = capture.wait_raw_frame().unwrap();
+///
+/// let diff = dxcapture::diff_frames(&a, &b).unwrap();
+/// println!("{}% changed in {:?}", diff.changed_percent, diff.bounding_boxes);
+/// ```
+pub fn diff_frames(a: &RawFrameData, b: &RawFrameData) -> anyhow::Result<FrameDiff, CaptureError> {
+    diff_frames_with(a, b, &DiffOptions::default())
+}
+
+/// Compare two frames of the same size.
+pub fn diff_frames_with(a: &RawFrameData, b: &RawFrameData, options: &DiffOptions) -> anyhow::Result<FrameDiff, CaptureError> {
+    if a.width != b.width || a.height != b.height || !a.has_all_rows() || !b.has_all_rows() {
+        return Err(CaptureError::SizeMismatch);
+    }
+
+    let width = a.width.max(0) as usize;
+    let height = a.height.max(0) as usize;
+    let cols = options.grid_cols.max(1).min(width.max(1));
+    let rows = options.grid_rows.max(1).min(height.max(1));
+
+    // changed pixel count and tight bounds in each cell
+    let mut counts = vec![0usize; cols * rows];
+    let mut bounds = vec![Rect::default(); cols * rows];
+    let mut changed_pixels = 0;
+
+    for y in 0..height {
+        let row = (y * rows) / height;
+        for x in 0..width {
+            let (a_offset, b_offset) = (a.pixel_offset(x, y), b.pixel_offset(x, y));
+            let changed = (0..3).any(|channel| {
+                options.threshold < a.data[a_offset + channel].abs_diff(b.data[b_offset + channel])
+            });
+            if !changed {
+                continue;
+            }
+
+            let cell = row * cols + (x * cols) / width;
+            counts[cell] += 1;
+            bounds[cell] = bounds[cell].union(&Rect::new(x as i32, y as i32, 1, 1));
+            changed_pixels += 1;
+        }
+    }
+
+    let regions = (0..cols * rows).map(|cell| {
+        let (col, row) = (cell % cols, cell / cols);
+        let x = (col * width / cols) as i32;
+        let y = (row * height / rows) as i32;
+        let right = ((col + 1) * width / cols) as i32;
+        let bottom = ((row + 1) * height / rows) as i32;
+
+        RegionChange{
+            rect: Rect::new(x, y, right - x, bottom - y),
+            changed_pixels: counts[cell],
+        }
+    }).collect();
+
+    // group neighbouring changed cells (8-connected)
+    let mut bounding_boxes = Vec::new();
+    let mut visited = vec![false; cols * rows];
+    for start in 0..cols * rows {
+        if visited[start] || counts[start] == 0 {
+            continue;
+        }
+
+        let mut rect = Rect::default();
+        let mut stack = vec![start];
+        visited[start] = true;
+        while let Some(cell) = stack.pop() {
+            rect = rect.union(&bounds[cell]);
+
+            let (col, row) = ((cell % cols) as isize, (cell / cols) as isize);
+            for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                let (ncol, nrow) = (col + dx, row + dy);
+                if ncol < 0 || nrow < 0 || cols as isize <= ncol || rows as isize <= nrow {
+                    continue;
+                }
+
+                let next = nrow as usize * cols + ncol as usize;
+                if !visited[next] && 0 < counts[next] {
+                    visited[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        bounding_boxes.push(rect);
+    }
+
+    let total_pixels = width * height;
+    let changed_percent = if total_pixels == 0 {
+        0.0
+    } else {
+        changed_pixels as f64 * 100.0 / total_pixels as f64
+    };
+
+    Ok(FrameDiff{
+        changed_pixels,
+        total_pixels,
+        changed_percent,
+        regions,
+        bounding_boxes,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: i32, height: i32, value: u8) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: vec![value; (width * height * 4) as usize],
+            ..Default::default()
+        }
+    }
+
+    fn set_pixel(frame: &mut RawFrameData, x: usize, y: usize, value: u8) {
+        let offset = frame.pixel_offset(x, y);
+        frame.data[offset..offset + 3].fill(value);
+    }
+
+    const GRID: DiffOptions = DiffOptions{ threshold: 16, grid_cols: 4, grid_rows: 4 };
+
+    #[test]
+    fn same_frames() {
+        let diff = diff_frames_with(&frame(8, 8, 0), &frame(8, 8, 0), &GRID).unwrap();
+
+        assert!(!diff.is_changed());
+        assert_eq!(diff.total_pixels, 64);
+        assert_eq!(diff.regions.len(), 16);
+        assert!(diff.regions.iter().all(|region| region.changed_pixels == 0));
+        assert_eq!(diff.changed_rect(), None);
+    }
+
+    #[test]
+    fn threshold() {
+        let mut b = frame(8, 8, 0);
+        set_pixel(&mut b, 3, 3, 16);
+
+        assert!(!diff_frames_with(&frame(8, 8, 0), &b, &GRID).unwrap().is_changed());
+        set_pixel(&mut b, 3, 3, 17);
+        assert!(diff_frames_with(&frame(8, 8, 0), &b, &GRID).unwrap().is_changed());
+    }
+
+    #[test]
+    fn separate_changes() {
+        let mut b = frame(8, 8, 0);
+        set_pixel(&mut b, 0, 0, 255);
+        set_pixel(&mut b, 7, 7, 255);
+        let diff = diff_frames_with(&frame(8, 8, 0), &b, &GRID).unwrap();
+
+        assert_eq!(diff.changed_pixels, 2);
+        assert_eq!(diff.changed_percent, 2.0 * 100.0 / 64.0);
+        assert_eq!(diff.regions[0], RegionChange{ rect: Rect::new(0, 0, 2, 2), changed_pixels: 1 });
+        assert_eq!(diff.regions[15], RegionChange{ rect: Rect::new(6, 6, 2, 2), changed_pixels: 1 });
+        assert_eq!(diff.bounding_boxes, vec![Rect::new(0, 0, 1, 1), Rect::new(7, 7, 1, 1)]);
+        assert_eq!(diff.changed_rect(), Some(Rect::new(0, 0, 8, 8)));
+    }
+
+    #[test]
+    fn neighbouring_changes() {
+        let mut b = frame(8, 8, 0);
+        set_pixel(&mut b, 1, 1, 255);
+        set_pixel(&mut b, 2, 3, 255);
+        let diff = diff_frames_with(&frame(8, 8, 0), &b, &GRID).unwrap();
+
+        // the cells touch diagonally, so one box
+        assert_eq!(diff.bounding_boxes, vec![Rect::new(1, 1, 2, 3)]);
+    }
+
+    #[test]
+    fn strided_frames() {
+        let mut packed = frame(3, 2, 0);
+        set_pixel(&mut packed, 2, 1, 255);
+        let mut strided = RawFrameData{ row_pitch: 16, data: vec![0; 16 * 2], ..frame(3, 2, 0) };
+        set_pixel(&mut strided, 2, 1, 255);
+
+        let diff = diff_frames_with(&frame(3, 2, 0), &strided, &GRID).unwrap();
+        assert_eq!(diff, diff_frames_with(&frame(3, 2, 0), &packed, &GRID).unwrap());
+        assert_eq!(diff.bounding_boxes, vec![Rect::new(2, 1, 1, 1)]);
+    }
+
+    #[test]
+    fn size_mismatch() {
+        assert_eq!(diff_frames_with(&frame(8, 8, 0), &frame(8, 4, 0), &GRID).unwrap_err(), CaptureError::SizeMismatch);
+
+        let short = RawFrameData{ data: vec![0; 8], ..frame(8, 8, 0) };
+        assert_eq!(diff_frames_with(&frame(8, 8, 0), &short, &GRID).unwrap_err(), CaptureError::SizeMismatch);
+    }
+
+    #[test]
+    fn change_detector() {
+        let mut detector = ChangeDetector::new(GRID);
+        let mut changed = frame(8, 8, 0);
+        set_pixel(&mut changed, 4, 4, 255);
+
+        // the first frame is changed as a whole
+        assert_eq!(detector.update(&frame(8, 8, 0)).unwrap().changed_rect(), Some(Rect::new(0, 0, 8, 8)));
+        assert!(!detector.update(&frame(8, 8, 0)).unwrap().is_changed());
+        assert_eq!(detector.update(&changed).unwrap().changed_rect(), Some(Rect::new(4, 4, 1, 1)));
+        // compared with the last one, not the first
+        assert!(!detector.update(&changed).unwrap().is_changed());
+
+        // and another size
+        assert_eq!(detector.update(&frame(4, 4, 0)).unwrap().changed_percent, 100.0);
+        detector.reset();
+        assert_eq!(detector.update(&frame(4, 4, 0)).unwrap().changed_pixels, 16);
+    }
+}
@@ -8,55 +8,76 @@ use winapi::{
     um::winuser::{EnumDisplayMonitors, GetMonitorInfoW, MONITORINFOEXW},
 };
 
+use crate::capture::{
+    CaptureError,
+    Rect,
+};
+
 #[derive(Debug, Clone)]
 pub struct DisplayInfo {
     pub handle: HMONITOR,
     pub display_name: String,
+    /// position and size on the virtual desktop.
+    pub rect: Rect,
+}
+
+/// State of an enumeration, passed to [enum_monitor] through the `LPARAM`.
+struct Enumeration {
+    displays: Vec<DisplayInfo>,
+    /// the error stopping the enumeration.
+    error: Option<windows::core::Error>,
 }
 
 extern "system" fn enum_monitor(handle: HMONITOR, _: HDC, _: LPRECT, lparam: LPARAM) -> BOOL {
+    let enumeration = unsafe { &mut *(lparam as *mut Enumeration) };
     let mut monitor_info = MONITORINFOEXW::default();
     monitor_info.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
 
     let result = unsafe { GetMonitorInfoW(handle, &mut monitor_info as *mut _ as *mut _) };
     if result == 0 {
-        panic!("GetMonitorInfoW failed!");
-        // TODO: GetLastError
-        // TODO: ErrorCode conversion
+        enumeration.error = Some(windows::core::Error::from_win32());
+        return 0;
     }
 
     let display_name = String::from_utf16_lossy(&monitor_info.szDevice)
         .trim_matches(char::from(0))
         .to_string();
 
+    let monitor = monitor_info.rcMonitor;
     let info = DisplayInfo {
         handle: handle,
         display_name: display_name,
+        rect: Rect::new(monitor.left, monitor.top, monitor.right - monitor.left, monitor.bottom - monitor.top),
     };
 
-    unsafe {
-        let list = std::mem::transmute::<LPARAM, *mut Vec<DisplayInfo>>(lparam);
-        (*list).push(info);
-    };
+    enumeration.displays.push(info);
 
     return 1;
 }
 
-/// Get all displays and returns them as a Vec.
-pub fn enumerate_displays() -> Vec<DisplayInfo> {
-    let mut displays: Vec<DisplayInfo> = Vec::new();
+/// Get all displays and returns them as a Vec. empty when no display is attached.
+/// ## Errors
+/// * [DirectxError](CaptureError::DirectxError): the error of `EnumDisplayMonitors` or `GetMonitorInfoW`,
+/// as while the displays are changed.
+pub fn enumerate_displays() -> anyhow::Result<Vec<DisplayInfo>, CaptureError> {
+    let mut enumeration = Enumeration {
+        displays: Vec::new(),
+        error: None,
+    };
     let result = unsafe {
         EnumDisplayMonitors(
             std::ptr::null_mut(),
             std::ptr::null_mut(),
             Some(enum_monitor),
-            &mut displays as *mut _ as _,
+            &mut enumeration as *mut _ as _,
         )
     };
+    if let Some(error) = enumeration.error {
+        return Err(CaptureError::DirectxError(error));
+    }
     if result == 0 {
-        panic!("EnumDisplayMonitors failed!");
-        // TODO: GetLastError
-        // TODO: ErrorCode conversion
+        return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
     }
-    displays
+
+    Ok(enumeration.displays)
 }
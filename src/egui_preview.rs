@@ -0,0 +1,139 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    RawFrameData,
+};
+
+
+/// `egui::TextureHandle` kept updated from a [Capture], for preview panels.
+///
+/// the texture is uploaded only when the capture has a new frame, at most [max_fps](Self::max_fps) times a second,
+/// and remade with the new size when the frame size changes. repaints are requested while previewing.
+///
+/// Required features: *`"egui"`*
+/// # Examples
+/// ```
+/// struct App {
+///     capture: dxcapture::Capture,
+///     preview: dxcapture::EguiPreview,
+/// }
+/// impl App {
+///     fn ui(&mut self, ctx: &egui::Context) {
+///         egui::CentralPanel::default().show(ctx, |ui| {
+///             // fitted to the panel, keeping the aspect ratio
+///             self.preview.show(ui, &self.capture);
+///         });
+///     }
+/// }
+/// ```
+#[derive(Clone)]
+pub struct EguiPreview {
+    name: String,
+    texture: Option<egui::TextureHandle>,
+    frame_time: Option<Duration>,
+    uploaded_at: Option<Instant>,
+    max_fps: Option<u32>,
+}
+impl Default for EguiPreview {
+    fn default() -> Self {
+        Self::new("dxcapture")
+    }
+}
+impl EguiPreview {
+    /// `name` of the texture, for the debugging of egui.
+    pub fn new(name: &str) -> Self {
+        Self{
+            name: name.to_string(),
+            texture: None,
+            frame_time: None,
+            uploaded_at: None,
+            max_fps: Some(30),
+        }
+    }
+
+    /// Upload at most `fps` frames a second. `None` uploads all the frames. default is 30.
+    pub fn max_fps(mut self, fps: Option<u32>) -> Self {
+        self.max_fps = fps;
+        self
+    }
+
+    /// Upload the current frame of `capture` if it is new, and return the texture.
+    ///
+    /// `None` until the first frame.
+    /// ## Errors
+    /// * [NotActive](CaptureError::NotActive): the capture is closed.
+    pub fn update(&mut self, ctx: &egui::Context, capture: &Capture) -> anyhow::Result<Option<&egui::TextureHandle>, CaptureError> {
+        // keep polling the capture
+        ctx.request_repaint();
+
+        let paced = match (self.max_fps, self.uploaded_at) {
+            (Some(fps), Some(uploaded_at)) if 0 < fps => uploaded_at.elapsed() < Duration::from_secs(1) / fps,
+            _ => false,
+        };
+        let frame_time = capture.get_frame_time();
+        if paced || (self.texture.is_some() && frame_time == self.frame_time) {
+            return Ok(self.texture.as_ref());
+        }
+
+        let frame = match capture.get_raw_frame() {
+            Ok(frame) => frame,
+            Err(CaptureError::NoTexture) => return Ok(self.texture.as_ref()),
+            Err(e) => return Err(e),
+        };
+        let image = Self::to_color_image(&frame);
+        match self.texture.as_mut() {
+            // set replaces the whole texture, the size too
+            Some(texture) => texture.set(image),
+            None => self.texture = Some(ctx.load_texture(self.name.clone(), image)),
+        }
+        self.frame_time = frame_time;
+        self.uploaded_at = Some(Instant::now());
+
+        Ok(self.texture.as_ref())
+    }
+
+    /// [update](Self::update) and show the texture fitted to the available size, keeping the aspect ratio.
+    ///
+    /// shows a spinner until the first frame, and the error when failed.
+    pub fn show(&mut self, ui: &mut egui::Ui, capture: &Capture) -> egui::Response {
+        let available = ui.available_size();
+        match self.update(ui.ctx(), capture) {
+            Ok(Some(texture)) => {
+                let size = texture.size_vec2();
+                let scale = (available.x / size.x).min(available.y / size.y);
+                let scale = if scale.is_finite() && 0.0 < scale { scale } else { 1.0 };
+                ui.image(texture, size * scale)
+            },
+            Ok(None) => ui.spinner(),
+            Err(e) => ui.label(e.to_string()),
+        }
+    }
+
+    /// Texture of the last uploaded frame.
+    pub fn texture(&self) -> Option<&egui::TextureHandle> {
+        self.texture.as_ref()
+    }
+
+    /// `[width, height]` of the last uploaded frame.
+    pub fn size(&self) -> Option<[usize; 2]> {
+        self.texture.as_ref().map(|texture| texture.size())
+    }
+
+    /// BGRA to opaque RGBA.
+    fn to_color_image(frame: &RawFrameData) -> egui::ColorImage {
+        let pixels = (0..frame.height.max(0) as usize)
+            .flat_map(|y| frame.row(y).chunks_exact(4))
+            .map(|pixel| egui::Color32::from_rgb(pixel[2], pixel[1], pixel[0]))
+            .collect();
+
+        egui::ColorImage{
+            size: [frame.width as usize, frame.height as usize],
+            pixels,
+        }
+    }
+}
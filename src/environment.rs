@@ -0,0 +1,76 @@
+use winapi::{
+    shared::minwindef::{
+        BOOL,
+        FALSE,
+        TRUE,
+    },
+    um::{
+        processthreadsapi::{
+            GetCurrentProcessId,
+            ProcessIdToSessionId,
+        },
+        winuser::{
+            CloseDesktop,
+            GetSystemMetrics,
+            GetUserObjectInformationW,
+            OpenInputDesktop,
+            DESKTOP_READOBJECTS,
+            SM_CMONITORS,
+            UOI_IO,
+        },
+    },
+};
+
+use crate::capture::CaptureError;
+
+
+/// Check whether this process can capture anything at all.
+///
+/// services in session 0, locked or disconnected RDP sessions and machines without monitors
+/// never deliver frames, so the frame getters would wait forever there.
+/// ## Errors
+/// * [NoDesktopSession](CaptureError::NoDesktopSession): no interactive desktop. (session 0, locked, secure desktop)
+/// * [NoDisplaysAttached](CaptureError::NoDisplaysAttached): no monitor is attached.
+/// # Examples
+/// ```
+/// match dxcapture::probe_environment() {
+///     Ok(_) => { /* capture */ },
+///     Err(e) => eprintln!("capture is not available: {}", e),
+/// }
+/// ```
+pub fn probe_environment() -> anyhow::Result<(), CaptureError> {
+    // services run in session 0 which has no desktop to capture
+    let mut session_id = 0;
+    let result = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut session_id) };
+    if result != 0 && session_id == 0 {
+        return Err(CaptureError::NoDesktopSession);
+    }
+
+    // the input desktop can't be opened while locked, or on the secure desktop.
+    // only looked at, so the least access is asked
+    unsafe {
+        let desktop = OpenInputDesktop(0, FALSE, DESKTOP_READOBJECTS);
+        if desktop.is_null() {
+            return Err(CaptureError::NoDesktopSession);
+        }
+        let mut receiving_input: BOOL = TRUE;
+        let queried = GetUserObjectInformationW(
+            desktop as _,
+            UOI_IO as i32,
+            &mut receiving_input as *mut _ as _,
+            std::mem::size_of::<BOOL>() as u32,
+            std::ptr::null_mut(),
+        );
+        CloseDesktop(desktop);
+        // the open succeeding is enough if it can't be asked
+        if queried != 0 && receiving_input == FALSE {
+            return Err(CaptureError::NoDesktopSession);
+        }
+    }
+
+    if unsafe { GetSystemMetrics(SM_CMONITORS) } <= 0 {
+        return Err(CaptureError::NoDisplaysAttached);
+    }
+
+    Ok(())
+}
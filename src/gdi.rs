@@ -0,0 +1,240 @@
+//! Fallback of [Capture](crate::Capture) to GDI, for the Windows without `Windows.Graphics.Capture` (before 10 1803, LTSB).
+//!
+//! with this feature, [Capture::new](crate::Capture::new) and [CaptureBuilder::build](crate::CaptureBuilder::build)
+//! copy the target by GDI where the system can't make the capture session, and the rest of the capture works
+//! on these frames the same.
+//! displays are copied by `BitBlt`, windows by `PrintWindow` (`PW_RENDERFULLCONTENT`, so covered windows are copied too)
+//! falling back to `BitBlt` from the window DC. tell it by [Capture::is_gdi](crate::Capture::is_gdi).
+//!
+//! the frames are copied on a thread of the capture every [FRAME_INTERVAL] at most, slower than the session,
+//! and some of the DirectX windows come out black. they are BGRA without the mouse cursor and the border,
+//! in the size of the target as it is: `pixel_format`, `buffer_count`, `cursor`, `border` and `resize_with_content`
+//! of the builder don't apply. only the [Device](crate::Device)s of a display or a window fall back,
+//! not the ones of [Device::new](crate::Device::new).
+//!
+//! Required features: *`"gdi"`*
+
+use std::time::Duration;
+
+use winapi::{
+    shared::{
+        windef::{
+            HDC,
+            HMONITOR,
+            HWND,
+            RECT,
+        },
+    },
+    um::{
+        wingdi::{
+            BitBlt,
+            CreateCompatibleDC,
+            CreateDIBSection,
+            DeleteDC,
+            DeleteObject,
+            GdiFlush,
+            SelectObject,
+            BITMAPINFO,
+            BITMAPINFOHEADER,
+            BI_RGB,
+            CAPTUREBLT,
+            DIB_RGB_COLORS,
+            SRCCOPY,
+        },
+        winuser::{
+            GetDC,
+            GetMonitorInfoW,
+            GetWindowDC,
+            GetWindowRect,
+            IsIconic,
+            IsWindow,
+            PrintWindow,
+            ReleaseDC,
+            MONITORINFO,
+            PW_RENDERFULLCONTENT,
+        },
+    },
+};
+use windows::{
+    Graphics::Capture::GraphicsCaptureSession,
+    Win32::Graphics::{
+        Direct3D11::{
+            ID3D11Device,
+            ID3D11Texture2D,
+            D3D11_SUBRESOURCE_DATA,
+            D3D11_TEXTURE2D_DESC,
+            D3D11_USAGE_DEFAULT,
+        },
+        Dxgi::Common::{
+            DXGI_FORMAT_B8G8R8A8_UNORM,
+            DXGI_SAMPLE_DESC,
+        },
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    Rect,
+};
+
+
+/// Shortest time between the frames copied by GDI, about the refresh of 60Hz displays.
+///
+/// Required features: *`"gdi"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "gdi")))]
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+
+/// Display or window of a [Device](crate::Device), copied when the capture falls back to GDI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GdiTarget {
+    /// `HMONITOR`, as an integer.
+    Monitor(isize),
+    /// `HWND`, as an integer.
+    Window(isize),
+}
+
+
+/// `Windows.Graphics.Capture` is available. false before Windows 10 1803, where the class is missing.
+///
+/// Required features: *`"gdi"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "gdi")))]
+pub fn is_graphics_capture_supported() -> bool {
+    GraphicsCaptureSession::IsSupported().unwrap_or(false)
+}
+
+/// `target` can be copied, checked once before the capture falls back to GDI.
+pub(crate) fn probe_target(target: GdiTarget) -> anyhow::Result<(), CaptureError> {
+    target_rect(target).map(|_| ())
+}
+
+/// Copy `target` into a new texture on `d3d_device`, with the size of the window or the display.
+/// ## Errors
+/// * [ItemClosed](CaptureError::ItemClosed): the window is closed or the display detached.
+/// * [NoTexture](CaptureError::NoTexture): the window is minimized.
+pub(crate) fn grab(d3d_device: &ID3D11Device, target: GdiTarget) -> anyhow::Result<(ID3D11Texture2D, (i32, i32)), CaptureError> {
+    let rect = target_rect(target)?;
+    if rect.is_empty() {
+        return Err(CaptureError::NoTexture);
+    }
+
+    let data = unsafe {
+        match target {
+            GdiTarget::Monitor(_) => copy_dib(rect.width, rect.height, |dc| {
+                let screen = GetDC(std::ptr::null_mut());
+                let result = BitBlt(dc, 0, 0, rect.width, rect.height, screen, rect.x, rect.y, SRCCOPY | CAPTUREBLT);
+                ReleaseDC(std::ptr::null_mut(), screen);
+                result != 0
+            })?,
+            GdiTarget::Window(handle) => copy_dib(rect.width, rect.height, |dc| {
+                let window = handle as HWND;
+                if PrintWindow(window, dc, PW_RENDERFULLCONTENT) != 0 {
+                    return true;
+                }
+                let window_dc = GetWindowDC(window);
+                let result = BitBlt(dc, 0, 0, rect.width, rect.height, window_dc, 0, 0, SRCCOPY);
+                ReleaseDC(window, window_dc);
+                result != 0
+            })?,
+        }
+    };
+
+    let desc = D3D11_TEXTURE2D_DESC{
+        Width: rect.width as u32,
+        Height: rect.height as u32,
+        MipLevels: 1,
+        ArraySize: 1,
+        Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+        SampleDesc: DXGI_SAMPLE_DESC{ Count: 1, Quality: 0 },
+        Usage: D3D11_USAGE_DEFAULT,
+        BindFlags: 0,
+        CPUAccessFlags: 0,
+        MiscFlags: 0,
+    };
+    let initial_data = D3D11_SUBRESOURCE_DATA{
+        pSysMem: data.as_ptr() as *const _,
+        SysMemPitch: rect.width as u32 * 4,
+        SysMemSlicePitch: 0,
+    };
+    let texture = unsafe { d3d_device.CreateTexture2D(&desc, &initial_data) }.map_err(|e| CaptureError::DirectxError(e))?;
+
+    Ok((texture, (rect.width, rect.height)))
+}
+
+/// Area of `target` on the virtual desktop. empty while the window is minimized.
+fn target_rect(target: GdiTarget) -> anyhow::Result<Rect, CaptureError> {
+    let rect = unsafe {
+        match target {
+            GdiTarget::Monitor(handle) => {
+                let mut info = MONITORINFO{
+                    cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                    ..Default::default()
+                };
+                if GetMonitorInfoW(handle as HMONITOR, &mut info) == 0 {
+                    return Err(CaptureError::ItemClosed);
+                }
+                info.rcMonitor
+            },
+            GdiTarget::Window(handle) => {
+                let window = handle as HWND;
+                let mut rect = RECT::default();
+                if IsWindow(window) == 0 || GetWindowRect(window, &mut rect) == 0 {
+                    return Err(CaptureError::ItemClosed);
+                }
+                if IsIconic(window) != 0 {
+                    return Ok(Rect::default());
+                }
+                rect
+            },
+        }
+    };
+
+    Ok(Rect::new(rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top))
+}
+
+/// Copy `width` x `height` into a top-down DIB with `copy`, and read it as BGRA.
+unsafe fn copy_dib<F>(width: i32, height: i32, copy: F) -> anyhow::Result<Vec<u8>, CaptureError>
+where
+    F: FnOnce(HDC) -> bool,
+{
+    let screen = GetDC(std::ptr::null_mut());
+    let dc = CreateCompatibleDC(screen);
+    let mut bitmap_info = BITMAPINFO::default();
+    bitmap_info.bmiHeader = BITMAPINFOHEADER{
+        biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+        biWidth: width,
+        // top-down
+        biHeight: -height,
+        biPlanes: 1,
+        biBitCount: 32,
+        biCompression: BI_RGB,
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    let dib = CreateDIBSection(dc, &bitmap_info, DIB_RGB_COLORS, &mut bits, std::ptr::null_mut(), 0);
+    if dib.is_null() || bits.is_null() {
+        DeleteDC(dc);
+        ReleaseDC(std::ptr::null_mut(), screen);
+        return Err(CaptureError::SourceError("CreateDIBSection failed".to_string()));
+    }
+    let previous = SelectObject(dc, dib as *mut _);
+
+    let copied = copy(dc);
+    GdiFlush();
+    let mut data = std::slice::from_raw_parts(bits as *const u8, (width * height * 4) as usize).to_vec();
+
+    SelectObject(dc, previous);
+    DeleteObject(dib as *mut _);
+    DeleteDC(dc);
+    ReleaseDC(std::ptr::null_mut(), screen);
+
+    if !copied {
+        return Err(CaptureError::SourceError("Failed to copy the screen".to_string()));
+    }
+
+    // GDI leaves the alpha 0
+    for pixel in data.chunks_exact_mut(4) {
+        pixel[3] = 255;
+    }
+    Ok(data)
+}
@@ -0,0 +1,268 @@
+//! Animated GIF recording, for short clips attached to bug reports without a video stack.
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use image::{
+    codecs::gif::{
+        GifEncoder,
+        Repeat,
+    },
+    Delay,
+    Frame,
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    ImgFrameData,
+    RawFrameData,
+};
+use crate::recorder::{
+    EncodedPacket,
+    Muxer,
+};
+
+
+fn image_error(e: image::ImageError) -> CaptureError {
+    CaptureError::IoError(e.to_string())
+}
+
+
+/// Options of [GifMuxer].
+///
+/// Required features: *`"img"`*
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GifOptions {
+    /// quantizer speed in `1..=30`. higher is faster with worse colors.
+    pub speed: i32,
+    /// play forever, or once.
+    pub looping: bool,
+}
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self{
+            speed: 10,
+            looping: true,
+        }
+    }
+}
+
+
+/// [Muxer] writing [RawEncoder](crate::RawEncoder) packets into an animated GIF.
+///
+/// every frame is quantized into 256 colors of its own. the delay of a frame is the time to the next one,
+/// so a frame is written when the next arrives, and the last one is shown as long as the one before.
+///
+/// Required features: *`"img"`*
+/// # Examples
+/// ```
+/// let recorder = dxcapture::Recorder::start(
+///     || {
+///         let device = dxcapture::Device::new_from_displays(None)?;
+///         Ok(dxcapture::Capture::new(&device)?)
+///     },
+///     dxcapture::RawEncoder,
+///     dxcapture::GifMuxer::create("bug.gif", Default::default()).unwrap(),
+///     dxcapture::RecorderOptions{ fps: 10, max_duration: Some(std::time::Duration::from_secs(5)), ..Default::default() },
+/// ).unwrap();
+/// ```
+pub struct GifMuxer {
+    encoder: Option<GifEncoder<BufWriter<File>>>,
+    pending: Option<EncodedPacket>,
+    last_delay: Duration,
+}
+impl GifMuxer {
+    pub fn create<P: AsRef<Path>>(path: P, options: GifOptions) -> anyhow::Result<Self, CaptureError> {
+        if !(1..=30).contains(&options.speed) {
+            return Err(CaptureError::InvalidArgument("speed must be in 1..=30.".to_string()));
+        }
+
+        let file = File::create(path).map_err(|e| CaptureError::IoError(e.to_string()))?;
+        let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), options.speed);
+        encoder.set_repeat(if options.looping { Repeat::Infinite } else { Repeat::Finite(0) }).map_err(image_error)?;
+
+        Ok(Self{
+            encoder: Some(encoder),
+            pending: None,
+            last_delay: Duration::from_millis(100),
+        })
+    }
+
+    fn encode(&mut self, packet: EncodedPacket, delay: Duration) -> anyhow::Result<(), CaptureError> {
+        let encoder = self.encoder.as_mut().ok_or(CaptureError::NotActive)?;
+        let image = ImgFrameData::from_raw(RawFrameData{
+            width: packet.width,
+            height: packet.height,
+            data: packet.data,
+            ..Default::default()
+        });
+
+        encoder.encode_frame(Frame::from_parts(image.data, 0, 0, Delay::from_saturating_duration(delay)))
+            .map_err(image_error)
+    }
+}
+impl Muxer for GifMuxer {
+    fn write_packet(&mut self, packet: &EncodedPacket) -> anyhow::Result<(), CaptureError> {
+        if let Some(pending) = self.pending.take() {
+            let delay = packet.timestamp.saturating_sub(pending.timestamp);
+            if !delay.is_zero() {
+                self.last_delay = delay;
+            }
+            self.encode(pending, self.last_delay)?;
+        }
+        self.pending = Some(packet.clone());
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError> {
+        if let Some(pending) = self.pending.take() {
+            self.encode(pending, self.last_delay)?;
+        }
+
+        // the trailer is written on drop of the encoder
+        self.encoder = None;
+        Ok(())
+    }
+}
+impl std::fmt::Debug for GifMuxer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GifMuxer")
+            .field("active", &self.encoder.is_some())
+            .field("last_delay", &self.last_delay)
+            .finish()
+    }
+}
+
+
+impl Capture {
+    /// Record `duration` of frames at `fps` into an animated GIF at `path`, and return the count of the frames.
+    ///
+    /// blocks until done. use [GifMuxer] with a [Recorder](crate::Recorder) to record in the background.
+    ///
+    /// Required features: *`"img"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// capture.record_gif("notepad.gif", std::time::Duration::from_secs(3), 10).expect("Failed to record");
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+    pub fn record_gif<P: AsRef<Path>>(&self, path: P, duration: Duration, fps: u32) -> anyhow::Result<usize, CaptureError> {
+        if fps == 0 {
+            return Err(CaptureError::InvalidArgument("fps must be greater than 0.".to_string()));
+        }
+
+        let mut muxer = GifMuxer::create(path, GifOptions::default())?;
+        let interval = Duration::from_secs(1) / fps;
+
+        let start = Instant::now();
+        let mut count = 0;
+        while interval * count as u32 < duration {
+            let deadline = interval * count as u32;
+            if let Some(wait) = deadline.checked_sub(start.elapsed()) {
+                std::thread::sleep(wait);
+            }
+
+            let raw = self.wait_raw_frame()?;
+            muxer.write_packet(&EncodedPacket{
+                timestamp: start.elapsed(),
+                width: raw.width,
+                height: raw.height,
+                keyframe: true,
+                data: raw.data,
+            })?;
+            count += 1;
+        }
+        muxer.finish()?;
+
+        Ok(count)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use image::{
+        codecs::gif::GifDecoder,
+        AnimationDecoder,
+    };
+
+    /// Path in the temp directory, removed when dropped.
+    struct TempPath(std::path::PathBuf);
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("dxcapture-{}-{}", std::process::id(), name)))
+        }
+    }
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn packet(millis: u64, value: u8) -> EncodedPacket {
+        EncodedPacket{
+            timestamp: Duration::from_millis(millis),
+            width: 2,
+            height: 2,
+            keyframe: true,
+            data: [value, value, value, 255].repeat(4),
+        }
+    }
+
+    /// Delays of the frames in milliseconds.
+    fn delays(path: &Path) -> Vec<u32> {
+        let decoder = GifDecoder::new(File::open(path).unwrap()).unwrap();
+
+        decoder.into_frames().map(|frame| {
+            let (numerator, denominator) = frame.unwrap().delay().numer_denom_ms();
+            numerator / denominator
+        }).collect()
+    }
+
+    #[test]
+    fn delay_to_the_next_frame() {
+        let path = TempPath::new("delay.gif");
+        let mut muxer = GifMuxer::create(&path.0, GifOptions::default()).unwrap();
+        for (millis, value) in [(0, 0), (100, 128), (300, 255)] {
+            muxer.write_packet(&packet(millis, value)).unwrap();
+        }
+        muxer.finish().unwrap();
+
+        // the last frame is shown as long as the one before
+        assert_eq!(delays(&path.0), vec![100, 200, 200]);
+    }
+
+    #[test]
+    fn same_timestamps() {
+        let path = TempPath::new("same_timestamps.gif");
+        let mut muxer = GifMuxer::create(&path.0, GifOptions::default()).unwrap();
+        for millis in [0, 0, 50] {
+            muxer.write_packet(&packet(millis, 0)).unwrap();
+        }
+        muxer.finish().unwrap();
+
+        // no delay keeps the last one
+        assert_eq!(delays(&path.0), vec![100, 50, 50]);
+    }
+
+    #[test]
+    fn invalid_speed() {
+        let path = TempPath::new("invalid_speed.gif");
+
+        assert!(matches!(GifMuxer::create(&path.0, GifOptions{ speed: 0, ..Default::default() }), Err(CaptureError::InvalidArgument(_))));
+        assert!(!path.0.exists());
+    }
+}
@@ -0,0 +1,254 @@
+//! Global hotkeys delivered as events.
+//!
+//! hotkeys are registered on a thread running its own message loop,
+//! so they work from console applications without any window.
+//!
+//! Required features: *`"hotkey"`*
+
+use std::{
+    ops::BitOr,
+    sync::mpsc::{
+        self,
+        Receiver,
+        RecvTimeoutError,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use winapi::{
+    shared::minwindef::UINT,
+    um::{
+        processthreadsapi::GetCurrentThreadId,
+        winuser::{
+            GetMessageW,
+            PeekMessageW,
+            PostThreadMessageW,
+            RegisterHotKey,
+            UnregisterHotKey,
+            MOD_ALT,
+            MOD_CONTROL,
+            MOD_NOREPEAT,
+            MOD_SHIFT,
+            MOD_WIN,
+            MSG,
+            PM_NOREMOVE,
+            WM_HOTKEY,
+            WM_QUIT,
+            WM_USER,
+        },
+    },
+};
+
+use crate::capture::CaptureError;
+
+
+/// Modifier keys of a [Hotkey]. combine with `|`.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "hotkey")))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers(u32);
+impl Modifiers {
+    pub const NONE: Self = Self(0);
+    pub const ALT: Self = Self(MOD_ALT as u32);
+    pub const CONTROL: Self = Self(MOD_CONTROL as u32);
+    pub const SHIFT: Self = Self(MOD_SHIFT as u32);
+    pub const WIN: Self = Self(MOD_WIN as u32);
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+impl BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+
+/// Key combination registered by [HotkeyListener].
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "hotkey")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    pub modifiers: Modifiers,
+    /// virtual-key code. (`VK_SNAPSHOT`, `'S' as u32`, ...)
+    pub key: u32,
+}
+impl Hotkey {
+    pub fn new(modifiers: Modifiers, key: u32) -> Self {
+        Self{ modifiers, key }
+    }
+}
+
+
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "hotkey")))]
+#[derive(Clone, Copy, Debug)]
+pub struct HotkeyEvent {
+    /// index of the hotkey given to [HotkeyListener::new].
+    pub id: usize,
+    pub hotkey: Hotkey,
+    pub time: Instant,
+}
+
+
+/// Listen global hotkeys on a background thread.
+///
+/// events are received on the thread owning the [Capture](crate::Capture) or [Recorder](crate::Recorder),
+/// so they can be acted on there. the hotkeys are unregistered on drop.
+///
+/// Required features: *`"hotkey"`*
+/// # Examples
+/// ```
+/// use dxcapture::{ Hotkey, HotkeyListener, Modifiers };
+///
+/// const VK_SNAPSHOT: u32 = 0x2C;
+/// let listener = HotkeyListener::new(&[
+///     Hotkey::new(Modifiers::CONTROL, VK_SNAPSHOT),
+///     Hotkey::new(Modifiers::CONTROL | Modifiers::SHIFT, 'R' as u32),
+/// ]).expect("Hotkey is already used");
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// let mut recorder: Option<dxcapture::Recorder> = None;
+///
+/// while let Some(event) = listener.recv() {
+///     match event.id {
+///         0 => {
+///             let raw = capture.wait_raw_frame().unwrap();
+///         },
+///         _ => match recorder.take() {
+///             Some(recording) => { recording.stop().unwrap(); },
+///             None => {
+///                 recorder = Some(dxcapture::Recorder::start(
+///                     || Ok(dxcapture::Capture::new(&dxcapture::Device::new_from_displays(None)?)?),
+///                     dxcapture::RawEncoder,
+///                     dxcapture::DxcapMuxer::create("session.dxcap").unwrap(),
+///                     Default::default(),
+///                 ).unwrap());
+///             },
+///         },
+///     }
+/// }
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "hotkey")))]
+pub struct HotkeyListener {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+    events: Receiver<HotkeyEvent>,
+}
+impl HotkeyListener {
+    /// Register `hotkeys` and start listening.
+    /// ## Errors
+    /// * [HotkeyInUse](CaptureError::HotkeyInUse): a hotkey is registered by another application. none of `hotkeys` are registered then.
+    pub fn new(hotkeys: &[Hotkey]) -> anyhow::Result<Self, CaptureError> {
+        let hotkeys = hotkeys.to_vec();
+        let (ready_sender, ready) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || {
+            let thread_id = unsafe {
+                // make the message queue of this thread, before anything is posted to it
+                let mut msg: MSG = std::mem::zeroed();
+                PeekMessageW(&mut msg, std::ptr::null_mut(), WM_USER, WM_USER, PM_NOREMOVE);
+                GetCurrentThreadId()
+            };
+
+            for (id, hotkey) in hotkeys.iter().enumerate() {
+                let registered = unsafe {
+                    RegisterHotKey(std::ptr::null_mut(), id as i32, hotkey.modifiers.bits() | MOD_NOREPEAT as UINT, hotkey.key)
+                };
+                if registered == 0 {
+                    for id in 0..id {
+                        unsafe { UnregisterHotKey(std::ptr::null_mut(), id as i32) };
+                    }
+                    let _ = ready_sender.send(Err(CaptureError::HotkeyInUse(hotkey.modifiers.bits(), hotkey.key)));
+                    return;
+                }
+            }
+            let _ = ready_sender.send(Ok(thread_id));
+
+            let mut msg: MSG = unsafe { std::mem::zeroed() };
+            // 0 on WM_QUIT, -1 on error
+            while 0 < unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) } {
+                if msg.message != WM_HOTKEY {
+                    continue;
+                }
+
+                let id = msg.wParam;
+                if let Some(hotkey) = hotkeys.get(id) {
+                    let event = HotkeyEvent{ id, hotkey: *hotkey, time: Instant::now() };
+                    if event_sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+
+            for id in 0..hotkeys.len() {
+                unsafe { UnregisterHotKey(std::ptr::null_mut(), id as i32) };
+            }
+        });
+
+        let thread_id = match ready.recv() {
+            Ok(Ok(thread_id)) => thread_id,
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                return Err(e);
+            },
+            Err(_) => return Err(CaptureError::SourceError("Hotkey thread panicked.".to_string())),
+        };
+
+        Ok(Self{
+            thread_id,
+            thread: Some(thread),
+            events,
+        })
+    }
+
+    /// Wait the next hotkey. `None` after the listener stopped.
+    pub fn recv(&self) -> Option<HotkeyEvent> {
+        self.events.recv().ok()
+    }
+
+    /// The pressed hotkey if any, without waiting.
+    pub fn try_recv(&self) -> Option<HotkeyEvent> {
+        self.events.try_recv().ok()
+    }
+
+    /// Wait the next hotkey for up to `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> anyhow::Result<HotkeyEvent, CaptureError> {
+        self.events.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => CaptureError::Timeout,
+            RecvTimeoutError::Disconnected => CaptureError::NotActive,
+        })
+    }
+
+    /// All hotkeys pressed since the last call.
+    pub fn pending(&self) -> Vec<HotkeyEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Unregister the hotkeys and stop the thread.
+    pub fn stop(mut self) {
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+            let _ = thread.join();
+        }
+    }
+}
+impl Drop for HotkeyListener {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -0,0 +1,459 @@
+//! Keystrokes and mouse clicks drawn onto frames, for tutorials and screencasts.
+//!
+//! Required features: *`"input"`*
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::mpsc::{
+        self,
+        Receiver,
+        Sender,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use winapi::{
+    shared::{
+        minwindef::{
+            LPARAM,
+            LRESULT,
+            WPARAM,
+        },
+        windef::HHOOK,
+    },
+    um::{
+        libloaderapi::GetModuleHandleW,
+        processthreadsapi::GetCurrentThreadId,
+        winuser::{
+            CallNextHookEx,
+            GetMessageW,
+            PeekMessageW,
+            PostThreadMessageW,
+            SetWindowsHookExW,
+            UnhookWindowsHookEx,
+            HC_ACTION,
+            KBDLLHOOKSTRUCT,
+            MSG,
+            MSLLHOOKSTRUCT,
+            PM_NOREMOVE,
+            WH_KEYBOARD_LL,
+            WH_MOUSE_LL,
+            WM_KEYDOWN,
+            WM_KEYUP,
+            WM_LBUTTONDOWN,
+            WM_MBUTTONDOWN,
+            WM_QUIT,
+            WM_RBUTTONDOWN,
+            WM_SYSKEYDOWN,
+            WM_SYSKEYUP,
+            WM_USER,
+        },
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+    Rect,
+};
+use crate::overlay::{
+    draw_text,
+    fill_rect,
+    Corner,
+    FrameFilter,
+    GLYPH_HEIGHT,
+    GLYPH_WIDTH,
+};
+
+
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+/// Input caught by the low-level hooks.
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    /// a key pressed with the modifiers held, like `CTRL+SHIFT+S`.
+    Key(String),
+    /// a click at the screen position.
+    Click(MouseButton, i32, i32),
+}
+
+#[derive(Default)]
+struct HookState {
+    sender: Option<Sender<(InputEvent, Instant)>>,
+    control: bool,
+    shift: bool,
+    alt: bool,
+    win: bool,
+}
+
+thread_local! {
+    // low-level hooks are called on the thread which set them
+    static HOOK_STATE: RefCell<HookState> = RefCell::new(HookState::default());
+}
+
+fn key_name(vk: u32) -> Option<String> {
+    let name = match vk {
+        0x08 => "BKSP",
+        0x09 => "TAB",
+        0x0D => "ENTER",
+        0x1B => "ESC",
+        0x20 => "SPACE",
+        0x21 => "PGUP",
+        0x22 => "PGDN",
+        0x23 => "END",
+        0x24 => "HOME",
+        0x25 => "LEFT",
+        0x26 => "UP",
+        0x27 => "RIGHT",
+        0x28 => "DOWN",
+        0x2C => "PRTSC",
+        0x2D => "INS",
+        0x2E => "DEL",
+        0x30..=0x39 | 0x41..=0x5A => return Some(char::from(vk as u8).to_string()),
+        0x60..=0x69 => return Some(char::from(b'0' + (vk - 0x60) as u8).to_string()),
+        0x70..=0x87 => return Some(format!("F{}", vk - 0x6F)),
+        0xBD => "-",
+        0xBE => ".",
+        0xBF => "/",
+        _ => return None,
+    };
+
+    Some(name.to_string())
+}
+
+unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION {
+        let vk = (*(lparam as *const KBDLLHOOKSTRUCT)).vkCode;
+        let down = wparam as u32 == WM_KEYDOWN || wparam as u32 == WM_SYSKEYDOWN;
+        let up = wparam as u32 == WM_KEYUP || wparam as u32 == WM_SYSKEYUP;
+
+        HOOK_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            match vk {
+                // VK_SHIFT, VK_LSHIFT, VK_RSHIFT
+                0x10 | 0xA0 | 0xA1 if down || up => state.shift = down,
+                // VK_CONTROL, VK_LCONTROL, VK_RCONTROL
+                0x11 | 0xA2 | 0xA3 if down || up => state.control = down,
+                // VK_MENU, VK_LMENU, VK_RMENU
+                0x12 | 0xA4 | 0xA5 if down || up => state.alt = down,
+                // VK_LWIN, VK_RWIN
+                0x5B | 0x5C if down || up => state.win = down,
+                _ if down => {
+                    if let Some(name) = key_name(vk) {
+                        let mut text = String::new();
+                        for (held, modifier) in [(state.control, "CTRL+"), (state.alt, "ALT+"), (state.shift, "SHIFT+"), (state.win, "WIN+")] {
+                            if held {
+                                text.push_str(modifier);
+                            }
+                        }
+                        text.push_str(&name);
+
+                        if let Some(sender) = &state.sender {
+                            let _ = sender.send((InputEvent::Key(text), Instant::now()));
+                        }
+                    }
+                },
+                _ => {},
+            }
+        });
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code == HC_ACTION {
+        let button = match wparam as u32 {
+            WM_LBUTTONDOWN => Some(MouseButton::Left),
+            WM_RBUTTONDOWN => Some(MouseButton::Right),
+            WM_MBUTTONDOWN => Some(MouseButton::Middle),
+            _ => None,
+        };
+
+        if let Some(button) = button {
+            let point = (*(lparam as *const MSLLHOOKSTRUCT)).pt;
+            HOOK_STATE.with(|state| {
+                if let Some(sender) = &state.borrow().sender {
+                    let _ = sender.send((InputEvent::Click(button, point.x, point.y), Instant::now()));
+                }
+            });
+        }
+    }
+
+    CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+}
+
+
+/// [FrameFilter] showing recent keystrokes and mouse clicks on each frame.
+///
+/// keystrokes are listed at a corner, and clicks are drawn as rings fading out at where they happened.
+/// the input is caught with low-level hooks on a background thread, which is stopped on drop.
+///
+/// Required features: *`"input"`*
+/// # Examples
+/// ```
+/// use dxcapture::{ Corner, FilteredSource, FrameSource, InputOverlay };
+///
+/// let overlay = InputOverlay::new().unwrap()
+///     .corner(Corner::BottomLeft)
+///     .font_size(21);
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(overlay);
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input")))]
+pub struct InputOverlay {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+    events: Receiver<(InputEvent, Instant)>,
+    keys: VecDeque<(String, Instant)>,
+    clicks: VecDeque<(MouseButton, i32, i32, Instant)>,
+
+    origin: (i32, i32),
+    corner: Corner,
+    font_size: u32,
+    margin: i32,
+    max_keys: usize,
+    key_duration: Duration,
+    click_duration: Duration,
+    click_radius: i32,
+    text_color: [u8; 4],
+    background_color: [u8; 4],
+}
+impl InputOverlay {
+    /// Start catching the input.
+    pub fn new() -> anyhow::Result<Self, CaptureError> {
+        let (ready_sender, ready) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || {
+            let thread_id = unsafe {
+                // make the message queue of this thread, before anything is posted to it
+                let mut msg: MSG = std::mem::zeroed();
+                PeekMessageW(&mut msg, std::ptr::null_mut(), WM_USER, WM_USER, PM_NOREMOVE);
+                GetCurrentThreadId()
+            };
+            HOOK_STATE.with(|state| state.borrow_mut().sender = Some(event_sender));
+
+            let (keyboard, mouse): (HHOOK, HHOOK) = unsafe {
+                let module = GetModuleHandleW(std::ptr::null());
+                (
+                    SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), module, 0),
+                    SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), module, 0),
+                )
+            };
+            if keyboard.is_null() || mouse.is_null() {
+                unsafe {
+                    if !keyboard.is_null() {
+                        UnhookWindowsHookEx(keyboard);
+                    }
+                    if !mouse.is_null() {
+                        UnhookWindowsHookEx(mouse);
+                    }
+                }
+                let _ = ready_sender.send(Err(CaptureError::SourceError("Failed to set the input hooks.".to_string())));
+                return;
+            }
+            let _ = ready_sender.send(Ok(thread_id));
+
+            // the hooks are called while this thread waits the messages
+            let mut msg: MSG = unsafe { std::mem::zeroed() };
+            while 0 < unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) } {}
+
+            unsafe {
+                UnhookWindowsHookEx(keyboard);
+                UnhookWindowsHookEx(mouse);
+            }
+        });
+
+        let thread_id = match ready.recv() {
+            Ok(Ok(thread_id)) => thread_id,
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                return Err(e);
+            },
+            Err(_) => return Err(CaptureError::SourceError("Input hook thread panicked.".to_string())),
+        };
+
+        Ok(Self{
+            thread_id,
+            thread: Some(thread),
+            events,
+            keys: VecDeque::new(),
+            clicks: VecDeque::new(),
+
+            origin: (0, 0),
+            corner: Corner::BottomLeft,
+            font_size: 21,
+            margin: 16,
+            max_keys: 5,
+            key_duration: Duration::from_secs(2),
+            click_duration: Duration::from_millis(500),
+            click_radius: 20,
+            text_color: [255, 255, 255, 255],
+            background_color: [0, 0, 0, 255],
+        })
+    }
+
+    /// Screen position of the top left of the frames, to place the clicks. (the top left of the captured display or window)
+    /// default is (0, 0), the primary display.
+    pub fn origin(mut self, x: i32, y: i32) -> Self {
+        self.origin = (x, y);
+        self
+    }
+
+    /// Where the keystrokes are listed. default is [BottomLeft](Corner::BottomLeft).
+    pub fn corner(mut self, corner: Corner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    /// Height of the characters in pixels. rounded down to a multiple of 7, at least 7.
+    pub fn font_size(mut self, pixels: u32) -> Self {
+        self.font_size = pixels;
+        self
+    }
+
+    /// Distance from the edges of the frame in pixels.
+    pub fn margin(mut self, pixels: i32) -> Self {
+        self.margin = pixels;
+        self
+    }
+
+    /// How many keystrokes are listed at most. default is 5.
+    pub fn max_keys(mut self, count: usize) -> Self {
+        self.max_keys = count;
+        self
+    }
+
+    /// How long a keystroke stays. default is 2 seconds.
+    pub fn key_duration(mut self, duration: Duration) -> Self {
+        self.key_duration = duration;
+        self
+    }
+
+    /// How long a click ring stays. default is 500ms.
+    pub fn click_duration(mut self, duration: Duration) -> Self {
+        self.click_duration = duration;
+        self
+    }
+
+    pub fn click_radius(mut self, pixels: i32) -> Self {
+        self.click_radius = pixels;
+        self
+    }
+
+    /// BGRA of the keystroke text and the box behind it. default is white on black.
+    pub fn colors(mut self, text: [u8; 4], background: [u8; 4]) -> Self {
+        self.text_color = text;
+        self.background_color = background;
+        self
+    }
+
+    fn receive(&mut self) {
+        for (event, time) in self.events.try_iter() {
+            match event {
+                InputEvent::Key(text) => self.keys.push_back((text, time)),
+                InputEvent::Click(button, x, y) => self.clicks.push_back((button, x, y, time)),
+            }
+        }
+
+        let (key_duration, click_duration) = (self.key_duration, self.click_duration);
+        self.keys.retain(|(_, time)| time.elapsed() < key_duration);
+        while self.max_keys < self.keys.len() {
+            self.keys.pop_front();
+        }
+        self.clicks.retain(|(_, _, _, time)| time.elapsed() < click_duration);
+    }
+
+    fn draw_keys(&self, frame: &mut RawFrameData) {
+        let scale = (self.font_size as i32 / GLYPH_HEIGHT).max(1);
+        let padding = scale * 2;
+        let line_height = GLYPH_HEIGHT * scale + padding * 2;
+
+        let count = self.keys.len() as i32;
+        for (i, (text, _)) in self.keys.iter().enumerate() {
+            let width = text.chars().count() as i32 * (GLYPH_WIDTH + 1) * scale - scale + padding * 2;
+            // the newest is the nearest to the corner
+            let line = count - 1 - i as i32;
+
+            let x = match self.corner {
+                Corner::TopLeft | Corner::BottomLeft => self.margin,
+                Corner::TopRight | Corner::BottomRight => frame.width - self.margin - width,
+            };
+            let y = match self.corner {
+                Corner::TopLeft | Corner::TopRight => self.margin + line * (line_height + scale),
+                Corner::BottomLeft | Corner::BottomRight => frame.height - self.margin - line_height - line * (line_height + scale),
+            };
+
+            fill_rect(frame, Rect::new(x, y, width, line_height), self.background_color);
+            draw_text(frame, text, x + padding, y + padding, scale, self.text_color);
+        }
+    }
+
+    fn draw_clicks(&self, frame: &mut RawFrameData) {
+        let bounds = Rect::new(0, 0, frame.width, frame.height);
+
+        for (button, x, y, time) in &self.clicks {
+            let bgra = match button {
+                MouseButton::Left => [0, 200, 255, 255],
+                MouseButton::Right => [255, 128, 0, 255],
+                MouseButton::Middle => [0, 255, 0, 255],
+            };
+            let fade = 1.0 - time.elapsed().as_secs_f32() / self.click_duration.as_secs_f32().max(f32::EPSILON);
+            let alpha = (fade.clamp(0.0, 1.0) * 255.0) as u32;
+            let (center_x, center_y) = frame.info.map_point(x - self.origin.0, y - self.origin.1);
+
+            // grows from the half while fading
+            let radius = self.click_radius as f32 * (1.0 - fade / 2.0);
+            let thickness = (self.click_radius as f32 / 6.0).max(2.0);
+            let reach = (radius + thickness) as i32 + 1;
+            let area = Rect::new(center_x - reach, center_y - reach, reach * 2, reach * 2).intersect(&bounds);
+
+            for py in area.y..area.bottom() {
+                for px in area.x..area.right() {
+                    let distance = (((px - center_x).pow(2) + (py - center_y).pow(2)) as f32).sqrt();
+                    if thickness / 2.0 < (distance - radius).abs() {
+                        continue;
+                    }
+
+                    let offset = ((py * frame.width + px) * 4) as usize;
+                    if let Some(pixel) = frame.data.get_mut(offset..offset + 3) {
+                        for (value, color) in pixel.iter_mut().zip(bgra) {
+                            *value = ((color as u32 * alpha + *value as u32 * (255 - alpha) + 127) / 255) as u8;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+impl FrameFilter for InputOverlay {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        frame.pack_rows();
+        self.receive();
+        self.draw_clicks(frame);
+        self.draw_keys(frame);
+    }
+}
+impl Drop for InputOverlay {
+    fn drop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+            let _ = thread.join();
+        }
+    }
+}
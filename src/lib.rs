@@ -9,7 +9,7 @@
 //! 
 //! # Examples
 //! ```
-//! let device = dxcapture::Device::default();
+//! let device = dxcapture::Device::new_from_displays(None).unwrap();
 //! let capture = dxcapture::Capture::new(&device).unwrap();
 //! 
 //! let raw = loop {
@@ -34,12 +34,150 @@
 
 pub mod d3d;
 pub mod capture;
+pub mod source;
+pub mod replay;
+pub mod replay_buffer;
+pub mod screenshot;
+pub mod scrolling;
+pub mod diff;
+pub mod luminance;
+pub mod environment;
+pub mod recorder;
+pub mod timelapse;
+pub mod clock;
+pub mod overlay;
+pub mod redaction;
+pub mod cursor;
+pub mod annotation;
+pub mod tensor;
+pub mod priority;
+pub mod bench;
 
 pub use d3d::*;
 pub use capture::*;
+pub use source::*;
+pub use replay::*;
+pub use replay_buffer::*;
+pub use screenshot::*;
+pub use scrolling::*;
+pub use diff::*;
+pub use luminance::*;
+pub use environment::*;
+pub use recorder::*;
+pub use timelapse::*;
+pub use clock::*;
+pub use overlay::*;
+pub use redaction::*;
+pub use cursor::*;
+pub use annotation::*;
+pub use tensor::*;
+pub use priority::*;
+
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "mock")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mock")))]
+pub use mock::*;
+
+#[cfg(feature = "img")]
+pub mod assertions;
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub use assertions::*;
+
+#[cfg(feature = "img")]
+pub mod metadata;
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub use metadata::*;
+
+#[cfg(feature = "img")]
+pub mod gif;
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub use gif::*;
+
+#[cfg(feature = "audio")]
+pub mod audio;
+#[cfg(feature = "audio")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "audio")))]
+pub use audio::*;
+
+#[cfg(feature = "hotkey")]
+pub mod hotkey;
+#[cfg(feature = "hotkey")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "hotkey")))]
+pub use hotkey::*;
+
+#[cfg(feature = "input")]
+pub mod input;
+#[cfg(feature = "input")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "input")))]
+pub use input::*;
+
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "config")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "config")))]
+pub use config::*;
+
+#[cfg(feature = "vulkan")]
+pub mod vulkan;
+#[cfg(feature = "vulkan")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "vulkan")))]
+pub use vulkan::*;
+
+#[cfg(feature = "egui")]
+pub mod egui_preview;
+#[cfg(feature = "egui")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "egui")))]
+pub use egui_preview::*;
+
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+#[cfg(feature = "bevy")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "bevy")))]
+pub use bevy_plugin::*;
+
+#[cfg(feature = "lifecycle")]
+pub mod lifecycle;
+#[cfg(feature = "lifecycle")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "lifecycle")))]
+pub use lifecycle::*;
+
+#[cfg(feature = "qr")]
+pub mod qr;
+#[cfg(feature = "qr")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "qr")))]
+pub use qr::*;
+
+#[cfg(feature = "mp4")]
+pub mod mp4;
+#[cfg(feature = "mp4")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+pub use mp4::*;
+
+#[cfg(feature = "gdi")]
+pub mod gdi;
+#[cfg(feature = "gdi")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "gdi")))]
+pub use gdi::*;
+
+#[cfg(feature = "picker")]
+pub mod picker;
+
+#[cfg(feature = "selector")]
+pub mod selector;
+#[cfg(feature = "selector")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "selector")))]
+pub use selector::*;
 
 mod displays;
 mod window_finder;
 
 pub use displays::enumerate_displays as enumerate_displays;
 pub use window_finder::get_capturable_windows as enumerate_windows;
+pub use window_finder::get_capturable_windows_with as enumerate_windows_with;
+pub use window_finder::WindowFilter;
+pub use window_finder::WindowInfo;
+pub use window_finder::WindowMatch;
@@ -0,0 +1,460 @@
+//! Recovery of the capture over sleep, lock and fast user switching.
+//!
+//! the capture session stops delivering frames, or loses its device, while the system sleeps or the session
+//! is switched away. [RecoveringSource] tears the source down on these events and makes it again when they end,
+//! reporting [LifecycleEvent]s so the recorders can splice their outputs.
+//!
+//! Required features: *`"lifecycle"`*
+
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    sync::{
+        mpsc::{
+            self,
+            Receiver,
+            RecvTimeoutError,
+            Sender,
+        },
+        Once,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use winapi::{
+    shared::{
+        minwindef::{
+            LPARAM,
+            LRESULT,
+            UINT,
+            WPARAM,
+        },
+        windef::HWND,
+    },
+    um::{
+        libloaderapi::GetModuleHandleW,
+        processthreadsapi::GetCurrentThreadId,
+        winuser::{
+            CreateWindowExW,
+            DefWindowProcW,
+            DestroyWindow,
+            DispatchMessageW,
+            GetMessageW,
+            PostThreadMessageW,
+            RegisterClassExW,
+            MSG,
+            PBT_APMRESUMEAUTOMATIC,
+            PBT_APMSUSPEND,
+            WM_POWERBROADCAST,
+            WM_QUIT,
+            WM_WTSSESSION_CHANGE,
+            WNDCLASSEXW,
+            WTS_CONSOLE_CONNECT,
+            WTS_CONSOLE_DISCONNECT,
+            WTS_REMOTE_CONNECT,
+            WTS_REMOTE_DISCONNECT,
+            WTS_SESSION_LOCK,
+            WTS_SESSION_UNLOCK,
+        },
+    },
+};
+use windows::Win32::{
+    Foundation::HWND as WindowHandle,
+    System::RemoteDesktop::{
+        WTSRegisterSessionNotification,
+        WTSUnRegisterSessionNotification,
+        NOTIFY_FOR_THIS_SESSION,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::source::FrameSource;
+
+
+const CLASS_NAME: &str = "dxcapture_lifecycle";
+
+/// Power and session change of the system. [Read more](SystemEventListener)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SystemEvent {
+    /// going to sleep or hibernate.
+    Suspending,
+    /// back from sleep or hibernate.
+    Resumed,
+    SessionLocked,
+    SessionUnlocked,
+    /// switched to another user, or the remote connection was closed.
+    SessionDisconnected,
+    /// switched back, or connected remotely.
+    SessionConnected,
+}
+
+
+thread_local! {
+    /// where the window procedure of the listener thread sends the events.
+    static EVENT_SENDER: RefCell<Option<Sender<(SystemEvent, Instant)>>> = RefCell::new(None);
+}
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let event = match (msg, wparam) {
+        (WM_POWERBROADCAST, PBT_APMSUSPEND) => Some(SystemEvent::Suspending),
+        // sent on every resume, with or without the user
+        (WM_POWERBROADCAST, PBT_APMRESUMEAUTOMATIC) => Some(SystemEvent::Resumed),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_LOCK) => Some(SystemEvent::SessionLocked),
+        (WM_WTSSESSION_CHANGE, WTS_SESSION_UNLOCK) => Some(SystemEvent::SessionUnlocked),
+        (WM_WTSSESSION_CHANGE, WTS_CONSOLE_DISCONNECT) | (WM_WTSSESSION_CHANGE, WTS_REMOTE_DISCONNECT) => Some(SystemEvent::SessionDisconnected),
+        (WM_WTSSESSION_CHANGE, WTS_CONSOLE_CONNECT) | (WM_WTSSESSION_CHANGE, WTS_REMOTE_CONNECT) => Some(SystemEvent::SessionConnected),
+        _ => None,
+    };
+    if let Some(event) = event {
+        EVENT_SENDER.with(|sender| {
+            if let Some(sender) = sender.borrow().as_ref() {
+                let _ = sender.send((event, Instant::now()));
+            }
+        });
+    }
+
+    DefWindowProcW(hwnd, msg, wparam, lparam)
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn register_class() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| unsafe {
+        let class_name = to_wide(CLASS_NAME);
+        let class = WNDCLASSEXW{
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(window_proc),
+            hInstance: GetModuleHandleW(std::ptr::null()),
+            // copied by RegisterClassExW
+            lpszClassName: class_name.as_ptr(),
+            ..Default::default()
+        };
+        RegisterClassExW(&class);
+    });
+}
+
+
+/// [SystemEvent]s delivered from a thread with a hidden window.
+///
+/// a hidden top level window rather than a message only one, which doesn't get the power broadcasts.
+/// # Examples
+/// ```
+/// let listener = dxcapture::SystemEventListener::new().unwrap();
+///
+/// while let Some((event, _)) = listener.recv() {
+///     println!("{:?}", event);
+/// }
+/// ```
+pub struct SystemEventListener {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+    events: Receiver<(SystemEvent, Instant)>,
+}
+impl SystemEventListener {
+    /// Create the window and start listening.
+    /// ## Errors
+    /// * [DirectxError](CaptureError::DirectxError): failed to create the window or to register for the session changes.
+    pub fn new() -> anyhow::Result<Self, CaptureError> {
+        let (ready_sender, ready) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+
+        let thread = std::thread::spawn(move || {
+            register_class();
+
+            let (thread_id, hwnd) = unsafe {
+                let class_name = to_wide(CLASS_NAME);
+                let hwnd = CreateWindowExW(
+                    0,
+                    class_name.as_ptr(),
+                    class_name.as_ptr(),
+                    // never shown
+                    0,
+                    0, 0, 0, 0,
+                    std::ptr::null_mut(),
+                    std::ptr::null_mut(),
+                    GetModuleHandleW(std::ptr::null()),
+                    std::ptr::null_mut(),
+                );
+                if hwnd.is_null() {
+                    let _ = ready_sender.send(Err(CaptureError::DirectxError(windows::core::Error::from_win32())));
+                    return;
+                }
+                if !WTSRegisterSessionNotification(WindowHandle(hwnd as isize), NOTIFY_FOR_THIS_SESSION).as_bool() {
+                    let _ = ready_sender.send(Err(CaptureError::DirectxError(windows::core::Error::from_win32())));
+                    DestroyWindow(hwnd);
+                    return;
+                }
+
+                (GetCurrentThreadId(), hwnd)
+            };
+            EVENT_SENDER.with(|sender| *sender.borrow_mut() = Some(event_sender));
+            let _ = ready_sender.send(Ok(thread_id));
+
+            let mut msg: MSG = unsafe { std::mem::zeroed() };
+            // 0 on WM_QUIT, -1 on error
+            while 0 < unsafe { GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) } {
+                unsafe { DispatchMessageW(&msg) };
+            }
+
+            unsafe {
+                WTSUnRegisterSessionNotification(WindowHandle(hwnd as isize));
+                DestroyWindow(hwnd);
+            }
+            EVENT_SENDER.with(|sender| *sender.borrow_mut() = None);
+        });
+
+        let thread_id = match ready.recv() {
+            Ok(Ok(thread_id)) => thread_id,
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                return Err(e);
+            },
+            Err(_) => return Err(CaptureError::SourceError("Lifecycle thread panicked.".to_string())),
+        };
+
+        Ok(Self{
+            thread_id,
+            thread: Some(thread),
+            events,
+        })
+    }
+
+    /// Wait the next event. `None` after the listener stopped.
+    pub fn recv(&self) -> Option<(SystemEvent, Instant)> {
+        self.events.recv().ok()
+    }
+
+    /// The next event if any, without waiting.
+    pub fn try_recv(&self) -> Option<(SystemEvent, Instant)> {
+        self.events.try_recv().ok()
+    }
+
+    /// Wait the next event for up to `timeout`.
+    pub fn recv_timeout(&self, timeout: Duration) -> anyhow::Result<(SystemEvent, Instant), CaptureError> {
+        self.events.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => CaptureError::Timeout,
+            RecvTimeoutError::Disconnected => CaptureError::NotActive,
+        })
+    }
+
+    /// All events since the last call.
+    pub fn pending(&self) -> Vec<(SystemEvent, Instant)> {
+        self.events.try_iter().collect()
+    }
+
+    /// Destroy the window and stop the thread.
+    pub fn stop(mut self) {
+        self.finish();
+    }
+
+    fn finish(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            unsafe { PostThreadMessageW(self.thread_id, WM_QUIT, 0, 0) };
+            let _ = thread.join();
+        }
+    }
+}
+impl Drop for SystemEventListener {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+
+/// What happened to a [RecoveringSource].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LifecycleKind {
+    /// the system changed, as received.
+    System(SystemEvent),
+    /// the source was torn down, on a system event or an error of the source. no frames until restarted.
+    Stopped,
+    /// the source was made again. the frames after this are of a new session, maybe of another size.
+    Restarted,
+}
+
+/// Event of a [RecoveringSource]. [Read more](RecoveringSource::events)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LifecycleEvent {
+    pub kind: LifecycleKind,
+    pub time: Instant,
+    /// how many times the source was made, after this event. the frames of a generation are continuous.
+    pub generation: u64,
+}
+
+
+type SourceFactory<S> = Box<dyn Fn() -> anyhow::Result<S>>;
+
+/// [FrameSource] tearing the source down while the system sleeps, the session is locked or switched away,
+/// and making it again by `source_factory` after.
+///
+/// the source is also made again after it fails, as when the device is lost. [get_raw_frame](FrameSource::get_raw_frame)
+/// returns [NoTexture](CaptureError::NoTexture) while there is no source, so the waits go on across the gap.
+/// # Examples
+/// ```
+/// use dxcapture::{ FrameSource, LifecycleKind, RecoveringSource };
+///
+/// let source = RecoveringSource::new(|| {
+///     let device = dxcapture::Device::new_from_displays(None)?;
+///     dxcapture::Capture::new(&device)
+/// }).unwrap();
+///
+/// loop {
+///     let raw = source.wait_raw_frame().unwrap();
+///     for event in source.events() {
+///         if event.kind == LifecycleKind::Restarted {
+///             // start a new segment of the recording
+///         }
+///     }
+/// }
+/// ```
+pub struct RecoveringSource<S: FrameSource> {
+    source_factory: SourceFactory<S>,
+    source: RefCell<Option<S>>,
+    listener: SystemEventListener,
+    suspended: Cell<bool>,
+    locked: Cell<bool>,
+    disconnected: Cell<bool>,
+    generation: Cell<u64>,
+    retry_interval: Duration,
+    last_attempt: Cell<Option<Instant>>,
+    events: RefCell<Vec<LifecycleEvent>>,
+}
+impl<S: FrameSource> RecoveringSource<S> {
+    /// Start listening to the system, and make the first source.
+    /// ## Errors
+    /// * [SourceError](CaptureError::SourceError): failed to make the first source.
+    /// * the errors of [SystemEventListener::new].
+    pub fn new<F>(source_factory: F) -> anyhow::Result<Self, CaptureError>
+    where
+        F: Fn() -> anyhow::Result<S> + 'static,
+    {
+        let listener = SystemEventListener::new()?;
+        let source = source_factory().map_err(|e| match e.downcast::<CaptureError>() {
+            Ok(e) => e,
+            Err(e) => CaptureError::SourceError(e.to_string()),
+        })?;
+
+        Ok(Self{
+            source_factory: Box::new(source_factory),
+            source: RefCell::new(Some(source)),
+            listener,
+            suspended: Cell::new(false),
+            locked: Cell::new(false),
+            disconnected: Cell::new(false),
+            generation: Cell::new(1),
+            retry_interval: Duration::from_secs(1),
+            last_attempt: Cell::new(None),
+            events: RefCell::new(Vec::new()),
+        })
+    }
+
+    /// Time between the tries to make the source again. default is 1 second.
+    pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// How many times the source was made.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// The system is asleep, locked or switched away, so no source is made.
+    pub fn is_paused(&self) -> bool {
+        self.suspended.get() || self.locked.get() || self.disconnected.get()
+    }
+
+    /// The source is running.
+    pub fn is_running(&self) -> bool {
+        self.source.borrow().is_some()
+    }
+
+    /// All events since the last call, oldest first.
+    pub fn events(&self) -> Vec<LifecycleEvent> {
+        self.handle_system_events();
+        self.events.borrow_mut().drain(..).collect()
+    }
+
+    fn push_event(&self, kind: LifecycleKind, time: Instant) {
+        self.events.borrow_mut().push(LifecycleEvent{
+            kind,
+            time,
+            generation: self.generation.get(),
+        });
+    }
+
+    fn stop_source(&self, time: Instant) {
+        if self.source.borrow_mut().take().is_some() {
+            self.push_event(LifecycleKind::Stopped, time);
+        }
+    }
+
+    fn handle_system_events(&self) {
+        for (event, time) in self.listener.pending() {
+            match event {
+                SystemEvent::Suspending => self.suspended.set(true),
+                SystemEvent::Resumed => self.suspended.set(false),
+                SystemEvent::SessionLocked => self.locked.set(true),
+                SystemEvent::SessionUnlocked => self.locked.set(false),
+                SystemEvent::SessionDisconnected => self.disconnected.set(true),
+                SystemEvent::SessionConnected => self.disconnected.set(false),
+            }
+            self.push_event(LifecycleKind::System(event), time);
+
+            if self.is_paused() {
+                self.stop_source(time);
+            }
+        }
+    }
+
+    /// Make the source again when nothing holds it back, at most once a [retry_interval](Self::retry_interval).
+    fn restart_source(&self) {
+        if self.is_paused() || self.source.borrow().is_some() {
+            return;
+        }
+        if self.last_attempt.get().is_some_and(|last_attempt| last_attempt.elapsed() < self.retry_interval) {
+            return;
+        }
+        self.last_attempt.set(Some(Instant::now()));
+
+        if let Ok(source) = (self.source_factory)() {
+            *self.source.borrow_mut() = Some(source);
+            self.generation.set(self.generation.get() + 1);
+            self.push_event(LifecycleKind::Restarted, Instant::now());
+        }
+    }
+}
+impl<S: FrameSource> FrameSource for RecoveringSource<S> {
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        self.handle_system_events();
+        self.restart_source();
+
+        let result = match self.source.borrow().as_ref() {
+            Some(source) => source.get_raw_frame(),
+            None => return Err(CaptureError::NoTexture),
+        };
+        match result {
+            Ok(raw) => Ok(raw),
+            Err(CaptureError::NoTexture) => Err(CaptureError::NoTexture),
+            Err(_) => {
+                // made again after the retry interval
+                self.stop_source(Instant::now());
+                self.last_attempt.set(Some(Instant::now()));
+                Err(CaptureError::NoTexture)
+            },
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use crate::capture::{
+    RawFrameData,
+    Rect,
+};
+
+
+/// Minimum change of relative luminance counted as a transition. (WCAG 2.x general flash threshold)
+pub const FLASH_LUMINANCE_DELTA: f64 = 0.1;
+/// A transition counts only when the darker side is below this relative luminance.
+pub const FLASH_DARK_LIMIT: f64 = 0.8;
+
+fn to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Average relative luminance (0.0..=1.0, sRGB / BT.709) of a frame.
+/// ## Parameters
+/// * region: area to be measured. default is the whole frame. clipped to the frame.
+pub fn average_luminance(frame: &RawFrameData, region: Option<Rect>) -> f64 {
+    let bounds = Rect::new(0, 0, frame.width, frame.height);
+    let region = region.map(|r| r.intersect(&bounds)).unwrap_or(bounds);
+    if region.is_empty() {
+        return 0.0;
+    }
+
+    let mut sum = 0.0;
+    for y in region.y..region.bottom() {
+        for x in region.x..region.right() {
+            let offset = frame.pixel_offset(x as usize, y as usize);
+            let pixel = match frame.data.get(offset..offset + 3) {
+                Some(pixel) => pixel,
+                None => continue,
+            };
+
+            // BGRA
+            sum += 0.0722 * to_linear(pixel[0]) + 0.7152 * to_linear(pixel[1]) + 0.2126 * to_linear(pixel[2]);
+        }
+    }
+
+    sum / (region.width * region.height) as f64
+}
+
+
+/// Summary of [LuminanceTracker::report].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FlickerReport {
+    /// pair of opposing transitions.
+    pub flash_count: usize,
+    /// the most flashes in any one second window.
+    pub max_flashes_per_second: usize,
+    /// flash rate over the whole tracked duration.
+    pub frequency_hz: f64,
+    /// more than three flashes in one second, which is the photosensitivity limit of WCAG.
+    pub exceeds_three_flash_limit: bool,
+}
+
+
+/// Track the average luminance of a region over time and detect flicker.
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// let mut tracker = dxcapture::LuminanceTracker::new(None);
+///
+/// let start = std::time::Instant::now();
+/// while start.elapsed().as_secs() < 5 {
+///     let raw = capture.wait_raw_frame().unwrap();
+///     tracker.push(&raw, start.elapsed());
+/// }
+/// println!("{:?}", tracker.report());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LuminanceTracker {
+    region: Option<Rect>,
+    samples: Vec<(Duration, f64)>,
+}
+impl LuminanceTracker {
+    pub fn new(region: Option<Rect>) -> Self {
+        Self{
+            region,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Measure a frame. `timestamp` must be increasing.
+    pub fn push(&mut self, frame: &RawFrameData, timestamp: Duration) -> f64 {
+        let luminance = average_luminance(frame, self.region);
+        self.samples.push((timestamp, luminance));
+        luminance
+    }
+
+    /// Measured `(timestamp, luminance)` so far.
+    pub fn samples(&self) -> &[(Duration, f64)] {
+        &self.samples
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Timestamps of each luminance transition large enough to be a half of a flash.
+    pub fn transitions(&self) -> Vec<Duration> {
+        let mut transitions = Vec::new();
+        let (mut low, mut high) = match self.samples.first() {
+            Some(&(_, luminance)) => (luminance, luminance),
+            None => return transitions,
+        };
+        let mut rising: Option<bool> = None;
+
+        for &(timestamp, luminance) in &self.samples[1..] {
+            match rising {
+                None => {
+                    if FLASH_LUMINANCE_DELTA <= luminance - low && low < FLASH_DARK_LIMIT {
+                        rising = Some(true);
+                    } else if FLASH_LUMINANCE_DELTA <= high - luminance && luminance < FLASH_DARK_LIMIT {
+                        rising = Some(false);
+                    } else {
+                        low = low.min(luminance);
+                        high = high.max(luminance);
+                        continue;
+                    }
+                    transitions.push(timestamp);
+                    low = luminance;
+                    high = luminance;
+                },
+                Some(true) => {
+                    // `high` is the peak of this rise
+                    if high < luminance {
+                        high = luminance;
+                    } else if FLASH_LUMINANCE_DELTA <= high - luminance && luminance < FLASH_DARK_LIMIT {
+                        transitions.push(timestamp);
+                        rising = Some(false);
+                        low = luminance;
+                    }
+                },
+                Some(false) => {
+                    // `low` is the bottom of this fall
+                    if luminance < low {
+                        low = luminance;
+                    } else if FLASH_LUMINANCE_DELTA <= luminance - low && low < FLASH_DARK_LIMIT {
+                        transitions.push(timestamp);
+                        rising = Some(true);
+                        high = luminance;
+                    }
+                },
+            }
+        }
+
+        transitions
+    }
+
+    /// Count flashes and their frequency.
+    pub fn report(&self) -> FlickerReport {
+        let transitions = self.transitions();
+
+        let mut max_transitions = 0;
+        let mut begin = 0;
+        for end in 0..transitions.len() {
+            while Duration::from_secs(1) <= transitions[end] - transitions[begin] {
+                begin += 1;
+            }
+            max_transitions = max_transitions.max(end - begin + 1);
+        }
+
+        let flash_count = transitions.len() / 2;
+        let duration = match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) => (last.0 - first.0).as_secs_f64(),
+            _ => 0.0,
+        };
+        let max_flashes_per_second = max_transitions / 2;
+
+        FlickerReport{
+            flash_count,
+            max_flashes_per_second,
+            frequency_hz: if 0.0 < duration { flash_count as f64 / duration } else { 0.0 },
+            exceeds_three_flash_limit: 3 < max_flashes_per_second,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray frame of `value`.
+    fn frame(value: u8) -> RawFrameData {
+        RawFrameData{
+            width: 2,
+            height: 2,
+            data: [value, value, value, 255].repeat(4),
+            ..Default::default()
+        }
+    }
+
+    /// Samples alternating between black and white, `period` apart, from black.
+    fn alternating(period: Duration, count: u32) -> LuminanceTracker {
+        let mut tracker = LuminanceTracker::new(None);
+        for i in 0..count {
+            tracker.push(&frame(if i % 2 == 0 { 0 } else { 255 }), period * i);
+        }
+
+        tracker
+    }
+
+    #[test]
+    fn luminance_of_frames() {
+        assert_eq!(average_luminance(&frame(0), None), 0.0);
+        assert!((average_luminance(&frame(255), None) - 1.0).abs() < 1e-9);
+        // sRGB mid gray is about a fifth of the light
+        assert!((average_luminance(&frame(128), None) - 0.2158).abs() < 1e-3);
+    }
+
+    #[test]
+    fn region_is_clipped() {
+        let mut half = frame(0);
+        half.data[4..8].copy_from_slice(&[255; 4]);
+        half.data[12..16].copy_from_slice(&[255; 4]);
+
+        assert!((average_luminance(&half, None) - 0.5).abs() < 1e-9);
+        assert!((average_luminance(&half, Some(Rect::new(1, 0, 5, 5))) - 1.0).abs() < 1e-9);
+        assert_eq!(average_luminance(&half, Some(Rect::new(4, 4, 2, 2))), 0.0);
+    }
+
+    #[test]
+    fn strided_frame() {
+        let mut strided = RawFrameData{ row_pitch: 16, data: vec![0; 32], ..frame(0) };
+        strided.data[8..16].copy_from_slice(&[255; 8]);
+
+        // the padding is not measured
+        assert_eq!(average_luminance(&strided, None), 0.0);
+    }
+
+    #[test]
+    fn fast_flicker() {
+        // 10 transitions in a second
+        let report = alternating(Duration::from_millis(100), 11).report();
+
+        assert_eq!(report.flash_count, 5);
+        assert_eq!(report.max_flashes_per_second, 5);
+        assert!((report.frequency_hz - 5.0).abs() < 1e-9);
+        assert!(report.exceeds_three_flash_limit);
+    }
+
+    #[test]
+    fn slow_flicker() {
+        let report = alternating(Duration::from_secs(1), 5).report();
+
+        assert_eq!(report.flash_count, 2);
+        assert_eq!(report.max_flashes_per_second, 0);
+        assert!(!report.exceeds_three_flash_limit);
+    }
+
+    #[test]
+    fn small_changes() {
+        let mut tracker = LuminanceTracker::new(None);
+        for i in 0..20 {
+            tracker.push(&frame(if i % 2 == 0 { 100 } else { 110 }), Duration::from_millis(50) * i);
+        }
+
+        assert!(tracker.transitions().is_empty());
+        assert_eq!(tracker.report(), FlickerReport::default());
+    }
+
+    #[test]
+    fn bright_changes() {
+        let mut tracker = LuminanceTracker::new(None);
+        for i in 0..20 {
+            tracker.push(&frame(if i % 2 == 0 { 240 } else { 255 }), Duration::from_millis(50) * i);
+        }
+
+        // the darker side is above the limit
+        assert!(tracker.transitions().is_empty());
+    }
+}
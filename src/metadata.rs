@@ -0,0 +1,366 @@
+//! Metadata embedded in saved screenshots.
+//!
+//! PNG files get `tEXt` (or `iTXt` for non Latin-1 text) chunks, and JPEG files get an EXIF segment,
+//! so that screenshots attached to bug reports still tell where and when they were taken.
+
+use std::path::Path;
+
+use image::{
+    DynamicImage,
+    ImageOutputFormat,
+};
+
+use crate::capture::{
+    CaptureError,
+    ImgFrameData,
+};
+use crate::overlay::local_time;
+
+
+/// Fields written by [ImgFrameData::save_with_metadata].
+///
+/// Required features: *`"img"`*
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImageMetadata {
+    /// local time like `2022-01-23 12:34:56.789`. PNG `Creation Time`, EXIF `DateTime`.
+    pub capture_time: Option<String>,
+    /// PNG `Title`.
+    pub window_title: Option<String>,
+    /// PNG `Source`.
+    pub monitor_name: Option<String>,
+    /// PNG `Software`, EXIF `Software`.
+    pub software: Option<String>,
+    /// other key and value pairs. written as they are to PNG, into the EXIF `ImageDescription` for JPEG.
+    pub extra: Vec<(String, String)>,
+}
+impl ImageMetadata {
+    /// The current local time and this crate as the software.
+    pub fn now() -> Self {
+        Self{
+            capture_time: Some(local_time()),
+            software: Some(concat!("dxcapture ", env!("CARGO_PKG_VERSION")).to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn window_title(mut self, title: &str) -> Self {
+        self.window_title = Some(title.to_string());
+        self
+    }
+
+    pub fn monitor_name(mut self, name: &str) -> Self {
+        self.monitor_name = Some(name.to_string());
+        self
+    }
+
+    /// Name and version of the application taking the screenshot, instead of this crate.
+    pub fn software(mut self, software: &str) -> Self {
+        self.software = Some(software.to_string());
+        self
+    }
+
+    pub fn field(mut self, key: &str, value: &str) -> Self {
+        self.extra.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Pairs of PNG keywords and text.
+    fn text_fields(&self) -> Vec<(&str, &str)> {
+        let mut fields = Vec::new();
+        let named = [
+            ("Creation Time", &self.capture_time),
+            ("Title", &self.window_title),
+            ("Source", &self.monitor_name),
+            ("Software", &self.software),
+        ];
+        for (key, value) in named.iter() {
+            if let Some(value) = value {
+                fields.push((*key, value.as_str()));
+            }
+        }
+        for (key, value) in &self.extra {
+            fields.push((key.as_str(), value.as_str()));
+        }
+
+        fields
+    }
+
+    /// `ImageDescription` of EXIF, as `key: value` lines.
+    fn description(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(title) = &self.window_title {
+            lines.push(format!("Window: {}", title));
+        }
+        if let Some(monitor) = &self.monitor_name {
+            lines.push(format!("Monitor: {}", monitor));
+        }
+        for (key, value) in &self.extra {
+            lines.push(format!("{}: {}", key, value));
+        }
+
+        lines.join("\n")
+    }
+}
+
+
+impl ImgFrameData {
+    /// Save the image with `metadata` embedded. the format is chosen by the extension.
+    ///
+    /// `.png` and `.jpg`/`.jpeg` carry the metadata. other formats are saved without it.
+    ///
+    /// Required features: *`"img"`*
+    /// # Examples
+    /// ```
+    /// let image = dxcapture::screenshot_window_img("Notepad").expect("Failed to capture");
+    ///
+    /// let metadata = dxcapture::ImageMetadata::now()
+    ///     .window_title("Untitled - Notepad")
+    ///     .software("my-app 1.2.0")
+    ///     .field("Build", "nightly");
+    /// image.save_with_metadata("bug.png", &metadata).expect("Failed to save");
+    /// ```
+    pub fn save_with_metadata<P: AsRef<Path>>(&self, path: P, metadata: &ImageMetadata) -> anyhow::Result<(), CaptureError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or("").to_ascii_lowercase();
+
+        let image = DynamicImage::ImageRgba8(self.data.clone());
+        let mut data = Vec::new();
+        match extension.as_str() {
+            "png" => {
+                image.write_to(&mut data, ImageOutputFormat::Png).map_err(|e| CaptureError::IoError(e.to_string()))?;
+                data = insert_png_text(data, metadata)?;
+            },
+            "jpg" | "jpeg" => {
+                let image = DynamicImage::ImageRgb8(image.to_rgb8());
+                image.write_to(&mut data, ImageOutputFormat::Jpeg(90)).map_err(|e| CaptureError::IoError(e.to_string()))?;
+                data = insert_jpeg_exif(data, metadata)?;
+            },
+            _ => return self.data.save(path).map_err(|e| CaptureError::IoError(e.to_string())),
+        }
+
+        std::fs::write(path, data).map_err(|e| CaptureError::IoError(e.to_string()))
+    }
+}
+
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn png_chunk(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(body.len() + 12);
+    chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(body);
+    chunk.extend_from_slice(&crc32(&chunk[4..]).to_be_bytes());
+
+    chunk
+}
+
+/// `tEXt` chunk if `text` fits in Latin-1, `iTXt` (UTF-8) otherwise.
+fn png_text_chunk(key: &str, text: &str) -> Vec<u8> {
+    // keywords are 1 to 79 Latin-1 characters
+    let key: Vec<u8> = key.chars().filter(|c| (' '..='~').contains(c)).take(79).map(|c| c as u8).collect();
+    let key = if key.is_empty() { b"Comment".to_vec() } else { key };
+
+    let mut body = key;
+    body.push(0);
+    if text.chars().all(|c| (c as u32) < 0x100 && c != '\0') {
+        body.extend(text.chars().map(|c| c as u8));
+        png_chunk(b"tEXt", &body)
+    } else {
+        // not compressed, no language tag nor translated keyword
+        body.extend_from_slice(&[0, 0, 0, 0]);
+        body.extend_from_slice(text.replace('\0', "").as_bytes());
+        png_chunk(b"iTXt", &body)
+    }
+}
+
+fn insert_png_text(png: Vec<u8>, metadata: &ImageMetadata) -> anyhow::Result<Vec<u8>, CaptureError> {
+    // signature (8) + IHDR (length 4, type 4, body 13, crc 4)
+    const IHDR_END: usize = 33;
+    if png.len() < IHDR_END || &png[12..16] != b"IHDR" {
+        return Err(CaptureError::IoError("encoded PNG has no IHDR.".to_string()));
+    }
+
+    let mut data = Vec::with_capacity(png.len() + 256);
+    data.extend_from_slice(&png[..IHDR_END]);
+    for (key, text) in metadata.text_fields() {
+        data.extend(png_text_chunk(key, text));
+    }
+    data.extend_from_slice(&png[IHDR_END..]);
+
+    Ok(data)
+}
+
+fn insert_jpeg_exif(jpeg: Vec<u8>, metadata: &ImageMetadata) -> anyhow::Result<Vec<u8>, CaptureError> {
+    if jpeg.len() < 4 || jpeg[..2] != [0xFF, 0xD8] {
+        return Err(CaptureError::IoError("encoded JPEG has no SOI.".to_string()));
+    }
+
+    // the EXIF segment goes after the JFIF APP0 segment, if any
+    let mut position = 2;
+    if jpeg[2..4] == [0xFF, 0xE0] && 6 <= jpeg.len() {
+        position = 4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize;
+    }
+    if jpeg.len() < position {
+        return Err(CaptureError::IoError("encoded JPEG is broken.".to_string()));
+    }
+
+    let exif = exif_segment(metadata);
+    let mut data = Vec::with_capacity(jpeg.len() + exif.len());
+    data.extend_from_slice(&jpeg[..position]);
+    data.extend(exif);
+    data.extend_from_slice(&jpeg[position..]);
+
+    Ok(data)
+}
+
+/// APP1 segment of a little endian TIFF with a single IFD of ASCII entries.
+fn exif_segment(metadata: &ImageMetadata) -> Vec<u8> {
+    // EXIF DateTime is `YYYY:MM:DD HH:MM:SS`
+    let date_time = metadata.capture_time.as_ref().map(|time| {
+        time.chars().take(19).enumerate().map(|(i, c)| if i < 10 && c == '-' { ':' } else { c }).collect::<String>()
+    });
+    let description = Some(metadata.description()).filter(|description| !description.is_empty());
+
+    // sorted by tag
+    let entries: Vec<(u16, String)> = [
+        (0x010E, description),
+        (0x0131, metadata.software.clone()),
+        (0x0132, date_time),
+    ].iter().filter_map(|(tag, value)| value.clone().map(|value| (*tag, value))).collect();
+
+    // header (8) + count (2) + entries (12 each) + next IFD (4)
+    let mut tiff = b"II*\0".to_vec();
+    tiff.extend_from_slice(&8u32.to_le_bytes());
+    tiff.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    let mut values = Vec::new();
+    let mut value_offset = 8 + 2 + entries.len() * 12 + 4;
+    for (tag, value) in &entries {
+        // the whole segment must fit in 64 KiB
+        let mut value = value.replace('\0', "");
+        while 16_000 < value.len() {
+            value.pop();
+        }
+        let mut bytes = value.into_bytes();
+        bytes.push(0);
+
+        tiff.extend_from_slice(&tag.to_le_bytes());
+        // ASCII
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            tiff.extend_from_slice(&bytes);
+        } else {
+            tiff.extend_from_slice(&(value_offset as u32).to_le_bytes());
+            value_offset += bytes.len();
+            values.extend(bytes);
+        }
+    }
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+    tiff.extend(values);
+
+    let mut segment = vec![0xFF, 0xE1];
+    segment.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+    segment.extend_from_slice(b"Exif\0\0");
+    segment.extend(tiff);
+
+    segment
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(format: ImageOutputFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgb8(2, 2).write_to(&mut data, format).unwrap();
+
+        data
+    }
+
+    #[test]
+    fn crc_of_iend() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+
+    #[test]
+    fn latin1_text() {
+        let chunk = png_text_chunk("Title", "Café");
+
+        assert_eq!(&chunk[..8], &[0, 0, 0, 10, b't', b'E', b'X', b't']);
+        assert_eq!(&chunk[8..18], b"Title\0Caf\xE9");
+        assert_eq!(&chunk[18..], &crc32(&chunk[4..18]).to_be_bytes());
+    }
+
+    #[test]
+    fn utf8_text() {
+        let chunk = png_text_chunk("", "✓");
+
+        // no keyword is a comment
+        assert_eq!(&chunk[4..8], b"iTXt");
+        assert_eq!(&chunk[8..chunk.len() - 4], b"Comment\0\0\0\0\0\xE2\x9C\x93");
+    }
+
+    #[test]
+    fn png_fields() {
+        let metadata = ImageMetadata::default().window_title("Notepad").field("Build", "nightly");
+        let png = insert_png_text(encoded(ImageOutputFormat::Png), &metadata).unwrap();
+
+        // right after IHDR, in order
+        let title = png_text_chunk("Title", "Notepad");
+        assert_eq!(&png[33..33 + title.len()], &title[..]);
+        let build = png_text_chunk("Build", "nightly");
+        assert_eq!(&png[33 + title.len()..][..build.len()], &build[..]);
+        assert_eq!(image::load_from_memory(&png).unwrap().to_rgb8().dimensions(), (2, 2));
+
+        assert!(insert_png_text(b"not a png".to_vec(), &metadata).is_err());
+    }
+
+    #[test]
+    fn exif_fields() {
+        let metadata = ImageMetadata{
+            capture_time: Some("2022-01-23 12:34:56.789".to_string()),
+            software: Some("app".to_string()),
+            ..Default::default()
+        }.monitor_name("DISPLAY1");
+        let segment = exif_segment(&metadata);
+
+        assert_eq!(&segment[..2], &[0xFF, 0xE1]);
+        assert_eq!(u16::from_be_bytes([segment[2], segment[3]]) as usize, segment.len() - 2);
+        assert_eq!(&segment[4..14], b"Exif\0\0II*\0");
+        // 3 entries
+        assert_eq!(&segment[18..20], &[3, 0]);
+        let contains = |bytes: &[u8]| segment.windows(bytes.len()).any(|window| window == bytes);
+        assert!(contains(b"Monitor: DISPLAY1\0"));
+        assert!(contains(b"2022:01:23 12:34:56\0"));
+        // 4 bytes fit in the entry
+        assert!(contains(&[0x31, 0x01, 2, 0, 4, 0, 0, 0, b'a', b'p', b'p', 0]));
+    }
+
+    #[test]
+    fn jpeg_exif_after_app0() {
+        let jpeg = encoded(ImageOutputFormat::Jpeg(90));
+        let metadata = ImageMetadata::default().software("app");
+        let with_exif = insert_jpeg_exif(jpeg.clone(), &metadata).unwrap();
+
+        let position = if jpeg[2..4] == [0xFF, 0xE0] { 4 + u16::from_be_bytes([jpeg[4], jpeg[5]]) as usize } else { 2 };
+        assert_eq!(&with_exif[..position], &jpeg[..position]);
+        assert_eq!(&with_exif[position..position + 2], &[0xFF, 0xE1]);
+        assert_eq!(image::load_from_memory(&with_exif).unwrap().to_rgb8().dimensions(), (2, 2));
+
+        assert!(insert_jpeg_exif(vec![0, 1, 2, 3], &metadata).is_err());
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        Mutex,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::source::FrameSource;
+
+
+#[derive(Default)]
+struct MockState {
+    queue: VecDeque<anyhow::Result<RawFrameData, CaptureError>>,
+    current: Option<RawFrameData>,
+    closed: bool,
+}
+
+
+/// [FrameSource] fed by hand, for testing the code which consumes frames.
+///
+/// no Windows capture machinery is involved. frames pushed through [FrameInjector] are
+/// returned in order, and the last one keeps being returned like a live [Capture](crate::Capture).
+///
+/// Required features: *`"mock"`*
+/// # Examples
+/// ```
+/// use dxcapture::FrameSource;
+///
+/// let source = dxcapture::MockSource::new();
+/// let injector = source.injector();
+///
+/// injector.push(dxcapture::RawFrameData{ width: 1, height: 1, data: vec![0, 0, 0, 255], ..Default::default() });
+/// let raw = source.wait_raw_frame().unwrap();
+/// assert_eq!(raw.width, 1);
+/// ```
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mock")))]
+#[derive(Clone, Default)]
+pub struct MockSource {
+    state: Arc<Mutex<MockState>>,
+}
+impl MockSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a handle for pushing frames. it can be sent to other threads.
+    pub fn injector(&self) -> FrameInjector {
+        FrameInjector{ state: self.state.clone() }
+    }
+}
+impl FrameSource for MockSource {
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(result) = state.queue.pop_front() {
+            let frame = result?;
+            state.current = Some(frame.clone());
+            return Ok(frame);
+        }
+        if state.closed {
+            return Err(CaptureError::NotActive);
+        }
+
+        state.current.clone().ok_or(CaptureError::NoTexture)
+    }
+}
+
+
+/// Handle to push synthetic frames into a [MockSource].
+///
+/// Required features: *`"mock"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mock")))]
+#[derive(Clone)]
+pub struct FrameInjector {
+    state: Arc<Mutex<MockState>>,
+}
+impl FrameInjector {
+    /// Queue a frame.
+    pub fn push(&self, frame: RawFrameData) {
+        self.state.lock().unwrap().queue.push_back(Ok(frame));
+    }
+
+    /// Queue an error, returned once in place of a frame.
+    pub fn push_error(&self, error: CaptureError) {
+        self.state.lock().unwrap().queue.push_back(Err(error));
+    }
+
+    /// Forget the last frame, so [NoTexture](CaptureError::NoTexture) is returned until a next push.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.queue.clear();
+        state.current = None;
+    }
+
+    /// Behave like a closed capture once the queued frames are consumed.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+    }
+}
@@ -0,0 +1,397 @@
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Mutex,
+    },
+    thread::ThreadId,
+    time::Duration,
+};
+
+use windows::Win32::{
+    Media::MediaFoundation::{
+        IMFMediaType,
+        IMFSinkWriter,
+        MFCreateMediaType,
+        MFCreateMemoryBuffer,
+        MFCreateSample,
+        MFCreateSinkWriterFromURL,
+        MFShutdown,
+        MFStartup,
+        MFMediaType_Video,
+        MFVideoFormat_H264,
+        MFVideoFormat_HEVC,
+        MFVideoFormat_RGB32,
+        MFVideoInterlace_Progressive,
+        MFSTARTUP_FULL,
+        MF_API_VERSION,
+        MF_MT_AVG_BITRATE,
+        MF_MT_DEFAULT_STRIDE,
+        MF_MT_FRAME_RATE,
+        MF_MT_FRAME_SIZE,
+        MF_MT_INTERLACE_MODE,
+        MF_MT_MAJOR_TYPE,
+        MF_MT_PIXEL_ASPECT_RATIO,
+        MF_MT_SUBTYPE,
+        MF_SDK_VERSION,
+    },
+    System::Com::{
+        CoInitializeEx,
+        CoUninitialize,
+        COINIT_MULTITHREADED,
+    },
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    FrameSubscription,
+    RawFrameData,
+};
+use crate::recorder::{
+    EncodedPacket,
+    Encoder,
+    Muxer,
+    RawEncoder,
+    Recorder,
+    RecorderOptions,
+    VideoCodec,
+};
+use crate::source::FrameSource;
+
+
+const MF_VERSION: u32 = (MF_SDK_VERSION << 16) | MF_API_VERSION;
+
+/// 100ns units of Media Foundation.
+const REFTIMES_PER_SEC: i64 = 10_000_000;
+
+fn directx_error(e: windows::core::Error) -> CaptureError {
+    CaptureError::DirectxError(e)
+}
+
+/// Two `u32` packed in a `UINT64` attribute, as `MFSetAttributeSize` and `MFSetAttributeRatio`.
+fn pack(high: u32, low: u32) -> u64 {
+    ((high as u64) << 32) | low as u64
+}
+
+
+/// Options of [Mp4Muxer].
+///
+/// Required features: *`"mp4"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+#[derive(Clone, Copy, Debug)]
+pub struct Mp4Options {
+    /// frame rate written into the file. [Recorder::new] takes it, the bitrate and the codec from [RecorderOptions].
+    pub fps: u32,
+    /// bits per second.
+    pub bitrate: u32,
+    pub codec: VideoCodec,
+}
+impl Default for Mp4Options {
+    fn default() -> Self {
+        Self{
+            fps: 30,
+            bitrate: 8_000_000,
+            codec: VideoCodec::default(),
+        }
+    }
+}
+
+
+/// [Muxer] encoding [RawEncoder] packets into an `.mp4` with the Media Foundation SinkWriter.
+///
+/// the encoder (hardware one if there is) is set up by the first packet, and the size of it is kept through
+/// the file, rounded down to even. later frames of other sizes are cropped or padded with black at the bottom right.
+///
+/// COM and Media Foundation are started by the first packet on the thread writing it, which is the only thread
+/// the muxer can write on afterwards, and shut down when the muxer is dropped there. a [Recorder] does all of it
+/// on its recording thread.
+///
+/// Required features: *`"mp4"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+pub struct Mp4Muxer {
+    path: PathBuf,
+    options: Mp4Options,
+    writer: Option<IMFSinkWriter>,
+    width: i32,
+    height: i32,
+    /// the thread writing, once opened.
+    thread: Option<ThreadId>,
+    com_initialized: bool,
+    mf_started: bool,
+}
+impl Mp4Muxer {
+    pub fn create<P: AsRef<Path>>(path: P, options: Mp4Options) -> anyhow::Result<Self, CaptureError> {
+        if options.fps == 0 {
+            return Err(CaptureError::InvalidArgument("fps must be greater than 0.".to_string()));
+        }
+
+        Ok(Self{
+            path: path.as_ref().to_path_buf(),
+            options,
+            writer: None,
+            width: 0,
+            height: 0,
+            thread: None,
+            com_initialized: false,
+            mf_started: false,
+        })
+    }
+
+    fn video_type(&self, subtype: &windows::core::GUID) -> windows::core::Result<IMFMediaType> {
+        unsafe {
+            let media_type = MFCreateMediaType()?;
+            media_type.SetGUID(&MF_MT_MAJOR_TYPE, &MFMediaType_Video)?;
+            media_type.SetGUID(&MF_MT_SUBTYPE, subtype)?;
+            media_type.SetUINT32(&MF_MT_INTERLACE_MODE, MFVideoInterlace_Progressive as u32)?;
+            media_type.SetUINT64(&MF_MT_FRAME_SIZE, pack(self.width as u32, self.height as u32))?;
+            media_type.SetUINT64(&MF_MT_FRAME_RATE, pack(self.options.fps, 1))?;
+            media_type.SetUINT64(&MF_MT_PIXEL_ASPECT_RATIO, pack(1, 1))?;
+
+            Ok(media_type)
+        }
+    }
+
+    /// Start COM and Media Foundation on this thread, and open the file and the encoder for the frames of `width` x `height`.
+    fn open(&mut self, width: i32, height: i32) -> windows::core::Result<IMFSinkWriter> {
+        self.width = width & !1;
+        self.height = height & !1;
+        self.thread = Some(std::thread::current().id());
+
+        unsafe {
+            // already initialized in another mode is fine, but not uninitialized by us
+            self.com_initialized = CoInitializeEx(std::ptr::null(), COINIT_MULTITHREADED).is_ok();
+            MFStartup(MF_VERSION, MFSTARTUP_FULL)?;
+            self.mf_started = true;
+
+            let writer = MFCreateSinkWriterFromURL(self.path.to_string_lossy().as_ref(), None, None)?;
+
+            let subtype = match self.options.codec {
+                VideoCodec::H264 => MFVideoFormat_H264,
+                VideoCodec::Hevc => MFVideoFormat_HEVC,
+            };
+            let output_type = self.video_type(&subtype)?;
+            output_type.SetUINT32(&MF_MT_AVG_BITRATE, self.options.bitrate)?;
+            let stream = writer.AddStream(&output_type)?;
+
+            let input_type = self.video_type(&MFVideoFormat_RGB32)?;
+            // positive stride is top-down
+            input_type.SetUINT32(&MF_MT_DEFAULT_STRIDE, (self.width * 4) as u32)?;
+            writer.SetInputMediaType(stream, &input_type, None)?;
+
+            writer.BeginWriting()?;
+
+            Ok(writer)
+        }
+    }
+
+    /// Copy `packet` into a buffer of the size of the file.
+    fn fit(&self, packet: &EncodedPacket) -> Vec<u8> {
+        let stride = self.width as usize * 4;
+        let mut data = vec![0; stride * self.height as usize];
+
+        let source_stride = packet.width as usize * 4;
+        let row_len = stride.min(source_stride);
+        for (y, row) in data.chunks_exact_mut(stride).take(packet.height as usize).enumerate() {
+            let start = y * source_stride;
+            row[..row_len].copy_from_slice(&packet.data[start..start + row_len]);
+        }
+
+        data
+    }
+
+    fn write(&self, writer: &IMFSinkWriter, data: &[u8], timestamp: Duration) -> windows::core::Result<()> {
+        unsafe {
+            let buffer = MFCreateMemoryBuffer(data.len() as u32)?;
+            let mut bytes = std::ptr::null_mut();
+            buffer.Lock(&mut bytes, std::ptr::null_mut(), std::ptr::null_mut())?;
+            std::ptr::copy_nonoverlapping(data.as_ptr(), bytes, data.len());
+            buffer.Unlock()?;
+            buffer.SetCurrentLength(data.len() as u32)?;
+
+            let sample = MFCreateSample()?;
+            sample.AddBuffer(&buffer)?;
+            sample.SetSampleTime((timestamp.as_nanos() / 100) as i64)?;
+            sample.SetSampleDuration(REFTIMES_PER_SEC / self.options.fps as i64)?;
+
+            // the only stream
+            writer.WriteSample(0, &sample)
+        }
+    }
+}
+impl Muxer for Mp4Muxer {
+    fn write_packet(&mut self, packet: &EncodedPacket) -> anyhow::Result<(), CaptureError> {
+        if packet.width < 2 || packet.height < 2 || packet.data.len() < (packet.width * packet.height * 4) as usize {
+            return Err(CaptureError::InvalidArgument("packet is not a BGRA frame.".to_string()));
+        }
+
+        if self.thread.is_some_and(|thread| thread != std::thread::current().id()) {
+            return Err(CaptureError::InvalidArgument("Mp4Muxer is written on another thread than opened.".to_string()));
+        }
+
+        let writer = match &self.writer {
+            Some(writer) => writer.clone(),
+            None => {
+                let writer = self.open(packet.width, packet.height).map_err(directx_error)?;
+                self.writer = Some(writer.clone());
+                writer
+            },
+        };
+
+        if packet.width == self.width && packet.height == self.height {
+            self.write(&writer, &packet.data, packet.timestamp)
+        } else {
+            self.write(&writer, &self.fit(packet), packet.timestamp)
+        }.map_err(directx_error)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError> {
+        match self.writer.take() {
+            Some(writer) => unsafe { writer.Finalize().map_err(directx_error) },
+            // nothing recorded
+            None => Ok(()),
+        }
+    }
+}
+// nothing is made until the first packet, so it's sent before it's opened and then stays on the thread writing
+unsafe impl Send for Mp4Muxer {}
+impl Drop for Mp4Muxer {
+    fn drop(&mut self) {
+        // the writer has to be released before the shutdown
+        self.writer = None;
+        unsafe {
+            if self.mf_started {
+                let _ = MFShutdown();
+            }
+            if self.com_initialized {
+                CoUninitialize();
+            }
+        }
+    }
+}
+impl std::fmt::Debug for Mp4Muxer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Mp4Muxer")
+            .field("path", &self.path)
+            .field("options", &self.options)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+
+/// [FrameSource] of the frames arriving at a [Capture], on any thread. the latest one is taken as
+/// [get_raw_frame](Capture::get_raw_frame) of the capture does.
+struct CaptureFeed {
+    latest: Arc<Mutex<Option<RawFrameData>>>,
+    subscription: FrameSubscription,
+}
+impl CaptureFeed {
+    fn new(capture: &Capture) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let subscription = {
+            let latest = latest.clone();
+            capture.on_frame(move |raw| *latest.lock().unwrap() = Some(raw))
+        };
+
+        Self{
+            latest,
+            subscription,
+        }
+    }
+}
+impl FrameSource for CaptureFeed {
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        if !self.subscription.is_active() {
+            return Err(CaptureError::NotActive);
+        }
+
+        self.latest.lock().unwrap().clone().ok_or(CaptureError::NoTexture)
+    }
+}
+
+
+impl Recorder {
+    /// Start recording the frames of `capture` into an `.mp4` at `path`, at the fps, bitrate and codec of `options`.
+    ///
+    /// the frames are handed from the frame arrived handler to the recording thread, where they are encoded by
+    /// [Mp4Muxer] with the Media Foundation SinkWriter. the recording stops with [NotActive](CaptureError::NotActive)
+    /// when the capture is dropped.
+    ///
+    /// Required features: *`"mp4"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_window("Notepad".to_string()).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// let recorder = dxcapture::Recorder::new(
+    ///     &capture,
+    ///     "window.mp4",
+    ///     dxcapture::RecorderOptions{ fps: 30, bitrate: 4_000_000, codec: dxcapture::VideoCodec::H264, ..Default::default() },
+    /// ).unwrap();
+    ///
+    /// std::thread::sleep(std::time::Duration::from_secs(10));
+    /// recorder.stop().expect("Failed to record");
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+    pub fn new<P: AsRef<Path>>(capture: &Capture, path: P, options: RecorderOptions) -> anyhow::Result<Self, CaptureError> {
+        let source = CaptureFeed::new(capture);
+
+        Self::start_mp4(move || Ok(source), path, options)
+    }
+
+    /// [new](Self::new) with the source made on the recording thread by `source_factory`, as [start](Self::start).
+    ///
+    /// Required features: *`"mp4"`*
+    /// # Examples
+    /// ```
+    /// let recorder = dxcapture::Recorder::start_mp4(
+    ///     || {
+    ///         let device = dxcapture::Device::new_from_window("Notepad".to_string())?;
+    ///         Ok(dxcapture::Capture::new(&device)?)
+    ///     },
+    ///     "window.mp4",
+    ///     dxcapture::RecorderOptions{ fps: 30, ..Default::default() },
+    /// ).unwrap();
+    ///
+    /// std::thread::sleep(std::time::Duration::from_secs(10));
+    /// recorder.stop().expect("Failed to record");
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+    pub fn start_mp4<S, F, P>(source_factory: F, path: P, options: RecorderOptions) -> anyhow::Result<Self, CaptureError>
+    where
+        S: FrameSource,
+        F: FnOnce() -> anyhow::Result<S> + Send + 'static,
+        P: AsRef<Path>,
+    {
+        let muxer = Mp4Muxer::create(path, Mp4Options{
+            fps: options.fps,
+            bitrate: options.bitrate,
+            codec: options.codec,
+        })?;
+
+        Self::start(source_factory, RawEncoder, muxer, options)
+    }
+}
+
+/// Encode a single frame sequence into an `.mp4` right away, such as the frames of a [ReplayBuffer](crate::ReplayBuffer).
+///
+/// Required features: *`"mp4"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "mp4")))]
+pub fn write_mp4<P: AsRef<Path>>(path: P, frames: &[RawFrameData], options: Mp4Options) -> anyhow::Result<(), CaptureError> {
+    let mut muxer = Mp4Muxer::create(path, options)?;
+    let interval = Duration::from_secs(1) / options.fps;
+
+    for (index, frame) in frames.iter().enumerate() {
+        // packs the strided frames
+        for packet in RawEncoder.encode(frame, interval * index as u32)? {
+            muxer.write_packet(&packet)?;
+        }
+    }
+
+    muxer.finish()
+}
@@ -0,0 +1,606 @@
+use std::sync::Mutex;
+
+use winapi::um::{
+    minwinbase::SYSTEMTIME,
+    sysinfoapi::GetLocalTime,
+};
+
+use crate::capture::{
+    CaptureError,
+    FrameInfo,
+    RawFrameData,
+    Rect,
+};
+use crate::source::FrameSource;
+
+
+/// Stage modifying each frame delivered by a [FilteredSource].
+pub trait FrameFilter: Send {
+    /// Modify `frame` in place. `index` counts the frames delivered from 0.
+    ///
+    /// `frame` may have the padded rows of [get_raw_frame_strided](crate::Capture::get_raw_frame_strided),
+    /// so index it by [stride](RawFrameData::stride), or [pack_rows](RawFrameData::pack_rows) it first.
+    fn apply(&mut self, frame: &mut RawFrameData, index: u64);
+}
+
+
+/// [FrameSource] running [FrameFilter]s over each frame of another source.
+///
+/// wrap the source handed to a [Recorder](crate::Recorder) to burn the overlays into the recording.
+/// # Examples
+/// ```
+/// use dxcapture::{ Corner, FilteredSource, FrameSource, TimestampOverlay };
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// let source = FilteredSource::new(capture)
+///     .with_filter(TimestampOverlay::new().frame_counter(true).corner(Corner::BottomRight));
+///
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+pub struct FilteredSource<S: FrameSource> {
+    source: S,
+    filters: Mutex<Vec<Box<dyn FrameFilter>>>,
+    frame_count: Mutex<u64>,
+}
+impl<S: FrameSource> FilteredSource<S> {
+    pub fn new(source: S) -> Self {
+        Self{
+            source,
+            filters: Mutex::new(Vec::new()),
+            frame_count: Mutex::new(0),
+        }
+    }
+
+    /// Append a filter. filters run in the order added.
+    pub fn with_filter<F: FrameFilter + 'static>(self, filter: F) -> Self {
+        self.add_filter(filter);
+        self
+    }
+
+    pub fn add_filter<F: FrameFilter + 'static>(&self, filter: F) {
+        self.filters.lock().unwrap().push(Box::new(filter));
+    }
+
+    pub fn clear_filters(&self) {
+        self.filters.lock().unwrap().clear();
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+impl<S: FrameSource> FrameSource for FilteredSource<S> {
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let mut frame = self.source.get_raw_frame()?;
+
+        let index = {
+            let mut frame_count = self.frame_count.lock().unwrap();
+            *frame_count += 1;
+            *frame_count - 1
+        };
+        for filter in self.filters.lock().unwrap().iter_mut() {
+            filter.apply(&mut frame, index);
+        }
+
+        Ok(frame)
+    }
+}
+
+
+/// [FrameFilter] cutting out a region of each frame.
+///
+/// the region is clipped to the frame, so the frames get smaller when it sticks out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CropFilter {
+    rect: Rect,
+}
+impl CropFilter {
+    pub fn new(rect: Rect) -> Self {
+        Self{
+            rect,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+}
+impl FrameFilter for CropFilter {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        frame.pack_rows();
+        let rect = self.rect.intersect(&Rect::new(0, 0, frame.width, frame.height));
+        if rect == Rect::new(0, 0, frame.width, frame.height) {
+            return;
+        }
+
+        let mut data = Vec::with_capacity((rect.width.max(0) * rect.height.max(0) * 4) as usize);
+        for y in rect.y..rect.bottom() {
+            let start = ((y * frame.width + rect.x) * 4) as usize;
+            data.extend_from_slice(&frame.data[start..start + (rect.width * 4) as usize]);
+        }
+
+        *frame = RawFrameData{
+            width: rect.width.max(0),
+            height: rect.height.max(0),
+            data,
+            // the later filters map the screen positions through it
+            info: FrameInfo{
+                region: Some(match frame.info.region {
+                    Some(region) => Rect::new(region.x + rect.x, region.y + rect.y, rect.width, rect.height),
+                    None => rect,
+                }),
+                ..frame.info
+            },
+            row_pitch: 0,
+        };
+    }
+}
+
+
+/// Corner of the frame an overlay is placed at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Glyph rows of the built-in 5x7 font, top to bottom. the low 5 bits are the pixels, left to right.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        _ => [0x00; 7],
+    }
+}
+
+pub(crate) const GLYPH_WIDTH: i32 = 5;
+pub(crate) const GLYPH_HEIGHT: i32 = 7;
+
+/// Fill `rect` of a BGRA frame, clipped to the frame. the padding of the rows is left as it is.
+pub(crate) fn fill_rect(frame: &mut RawFrameData, rect: Rect, bgra: [u8; 4]) {
+    let rect = rect.intersect(&Rect::new(0, 0, frame.width, frame.height));
+    for y in rect.y..rect.bottom() {
+        for x in rect.x..rect.right() {
+            let offset = frame.pixel_offset(x as usize, y as usize);
+            if let Some(pixel) = frame.data.get_mut(offset..offset + 4) {
+                pixel.copy_from_slice(&bgra);
+            }
+        }
+    }
+}
+
+/// Draw `text` with the built-in font at `x`, `y`. `scale` pixels per font dot.
+pub(crate) fn draw_text(frame: &mut RawFrameData, text: &str, x: i32, y: i32, scale: i32, bgra: [u8; 4]) {
+    for (i, c) in text.chars().enumerate() {
+        let left = x + i as i32 * (GLYPH_WIDTH + 1) * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (0x10 >> column) != 0 {
+                    fill_rect(frame, Rect::new(left + column * scale, y + row as i32 * scale, scale, scale), bgra);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn local_time() -> String {
+    let mut time: SYSTEMTIME = unsafe { std::mem::zeroed() };
+    unsafe { GetLocalTime(&mut time) };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}",
+        time.wYear, time.wMonth, time.wDay, time.wHour, time.wMinute, time.wSecond, time.wMilliseconds,
+    )
+}
+
+
+/// [FrameFilter] burning the local wall-clock time and/or the frame counter into each frame.
+///
+/// the text is like `2022-01-23 12:34:56.789 #000042`, white on black so it stays readable on any content.
+#[derive(Clone, Debug)]
+pub struct TimestampOverlay {
+    timestamp: bool,
+    frame_counter: bool,
+    corner: Corner,
+    font_size: u32,
+    margin: i32,
+}
+impl TimestampOverlay {
+    /// Timestamp only, at the top left in 14px.
+    pub fn new() -> Self {
+        Self{
+            timestamp: true,
+            frame_counter: false,
+            corner: Corner::TopLeft,
+            font_size: 14,
+            margin: 8,
+        }
+    }
+
+    pub fn timestamp(mut self, enabled: bool) -> Self {
+        self.timestamp = enabled;
+        self
+    }
+
+    pub fn frame_counter(mut self, enabled: bool) -> Self {
+        self.frame_counter = enabled;
+        self
+    }
+
+    pub fn corner(mut self, corner: Corner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    /// Height of the characters in pixels. rounded down to a multiple of 7, at least 7.
+    pub fn font_size(mut self, pixels: u32) -> Self {
+        self.font_size = pixels;
+        self
+    }
+
+    /// Distance from the edges of the frame in pixels.
+    pub fn margin(mut self, pixels: i32) -> Self {
+        self.margin = pixels;
+        self
+    }
+
+    fn text(&self, index: u64) -> String {
+        let mut parts = Vec::new();
+        if self.timestamp {
+            parts.push(local_time());
+        }
+        if self.frame_counter {
+            parts.push(format!("#{:06}", index));
+        }
+
+        parts.join(" ")
+    }
+}
+impl Default for TimestampOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl FrameFilter for TimestampOverlay {
+    fn apply(&mut self, frame: &mut RawFrameData, index: u64) {
+        frame.pack_rows();
+        let text = self.text(index);
+        if text.is_empty() {
+            return;
+        }
+
+        let scale = (self.font_size as i32 / GLYPH_HEIGHT).max(1);
+        let padding = scale;
+        let width = text.chars().count() as i32 * (GLYPH_WIDTH + 1) * scale - scale + padding * 2;
+        let height = GLYPH_HEIGHT * scale + padding * 2;
+
+        let x = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => self.margin,
+            Corner::TopRight | Corner::BottomRight => frame.width - self.margin - width,
+        };
+        let y = match self.corner {
+            Corner::TopLeft | Corner::TopRight => self.margin,
+            Corner::BottomLeft | Corner::BottomRight => frame.height - self.margin - height,
+        };
+
+        fill_rect(frame, Rect::new(x, y, width, height), [0, 0, 0, 255]);
+        draw_text(frame, &text, x + padding, y + padding, scale, [255, 255, 255, 255]);
+    }
+}
+
+
+/// [FrameFilter] blending an RGBA image, such as a logo or a confidentiality stamp, onto each frame.
+///
+/// as a filter, it's blended on the CPU onto the frames of a [FilteredSource](crate::FilteredSource) only.
+/// [Capture::set_watermark](crate::Capture::set_watermark) blends it on the GPU before the frames are copied out,
+/// for all the readers of the capture.
+/// # Examples
+/// ```
+/// use dxcapture::{ Corner, FilteredSource, FrameSource, WatermarkOverlay };
+///
+/// // 2x2 half transparent red
+/// let logo = WatermarkOverlay::new(2, 2, [255, 0, 0, 128].repeat(4)).unwrap()
+///     .scale(32.0)
+///     .opacity(0.5)
+///     .corner(Corner::BottomRight);
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(logo);
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct WatermarkOverlay {
+    width: i32,
+    height: i32,
+    /// RGBA as given.
+    rgba: Vec<u8>,
+    scale: f32,
+    opacity: f32,
+    corner: Corner,
+    margin: i32,
+    position: Option<(i32, i32)>,
+    /// BGRA of the scaled image, made on the first frame.
+    scaled: Option<RawFrameData>,
+}
+impl WatermarkOverlay {
+    /// ## Parameters
+    /// * rgba: `width` * `height` pixels of RGBA, not premultiplied.
+    pub fn new(width: i32, height: i32, rgba: Vec<u8>) -> anyhow::Result<Self, CaptureError> {
+        if width <= 0 || height <= 0 || rgba.len() != (width * height * 4) as usize {
+            return Err(CaptureError::InvalidArgument(format!("rgba must be {}x{} pixels.", width, height)));
+        }
+
+        Ok(Self{
+            width,
+            height,
+            rgba,
+            scale: 1.0,
+            opacity: 1.0,
+            corner: Corner::BottomRight,
+            margin: 8,
+            position: None,
+            scaled: None,
+        })
+    }
+
+    /// Load an image file.
+    ///
+    /// Required features: *`"img"`*
+    #[cfg(feature = "img")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        let image = image::open(path).map_err(|e| CaptureError::IoError(e.to_string()))?.to_rgba8();
+
+        Self::new(image.width() as i32, image.height() as i32, image.into_raw())
+    }
+
+    /// Size multiplier of the image. default is 1.0.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale.max(0.0);
+        self.scaled = None;
+        self
+    }
+
+    /// 0.0 (invisible) ..= 1.0 (as the alpha of the image). default is 1.0.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self.scaled = None;
+        self
+    }
+
+    /// Default is [BottomRight](Corner::BottomRight).
+    pub fn corner(mut self, corner: Corner) -> Self {
+        self.corner = corner;
+        self
+    }
+
+    /// Distance from the edges of the frame in pixels.
+    pub fn margin(mut self, pixels: i32) -> Self {
+        self.margin = pixels;
+        self
+    }
+
+    /// Place the top left of the image at `x`, `y` instead of a corner.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Resize with nearest neighbor and fold the opacity into the alpha.
+    pub(crate) fn scaled(&mut self) -> &RawFrameData {
+        let (width, height, rgba, scale, opacity) = (self.width, self.height, &self.rgba, self.scale, self.opacity);
+
+        self.scaled.get_or_insert_with(|| {
+            let scaled_width = (width as f32 * scale).round() as i32;
+            let scaled_height = (height as f32 * scale).round() as i32;
+
+            let mut data = Vec::with_capacity((scaled_width.max(0) * scaled_height.max(0) * 4) as usize);
+            for y in 0..scaled_height {
+                let source_y = ((y as f32 / scale) as i32).min(height - 1);
+                for x in 0..scaled_width {
+                    let source_x = ((x as f32 / scale) as i32).min(width - 1);
+                    let offset = ((source_y * width + source_x) * 4) as usize;
+                    let pixel = &rgba[offset..offset + 4];
+                    data.extend_from_slice(&[pixel[2], pixel[1], pixel[0], (pixel[3] as f32 * opacity).round() as u8]);
+                }
+            }
+
+            RawFrameData{
+                width: scaled_width,
+                height: scaled_height,
+                data,
+                ..Default::default()
+            }
+        })
+    }
+
+    /// Top left of the scaled image of `image_width` x `image_height` on a frame of `width` x `height`.
+    pub(crate) fn placement(&self, width: i32, height: i32, image_width: i32, image_height: i32) -> (i32, i32) {
+        self.position.unwrap_or_else(|| {
+            let x = match self.corner {
+                Corner::TopLeft | Corner::BottomLeft => self.margin,
+                Corner::TopRight | Corner::BottomRight => width - self.margin - image_width,
+            };
+            let y = match self.corner {
+                Corner::TopLeft | Corner::TopRight => self.margin,
+                Corner::BottomLeft | Corner::BottomRight => height - self.margin - image_height,
+            };
+            (x, y)
+        })
+    }
+}
+impl FrameFilter for WatermarkOverlay {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        frame.pack_rows();
+        let (image_width, image_height) = {
+            let image = self.scaled();
+            (image.width, image.height)
+        };
+        if image_width <= 0 || image_height <= 0 {
+            return;
+        }
+        let (left, top) = self.placement(frame.width, frame.height, image_width, image_height);
+        let image = self.scaled();
+
+        let area = Rect::new(left, top, image.width, image.height).intersect(&Rect::new(0, 0, frame.width, frame.height));
+        for y in area.y..area.bottom() {
+            for x in area.x..area.right() {
+                let source_offset = (((y - top) * image.width + (x - left)) * 4) as usize;
+                let source = &image.data[source_offset..source_offset + 4];
+                let alpha = source[3] as u32;
+                if alpha == 0 {
+                    continue;
+                }
+
+                let offset = ((y * frame.width + x) * 4) as usize;
+                if let Some(pixel) = frame.data.get_mut(offset..offset + 3) {
+                    for channel in 0..3 {
+                        pixel[channel] = ((source[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha) + 127) / 255) as u8;
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(width: i32, height: i32) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: vec![0; (width * height * 4) as usize],
+            ..Default::default()
+        }
+    }
+
+    /// Pixels of `frame` filled with `bgra`, as (x, y).
+    fn filled(frame: &RawFrameData, bgra: [u8; 4]) -> Vec<(i32, i32)> {
+        (0..frame.height).flat_map(|y| (0..frame.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                let offset = frame.pixel_offset(x as usize, y as usize);
+                frame.data[offset..offset + 4] == bgra
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fill_rect_is_clipped() {
+        let mut frame = frame(3, 3);
+        fill_rect(&mut frame, Rect::new(-1, 1, 3, 5), [1, 2, 3, 4]);
+
+        assert_eq!(filled(&frame, [1, 2, 3, 4]), vec![(0, 1), (1, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn fill_rect_strided() {
+        let mut strided = RawFrameData{ row_pitch: 12, data: vec![0; 24], ..frame(2, 2) };
+        fill_rect(&mut strided, Rect::new(0, 0, 2, 2), [255; 4]);
+
+        assert_eq!(filled(&strided, [255; 4]).len(), 4);
+        // the padding is kept
+        assert_eq!(&strided.data[8..12], &[0; 4]);
+        assert_eq!(&strided.data[20..24], &[0; 4]);
+    }
+
+    #[test]
+    fn draw_text_glyphs() {
+        let mut frame = frame(12, 7);
+        draw_text(&mut frame, "1/", 0, 0, 1, [255; 4]);
+
+        // the stem of 1, and a space column between the glyphs
+        assert!(filled(&frame, [255; 4]).contains(&(2, 3)));
+        assert!(!filled(&frame, [255; 4]).iter().any(|&(x, _)| x == 5));
+        assert_eq!(filled(&frame, [255; 4]).len(), glyph_dots('1') + glyph_dots('/'));
+    }
+
+    fn glyph_dots(c: char) -> usize {
+        glyph(c).iter().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    #[test]
+    fn frame_counter_at_corner() {
+        let mut frame = frame(100, 50);
+        let mut overlay = TimestampOverlay::new().timestamp(false).frame_counter(true).font_size(7).margin(2).corner(Corner::BottomRight);
+        overlay.apply(&mut frame, 42);
+
+        // 7 characters of 6 dots, without the last space, and a dot of padding on each side
+        let width = 7 * 6 - 1 + 2;
+        let height = 7 + 2;
+        let background = filled(&frame, [0, 0, 0, 255]);
+        assert_eq!(background.iter().map(|&(x, _)| x).min(), Some(100 - 2 - width));
+        assert_eq!(background.iter().map(|&(_, y)| y).min(), Some(50 - 2 - height));
+        assert_eq!(background.iter().map(|&(x, _)| x).max(), Some(100 - 2 - 1));
+    }
+
+    #[test]
+    fn watermark_placement() {
+        let overlay = WatermarkOverlay::new(1, 1, vec![0, 0, 0, 255]).unwrap().margin(4);
+
+        assert_eq!(overlay.clone().corner(Corner::TopLeft).placement(100, 50, 10, 5), (4, 4));
+        assert_eq!(overlay.clone().corner(Corner::BottomRight).placement(100, 50, 10, 5), (86, 41));
+        assert_eq!(overlay.corner(Corner::TopRight).position(7, 9).placement(100, 50, 10, 5), (7, 9));
+    }
+
+    #[test]
+    fn watermark_blends_by_alpha() {
+        // white, at half opacity
+        let mut overlay = WatermarkOverlay::new(1, 1, vec![255, 255, 255, 255]).unwrap()
+            .opacity(0.5)
+            .position(1, 0);
+        let mut raw = frame(3, 1);
+        overlay.apply(&mut raw, 0);
+
+        assert_eq!(raw.data, [0, 0, 0, 0, 128, 128, 128, 0, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,55 @@
+//! The capture picker of the system, letting the user choose what to capture.
+//!
+//! Required features: *`"picker"`*
+
+use windows::{
+    core::Interface,
+    Graphics::Capture::GraphicsCapturePicker,
+    Win32::{
+        Foundation::HWND,
+        UI::Shell::IInitializeWithWindow,
+    },
+};
+
+use crate::d3d::Device;
+
+
+impl Device {
+    /// Show the capture picker of the system over the window `hwnd`, and create Device from the window
+    /// or the display the user chose. `None` when the user cancelled.
+    ///
+    /// the picker is a dialog owned by `hwnd`, so poll the future on a thread pumping the messages
+    /// of the window, as the UI thread of the app with an async runtime of its own. blocking that thread
+    /// on the future hangs the dialog.
+    ///
+    /// Required features: *`"picker"`*
+    /// # Examples
+    /// ```
+    /// # async fn app(hwnd: isize) {
+    /// match dxcapture::Device::pick_async(hwnd).await.unwrap() {
+    ///     Some(device) => { let capture = dxcapture::Capture::new(&device).unwrap(); },
+    ///     None => println!("cancelled"),
+    /// }
+    /// # }
+    /// ```
+    /// ## Errors
+    /// * [CaptureError](crate::CaptureError) from [probe_environment](crate::probe_environment) when there is nothing to capture.
+    /// * the errors of the system, as for `hwnd` not a window of this process.
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "picker")))]
+    pub async fn pick_async(hwnd: isize) -> anyhow::Result<Option<Self>> {
+        crate::environment::probe_environment()?;
+
+        let picker = GraphicsCapturePicker::new()?;
+        // a desktop app has no CoreWindow, so the picker is told its owner
+        unsafe {
+            picker.cast::<IInitializeWithWindow>()?.Initialize(HWND{ 0: hwnd })?;
+        }
+
+        match picker.PickSingleItemAsync()?.await {
+            Ok(item) => Ok(Some(Self::new( item ))),
+            // cancelled, returned as null
+            Err(e) if e.code().is_ok() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
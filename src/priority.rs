@@ -0,0 +1,186 @@
+use winapi::{
+    shared::{
+        basetsd::DWORD_PTR,
+        minwindef::DWORD,
+        ntdef::HANDLE,
+    },
+    um::{
+        avrt::{
+            AvRevertMmThreadCharacteristics,
+            AvSetMmThreadCharacteristicsW,
+        },
+        processthreadsapi::{
+            GetCurrentThread,
+            GetThreadPriority,
+            SetThreadPriority,
+        },
+        winbase::{
+            SetThreadAffinityMask,
+            THREAD_PRIORITY_ABOVE_NORMAL,
+            THREAD_PRIORITY_ERROR_RETURN,
+            THREAD_PRIORITY_HIGHEST,
+            THREAD_PRIORITY_NORMAL,
+            THREAD_PRIORITY_TIME_CRITICAL,
+        },
+    },
+};
+
+use crate::capture::CaptureError;
+
+
+/// Task of the Multimedia Class Scheduler Service, under
+/// `HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion\Multimedia\SystemProfile\Tasks`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MmcssTask {
+    Capture,
+    Games,
+    Playback,
+    Audio,
+    ProAudio,
+    Distribution,
+}
+impl MmcssTask {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Capture => "Capture",
+            Self::Games => "Games",
+            Self::Playback => "Playback",
+            Self::Audio => "Audio",
+            Self::ProAudio => "Pro Audio",
+            Self::Distribution => "Distribution",
+        }
+    }
+}
+
+/// Priority of a capture thread. [Read more](ThreadOptions)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThreadPriority {
+    /// as it is.
+    #[default]
+    Normal,
+    AboveNormal,
+    Highest,
+    /// above the most of the game threads. keep the work of the thread short.
+    TimeCritical,
+    /// scheduled by MMCSS as `task`, boosted while the system is busy and throttled for the rest of the system.
+    Mmcss(MmcssTask),
+}
+
+
+/// Priority and core affinity of the capture threads, so the frame pacing holds while the captured game
+/// loads all the cores.
+///
+/// used by [Capture::set_thread_options](crate::Capture::set_thread_options) for the frame arrived handler,
+/// and by the options of [Recorder](crate::RecorderOptions::thread), [ReplayBuffer](crate::ReplayBufferOptions::thread)
+/// and [TimelapseRecorder](crate::TimelapseOptions::thread) for their threads.
+/// # Examples
+/// ```
+/// let options = dxcapture::ThreadOptions::capture().affinity(Some(0b1100));
+///
+/// let guard = options.apply().unwrap();
+/// // capture on this thread
+/// drop(guard);
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ThreadOptions {
+    pub priority: ThreadPriority,
+    /// mask of the logical processors the thread runs on. `None` is all of the process.
+    pub affinity_mask: Option<usize>,
+}
+impl ThreadOptions {
+    pub fn new(priority: ThreadPriority) -> Self {
+        Self{
+            priority,
+            affinity_mask: None,
+        }
+    }
+
+    /// The MMCSS "Capture" task.
+    pub fn capture() -> Self {
+        Self::new(ThreadPriority::Mmcss(MmcssTask::Capture))
+    }
+
+    pub fn affinity(mut self, affinity_mask: Option<usize>) -> Self {
+        self.affinity_mask = affinity_mask;
+        self
+    }
+
+    /// Apply to the current thread, until the returned guard is dropped.
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): the affinity mask is empty.
+    /// * [DirectxError](CaptureError::DirectxError): refused by the system, as for a mask out of the process.
+    pub fn apply(&self) -> anyhow::Result<ThreadGuard, CaptureError> {
+        if self.affinity_mask == Some(0) {
+            return Err(CaptureError::InvalidArgument("affinity mask must not be empty.".to_string()));
+        }
+
+        let mut guard = ThreadGuard{
+            thread: unsafe { GetCurrentThread() },
+            priority: None,
+            mmcss: None,
+            affinity_mask: None,
+        };
+
+        let priority = match self.priority {
+            ThreadPriority::Normal => None,
+            ThreadPriority::AboveNormal => Some(THREAD_PRIORITY_ABOVE_NORMAL),
+            ThreadPriority::Highest => Some(THREAD_PRIORITY_HIGHEST),
+            ThreadPriority::TimeCritical => Some(THREAD_PRIORITY_TIME_CRITICAL),
+            ThreadPriority::Mmcss(task) => {
+                let name: Vec<u16> = task.name().encode_utf16().chain(std::iter::once(0)).collect();
+                let mut task_index: DWORD = 0;
+                let handle = unsafe { AvSetMmThreadCharacteristicsW(name.as_ptr(), &mut task_index) };
+                if handle.is_null() {
+                    return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
+                }
+                guard.mmcss = Some(handle as isize);
+                None
+            },
+        };
+        if let Some(priority) = priority {
+            unsafe {
+                let previous = GetThreadPriority(guard.thread);
+                if previous as DWORD == THREAD_PRIORITY_ERROR_RETURN || SetThreadPriority(guard.thread, priority as i32) == 0 {
+                    return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
+                }
+                guard.priority = Some(previous);
+            }
+        }
+
+        if let Some(affinity_mask) = self.affinity_mask {
+            let previous = unsafe { SetThreadAffinityMask(guard.thread, affinity_mask as DWORD_PTR) };
+            if previous == 0 {
+                return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
+            }
+            guard.affinity_mask = Some(previous);
+        }
+
+        Ok(guard)
+    }
+}
+
+
+/// Restores the priority and the affinity of the thread on drop. [Read more](ThreadOptions::apply)
+#[derive(Debug)]
+pub struct ThreadGuard {
+    /// pseudo handle of the current thread, not closed.
+    thread: HANDLE,
+    priority: Option<i32>,
+    mmcss: Option<isize>,
+    affinity_mask: Option<DWORD_PTR>,
+}
+impl Drop for ThreadGuard {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(affinity_mask) = self.affinity_mask {
+                SetThreadAffinityMask(self.thread, affinity_mask);
+            }
+            if let Some(priority) = self.priority {
+                SetThreadPriority(self.thread, priority);
+            }
+            if let Some(mmcss) = self.mmcss {
+                AvRevertMmThreadCharacteristics(mmcss as HANDLE);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::capture::{
+    Capture,
+    CaptureError,
+    RawFrameData,
+    Rect,
+};
+
+
+/// QR code found in a frame.
+///
+/// Required features: *`"qr"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "qr")))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedCode {
+    pub payload: String,
+    /// corners in frame coordinates, clockwise from the top left of the code.
+    pub corners: [(i32, i32); 4],
+    /// bounds of the corners.
+    pub rect: Rect,
+}
+
+
+/// Scan a frame for QR codes and decode them.
+/// ## Parameters
+/// * region: area to be scanned. default is the whole frame. positions are returned in frame coordinates anyway.
+///
+/// codes which are found but can't be decoded are skipped.
+///
+/// Required features: *`"qr"`*
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "qr")))]
+pub fn detect_codes(frame: &RawFrameData, region: Option<Rect>) -> Vec<DecodedCode> {
+    let bounds = Rect::new(0, 0, frame.width, frame.height);
+    let region = region.map(|r| r.intersect(&bounds)).unwrap_or(bounds);
+    if region.is_empty() || !frame.has_all_rows() {
+        return Vec::new();
+    }
+
+    let mut image = rqrr::PreparedImage::prepare_from_greyscale(
+        region.width as usize, region.height as usize,
+        |x, y| {
+            let offset = frame.pixel_offset(region.x as usize + x, region.y as usize + y);
+            let (b, g, r) = (frame.data[offset] as u32, frame.data[offset + 1] as u32, frame.data[offset + 2] as u32);
+            ((r * 299 + g * 587 + b * 114) / 1000) as u8
+        },
+    );
+
+    image.detect_grids().into_iter().filter_map(|grid| {
+        let (_, payload) = grid.decode().ok()?;
+
+        let corners = grid.bounds.map(|point| (region.x + point.x, region.y + point.y));
+        let rect = corners.iter().fold(Rect::default(), |rect, &(x, y)| rect.union(&Rect::new(x, y, 1, 1)));
+
+        Some(DecodedCode{ payload, corners, rect })
+    }).collect()
+}
+
+impl Capture {
+    /// Scan current frame for QR codes. with throught NoTexture
+    ///
+    /// Required features: *`"qr"`*
+    /// # Examples
+    /// ```
+    /// let device = dxcapture::Device::new_from_displays(None).unwrap();
+    /// let capture = dxcapture::Capture::new(&device).unwrap();
+    ///
+    /// for code in capture.wait_qr_codes(None).unwrap() {
+    ///     println!("{} at {:?}", code.payload, code.rect);
+    /// }
+    /// ```
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "qr")))]
+    pub fn wait_qr_codes(&self, region: Option<Rect>) -> anyhow::Result<Vec<DecodedCode>, CaptureError> {
+        let raw = self.wait_raw_frame()?;
+
+        Ok(detect_codes(&raw, region))
+    }
+}
@@ -0,0 +1,617 @@
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    FrameInfo,
+    RawFrameData,
+};
+use crate::priority::ThreadOptions;
+use crate::replay::DxcapWriter;
+use crate::source::FrameSource;
+
+
+/// Encoded unit handed from an [Encoder] to a [Muxer].
+#[derive(Clone, Debug, Default)]
+pub struct EncodedPacket {
+    /// time from the start of the recording, pauses excluded.
+    pub timestamp: Duration,
+    pub width: i32,
+    pub height: i32,
+    pub keyframe: bool,
+    pub data: Vec<u8>,
+}
+
+
+/// Turn frames into packets.
+pub trait Encoder: Send {
+    /// Encode a frame. may return no packets while buffering.
+    fn encode(&mut self, frame: &RawFrameData, timestamp: Duration) -> anyhow::Result<Vec<EncodedPacket>, CaptureError>;
+
+    /// Drain the buffered packets at the end of the recording.
+    fn flush(&mut self) -> anyhow::Result<Vec<EncodedPacket>, CaptureError> {
+        Ok(Vec::new())
+    }
+}
+
+/// Write packets into a container.
+pub trait Muxer: Send {
+    fn write_packet(&mut self, packet: &EncodedPacket) -> anyhow::Result<(), CaptureError>;
+
+    /// Finalize the container. called once at the end of the recording.
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError>;
+}
+
+
+/// [Encoder] passing the BGRA frames through as they are.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RawEncoder;
+impl Encoder for RawEncoder {
+    fn encode(&mut self, frame: &RawFrameData, timestamp: Duration) -> anyhow::Result<Vec<EncodedPacket>, CaptureError> {
+        let mut frame = frame.clone();
+        // the muxers take the tightly packed rows
+        frame.pack_rows();
+
+        Ok(vec![EncodedPacket{
+            timestamp,
+            width: frame.width,
+            height: frame.height,
+            keyframe: true,
+            data: frame.data,
+        }])
+    }
+}
+
+/// [Muxer] writing [RawEncoder] packets into a `.dxcap` file, replayable by [ReplaySource](crate::ReplaySource).
+pub struct DxcapMuxer {
+    writer: Option<DxcapWriter>,
+}
+impl DxcapMuxer {
+    pub fn create<P: AsRef<Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        Ok(Self{
+            writer: Some(DxcapWriter::create(path)?),
+        })
+    }
+}
+impl Muxer for DxcapMuxer {
+    fn write_packet(&mut self, packet: &EncodedPacket) -> anyhow::Result<(), CaptureError> {
+        let writer = self.writer.as_mut().ok_or(CaptureError::NotActive)?;
+        let frame = RawFrameData{
+            width: packet.width,
+            height: packet.height,
+            data: packet.data.clone(),
+            ..Default::default()
+        };
+
+        writer.write_frame(&frame, packet.timestamp)
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError> {
+        match self.writer.take() {
+            Some(writer) => writer.finish(),
+            None => Ok(()),
+        }
+    }
+}
+
+
+/// Video codec of the `.mp4` recordings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VideoCodec {
+    #[default]
+    H264,
+    /// needs the HEVC Video Extensions on Windows 10 and later.
+    Hevc,
+}
+
+/// Options of [Recorder].
+#[derive(Clone, Debug)]
+pub struct RecorderOptions {
+    /// frames per second taken from the source.
+    pub fps: u32,
+    /// bits per second of the `.mp4` of [Recorder::new]. the other encoders take their own.
+    pub bitrate: u32,
+    /// codec of the `.mp4` of [Recorder::new].
+    pub codec: VideoCodec,
+    /// stop by itself after this much recorded time. (pauses excluded)
+    pub max_duration: Option<Duration>,
+    /// stop by itself after this many bytes of packets.
+    pub max_bytes: Option<u64>,
+    /// write a JSON of the settings, frame timestamps, dropped frames and [markers](Recorder::add_marker) here at the end.
+    /// the frame timestamps are the [capture times](FrameInfo::time) from the first frame, pauses excluded,
+    /// or the recorded time for the sources without them.
+    pub sidecar: Option<PathBuf>,
+    /// priority and affinity of the recording thread.
+    pub thread: Option<ThreadOptions>,
+}
+impl Default for RecorderOptions {
+    fn default() -> Self {
+        Self{
+            fps: 30,
+            bitrate: 8_000_000,
+            codec: VideoCodec::default(),
+            max_duration: None,
+            max_bytes: None,
+            sidecar: None,
+            thread: None,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecorderState {
+    Recording,
+    Paused,
+    Stopped,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecorderProgress {
+    pub frames: u64,
+    /// recorded time, pauses excluded.
+    pub duration: Duration,
+    pub bytes: u64,
+    /// frames missed: the ticks skipped because the recording thread fell behind, and the frames of the source
+    /// skipped between two recorded frames, by the gaps in their [sequence](FrameInfo::sequence).
+    /// the source taking more frames than [fps](RecorderOptions::fps) skips some by itself,
+    /// so limit it to the fps (as [set_max_fps](crate::Capture::set_max_fps)) for these to be real drops.
+    pub dropped: u64,
+}
+
+/// Chapter marker added by [Recorder::add_marker].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecorderMarker {
+    /// recorded time when added.
+    pub timestamp: Duration,
+    pub label: String,
+}
+
+type ProgressCallback = Box<dyn FnMut(&RecorderProgress) + Send>;
+
+struct RecorderShared {
+    state: Mutex<RecorderState>,
+    state_changed: Condvar,
+    progress: Mutex<RecorderProgress>,
+    on_progress: Mutex<Option<ProgressCallback>>,
+    markers: Mutex<Vec<RecorderMarker>>,
+}
+
+/// What the sidecar is written from.
+#[derive(Default)]
+struct RecordingLog {
+    width: i32,
+    height: i32,
+    frame_timestamps: Vec<Duration>,
+    /// recorded time and count of each drop.
+    dropped: Vec<(Duration, u64)>,
+    /// capture time of the first frame, and the paused time taken off the later frames.
+    first_time: Option<Duration>,
+    paused: Duration,
+    /// capture time and sequence number of the last frame.
+    last_time: Option<Duration>,
+    last_sequence: u64,
+    /// the recording was paused since the last frame.
+    resumed: bool,
+}
+impl RecordingLog {
+    /// Log the timestamp of a frame recorded at `recorded`, and return the frames of the source missed before it
+    /// by the gap in the [sequence numbers](FrameInfo::sequence). 0 when unknown or right after a pause.
+    fn push_frame(&mut self, info: &FrameInfo, recorded: Duration, interval: Duration) -> u64 {
+        let timestamp = match (info.time, self.first_time) {
+            (Some(time), Some(first)) => {
+                if let (true, Some(last)) = (self.resumed, self.last_time) {
+                    // the frame after a pause follows the last one by an interval
+                    self.paused += time.saturating_sub(last).saturating_sub(interval);
+                }
+                time.saturating_sub(first).saturating_sub(self.paused)
+            },
+            (Some(time), None) => {
+                self.first_time = Some(time);
+                Duration::ZERO
+            },
+            (None, _) => recorded,
+        };
+        self.frame_timestamps.push(timestamp);
+
+        let missed = match (self.last_sequence, info.sequence) {
+            (0, _) | (_, 0) => 0,
+            _ if self.resumed => 0,
+            (last, sequence) => sequence.saturating_sub(last + 1),
+        };
+        self.last_time = info.time.or(self.last_time);
+        self.last_sequence = info.sequence;
+        self.resumed = false;
+
+        missed
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+
+    json
+}
+
+fn json_ms(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+fn write_sidecar(path: &Path, options: &RecorderOptions, progress: &RecorderProgress, log: &RecordingLog, markers: &[RecorderMarker]) -> anyhow::Result<(), CaptureError> {
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+
+    let timestamps: Vec<String> = log.frame_timestamps.iter().map(|timestamp| json_ms(*timestamp)).collect();
+    let dropped: Vec<String> = log.dropped.iter()
+        .map(|(timestamp, count)| format!("{{ \"timestamp_ms\": {}, \"count\": {} }}", json_ms(*timestamp), count))
+        .collect();
+    let markers: Vec<String> = markers.iter()
+        .map(|marker| format!("{{ \"timestamp_ms\": {}, \"label\": {} }}", json_ms(marker.timestamp), json_string(&marker.label)))
+        .collect();
+
+    let json = format!(
+        concat!(
+            "{{\n",
+            "  \"settings\": {{ \"fps\": {}, \"max_duration_ms\": {}, \"max_bytes\": {} }},\n",
+            "  \"width\": {},\n",
+            "  \"height\": {},\n",
+            "  \"frames\": {},\n",
+            "  \"duration_ms\": {},\n",
+            "  \"bytes\": {},\n",
+            "  \"dropped_frames\": {},\n",
+            "  \"frame_timestamps_ms\": [{}],\n",
+            "  \"dropped\": [{}],\n",
+            "  \"markers\": [{}]\n",
+            "}}\n",
+        ),
+        options.fps, optional(options.max_duration.map(json_ms)), optional(options.max_bytes.map(|bytes| bytes.to_string())),
+        log.width,
+        log.height,
+        progress.frames,
+        json_ms(progress.duration),
+        progress.bytes,
+        progress.dropped,
+        timestamps.join(", "),
+        dropped.join(", "),
+        markers.join(", "),
+    );
+
+    std::fs::write(path, json).map_err(|e| CaptureError::IoError(e.to_string()))
+}
+
+
+/// Record frames of a [FrameSource] through an [Encoder] and a [Muxer] on a background thread.
+///
+/// the source is created on that thread by `source_factory`, since [Capture](crate::Capture) can't be sent between threads.
+/// # Examples
+/// ```
+/// let recorder = dxcapture::Recorder::start(
+///     || {
+///         let device = dxcapture::Device::new_from_displays(None)?;
+///         Ok(dxcapture::Capture::new(&device)?)
+///     },
+///     dxcapture::RawEncoder,
+///     dxcapture::DxcapMuxer::create("session.dxcap").unwrap(),
+///     dxcapture::RecorderOptions{ fps: 10, ..Default::default() },
+/// ).unwrap();
+/// recorder.on_progress(|progress| println!("{:?}", progress));
+///
+/// std::thread::sleep(std::time::Duration::from_secs(3));
+/// recorder.pause();
+/// std::thread::sleep(std::time::Duration::from_secs(1));
+/// recorder.resume();
+/// std::thread::sleep(std::time::Duration::from_secs(3));
+///
+/// let progress = recorder.stop().expect("Failed to record");
+/// ```
+pub struct Recorder {
+    shared: Arc<RecorderShared>,
+    thread: Option<JoinHandle<anyhow::Result<RecorderProgress, CaptureError>>>,
+}
+impl Recorder {
+    /// Start recording.
+    pub fn start<S, F, E, M>(source_factory: F, encoder: E, muxer: M, options: RecorderOptions) -> anyhow::Result<Self, CaptureError>
+    where
+        S: FrameSource,
+        F: FnOnce() -> anyhow::Result<S> + Send + 'static,
+        E: Encoder + 'static,
+        M: Muxer + 'static,
+    {
+        if options.fps == 0 {
+            return Err(CaptureError::InvalidArgument("fps must be greater than 0.".to_string()));
+        }
+
+        let shared = Arc::new(RecorderShared{
+            state: Mutex::new(RecorderState::Recording),
+            state_changed: Condvar::new(),
+            progress: Mutex::new(RecorderProgress::default()),
+            on_progress: Mutex::new(None),
+            markers: Mutex::new(Vec::new()),
+        });
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+
+            move || {
+                let result = Self::run(&shared, source_factory, encoder, muxer, options);
+                *shared.state.lock().unwrap() = RecorderState::Stopped;
+                shared.state_changed.notify_all();
+
+                result
+            }
+        });
+
+        Ok(Self{
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    fn run<S, F, E, M>(shared: &RecorderShared, source_factory: F, mut encoder: E, mut muxer: M, options: RecorderOptions) -> anyhow::Result<RecorderProgress, CaptureError>
+    where
+        S: FrameSource,
+        F: FnOnce() -> anyhow::Result<S>,
+        E: Encoder,
+        M: Muxer,
+    {
+        let _thread = options.thread.map(|thread| thread.apply()).transpose()?;
+        let source = source_factory().map_err(|e| match e.downcast::<CaptureError>() {
+            Ok(e) => e,
+            Err(e) => CaptureError::SourceError(e.to_string()),
+        })?;
+
+        let interval = Duration::from_secs(1) / options.fps;
+        let mut progress = RecorderProgress::default();
+        let mut log = RecordingLog::default();
+        let mut next_tick = Instant::now();
+
+        loop {
+            {
+                let mut state = shared.state.lock().unwrap();
+                if *state == RecorderState::Paused {
+                    while *state == RecorderState::Paused {
+                        state = shared.state_changed.wait(state).unwrap();
+                    }
+                    // the paused time is not recorded
+                    next_tick = Instant::now();
+                    log.resumed = true;
+                }
+                if *state == RecorderState::Stopped {
+                    break;
+                }
+            }
+
+            let mut skipped = 0;
+            match next_tick.checked_duration_since(Instant::now()) {
+                Some(wait) => std::thread::sleep(wait),
+                None => {
+                    // skip the ticks already passed instead of bursting to catch up
+                    let behind = Instant::now() - next_tick;
+                    skipped = (behind.as_nanos() / interval.as_nanos()) as u64;
+                    if 0 < skipped {
+                        log.dropped.push((progress.duration, skipped));
+                        progress.dropped += skipped;
+                        progress.duration += interval * skipped as u32;
+                        next_tick += interval * skipped as u32;
+                    }
+                },
+            }
+            next_tick += interval;
+
+            let frame = match source.get_raw_frame() {
+                Ok(frame) => frame,
+                Err(CaptureError::NoTexture) => continue,
+                Err(e) => return Err(e),
+            };
+
+            for packet in encoder.encode(&frame, progress.duration)? {
+                muxer.write_packet(&packet)?;
+                progress.bytes += packet.data.len() as u64;
+            }
+            if log.frame_timestamps.is_empty() {
+                log.width = frame.width;
+                log.height = frame.height;
+            }
+            // the frames of the skipped ticks are in the gap too
+            let missed = log.push_frame(&frame.info, progress.duration, interval).saturating_sub(skipped);
+            if 0 < missed {
+                log.dropped.push((progress.duration, missed));
+                progress.dropped += missed;
+            }
+            progress.frames += 1;
+            progress.duration += interval;
+
+            *shared.progress.lock().unwrap() = progress;
+            if let Some(on_progress) = shared.on_progress.lock().unwrap().as_mut() {
+                on_progress(&progress);
+            }
+
+            let over_duration = options.max_duration.is_some_and(|max| max <= progress.duration);
+            let over_bytes = options.max_bytes.is_some_and(|max| max <= progress.bytes);
+            if over_duration || over_bytes {
+                break;
+            }
+        }
+
+        for packet in encoder.flush()? {
+            muxer.write_packet(&packet)?;
+            progress.bytes += packet.data.len() as u64;
+        }
+        muxer.finish()?;
+
+        *shared.progress.lock().unwrap() = progress;
+        if let Some(sidecar) = &options.sidecar {
+            write_sidecar(sidecar, &options, &progress, &log, &shared.markers.lock().unwrap())?;
+        }
+
+        Ok(progress)
+    }
+
+    /// Set a callback called after each recorded frame, on the recording thread.
+    pub fn on_progress<F: FnMut(&RecorderProgress) + Send + 'static>(&self, callback: F) {
+        *self.shared.on_progress.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Stop taking frames until [resume](Self::resume). the paused time is left out of the recording.
+    pub fn pause(&self) {
+        self.set_state(RecorderState::Paused);
+    }
+
+    pub fn resume(&self) {
+        self.set_state(RecorderState::Recording);
+    }
+
+    fn set_state(&self, new_state: RecorderState) {
+        let mut state = self.shared.state.lock().unwrap();
+        if *state != RecorderState::Stopped {
+            *state = new_state;
+            self.shared.state_changed.notify_all();
+        }
+    }
+
+    /// [Stopped](RecorderState::Stopped) also after reaching a limit or an error.
+    pub fn state(&self) -> RecorderState {
+        *self.shared.state.lock().unwrap()
+    }
+
+    pub fn progress(&self) -> RecorderProgress {
+        *self.shared.progress.lock().unwrap()
+    }
+
+    /// Mark the current point of the recording, such as `"bug reproduced"`. written into the [sidecar](RecorderOptions::sidecar).
+    ///
+    /// returns the recorded time of the marker.
+    pub fn add_marker(&self, label: &str) -> Duration {
+        let timestamp = self.progress().duration;
+        self.shared.markers.lock().unwrap().push(RecorderMarker{
+            timestamp,
+            label: label.to_string(),
+        });
+
+        timestamp
+    }
+
+    pub fn markers(&self) -> Vec<RecorderMarker> {
+        self.shared.markers.lock().unwrap().clone()
+    }
+
+    /// Finish the recording and wait the output to be written.
+    pub fn stop(mut self) -> anyhow::Result<RecorderProgress, CaptureError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> anyhow::Result<RecorderProgress, CaptureError> {
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            *state = RecorderState::Stopped;
+            self.shared.state_changed.notify_all();
+        }
+
+        match self.thread.take() {
+            Some(thread) => thread.join().unwrap_or(Err(CaptureError::SourceError("Recording thread panicked.".to_string()))),
+            None => Ok(self.progress()),
+        }
+    }
+}
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(json_string("C:\\clips\\a.mp4"), "\"C:\\\\clips\\\\a.mp4\"");
+        assert_eq!(json_string("a\nb\r\tc"), "\"a\\nb\\r\\tc\"");
+        assert_eq!(json_string("\u{1}\u{1f}"), "\"\\u0001\\u001f\"");
+        // not escaped above the controls
+        assert_eq!(json_string("ü ✓ 🎬"), "\"ü ✓ 🎬\"");
+    }
+
+    #[test]
+    fn json_ms_is_fixed() {
+        assert_eq!(json_ms(Duration::ZERO), "0.000");
+        assert_eq!(json_ms(Duration::from_micros(16_667)), "16.667");
+        assert_eq!(json_ms(Duration::from_secs(2)), "2000.000");
+    }
+
+    fn info(time_ms: u64, sequence: u64) -> FrameInfo {
+        FrameInfo{
+            time: Some(Duration::from_millis(time_ms)),
+            sequence,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn timestamps_from_the_first_frame() {
+        let interval = Duration::from_millis(100);
+        let mut log = RecordingLog::default();
+        log.push_frame(&info(5_000, 1), Duration::ZERO, interval);
+        log.push_frame(&info(5_120, 2), interval, interval);
+        // paused for about 2s
+        log.resumed = true;
+        log.push_frame(&info(7_220, 30), interval * 2, interval);
+        log.push_frame(&info(7_300, 31), interval * 3, interval);
+
+        let ms = |ms| Duration::from_millis(ms);
+        assert_eq!(log.frame_timestamps, [ms(0), ms(120), ms(220), ms(300)]);
+    }
+
+    #[test]
+    fn timestamps_without_capture_times() {
+        let mut log = RecordingLog::default();
+        log.push_frame(&FrameInfo::default(), Duration::from_millis(40), Duration::from_millis(20));
+
+        assert_eq!(log.frame_timestamps, [Duration::from_millis(40)]);
+    }
+
+    #[test]
+    fn sequence_gaps_are_missed() {
+        let interval = Duration::from_millis(100);
+        let mut log = RecordingLog::default();
+        assert_eq!(log.push_frame(&info(0, 3), Duration::ZERO, interval), 0);
+        assert_eq!(log.push_frame(&info(100, 4), Duration::ZERO, interval), 0);
+        assert_eq!(log.push_frame(&info(400, 7), Duration::ZERO, interval), 2);
+        // same frame again
+        assert_eq!(log.push_frame(&info(400, 7), Duration::ZERO, interval), 0);
+        // unknown
+        assert_eq!(log.push_frame(&info(500, 0), Duration::ZERO, interval), 0);
+        assert_eq!(log.push_frame(&info(600, 12), Duration::ZERO, interval), 0);
+
+        log.resumed = true;
+        assert_eq!(log.push_frame(&info(900, 40), Duration::ZERO, interval), 0);
+    }
+}
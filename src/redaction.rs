@@ -0,0 +1,427 @@
+use std::{
+    collections::HashMap,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use winapi::{
+    shared::windef::{
+        HWND,
+        RECT,
+    },
+    um::{
+        dwmapi::{
+            DwmGetWindowAttribute,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+        },
+        winuser::{
+            GetWindowRect,
+            IsIconic,
+            IsWindow,
+            IsWindowVisible,
+        },
+    },
+};
+
+use crate::capture::{
+    FrameInfo,
+    RawFrameData,
+    Rect,
+};
+use crate::displays::DisplayInfo;
+use crate::overlay::FrameFilter;
+use crate::window_finder::find_window;
+
+
+/// How a [RedactionFilter] hides the regions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedactionStyle {
+    /// fill with black.
+    Black,
+    /// box blur of the radius in pixels. small text may stay readable under a light blur.
+    Blur(u32),
+    /// mosaic of blocks of the size in pixels.
+    Pixelate(u32),
+}
+
+/// Area hidden by a [RedactionFilter].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RedactionRegion {
+    /// in frame coordinates.
+    Frame(Rect),
+    /// in screen coordinates, converted with the [origin](RedactionFilter::origin) of the filter,
+    /// then through the [scale and region](FrameInfo::map_rect) of each frame.
+    Screen(Rect),
+    /// a rect relative to the top left of the window whose title contains `title`. `None` is the whole window.
+    /// the window is looked up by the title every [WINDOW_LOOKUP_INTERVAL](RedactionFilter::WINDOW_LOOKUP_INTERVAL),
+    /// and followed by its handle every frame as it moves. skipped while no such window is shown.
+    Window {
+        title: String,
+        rect: Option<Rect>,
+    },
+    /// a rect relative to the top left of the window of `handle` (`HWND`), as the own UI of the app.
+    /// `None` is the whole window. followed every frame, and skipped while it is minimized, hidden or closed.
+    Handle {
+        handle: isize,
+        rect: Option<Rect>,
+    },
+}
+
+
+/// [FrameFilter] blacking out or blurring regions of each frame, to keep email previews,
+/// chat windows or credential fields out of recordings and streams.
+/// # Examples
+/// ```
+/// use dxcapture::{ FilteredSource, FrameSource, Rect, RedactionFilter, RedactionStyle };
+///
+/// let redaction = RedactionFilter::new(RedactionStyle::Pixelate(16))
+///     .region(Rect::new(0, 0, 400, 60))
+///     .window("Slack")
+///     .window_region("Outlook", Rect::new(0, 120, 480, 600));
+///
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(redaction);
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+///
+/// hiding the own window of a recording tool in the capture of the second display.
+/// ```
+/// use dxcapture::{ FilteredSource, FrameSource, RedactionFilter, RedactionStyle };
+///
+/// let display = dxcapture::enumerate_displays().unwrap().remove(1);
+/// # let hwnd = std::ptr::null_mut();
+/// let redaction = RedactionFilter::new(RedactionStyle::Blur(12))
+///     .display(&display)
+///     .window_handle(hwnd);
+///
+/// let device = dxcapture::Device::new_from_displays(Some(2)).unwrap();
+/// let source = FilteredSource::new(dxcapture::Capture::new(&device).unwrap()).with_filter(redaction);
+/// let raw = source.wait_raw_frame().unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RedactionFilter {
+    style: RedactionStyle,
+    regions: Vec<RedactionRegion>,
+    origin: (i32, i32),
+    /// windows of the [Window](RedactionRegion::Window) regions, by the title.
+    lookups: HashMap<String, WindowLookup>,
+}
+impl RedactionFilter {
+    /// How long the window of a [Window](RedactionRegion::Window) region is followed by its handle
+    /// before it is looked up by the title again, which enumerates all the windows.
+    pub const WINDOW_LOOKUP_INTERVAL: Duration = Duration::from_secs(1);
+
+    pub fn new(style: RedactionStyle) -> Self {
+        Self{
+            style,
+            regions: Vec::new(),
+            origin: (0, 0),
+            lookups: HashMap::new(),
+        }
+    }
+
+    /// Screen position of the top left of the frames. (the top left of the captured display or window)
+    /// default is (0, 0), the primary display.
+    pub fn origin(mut self, x: i32, y: i32) -> Self {
+        self.origin = (x, y);
+        self
+    }
+
+    /// Set the [origin](Self::origin) to the top left of `display`, for the frames of the display.
+    pub fn display(self, display: &DisplayInfo) -> Self {
+        self.origin(display.rect.x, display.rect.y)
+    }
+
+    /// Hide `rect` in frame coordinates.
+    pub fn region(self, rect: Rect) -> Self {
+        self.add_region(RedactionRegion::Frame(rect))
+    }
+
+    /// Hide `rect` in screen coordinates.
+    pub fn screen_region(self, rect: Rect) -> Self {
+        self.add_region(RedactionRegion::Screen(rect))
+    }
+
+    /// Hide the whole window whose title contains `title`.
+    pub fn window(self, title: &str) -> Self {
+        self.add_region(RedactionRegion::Window{ title: title.to_string(), rect: None })
+    }
+
+    /// Hide `rect` relative to the window whose title contains `title`.
+    pub fn window_region(self, title: &str, rect: Rect) -> Self {
+        self.add_region(RedactionRegion::Window{ title: title.to_string(), rect: Some(rect) })
+    }
+
+    /// Hide the whole window of `handle`.
+    pub fn window_handle(self, handle: HWND) -> Self {
+        self.add_region(RedactionRegion::Handle{ handle: handle as isize, rect: None })
+    }
+
+    /// Hide `rect` relative to the window of `handle`.
+    pub fn window_handle_region(self, handle: HWND, rect: Rect) -> Self {
+        self.add_region(RedactionRegion::Handle{ handle: handle as isize, rect: Some(rect) })
+    }
+
+    pub fn add_region(mut self, region: RedactionRegion) -> Self {
+        self.regions.push(region);
+        self
+    }
+
+    pub fn regions(&self) -> &[RedactionRegion] {
+        &self.regions
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// Regions in frame coordinates for the current frame, mapped through the [scale and region](FrameInfo::map_rect) of `info`.
+    fn frame_rects(&mut self, info: &FrameInfo) -> Vec<Rect> {
+        let (origin_x, origin_y) = self.origin;
+        let (regions, lookups) = (&self.regions, &mut self.lookups);
+        let now = Instant::now();
+        let to_frame = |rect: Rect| info.map_rect(Rect::new(rect.x - origin_x, rect.y - origin_y, rect.width, rect.height));
+        let in_window = |window: Rect, rect: &Option<Rect>| match rect {
+            Some(rect) => Rect::new(window.x + rect.x, window.y + rect.y, rect.width, rect.height),
+            None => window,
+        };
+
+        regions.iter().filter_map(|region| match region {
+            RedactionRegion::Frame(rect) => Some(*rect),
+            RedactionRegion::Screen(rect) => Some(to_frame(*rect)),
+            RedactionRegion::Window{ title, rect } => {
+                let handle = lookup_window(lookups, title, now)?;
+                if !is_shown(handle) {
+                    return None;
+                }
+                Some(to_frame(in_window(window_rect(handle)?, rect)))
+            },
+            RedactionRegion::Handle{ handle, rect } => {
+                let handle = *handle as HWND;
+                if !is_shown(handle) {
+                    return None;
+                }
+                Some(to_frame(in_window(window_rect(handle)?, rect)))
+            },
+        }).collect()
+    }
+}
+impl FrameFilter for RedactionFilter {
+    fn apply(&mut self, frame: &mut RawFrameData, _index: u64) {
+        if !frame.has_all_rows() {
+            return;
+        }
+        let bounds = Rect::new(0, 0, frame.width, frame.height);
+
+        for rect in self.frame_rects(&frame.info) {
+            let rect = rect.intersect(&bounds);
+            if rect.is_empty() {
+                continue;
+            }
+
+            match self.style {
+                RedactionStyle::Black => black_out(frame, rect),
+                RedactionStyle::Blur(radius) => {
+                    // three box blurs are close to a gaussian blur
+                    for _ in 0..3 {
+                        box_blur(frame, rect, radius as i32);
+                    }
+                },
+                RedactionStyle::Pixelate(block) => pixelate(frame, rect, block.max(1) as i32),
+            }
+        }
+    }
+}
+
+
+/// Window found for a title by the last lookup.
+#[derive(Clone, Copy, Debug)]
+struct WindowLookup {
+    /// `HWND` of the first shown window, as an integer.
+    handle: Option<isize>,
+    time: Instant,
+}
+
+/// The window whose title contains `title`, looked up again when the last lookup is older than
+/// [WINDOW_LOOKUP_INTERVAL](RedactionFilter::WINDOW_LOOKUP_INTERVAL) or its window is closed.
+fn lookup_window(lookups: &mut HashMap<String, WindowLookup>, title: &str, now: Instant) -> Option<HWND> {
+    let stale = lookups.get(title).map_or(true, |lookup| {
+        RedactionFilter::WINDOW_LOOKUP_INTERVAL <= now.duration_since(lookup.time)
+            || lookup.handle.is_some_and(|handle| unsafe { IsWindow(handle as HWND) } == 0)
+    });
+    if stale {
+        let handle = find_window(title).into_iter().find(|window| is_shown(window.handle)).map(|window| window.handle as isize);
+        lookups.insert(title.to_string(), WindowLookup{ handle, time: now });
+    }
+
+    lookups.get(title)?.handle.map(|handle| handle as HWND)
+}
+
+/// The window exists, and is visible and not minimized.
+fn is_shown(handle: HWND) -> bool {
+    unsafe { IsWindow(handle) != 0 && IsWindowVisible(handle) != 0 && IsIconic(handle) == 0 }
+}
+
+/// Screen rect of the window, without the invisible resize borders.
+fn window_rect(handle: HWND) -> Option<Rect> {
+    let mut rect = RECT::default();
+    let result = unsafe {
+        DwmGetWindowAttribute(
+            handle,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut rect as *mut _ as *mut _,
+            std::mem::size_of::<RECT>() as u32,
+        )
+    };
+    if result < 0 && unsafe { GetWindowRect(handle, &mut rect) } == 0 {
+        return None;
+    }
+
+    Some(Rect::new(rect.left, rect.top, rect.right - rect.left, rect.bottom - rect.top))
+}
+
+/// with the stride, so the padded rows of [get_raw_frame_strided](crate::Capture::get_raw_frame_strided) are covered right.
+fn pixel_offset(frame: &RawFrameData, x: i32, y: i32) -> usize {
+    frame.pixel_offset(x as usize, y as usize)
+}
+
+fn black_out(frame: &mut RawFrameData, rect: Rect) {
+    for y in rect.y..rect.bottom() {
+        let start = pixel_offset(frame, rect.x, y);
+        let end = pixel_offset(frame, rect.right(), y);
+        for pixel in frame.data[start..end].chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0, 0, 0, 255]);
+        }
+    }
+}
+
+/// Horizontal then vertical box blur inside `rect`. pixels outside are not read.
+fn box_blur(frame: &mut RawFrameData, rect: Rect, radius: i32) {
+    if radius <= 0 {
+        return;
+    }
+
+    let mut line = Vec::new();
+    let mut blur_line = |frame: &mut RawFrameData, offsets: &[usize]| {
+        line.clear();
+        line.extend(offsets.iter().map(|&offset| [frame.data[offset], frame.data[offset + 1], frame.data[offset + 2]]));
+
+        let len = line.len() as i32;
+        for (i, &offset) in offsets.iter().enumerate() {
+            let from = (i as i32 - radius).max(0);
+            let to = (i as i32 + radius).min(len - 1);
+            let mut sum = [0u32; 3];
+            for pixel in &line[from as usize..=to as usize] {
+                for (sum, value) in sum.iter_mut().zip(pixel) {
+                    *sum += *value as u32;
+                }
+            }
+            let count = (to - from + 1) as u32;
+            for (channel, sum) in sum.iter().enumerate() {
+                frame.data[offset + channel] = (sum / count) as u8;
+            }
+        }
+    };
+
+    for y in rect.y..rect.bottom() {
+        let offsets: Vec<usize> = (rect.x..rect.right()).map(|x| pixel_offset(frame, x, y)).collect();
+        blur_line(frame, &offsets);
+    }
+    for x in rect.x..rect.right() {
+        let offsets: Vec<usize> = (rect.y..rect.bottom()).map(|y| pixel_offset(frame, x, y)).collect();
+        blur_line(frame, &offsets);
+    }
+}
+
+fn pixelate(frame: &mut RawFrameData, rect: Rect, block: i32) {
+    for block_y in (rect.y..rect.bottom()).step_by(block as usize) {
+        for block_x in (rect.x..rect.right()).step_by(block as usize) {
+            let area = Rect::new(block_x, block_y, block, block).intersect(&rect);
+
+            let mut sum = [0u32; 3];
+            for y in area.y..area.bottom() {
+                for x in area.x..area.right() {
+                    let offset = pixel_offset(frame, x, y);
+                    for (sum, value) in sum.iter_mut().zip(&frame.data[offset..offset + 3]) {
+                        *sum += *value as u32;
+                    }
+                }
+            }
+            let count = (area.width * area.height) as u32;
+            let average = [(sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8];
+
+            for y in area.y..area.bottom() {
+                for x in area.x..area.right() {
+                    let offset = pixel_offset(frame, x, y);
+                    frame.data[offset..offset + 3].copy_from_slice(&average);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Gray frame, B, G and R of each pixel set to `values` row by row.
+    fn frame(width: i32, height: i32, values: &[u8]) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: values.iter().flat_map(|&value| [value, value, value, 255]).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn values(frame: &RawFrameData) -> Vec<u8> {
+        (0..frame.height).flat_map(|y| (0..frame.width).map(move |x| (x, y)))
+            .map(|(x, y)| frame.data[frame.pixel_offset(x as usize, y as usize)])
+            .collect()
+    }
+
+    #[test]
+    fn black_strided() {
+        let mut strided = RawFrameData{ row_pitch: 16, data: vec![200; 32], ..frame(3, 2, &[]) };
+        RedactionFilter::new(RedactionStyle::Black).region(Rect::new(1, 0, 5, 5)).apply(&mut strided, 0);
+
+        assert_eq!(values(&strided), vec![200, 0, 0, 200, 0, 0]);
+        // the padding is kept
+        assert_eq!(&strided.data[12..16], &[200; 4]);
+    }
+
+    #[test]
+    fn screen_regions() {
+        let mut filter = RedactionFilter::new(RedactionStyle::Black).origin(100, 50).screen_region(Rect::new(101, 51, 1, 1));
+
+        let mut whole = frame(3, 3, &[255; 9]);
+        filter.apply(&mut whole, 0);
+        assert_eq!(values(&whole), vec![255, 255, 255, 255, 0, 255, 255, 255, 255]);
+
+        // a frame of the region from x 1 of the screen
+        let mut cropped = RawFrameData{ info: FrameInfo{ region: Some(Rect::new(1, 0, 2, 3)), ..Default::default() }, ..frame(2, 3, &[255; 6]) };
+        filter.apply(&mut cropped, 0);
+        assert_eq!(values(&cropped), vec![255, 255, 0, 255, 255, 255]);
+    }
+
+    #[test]
+    fn pixelate_averages_blocks() {
+        let mut frame = frame(3, 1, &[0, 100, 30]);
+        RedactionFilter::new(RedactionStyle::Pixelate(2)).region(Rect::new(0, 0, 3, 1)).apply(&mut frame, 0);
+
+        // the last block is cut by the region
+        assert_eq!(values(&frame), vec![50, 50, 30]);
+    }
+
+    #[test]
+    fn blur_stays_inside() {
+        let mut frame = frame(4, 1, &[0, 90, 0, 90]);
+        RedactionFilter::new(RedactionStyle::Blur(1)).region(Rect::new(0, 0, 3, 1)).apply(&mut frame, 0);
+
+        assert_eq!(values(&frame), vec![38, 38, 38, 90]);
+        assert!(frame.data.chunks_exact(4).all(|pixel| pixel[3] == 255));
+    }
+}
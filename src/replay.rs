@@ -0,0 +1,310 @@
+use std::{
+    fs::File,
+    io::{
+        BufReader,
+        BufWriter,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::Mutex,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::source::FrameSource;
+
+
+/// Magic bytes at the head of a `.dxcap` file.
+pub const DXCAP_MAGIC: &[u8; 6] = b"DXCAP\0";
+/// Current version of the `.dxcap` format.
+pub const DXCAP_VERSION: u16 = 1;
+
+fn io_error(e: std::io::Error) -> CaptureError {
+    CaptureError::IoError(e.to_string())
+}
+
+
+/// Writer of the `.dxcap` format.
+///
+/// layout is the [DXCAP_MAGIC], a little endian `u16` version and then each frame as
+/// `timestamp_us: u64`, `width: i32`, `height: i32`, `len: u32` followed by `len` bytes of BGRA.
+pub struct DxcapWriter {
+    writer: BufWriter<File>,
+}
+impl DxcapWriter {
+    /// Create a new `.dxcap` file. existing file is truncated.
+    pub fn create<P: AsRef<Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(io_error)?);
+        writer.write_all(DXCAP_MAGIC).map_err(io_error)?;
+        writer.write_all(&DXCAP_VERSION.to_le_bytes()).map_err(io_error)?;
+
+        Ok(Self{ writer })
+    }
+
+    /// Append a frame. `timestamp` is the time since the start of the recording.
+    pub fn write_frame(&mut self, frame: &RawFrameData, timestamp: Duration) -> anyhow::Result<(), CaptureError> {
+        let (width, height) = (frame.width.max(0) as usize, frame.height.max(0) as usize);
+        if !frame.has_all_rows() {
+            return Err(CaptureError::InvalidArgument("data is shorter than height * stride.".to_string()));
+        }
+
+        let writer = &mut self.writer;
+        writer.write_all(&(timestamp.as_micros() as u64).to_le_bytes()).map_err(io_error)?;
+        writer.write_all(&frame.width.to_le_bytes()).map_err(io_error)?;
+        writer.write_all(&frame.height.to_le_bytes()).map_err(io_error)?;
+        // the padding of the strided frames is not written
+        writer.write_all(&((width * height * 4) as u32).to_le_bytes()).map_err(io_error)?;
+        for y in 0..height {
+            writer.write_all(frame.row(y)).map_err(io_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush the remaining frames to the disk.
+    pub fn finish(mut self) -> anyhow::Result<(), CaptureError> {
+        self.writer.flush().map_err(io_error)
+    }
+}
+
+
+/// Reader of the `.dxcap` format, frame by frame.
+pub struct DxcapReader {
+    reader: BufReader<File>,
+}
+impl DxcapReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        let mut reader = BufReader::new(File::open(path).map_err(io_error)?);
+
+        let mut magic = [0u8; 6];
+        reader.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != DXCAP_MAGIC {
+            return Err(CaptureError::IoError("Not a dxcap file.".to_string()));
+        }
+        let mut version = [0u8; 2];
+        reader.read_exact(&mut version).map_err(io_error)?;
+        if u16::from_le_bytes(version) != DXCAP_VERSION {
+            return Err(CaptureError::IoError("Unsupported dxcap version.".to_string()));
+        }
+
+        Ok(Self{ reader })
+    }
+
+    /// Read a next frame and its timestamp. `None` is the end of the file.
+    pub fn read_frame(&mut self) -> anyhow::Result<Option<(Duration, RawFrameData)>, CaptureError> {
+        let mut timestamp = [0u8; 8];
+        match self.reader.read_exact(&mut timestamp) {
+            Ok(_) => {},
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let mut word = [0u8; 4];
+        self.reader.read_exact(&mut word).map_err(io_error)?;
+        let width = i32::from_le_bytes(word);
+        self.reader.read_exact(&mut word).map_err(io_error)?;
+        let height = i32::from_le_bytes(word);
+        self.reader.read_exact(&mut word).map_err(io_error)?;
+        let len = u32::from_le_bytes(word);
+
+        // checked before allocating, a broken file could ask for gigabytes
+        let expected = (width as i64).checked_mul(height as i64).and_then(|pixels| pixels.checked_mul(4));
+        if width <= 0 || height <= 0 || expected != Some(len as i64) {
+            return Err(CaptureError::IoError(format!("Broken dxcap frame of {}x{} with {} bytes.", width, height, len)));
+        }
+
+        let mut data = vec![0u8; len as usize];
+        self.reader.read_exact(&mut data).map_err(io_error)?;
+
+        Ok(Some((
+            Duration::from_micros(u64::from_le_bytes(timestamp)),
+            RawFrameData{ width, height, data, ..Default::default() },
+        )))
+    }
+}
+
+
+struct ReplayState {
+    reader: DxcapReader,
+    start: Instant,
+    current: Option<RawFrameData>,
+    pending: Option<(Duration, RawFrameData)>,
+    finished: bool,
+}
+
+/// Replay a recorded `.dxcap` file with original timing.
+///
+/// can be used anywhere a [FrameSource] is expected, to develop and debug a pipeline offline.
+/// # Examples
+/// ```
+/// use dxcapture::FrameSource;
+///
+/// let source = dxcapture::ReplaySource::open("session.dxcap").unwrap();
+/// while !source.is_finished() {
+///     let raw = source.wait_raw_frame().expect("Failed to replay");
+///     // hoge raw
+/// }
+/// ```
+pub struct ReplaySource {
+    path: PathBuf,
+    looping: bool,
+    state: Mutex<ReplayState>,
+}
+impl ReplaySource {
+    /// Open a `.dxcap` file. the clock starts from here.
+    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self, CaptureError> {
+        let path = path.as_ref().to_path_buf();
+        let state = Self::new_state(&path)?;
+
+        Ok(Self{
+            path,
+            looping: false,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Start over from the first frame after the last one instead of finishing.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Whether the last frame has been reached. never true with [looping](Self::looping).
+    pub fn is_finished(&self) -> bool {
+        self.state.lock().unwrap().finished
+    }
+
+    /// Rewind to the first frame and restart the clock.
+    pub fn restart(&self) -> anyhow::Result<(), CaptureError> {
+        *self.state.lock().unwrap() = Self::new_state(&self.path)?;
+
+        Ok(())
+    }
+
+    fn new_state(path: &Path) -> anyhow::Result<ReplayState, CaptureError> {
+        let mut reader = DxcapReader::open(path)?;
+        let pending = reader.read_frame()?;
+
+        Ok(ReplayState{
+            reader,
+            start: Instant::now(),
+            current: None,
+            finished: pending.is_none(),
+            pending,
+        })
+    }
+}
+impl FrameSource for ReplaySource {
+    /// Return the frame which should be on the screen at the moment.
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let mut state = self.state.lock().unwrap();
+
+        let elapsed = state.start.elapsed();
+        while let Some((timestamp, _)) = &state.pending {
+            if elapsed < *timestamp {
+                break;
+            }
+
+            let (_, frame) = state.pending.take().unwrap();
+            state.current = Some(frame);
+            state.pending = state.reader.read_frame()?;
+        }
+
+        if state.pending.is_none() {
+            if self.looping {
+                let current = state.current.take();
+                *state = Self::new_state(&self.path)?;
+                state.current = current;
+            } else {
+                state.finished = true;
+            }
+        }
+
+        match state.current.clone() {
+            Some(frame) => Ok(frame),
+            None if state.finished => Err(CaptureError::NotActive),
+            None => Err(CaptureError::NoTexture),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Path in the temp directory, removed when dropped.
+    struct TempPath(PathBuf);
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("dxcapture-{}-{}", std::process::id(), name)))
+        }
+    }
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = TempPath::new("round_trip.dxcap");
+        let first = RawFrameData{ width: 2, height: 1, data: vec![1, 2, 3, 4, 5, 6, 7, 8], ..Default::default() };
+        // the padding is not written
+        let strided = RawFrameData{ width: 1, height: 2, data: vec![1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 7, 8], row_pitch: 8, ..Default::default() };
+
+        let mut writer = DxcapWriter::create(&path.0).unwrap();
+        writer.write_frame(&first, Duration::ZERO).unwrap();
+        writer.write_frame(&strided, Duration::from_micros(16_667)).unwrap();
+        writer.finish().unwrap();
+
+        let mut reader = DxcapReader::open(&path.0).unwrap();
+        let (timestamp, frame) = reader.read_frame().unwrap().unwrap();
+        assert_eq!(timestamp, Duration::ZERO);
+        assert_eq!((frame.width, frame.height, frame.data), (2, 1, first.data));
+        let (timestamp, frame) = reader.read_frame().unwrap().unwrap();
+        assert_eq!(timestamp, Duration::from_micros(16_667));
+        assert_eq!((frame.width, frame.height, frame.data), (1, 2, vec![1, 2, 3, 4, 5, 6, 7, 8]));
+        assert!(reader.read_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn short_frame_is_rejected() {
+        let path = TempPath::new("short_frame.dxcap");
+        let mut writer = DxcapWriter::create(&path.0).unwrap();
+        let short = RawFrameData{ width: 2, height: 2, data: vec![0; 8], ..Default::default() };
+
+        assert!(matches!(writer.write_frame(&short, Duration::ZERO), Err(CaptureError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn broken_files() {
+        let path = TempPath::new("broken.dxcap");
+
+        std::fs::write(&path.0, b"NOTCAP\x01\x00").unwrap();
+        assert!(matches!(DxcapReader::open(&path.0), Err(CaptureError::IoError(_))));
+
+        // a frame of 2x2 claiming 8 bytes
+        let mut file = DXCAP_MAGIC.to_vec();
+        file.extend_from_slice(&DXCAP_VERSION.to_le_bytes());
+        file.extend_from_slice(&0u64.to_le_bytes());
+        file.extend_from_slice(&2i32.to_le_bytes());
+        file.extend_from_slice(&2i32.to_le_bytes());
+        file.extend_from_slice(&8u32.to_le_bytes());
+        file.extend_from_slice(&[0; 8]);
+        std::fs::write(&path.0, file).unwrap();
+
+        let mut reader = DxcapReader::open(&path.0).unwrap();
+        assert!(matches!(reader.read_frame(), Err(CaptureError::IoError(_))));
+    }
+}
@@ -0,0 +1,396 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::capture::CaptureError;
+use crate::recorder::{
+    DxcapMuxer,
+    EncodedPacket,
+    Encoder,
+    Muxer,
+};
+use crate::priority::ThreadOptions;
+use crate::source::FrameSource;
+
+
+/// Options of [ReplayBuffer].
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayBufferOptions {
+    /// frames per second taken from the source.
+    pub fps: u32,
+    /// how far back is kept. the longest clip that can be saved.
+    pub length: Duration,
+    /// also keep the packets under this many bytes, dropping the oldest, so less than [length](Self::length)
+    /// is kept when it is smaller than [required_bytes](Self::required_bytes).
+    /// `None`, the default, is sized to hold all of `length` at the size of the newest frame.
+    pub max_bytes: Option<u64>,
+    /// priority and affinity of the capture thread.
+    pub thread: Option<ThreadOptions>,
+}
+impl ReplayBufferOptions {
+    /// Bytes of [length](Self::length) of the [RawEncoder](crate::RawEncoder) frames of `width` x `height`,
+    /// which the buffer holds with the default [max_bytes](Self::max_bytes).
+    /// about 2.5 GB for the default 10 seconds at 1080p.
+    pub fn required_bytes(&self, width: i32, height: i32) -> u64 {
+        self.frames_in_length() * width.max(0) as u64 * height.max(0) as u64 * 4
+    }
+
+    /// Frames of [length](Self::length), both ends included.
+    fn frames_in_length(&self) -> u64 {
+        let nanos = self.length.as_nanos() * self.fps as u128;
+        ((nanos + 999_999_999) / 1_000_000_000) as u64 + 1
+    }
+
+    /// [max_bytes](Self::max_bytes), or the bytes of [length](Self::length) of the frames of `packet_bytes`.
+    fn cap(&self, packet_bytes: usize) -> u64 {
+        self.max_bytes.unwrap_or_else(|| self.frames_in_length() * packet_bytes as u64)
+    }
+}
+impl Default for ReplayBufferOptions {
+    /// 10 seconds at 30 fps, sized to hold all of it.
+    fn default() -> Self {
+        Self{
+            fps: 30,
+            length: Duration::from_secs(10),
+            max_bytes: None,
+            thread: None,
+        }
+    }
+}
+
+/// Clip written by [ReplayBuffer::save_clip].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClipInfo {
+    pub frames: u64,
+    pub duration: Duration,
+    pub bytes: u64,
+}
+
+
+#[derive(Default)]
+struct PacketRing {
+    packets: VecDeque<EncodedPacket>,
+    bytes: u64,
+}
+impl PacketRing {
+    fn push(&mut self, packet: EncodedPacket, options: &ReplayBufferOptions) {
+        self.bytes += packet.data.len() as u64;
+        self.packets.push_back(packet);
+
+        let (newest, cap) = self.packets.back().map_or((Duration::ZERO, 0), |packet| (packet.timestamp, options.cap(packet.data.len())));
+        let too_old = |packet: &EncodedPacket| options.length < newest.saturating_sub(packet.timestamp);
+        let too_big = |bytes: u64| cap < bytes;
+        while self.packets.front().is_some_and(|oldest| too_old(oldest) || too_big(self.bytes)) {
+            self.pop_front();
+        }
+        // the clips can only start at a keyframe
+        while 1 < self.packets.len() && self.packets.front().is_some_and(|oldest| !oldest.keyframe) {
+            self.pop_front();
+        }
+    }
+
+    fn pop_front(&mut self) {
+        if let Some(packet) = self.packets.pop_front() {
+            self.bytes -= packet.data.len() as u64;
+        }
+    }
+}
+
+struct ReplayShared {
+    stopped: Mutex<bool>,
+    stop_requested: Condvar,
+    ring: Mutex<PacketRing>,
+}
+
+
+/// Keep the last [length](ReplayBufferOptions::length) of frames encoded in memory, and save them as a clip on demand.
+///
+/// for "clip that!": nothing is written to the disk until [save_clip](Self::save_clip) is called,
+/// from a hotkey, an API call or a test failure. the frames are encoded as they are taken, so pick an [Encoder]
+/// making keyframes often enough; the clips start at the first keyframe in the requested duration.
+///
+/// the `.mp4` clips of [save_clip](Self::save_clip) take only the raw BGRA packets of [RawEncoder](crate::RawEncoder),
+/// so the frames are held uncompressed: [required_bytes](ReplayBufferOptions::required_bytes) tells the memory
+/// for the size, and a lower fps, a shorter length or a smaller [output size](crate::Capture::set_output_size) cuts it.
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// let replay = dxcapture::ReplayBuffer::start(
+///     || {
+///         let device = dxcapture::Device::new_from_displays(None)?;
+///         Ok(dxcapture::Capture::new(&device)?)
+///     },
+///     dxcapture::RawEncoder,
+///     dxcapture::ReplayBufferOptions{ fps: 10, length: Duration::from_secs(20), ..Default::default() },
+/// ).unwrap();
+///
+/// std::thread::sleep(Duration::from_secs(30));
+/// // the last 15 seconds
+/// let clip = replay.save_clip("clip.mp4", Duration::from_secs(15)).expect("Failed to save");
+/// ```
+pub struct ReplayBuffer {
+    options: ReplayBufferOptions,
+    shared: Arc<ReplayShared>,
+    thread: Option<JoinHandle<anyhow::Result<(), CaptureError>>>,
+}
+impl ReplayBuffer {
+    /// Start buffering.
+    pub fn start<S, F, E>(source_factory: F, encoder: E, options: ReplayBufferOptions) -> anyhow::Result<Self, CaptureError>
+    where
+        S: FrameSource,
+        F: FnOnce() -> anyhow::Result<S> + Send + 'static,
+        E: Encoder + 'static,
+    {
+        if options.fps == 0 {
+            return Err(CaptureError::InvalidArgument("fps must be greater than 0.".to_string()));
+        }
+
+        let shared = Arc::new(ReplayShared{
+            stopped: Mutex::new(false),
+            stop_requested: Condvar::new(),
+            ring: Mutex::new(PacketRing::default()),
+        });
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+
+            move || Self::run(&shared, source_factory, encoder, options)
+        });
+
+        Ok(Self{
+            options,
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    fn run<S, F, E>(shared: &ReplayShared, source_factory: F, mut encoder: E, options: ReplayBufferOptions) -> anyhow::Result<(), CaptureError>
+    where
+        S: FrameSource,
+        F: FnOnce() -> anyhow::Result<S>,
+        E: Encoder,
+    {
+        let _thread = options.thread.map(|thread| thread.apply()).transpose()?;
+        let source = source_factory().map_err(|e| match e.downcast::<CaptureError>() {
+            Ok(e) => e,
+            Err(e) => CaptureError::SourceError(e.to_string()),
+        })?;
+
+        let interval = Duration::from_secs(1) / options.fps;
+        let start = Instant::now();
+        let mut next_tick = start;
+
+        loop {
+            // sleep until the next tick, waking up on stop
+            {
+                let mut stopped = shared.stopped.lock().unwrap();
+                while !*stopped {
+                    let wait = match next_tick.checked_duration_since(Instant::now()) {
+                        Some(wait) => wait,
+                        None => break,
+                    };
+                    stopped = shared.stop_requested.wait_timeout(stopped, wait).unwrap().0;
+                }
+                if *stopped {
+                    break;
+                }
+            }
+            // skip the ticks already passed
+            while next_tick <= Instant::now() {
+                next_tick += interval;
+            }
+
+            let frame = match source.get_raw_frame() {
+                Ok(frame) => frame,
+                Err(CaptureError::NoTexture) => continue,
+                Err(e) => return Err(e),
+            };
+
+            let packets = encoder.encode(&frame, start.elapsed())?;
+            let mut ring = shared.ring.lock().unwrap();
+            for packet in packets {
+                ring.push(packet, &options);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Options it was started with.
+    pub fn options(&self) -> ReplayBufferOptions {
+        self.options
+    }
+
+    /// Time held in memory now.
+    pub fn buffered(&self) -> Duration {
+        let ring = self.shared.ring.lock().unwrap();
+        match (ring.packets.front(), ring.packets.back()) {
+            (Some(oldest), Some(newest)) => newest.timestamp - oldest.timestamp,
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Bytes of the packets held in memory now.
+    pub fn buffered_bytes(&self) -> u64 {
+        self.shared.ring.lock().unwrap().bytes
+    }
+
+    /// Write the last `duration` into a video file. [Read more](Self::save_clip_to)
+    ///
+    /// an `.mp4` with [Mp4Muxer](crate::Mp4Muxer) at the fps of the options, which takes only the packets of
+    /// [RawEncoder](crate::RawEncoder), failing on the others. a `.dxcap` with [DxcapMuxer] when the extension
+    /// of `path` is `dxcap`, or without the `mp4` feature.
+    pub fn save_clip<P: AsRef<Path>>(&self, path: P, duration: Duration) -> anyhow::Result<ClipInfo, CaptureError> {
+        let path = path.as_ref();
+
+        #[cfg(feature = "mp4")]
+        if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("dxcap")) {
+            let options = crate::mp4::Mp4Options{ fps: self.options.fps, ..Default::default() };
+            return self.save_clip_to(crate::mp4::Mp4Muxer::create(path, options)?, duration);
+        }
+
+        self.save_clip_to(DxcapMuxer::create(path)?, duration)
+    }
+
+    /// Write the last `duration` into `muxer` and finish it. the timestamps of the clip start from 0.
+    ///
+    /// buffering goes on while saving.
+    /// ## Errors
+    /// * [NoTexture](CaptureError::NoTexture): nothing is buffered yet.
+    pub fn save_clip_to<M: Muxer>(&self, mut muxer: M, duration: Duration) -> anyhow::Result<ClipInfo, CaptureError> {
+        // copy out not to stop the buffering while writing
+        let packets: Vec<EncodedPacket> = {
+            let ring = self.shared.ring.lock().unwrap();
+            let newest = ring.packets.back().ok_or(CaptureError::NoTexture)?.timestamp;
+            let from = newest.saturating_sub(duration);
+
+            // or the last keyframe before, when there is none in the duration
+            let first = ring.packets.iter()
+                .position(|packet| from <= packet.timestamp && packet.keyframe)
+                .or_else(|| ring.packets.iter().rposition(|packet| packet.keyframe))
+                .unwrap_or(0);
+            ring.packets.iter().skip(first).cloned().collect()
+        };
+
+        let origin = packets.first().map_or(Duration::ZERO, |packet| packet.timestamp);
+        let mut clip = ClipInfo::default();
+        for mut packet in packets {
+            packet.timestamp -= origin;
+            clip.duration = packet.timestamp;
+            clip.bytes += packet.data.len() as u64;
+            clip.frames += 1;
+
+            muxer.write_packet(&packet)?;
+        }
+        muxer.finish()?;
+
+        Ok(clip)
+    }
+
+    /// Stop buffering and drop the buffered frames.
+    pub fn stop(mut self) -> anyhow::Result<(), CaptureError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError> {
+        *self.shared.stopped.lock().unwrap() = true;
+        self.shared.stop_requested.notify_all();
+
+        match self.thread.take() {
+            Some(thread) => thread.join().unwrap_or(Err(CaptureError::SourceError("Replay buffer thread panicked.".to_string()))),
+            None => Ok(()),
+        }
+    }
+}
+impl Drop for ReplayBuffer {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(length: u64, max_bytes: Option<u64>) -> ReplayBufferOptions {
+        ReplayBufferOptions{
+            fps: 10,
+            length: Duration::from_millis(length),
+            max_bytes,
+            thread: None,
+        }
+    }
+
+    fn packet(millis: u64, keyframe: bool) -> EncodedPacket {
+        EncodedPacket{
+            timestamp: Duration::from_millis(millis),
+            keyframe,
+            data: vec![0; 4],
+            ..Default::default()
+        }
+    }
+
+    fn timestamps(ring: &PacketRing) -> Vec<u64> {
+        ring.packets.iter().map(|packet| packet.timestamp.as_millis() as u64).collect()
+    }
+
+    #[test]
+    fn keeps_the_length() {
+        let options = options(300, None);
+        let mut ring = PacketRing::default();
+        for i in 0..10 {
+            ring.push(packet(i * 100, true), &options);
+        }
+
+        assert_eq!(timestamps(&ring), vec![600, 700, 800, 900]);
+        assert_eq!(ring.bytes, 16);
+    }
+
+    #[test]
+    fn cap_drops_the_oldest() {
+        let options = options(1000, Some(10));
+        let mut ring = PacketRing::default();
+        for i in 0..3 {
+            ring.push(packet(i * 100, true), &options);
+        }
+
+        assert_eq!(timestamps(&ring), vec![100, 200]);
+        assert_eq!(ring.bytes, 8);
+    }
+
+    #[test]
+    fn starts_at_a_keyframe() {
+        let options = options(200, None);
+        let mut ring = PacketRing::default();
+        for (millis, keyframe) in [(0, true), (100, false), (200, false), (300, true), (400, false)] {
+            ring.push(packet(millis, keyframe), &options);
+        }
+
+        // the deltas after the dropped keyframe go with it
+        assert_eq!(timestamps(&ring), vec![300, 400]);
+        assert_eq!(ring.bytes, 8);
+    }
+
+    #[test]
+    fn default_cap_holds_the_length() {
+        let options = ReplayBufferOptions::default();
+
+        assert_eq!(options.required_bytes(1920, 1080), 301 * 1920 * 1080 * 4);
+        assert_eq!(options.cap(16), 301 * 16);
+        assert_eq!(self::options(250, Some(7)).cap(16), 7);
+        assert_eq!(self::options(250, None).required_bytes(-1, 10), 0);
+    }
+}
@@ -0,0 +1,256 @@
+//! One-liner screenshots.
+//!
+//! create the [Device] and [Capture], wait for the first frame up to [SCREENSHOT_TIMEOUT] and release all of them.
+//! for taking many frames, keep a [Capture] instead.
+
+use std::time::Duration;
+
+#[cfg(feature = "img")]
+use std::path::Path;
+
+use winapi::{
+    shared::windef::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+    um::winuser::SetThreadDpiAwarenessContext,
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    RawFrameData,
+    Rect,
+};
+use crate::d3d::Device;
+use crate::displays::{
+    enumerate_displays,
+    DisplayInfo,
+};
+
+#[cfg(feature = "img")]
+use crate::capture::ImgFrameData;
+
+
+/// How long the screenshots wait for the first frame, before failing with [Timeout](crate::CaptureError::Timeout).
+///
+/// minimized windows never deliver frames.
+pub const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn take_raw(device: Device) -> anyhow::Result<RawFrameData> {
+    let capture = Capture::new(&device)?;
+    let raw = capture.wait_raw_frame_timeout(SCREENSHOT_TIMEOUT)?;
+    capture.stop()?;
+
+    Ok(raw)
+}
+
+/// Take a screenshot of the primary monitor.
+/// # Examples
+/// ```
+/// let raw = dxcapture::screenshot_primary().expect("Failed to capture");
+/// ```
+pub fn screenshot_primary() -> anyhow::Result<RawFrameData> {
+    take_raw(Device::new_from_displays(None)?)
+}
+
+/// Take a screenshot of a display.
+/// ## Parameters
+/// * display_id: same as [Device::new_from_displays]. range is [1..=len].
+pub fn screenshot_display(display_id: usize) -> anyhow::Result<RawFrameData> {
+    take_raw(Device::new_from_displays(Some(display_id))?)
+}
+
+/// Take a screenshot of the first window whose caption contains `query`.
+pub fn screenshot_window(query: &str) -> anyhow::Result<RawFrameData> {
+    take_raw(Device::new_from_window(query.to_string())?)
+}
+
+
+/// Compose frames into one frame by their positions on the virtual desktop.
+///
+/// the rects are in physical pixels, as the frames are. frames not in the size of their rect are scaled to fit.
+/// gaps are filled with `background`. (BGRA)
+/// ## Errors
+/// * [NoDisplaysAttached](CaptureError::NoDisplaysAttached): `frames` is empty.
+pub fn compose_panorama(frames: &[(Rect, RawFrameData)], background: [u8; 4]) -> anyhow::Result<RawFrameData, CaptureError> {
+    if frames.is_empty() {
+        return Err(CaptureError::NoDisplaysAttached);
+    }
+    let bounds = frames.iter().fold(Rect::default(), |bounds, (rect, _)| bounds.union(rect));
+    let mut data = background.repeat((bounds.width.max(0) * bounds.height.max(0)) as usize);
+
+    for (rect, frame) in frames {
+        if rect.is_empty() || frame.width <= 0 || frame.height <= 0 {
+            continue;
+        }
+
+        for y in 0..rect.height {
+            let source_y = (y as i64 * frame.height as i64 / rect.height as i64) as i32;
+            for x in 0..rect.width {
+                let source_x = (x as i64 * frame.width as i64 / rect.width as i64) as i32;
+                let source = frame.pixel_offset(source_x as usize, source_y as usize);
+                let offset = (((rect.y - bounds.y + y) * bounds.width + (rect.x - bounds.x + x)) * 4) as usize;
+                if let Some(pixel) = frame.data.get(source..source + 4) {
+                    data[offset..offset + 4].copy_from_slice(pixel);
+                }
+            }
+        }
+    }
+
+    Ok(RawFrameData{
+        width: bounds.width,
+        height: bounds.height,
+        data,
+        ..Default::default()
+    })
+}
+
+/// Displays with their rects in physical pixels, whatever the DPI awareness of the app is.
+fn enumerate_displays_physical() -> anyhow::Result<Vec<DisplayInfo>, CaptureError> {
+    unsafe {
+        let previous = SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+        let displays = enumerate_displays();
+        if !previous.is_null() {
+            SetThreadDpiAwarenessContext(previous);
+        }
+        displays
+    }
+}
+
+/// Take a screenshot of all displays composed into one, laid out as on the virtual desktop in physical pixels.
+///
+/// the gaps between displays of different sizes are black.
+/// ## Errors
+/// * [NoDisplaysAttached](CaptureError::NoDisplaysAttached): no display is attached.
+/// # Examples
+/// ```
+/// let raw = dxcapture::screenshot_all_displays().expect("Failed to capture");
+/// ```
+pub fn screenshot_all_displays() -> anyhow::Result<RawFrameData> {
+    let displays = enumerate_displays_physical()?;
+
+    let mut frames = Vec::with_capacity(displays.len());
+    for (index, display) in displays.iter().enumerate() {
+        frames.push((display.rect, take_raw(Device::new_from_displays(Some(index + 1))?)?));
+    }
+
+    Ok(compose_panorama(&frames, [0, 0, 0, 255])?)
+}
+
+
+#[cfg(feature = "img")]
+fn take_img(device: Device) -> anyhow::Result<ImgFrameData> {
+    let capture = Capture::new(&device)?;
+    let image = capture.wait_img_frame_timeout(SCREENSHOT_TIMEOUT)?;
+    capture.stop()?;
+
+    Ok(image)
+}
+
+/// Take a screenshot of the primary monitor as image.
+///
+/// Required features: *`"img"`*
+/// # Examples
+/// ```
+/// let image = dxcapture::screenshot_primary_img().expect("Failed to capture");
+/// image.data.save("image.png").expect("Failed to save");
+/// ```
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn screenshot_primary_img() -> anyhow::Result<ImgFrameData> {
+    take_img(Device::new_from_displays(None)?)
+}
+
+/// Take a screenshot of a display as image.
+///
+/// Required features: *`"img"`*
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn screenshot_display_img(display_id: usize) -> anyhow::Result<ImgFrameData> {
+    take_img(Device::new_from_displays(Some(display_id))?)
+}
+
+/// Take a screenshot of the first window whose caption contains `query` as image.
+///
+/// Required features: *`"img"`*
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn screenshot_window_img(query: &str) -> anyhow::Result<ImgFrameData> {
+    take_img(Device::new_from_window(query.to_string())?)
+}
+
+/// Take a screenshot of all displays composed into one as image.
+///
+/// Required features: *`"img"`*
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn screenshot_all_displays_img() -> anyhow::Result<ImgFrameData> {
+    Ok(ImgFrameData::from_raw(screenshot_all_displays()?))
+}
+
+/// Take a screenshot of the primary monitor and save it as PNG.
+///
+/// Required features: *`"img"`*
+/// # Examples
+/// ```
+/// dxcapture::save_png("screenshot.png").expect("Failed to capture");
+/// ```
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn save_png<P: AsRef<Path>>(path: P) -> anyhow::Result<()> {
+    screenshot_primary_img()?.save_png(path)?;
+
+    Ok(())
+}
+
+/// Take a screenshot of the first window whose caption contains `query` and save it as PNG.
+///
+/// Required features: *`"img"`*
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub fn save_window_png<P: AsRef<Path>>(query: &str, path: P) -> anyhow::Result<()> {
+    screenshot_window_img(query)?.save_png(path)?;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: i32, height: i32, pixel: [u8; 4]) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: pixel.repeat((width * height) as usize),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_frames() {
+        assert!(matches!(compose_panorama(&[], [0, 0, 0, 255]), Err(CaptureError::NoDisplaysAttached)));
+    }
+
+    #[test]
+    fn frames_at_their_rects() {
+        let frames = [
+            (Rect::new(0, 0, 2, 2), solid(2, 2, [1, 1, 1, 255])),
+            (Rect::new(2, 1, 1, 1), solid(1, 1, [2, 2, 2, 255])),
+        ];
+        let panorama = compose_panorama(&frames, [0, 0, 0, 255]).unwrap();
+
+        assert_eq!((panorama.width, panorama.height), (3, 2));
+        assert_eq!(&panorama.data[0..12], &[1, 1, 1, 255, 1, 1, 1, 255, 0, 0, 0, 255]);
+        assert_eq!(&panorama.data[12..24], &[1, 1, 1, 255, 1, 1, 1, 255, 2, 2, 2, 255]);
+    }
+
+    #[test]
+    fn frame_scaled_to_its_rect() {
+        let mut frame = solid(2, 1, [1, 1, 1, 255]);
+        frame.data[4..8].copy_from_slice(&[2, 2, 2, 255]);
+        let panorama = compose_panorama(&[(Rect::new(-4, 0, 4, 1), frame)], [0, 0, 0, 255]).unwrap();
+
+        assert_eq!((panorama.width, panorama.height), (4, 1));
+        assert_eq!(panorama.data, [[1, 1, 1, 255], [1, 1, 1, 255], [2, 2, 2, 255], [2, 2, 2, 255]].concat());
+    }
+}
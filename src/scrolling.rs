@@ -0,0 +1,273 @@
+//! Scrolling ("full page") screenshots.
+//!
+//! the window is scrolled by the mouse wheel while capturing, and the overlapping frames are stitched into one tall frame.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{
+        Hash,
+        Hasher,
+    },
+    time::Duration,
+};
+
+use winapi::{
+    shared::windef::{
+        HWND,
+        POINT,
+        RECT,
+    },
+    um::winuser::{
+        GetCursorPos,
+        GetWindowRect,
+        PostMessageW,
+        SendInput,
+        SetCursorPos,
+        SetForegroundWindow,
+        INPUT,
+        INPUT_MOUSE,
+        MOUSEEVENTF_WHEEL,
+        MOUSEINPUT,
+        WHEEL_DELTA,
+        WM_MOUSEWHEEL,
+    },
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    RawFrameData,
+};
+use crate::d3d::Device;
+use crate::window_finder::find_window;
+
+
+/// Options of [stitch_vertical].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StitchOptions {
+    /// rows at the top not scrolling, such as toolbars and sticky headers. kept from the first frame only.
+    pub ignore_top: i32,
+    /// rows at the bottom not scrolling, such as status bars. kept from the last frame only.
+    pub ignore_bottom: i32,
+    /// columns at the right not compared, such as the scroll bar.
+    pub ignore_right: i32,
+    /// least rows two frames must share to be stitched.
+    pub min_overlap: i32,
+    /// matched rows in percent (0.0..=100.0) of the overlap needed to accept an offset.
+    pub min_match: u32,
+}
+impl Default for StitchOptions {
+    fn default() -> Self {
+        Self{
+            ignore_top: 0,
+            ignore_bottom: 0,
+            ignore_right: 24,
+            min_overlap: 32,
+            min_match: 90,
+        }
+    }
+}
+
+fn row_hashes(frame: &RawFrameData, options: &StitchOptions) -> Vec<u64> {
+    let width = (frame.width - options.ignore_right).max(1) as usize;
+    (0..frame.height).map(|y| {
+        let mut hasher = DefaultHasher::new();
+        frame.row(y as usize)[..width * 4].hash(&mut hasher);
+        hasher.finish()
+    }).collect()
+}
+
+/// Rows `next` is scrolled down from `previous`, compared on the scrolling area. `None` if they don't overlap.
+fn scroll_offset(previous: &[u64], next: &[u64], options: &StitchOptions) -> Option<usize> {
+    let len = previous.len().min(next.len());
+    if len == 0 {
+        return Some(0);
+    }
+    let min_overlap = (options.min_overlap.max(1) as usize).min(len);
+
+    let mut best: Option<(usize, f64)> = None;
+    for offset in 0..=len - min_overlap {
+        let overlap = len - offset;
+        let matched = (0..overlap).filter(|&row| previous[row + offset] == next[row]).count();
+        if matched * 100 < overlap * options.min_match as usize {
+            continue;
+        }
+
+        // the smallest offset wins ties, as blank rows match at many offsets
+        let ratio = matched as f64 / overlap as f64;
+        if best.map_or(true, |(_, best_ratio)| best_ratio < ratio) {
+            best = Some((offset, ratio));
+        }
+    }
+
+    best.map(|(offset, _)| offset)
+}
+
+/// Stitch frames of a vertically scrolling view into one tall frame.
+///
+/// each frame must be scrolled down from the previous one, less than its height. frames not scrolled are skipped.
+/// ## Errors
+/// * [SizeMismatch](CaptureError::SizeMismatch): the frames differ in size.
+/// * [InvalidArgument](CaptureError::InvalidArgument): no frames, or two frames have no overlap.
+pub fn stitch_vertical(frames: &[RawFrameData], options: &StitchOptions) -> anyhow::Result<RawFrameData, CaptureError> {
+    let first = frames.first().ok_or_else(|| CaptureError::InvalidArgument("no frames to stitch.".to_string()))?;
+    if frames.iter().any(|frame| frame.width != first.width || frame.height != first.height) {
+        return Err(CaptureError::SizeMismatch);
+    }
+
+    let top = options.ignore_top.clamp(0, first.height) as usize;
+    let bottom = (first.height - options.ignore_bottom).clamp(top as i32, first.height) as usize;
+    let row_bytes = (first.width * 4) as usize;
+    let rows = |frame: &RawFrameData, range: std::ops::Range<usize>, data: &mut Vec<u8>| {
+        for y in range {
+            data.extend_from_slice(frame.row(y));
+        }
+    };
+
+    // header and the scrolling area of the first frame
+    let mut data = Vec::new();
+    rows(first, 0..bottom, &mut data);
+    let mut previous = row_hashes(first, options);
+    for (index, frame) in frames.iter().enumerate().skip(1) {
+        let hashes = row_hashes(frame, options);
+        let offset = scroll_offset(&previous[top..bottom], &hashes[top..bottom], options)
+            .ok_or_else(|| CaptureError::InvalidArgument(format!("frame {} doesn't overlap the previous one.", index)))?;
+
+        // rows scrolled in at the bottom of the scrolling area
+        rows(frame, bottom - offset..bottom, &mut data);
+        previous = hashes;
+    }
+    // footer of the last frame
+    let last = frames.last().unwrap();
+    rows(last, bottom..first.height as usize, &mut data);
+
+    Ok(RawFrameData{
+        width: first.width,
+        height: (data.len() / row_bytes) as i32,
+        data,
+        ..Default::default()
+    })
+}
+
+
+/// How the window is scrolled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScrollInput {
+    /// real wheel input with `SendInput` over the center of the window. works with most applications,
+    /// but brings the window to the front and moves the cursor while scrolling.
+    MouseWheel,
+    /// `WM_MOUSEWHEEL` posted to the window. leaves the cursor alone, but some applications ignore it.
+    WindowMessage,
+}
+
+/// Options of [screenshot_scrolling].
+#[derive(Clone, Copy, Debug)]
+pub struct ScrollOptions {
+    pub input: ScrollInput,
+    /// wheel notches per scroll. must scroll less than a screen.
+    pub wheel_clicks: u32,
+    /// stop after this many scrolls even if the end is not reached.
+    pub max_scrolls: usize,
+    /// how long the frames must keep unchanged after a scroll, for smooth scrolling and lazy loading.
+    pub settle: Duration,
+    /// give up waiting the frames to settle after this.
+    pub settle_timeout: Duration,
+    pub stitch: StitchOptions,
+}
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        Self{
+            input: ScrollInput::MouseWheel,
+            wheel_clicks: 3,
+            max_scrolls: 50,
+            settle: Duration::from_millis(200),
+            settle_timeout: Duration::from_secs(3),
+            stitch: StitchOptions::default(),
+        }
+    }
+}
+
+fn scroll(window: HWND, input: ScrollInput, clicks: u32) {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(window, &mut rect) };
+    let (x, y) = ((rect.left + rect.right) / 2, (rect.top + rect.bottom) / 2);
+    let delta = -(WHEEL_DELTA as i32) * clicks as i32;
+
+    match input {
+        ScrollInput::MouseWheel => unsafe {
+            let mut cursor = POINT::default();
+            GetCursorPos(&mut cursor);
+            SetForegroundWindow(window);
+            SetCursorPos(x, y);
+
+            let mut input = INPUT{
+                type_: INPUT_MOUSE,
+                ..Default::default()
+            };
+            *input.u.mi_mut() = MOUSEINPUT{
+                mouseData: delta as u32,
+                dwFlags: MOUSEEVENTF_WHEEL,
+                ..Default::default()
+            };
+            SendInput(1, &mut input, std::mem::size_of::<INPUT>() as i32);
+
+            // wait the wheel to be delivered before moving the cursor back
+            std::thread::sleep(Duration::from_millis(50));
+            SetCursorPos(cursor.x, cursor.y);
+        },
+        ScrollInput::WindowMessage => unsafe {
+            let wparam = (delta as u16 as usize) << 16;
+            let lparam = ((y as u16 as isize) << 16) | (x as u16 as isize);
+            PostMessageW(window, WM_MOUSEWHEEL, wparam, lparam);
+        },
+    }
+}
+
+/// Take a scrolling screenshot of the first window whose caption contains `query`.
+///
+/// the window is scrolled down from where it is now until the frames stop changing or
+/// [max_scrolls](ScrollOptions::max_scrolls) is reached, and the frames are stitched with [stitch_vertical].
+/// scroll the window to the top before calling, to take the whole page.
+/// # Examples
+/// ```
+/// let options = dxcapture::ScrollOptions{
+///     stitch: dxcapture::StitchOptions{ ignore_top: 120, ..Default::default() },
+///     ..Default::default()
+/// };
+/// let page = dxcapture::screenshot_scrolling("Mozilla Firefox", &options).expect("Failed to capture");
+/// ```
+pub fn screenshot_scrolling(query: &str, options: &ScrollOptions) -> anyhow::Result<RawFrameData> {
+    let window = find_window(query).first().map(|window| window.handle)
+        .ok_or_else(|| anyhow::anyhow!("Window is not found"))?;
+    let device = Device::new_from_window(query.to_string())?;
+    let capture = Capture::new(&device)?;
+
+    let settled = |capture: &Capture| match capture.wait_for_stable(options.settle, 0.0, options.settle_timeout) {
+        // keeps changing (animations, videos). take it as it is
+        Err(CaptureError::Timeout) => capture.wait_raw_frame(),
+        result => result,
+    };
+
+    let mut frames = vec![settled(&capture)?];
+    let mut previous = row_hashes(&frames[0], &options.stitch);
+    for _ in 0..options.max_scrolls {
+        scroll(window, options.input, options.wheel_clicks);
+        let frame = settled(&capture)?;
+
+        if frame.width != frames[0].width || frame.height != frames[0].height {
+            break;
+        }
+        let hashes = row_hashes(&frame, &options.stitch);
+        let top = (options.stitch.ignore_top.max(0) as usize).min(hashes.len());
+        let bottom = hashes.len().saturating_sub(options.stitch.ignore_bottom.max(0) as usize).max(top);
+        // the end of the page
+        if hashes[top..bottom] == previous[top..bottom] {
+            break;
+        }
+
+        previous = hashes;
+        frames.push(frame);
+    }
+
+    Ok(stitch_vertical(&frames, &options.stitch)?)
+}
@@ -0,0 +1,364 @@
+//! Interactive selection of a region of the screen, like the Snipping Tool.
+//!
+//! a translucent window covers all the displays, and the user drags a rectangle on it.
+//!
+//! Required features: *`"selector"`*
+
+use std::{
+    cell::RefCell,
+    sync::Once,
+};
+
+use winapi::{
+    shared::{
+        minwindef::{
+            LPARAM,
+            LRESULT,
+            UINT,
+            WPARAM,
+        },
+        windef::{
+            HWND,
+            POINT,
+            RECT,
+            DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+        },
+    },
+    um::{
+        libloaderapi::GetModuleHandleW,
+        wingdi::{
+            CreateSolidBrush,
+            DeleteObject,
+            RGB,
+        },
+        winuser::{
+            BeginPaint,
+            CreateWindowExW,
+            DefWindowProcW,
+            DestroyWindow,
+            DispatchMessageW,
+            EndPaint,
+            FillRect,
+            FrameRect,
+            GetClientRect,
+            GetMessageW,
+            GetMonitorInfoW,
+            GetSystemMetrics,
+            InvalidateRect,
+            LoadCursorW,
+            MonitorFromRect,
+            PostQuitMessage,
+            RegisterClassExW,
+            ReleaseCapture,
+            SetCapture,
+            SetForegroundWindow,
+            SetLayeredWindowAttributes,
+            SetThreadDpiAwarenessContext,
+            ShowWindow,
+            TranslateMessage,
+            IDC_CROSS,
+            LWA_ALPHA,
+            LWA_COLORKEY,
+            MONITORINFO,
+            MONITOR_DEFAULTTONEAREST,
+            MSG,
+            PAINTSTRUCT,
+            SM_CXVIRTUALSCREEN,
+            SM_CYVIRTUALSCREEN,
+            SM_XVIRTUALSCREEN,
+            SM_YVIRTUALSCREEN,
+            SW_SHOW,
+            VK_ESCAPE,
+            WM_DESTROY,
+            WM_KEYDOWN,
+            WM_LBUTTONDOWN,
+            WM_LBUTTONUP,
+            WM_MOUSEMOVE,
+            WM_PAINT,
+            WM_RBUTTONUP,
+            WNDCLASSEXW,
+            WS_EX_LAYERED,
+            WS_EX_TOOLWINDOW,
+            WS_EX_TOPMOST,
+            WS_POPUP,
+        },
+    },
+};
+
+use crate::capture::{
+    Capture,
+    CaptureError,
+    Rect,
+};
+use crate::d3d::Device;
+
+
+const CLASS_NAME: &str = "dxcapture_selector";
+
+/// alpha of the dimmed screen.
+const DIM_ALPHA: u8 = 96;
+
+/// painted where the selection is, and made fully transparent by the color key.
+fn key_color() -> u32 {
+    RGB(255, 0, 255)
+}
+
+
+/// Drag on the selector window, in the screen coordinates.
+#[derive(Default)]
+struct DragState {
+    /// the virtual screen origin, where the client area starts.
+    origin: POINT,
+    start: Option<POINT>,
+    current: POINT,
+    /// `Some` once done, with `None` when cancelled.
+    result: Option<Option<RECT>>,
+}
+impl DragState {
+    fn rect(&self) -> Option<RECT> {
+        self.start.map(|start| RECT{
+            left: start.x.min(self.current.x),
+            top: start.y.min(self.current.y),
+            right: start.x.max(self.current.x),
+            bottom: start.y.max(self.current.y),
+        })
+    }
+}
+
+thread_local! {
+    /// drag of the selector window of this thread.
+    static DRAG: RefCell<DragState> = RefCell::new(DragState::default());
+}
+
+/// the client position in `lparam` of the mouse messages, in the screen coordinates.
+fn screen_point(lparam: LPARAM, origin: POINT) -> POINT {
+    POINT{
+        x: (lparam & 0xFFFF) as i16 as i32 + origin.x,
+        y: ((lparam >> 16) & 0xFFFF) as i16 as i32 + origin.y,
+    }
+}
+
+unsafe extern "system" fn window_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    match msg {
+        WM_LBUTTONDOWN => {
+            DRAG.with(|drag| {
+                let mut drag = drag.borrow_mut();
+                let point = screen_point(lparam, drag.origin);
+                drag.start = Some(point);
+                drag.current = point;
+            });
+            SetCapture(hwnd);
+            0
+        },
+        WM_MOUSEMOVE => {
+            let dragging = DRAG.with(|drag| {
+                let mut drag = drag.borrow_mut();
+                drag.current = screen_point(lparam, drag.origin);
+                drag.start.is_some()
+            });
+            if dragging {
+                InvalidateRect(hwnd, std::ptr::null(), 0);
+            }
+            0
+        },
+        WM_LBUTTONUP => {
+            ReleaseCapture();
+            DRAG.with(|drag| {
+                let mut drag = drag.borrow_mut();
+                drag.current = screen_point(lparam, drag.origin);
+                // a click without a drag selects nothing
+                let rect = drag.rect().filter(|rect| rect.left < rect.right && rect.top < rect.bottom);
+                drag.result = Some(rect);
+            });
+            DestroyWindow(hwnd);
+            0
+        },
+        WM_RBUTTONUP => {
+            DRAG.with(|drag| drag.borrow_mut().result = Some(None));
+            DestroyWindow(hwnd);
+            0
+        },
+        WM_KEYDOWN if wparam as i32 == VK_ESCAPE => {
+            DRAG.with(|drag| drag.borrow_mut().result = Some(None));
+            DestroyWindow(hwnd);
+            0
+        },
+        WM_PAINT => {
+            let mut paint = PAINTSTRUCT::default();
+            let dc = BeginPaint(hwnd, &mut paint);
+
+            let mut client = RECT::default();
+            GetClientRect(hwnd, &mut client);
+            let background = CreateSolidBrush(RGB(0, 0, 0));
+            FillRect(dc, &client, background);
+            DeleteObject(background as _);
+
+            let selection = DRAG.with(|drag| {
+                let drag = drag.borrow();
+                drag.rect().map(|rect| RECT{
+                    left: rect.left - drag.origin.x,
+                    top: rect.top - drag.origin.y,
+                    right: rect.right - drag.origin.x,
+                    bottom: rect.bottom - drag.origin.y,
+                })
+            });
+            if let Some(selection) = selection {
+                let hole = CreateSolidBrush(key_color());
+                FillRect(dc, &selection, hole);
+                DeleteObject(hole as _);
+
+                let frame = CreateSolidBrush(RGB(255, 255, 255));
+                FrameRect(dc, &selection, frame);
+                DeleteObject(frame as _);
+            }
+
+            EndPaint(hwnd, &paint);
+            0
+        },
+        WM_DESTROY => {
+            PostQuitMessage(0);
+            0
+        },
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}
+
+fn to_wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn register_class() {
+    static REGISTER: Once = Once::new();
+
+    REGISTER.call_once(|| unsafe {
+        let class_name = to_wide(CLASS_NAME);
+        let class = WNDCLASSEXW{
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(window_proc),
+            hInstance: GetModuleHandleW(std::ptr::null()),
+            hCursor: LoadCursorW(std::ptr::null_mut(), IDC_CROSS),
+            // copied by RegisterClassExW
+            lpszClassName: class_name.as_ptr(),
+            ..Default::default()
+        };
+        RegisterClassExW(&class);
+    });
+}
+
+/// Show the selector window on this thread until the user is done, and return the selection in the screen coordinates.
+fn run_selector() -> anyhow::Result<Option<RECT>, CaptureError> {
+    unsafe {
+        // the physical pixels of the frames, whatever the awareness of the app is
+        SetThreadDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+        register_class();
+
+        let origin = POINT{
+            x: GetSystemMetrics(SM_XVIRTUALSCREEN),
+            y: GetSystemMetrics(SM_YVIRTUALSCREEN),
+        };
+        DRAG.with(|drag| *drag.borrow_mut() = DragState{ origin, ..Default::default() });
+
+        let class_name = to_wide(CLASS_NAME);
+        let hwnd = CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_LAYERED | WS_EX_TOOLWINDOW,
+            class_name.as_ptr(),
+            class_name.as_ptr(),
+            WS_POPUP,
+            origin.x,
+            origin.y,
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            GetModuleHandleW(std::ptr::null()),
+            std::ptr::null_mut(),
+        );
+        if hwnd.is_null() {
+            return Err(CaptureError::DirectxError(windows::core::Error::from_win32()));
+        }
+        SetLayeredWindowAttributes(hwnd, key_color(), DIM_ALPHA, LWA_COLORKEY | LWA_ALPHA);
+        ShowWindow(hwnd, SW_SHOW);
+        // for the escape key
+        SetForegroundWindow(hwnd);
+
+        let mut msg = MSG::default();
+        while 0 < GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+
+    Ok(DRAG.with(|drag| drag.borrow_mut().result.take()).flatten())
+}
+
+
+/// Region the user selected by [select_region]. [Read more](select_region)
+///
+/// Required features: *`"selector"`*
+#[derive(Debug)]
+pub struct Selection {
+    /// the display the selection is on.
+    pub device: Device,
+    /// the selection in the frames of the display, clipped to it.
+    pub region: Rect,
+}
+impl Selection {
+    /// Start capturing only the selection.
+    pub fn capture(&self) -> anyhow::Result<Capture> {
+        Capture::builder(&self.device)
+            .region(self.region)
+            .build()
+    }
+}
+
+/// Dim all the displays and let the user drag a rectangle on them, as the screenshot tools.
+/// `None` when cancelled by escape or the right button, or by a click without a drag.
+///
+/// blocks until the user is done. the window is made on a thread of its own with its own message loop,
+/// so it's fine to call from a console app. a selection over several displays is cut to the display
+/// with the most of it, since a capture is of a single display.
+///
+/// Required features: *`"selector"`*
+/// # Examples
+/// ```
+/// if let Some(selection) = dxcapture::select_region().unwrap() {
+///     let capture = selection.capture().unwrap();
+///     let raw = capture.wait_raw_frame().unwrap();
+///     println!("{:?}: {}x{}", selection.region, raw.width, raw.height);
+/// }
+/// ```
+/// ## Errors
+/// * [DirectxError](CaptureError::DirectxError): failed to create the window.
+/// * the errors of [Device::new_from_hmonitor].
+pub fn select_region() -> anyhow::Result<Option<Selection>> {
+    let selected = std::thread::spawn(run_selector)
+        .join()
+        .unwrap_or(Err(CaptureError::SourceError("Selector thread panicked.".to_string())))?;
+    let selected = match selected {
+        Some(selected) => selected,
+        None => return Ok(None),
+    };
+
+    let (monitor, bounds) = unsafe {
+        let monitor = MonitorFromRect(&selected, MONITOR_DEFAULTTONEAREST);
+        let mut info = MONITORINFO{
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        GetMonitorInfoW(monitor, &mut info);
+
+        (monitor, info.rcMonitor)
+    };
+
+    let monitor_rect = Rect::new(bounds.left, bounds.top, bounds.right - bounds.left, bounds.bottom - bounds.top);
+    let region = Rect::new(selected.left, selected.top, selected.right - selected.left, selected.bottom - selected.top)
+        .intersect(&monitor_rect);
+    if region.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Selection{
+        device: Device::new_from_hmonitor(monitor as isize)?,
+        region: Rect::new(region.x - bounds.left, region.y - bounds.top, region.width, region.height),
+    }))
+}
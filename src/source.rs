@@ -0,0 +1,73 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::diff::diff_frames;
+
+
+/// Longest sleep between the tries of [FrameSource::wait_raw_frame], about a frame at 60fps.
+pub const MAX_POLL_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Anything that delivers frames as [RawFrameData].
+///
+/// Implemented by [Capture](crate::Capture) and [ReplaySource](crate::ReplaySource),
+/// so a pipeline written against this trait runs the same on a live session or a recorded one.
+pub trait FrameSource {
+    /// Return current frame with [RawFrameData]
+    fn get_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError>;
+
+    /// Return current frame with [RawFrameData]. with throught NoTexture
+    ///
+    /// polls with a backoff up to [MAX_POLL_INTERVAL], the sources told of the frames override it.
+    fn wait_raw_frame(&self) -> anyhow::Result<RawFrameData, CaptureError> {
+        let mut interval = Duration::from_millis(1);
+        loop {
+            match self.get_raw_frame() {
+                Err(CaptureError::NoTexture) => {},
+                result => return result,
+            }
+            std::thread::sleep(interval);
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+
+    /// Wait until the frames stop changing, and return the settled frame.
+    ///
+    /// for waiting animations or loading spinners to finish before asserting on or saving a screenshot.
+    /// ## Parameters
+    /// * duration: how long the frames must keep effectively identical.
+    /// * tolerance: changed pixels in percent (0.0..=100.0) still regarded as identical.
+    /// * timeout: give up with [Timeout](CaptureError::Timeout) after this.
+    fn wait_for_stable(&self, duration: Duration, tolerance: f64, timeout: Duration) -> anyhow::Result<RawFrameData, CaptureError> {
+        let start = Instant::now();
+        let mut stable_since = Instant::now();
+        let mut previous = self.wait_raw_frame()?;
+
+        loop {
+            if timeout <= start.elapsed() {
+                return Err(CaptureError::Timeout);
+            }
+            std::thread::sleep(Duration::from_millis(16));
+
+            let current = self.wait_raw_frame()?;
+            let settled = match diff_frames(&previous, &current) {
+                Ok(diff) => diff.changed_percent <= tolerance,
+                // resized
+                Err(CaptureError::SizeMismatch) => false,
+                Err(e) => return Err(e),
+            };
+            if !settled {
+                stable_since = Instant::now();
+            } else if duration <= stable_since.elapsed() {
+                return Ok(current);
+            }
+
+            previous = current;
+        }
+    }
+}
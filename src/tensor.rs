@@ -0,0 +1,255 @@
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+    Rect,
+};
+
+
+/// Order of the values in a tensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TensorLayout {
+    /// `[1, 3, height, width]`, a plane for each of R, G, B. most of the ONNX and PyTorch models.
+    #[default]
+    Nchw,
+    /// `[1, height, width, 3]`, RGB of each pixel in a row. most of the TensorFlow models.
+    Nhwc,
+}
+
+
+/// How frames are made into input tensors of a model.
+///
+/// `mean` and `std` are per RGB channel on the 0..1 scale, each value is `(value / 255 - mean) / std`.
+/// the default makes 224x224 tensors of 0..1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TensorOptions {
+    /// size of the tensor. the frame (or the crop) is stretched to it.
+    pub width: u32,
+    pub height: u32,
+    /// area of the frame to use. the whole frame when `None`.
+    pub crop: Option<Rect>,
+    pub mean: [f32; 3],
+    pub std: [f32; 3],
+}
+impl Default for TensorOptions {
+    fn default() -> Self {
+        Self{
+            width: 224,
+            height: 224,
+            crop: None,
+            mean: [0.0; 3],
+            std: [1.0; 3],
+        }
+    }
+}
+impl TensorOptions {
+    /// `width` x `height` of 0..1.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self{
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Normalized with the mean and the std of ImageNet, as most of the vision models.
+    pub fn imagenet(width: u32, height: u32) -> Self {
+        Self::new(width, height).normalize([0.485, 0.456, 0.406], [0.229, 0.224, 0.225])
+    }
+
+    pub fn crop(mut self, crop: Rect) -> Self {
+        self.crop = Some(crop);
+        self
+    }
+
+    pub fn normalize(mut self, mean: [f32; 3], std: [f32; 3]) -> Self {
+        self.mean = mean;
+        self.std = std;
+        self
+    }
+
+    /// The crop inside a frame of `width` x `height`.
+    /// ## Errors
+    /// * [InvalidArgument](CaptureError::InvalidArgument): empty size, crop out of the frame, or 0 in `std`.
+    pub fn source_rect(&self, width: i32, height: i32) -> anyhow::Result<Rect, CaptureError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(CaptureError::InvalidArgument("tensor size must not be empty.".to_string()));
+        }
+        if self.std.contains(&0.0) {
+            return Err(CaptureError::InvalidArgument("std must not be 0.".to_string()));
+        }
+
+        let frame = Rect::new(0, 0, width, height);
+        let rect = self.crop.map_or(frame, |crop| crop.intersect(&frame));
+        if rect.is_empty() {
+            return Err(CaptureError::InvalidArgument("crop is out of the frame.".to_string()));
+        }
+
+        Ok(rect)
+    }
+
+    /// Shape of the tensors in `layout`.
+    pub fn shape(&self, layout: TensorLayout) -> [usize; 4] {
+        let (width, height) = (self.width as usize, self.height as usize);
+        match layout {
+            TensorLayout::Nchw => [1, 3, height, width],
+            TensorLayout::Nhwc => [1, height, width, 3],
+        }
+    }
+}
+
+
+/// Make a BGRA frame into a RGB f32 tensor of [shape](TensorOptions::shape) on the CPU.
+///
+/// the crop is resized bilinearly with the pixel centers aligned, as the resize of ONNX,
+/// then normalized with `mean` and `std`.
+/// ## Errors
+/// * [InvalidArgument](CaptureError::InvalidArgument): as [source_rect](TensorOptions::source_rect), or `data` is short for the size.
+/// # Examples
+/// ```
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+///
+/// let frame = capture.wait_raw_frame().expect("Failed to capture");
+/// let options = dxcapture::TensorOptions::imagenet(224, 224);
+/// let tensor = dxcapture::frame_to_tensor(&frame, &options, dxcapture::TensorLayout::Nchw).unwrap();
+/// assert_eq!(tensor.len(), 3 * 224 * 224);
+/// ```
+pub fn frame_to_tensor(frame: &RawFrameData, options: &TensorOptions, layout: TensorLayout) -> anyhow::Result<Vec<f32>, CaptureError> {
+    let rect = options.source_rect(frame.width, frame.height)?;
+    if !frame.has_all_rows() {
+        return Err(CaptureError::InvalidArgument("data is shorter than height * stride.".to_string()));
+    }
+
+    let (width, height) = (options.width as usize, options.height as usize);
+    let xs = taps(rect.x, rect.width, width);
+    let ys = taps(rect.y, rect.height, height);
+    let stride = frame.stride();
+    // (value / 255 - mean) / std as value * scale + bias
+    let scale: Vec<f32> = options.std.iter().map(|std| 1.0 / (255.0 * std)).collect();
+    let bias: Vec<f32> = options.mean.iter().zip(options.std.iter()).map(|(mean, std)| -mean / std).collect();
+
+    let plane = width * height;
+    let mut tensor = vec![0.0; plane * 3];
+    for (y, &(y0, y1, fy)) in ys.iter().enumerate() {
+        let (row0, row1) = (&frame.data[y0 * stride..], &frame.data[y1 * stride..]);
+
+        for (x, &(x0, x1, fx)) in xs.iter().enumerate() {
+            for channel in 0..3 {
+                // RGB from BGRA
+                let offset = 2 - channel;
+                let top = lerp(row0[x0 * 4 + offset], row0[x1 * 4 + offset], fx);
+                let bottom = lerp(row1[x0 * 4 + offset], row1[x1 * 4 + offset], fx);
+                let value = (top + (bottom - top) * fy) * scale[channel] + bias[channel];
+
+                let index = match layout {
+                    TensorLayout::Nchw => channel * plane + y * width + x,
+                    TensorLayout::Nhwc => (y * width + x) * 3 + channel,
+                };
+                tensor[index] = value;
+            }
+        }
+    }
+
+    Ok(tensor)
+}
+
+/// Source pixels and weight of the second for each of `size` outputs from `start..start + length`.
+fn taps(start: i32, length: i32, size: usize) -> Vec<(usize, usize, f32)> {
+    let ratio = length as f32 / size as f32;
+    let last = (length - 1) as f32;
+
+    (0..size).map(|i| {
+        let position = ((i as f32 + 0.5) * ratio - 0.5).clamp(0.0, last);
+        let first = position.floor();
+        let second = (first + 1.0).min(last);
+
+        ((start + first as i32) as usize, (start + second as i32) as usize, position - first)
+    }).collect()
+}
+
+fn lerp(a: u8, b: u8, t: f32) -> f32 {
+    a as f32 + (b as f32 - a as f32) * t
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame of the RGB `pixels`, row by row.
+    fn frame(width: i32, height: i32, pixels: &[[u8; 3]]) -> RawFrameData {
+        RawFrameData{
+            width,
+            height,
+            data: pixels.iter().flat_map(|&[r, g, b]| [b, g, r, 255]).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(tensor: &[f32], expected: &[f32]) {
+        assert_eq!(tensor.len(), expected.len());
+        for (value, expected) in tensor.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-5, "{:?} != {:?}", tensor, expected);
+        }
+    }
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+
+    #[test]
+    fn layouts() {
+        let frame = frame(2, 1, &[RED, BLUE]);
+        let options = TensorOptions::new(2, 1);
+
+        assert_eq!(options.shape(TensorLayout::Nchw), [1, 3, 1, 2]);
+        assert_eq!(options.shape(TensorLayout::Nhwc), [1, 1, 2, 3]);
+        assert_close(&frame_to_tensor(&frame, &options, TensorLayout::Nchw).unwrap(), &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+        assert_close(&frame_to_tensor(&frame, &options, TensorLayout::Nhwc).unwrap(), &[1.0, 0.0, 0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn bilinear_resize() {
+        let frame = frame(2, 1, &[RED, BLUE]);
+        let half = frame_to_tensor(&frame, &TensorOptions::new(1, 1), TensorLayout::Nchw).unwrap();
+        let double = frame_to_tensor(&frame, &TensorOptions::new(4, 1), TensorLayout::Nhwc).unwrap();
+
+        assert_close(&half, &[0.5, 0.0, 0.5]);
+        // the pixel centers are aligned, the edges are clamped
+        assert_close(&double, &[
+            1.0, 0.0, 0.0,
+            0.75, 0.0, 0.25,
+            0.25, 0.0, 0.75,
+            0.0, 0.0, 1.0,
+        ]);
+    }
+
+    #[test]
+    fn crop_and_normalize() {
+        let frame = frame(2, 2, &[RED, RED, BLUE, [0, 0, 0]]);
+        let options = TensorOptions::new(1, 1).crop(Rect::new(0, 1, 5, 5)).normalize([0.5; 3], [0.5; 3]);
+
+        assert_close(&frame_to_tensor(&frame, &options, TensorLayout::Nchw).unwrap(), &[-1.0, -1.0, 0.0]);
+    }
+
+    #[test]
+    fn strided_frame() {
+        let packed = frame(1, 2, &[RED, BLUE]);
+        let mut strided = RawFrameData{ row_pitch: 8, data: vec![0; 12], ..packed.clone() };
+        strided.data[..4].copy_from_slice(&packed.data[..4]);
+        strided.data[8..12].copy_from_slice(&packed.data[4..]);
+        let options = TensorOptions::new(1, 2);
+
+        assert_eq!(frame_to_tensor(&strided, &options, TensorLayout::Nchw).unwrap(), frame_to_tensor(&packed, &options, TensorLayout::Nchw).unwrap());
+    }
+
+    #[test]
+    fn invalid_options() {
+        let frame = frame(2, 1, &[RED, BLUE]);
+        let invalid = |options: TensorOptions| matches!(frame_to_tensor(&frame, &options, TensorLayout::Nchw), Err(CaptureError::InvalidArgument(_)));
+
+        assert!(invalid(TensorOptions::new(0, 1)));
+        assert!(invalid(TensorOptions::new(1, 1).normalize([0.0; 3], [1.0, 0.0, 1.0])));
+        assert!(invalid(TensorOptions::new(1, 1).crop(Rect::new(2, 0, 1, 1))));
+        assert!(frame_to_tensor(&RawFrameData{ data: vec![0; 4], ..frame.clone() }, &TensorOptions::new(1, 1), TensorLayout::Nchw).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
+    sync::{
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread::JoinHandle,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use crate::capture::{
+    CaptureError,
+    RawFrameData,
+};
+use crate::recorder::{
+    Encoder,
+    Muxer,
+};
+use crate::priority::ThreadOptions;
+use crate::source::FrameSource;
+
+#[cfg(feature = "img")]
+use crate::capture::ImgFrameData;
+#[cfg(feature = "img")]
+use crate::recorder::EncodedPacket;
+
+
+/// Options of [TimelapseRecorder].
+#[derive(Clone, Debug)]
+pub struct TimelapseOptions {
+    /// time between the captured frames.
+    pub interval: Duration,
+    /// frames per second of the output. `interval` * `playback_fps` is the speed up.
+    pub playback_fps: u32,
+    /// where the segments are written, as `timelapse_00000.<extension>` and so on.
+    pub output_dir: PathBuf,
+    /// no extension for directories, as of [ImageSequenceMuxer].
+    pub extension: String,
+    /// start a new segment after this many frames. a segment is also started when the frame size changes.
+    pub segment_frames: Option<u64>,
+    /// delete the oldest segments to keep this many, for the recordings running for days.
+    pub max_segments: Option<usize>,
+    /// stop by itself after this many frames.
+    pub max_frames: Option<u64>,
+    /// priority and affinity of the capture thread.
+    pub thread: Option<ThreadOptions>,
+}
+impl TimelapseOptions {
+    /// How many times faster the output plays than the real time.
+    pub fn speed_up(&self) -> f64 {
+        self.interval.as_secs_f64() * self.playback_fps as f64
+    }
+}
+impl Default for TimelapseOptions {
+    /// A frame every 10 seconds, played at 30 fps. (300x)
+    fn default() -> Self {
+        Self{
+            interval: Duration::from_secs(10),
+            playback_fps: 30,
+            output_dir: PathBuf::from("timelapse"),
+            extension: "dxcap".to_string(),
+            segment_frames: None,
+            max_segments: None,
+            max_frames: None,
+            thread: None,
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimelapseProgress {
+    pub frames: u64,
+    /// segments started so far, including deleted ones.
+    pub segments: u64,
+    /// ticks without a frame. (display turned off, capture lost, ...)
+    pub skipped: u64,
+    /// times the source was created again after failing.
+    pub reconnects: u64,
+}
+
+struct TimelapseShared {
+    stopped: Mutex<bool>,
+    stop_requested: Condvar,
+    progress: Mutex<TimelapseProgress>,
+}
+
+struct Segment<M: Muxer> {
+    muxer: M,
+    width: i32,
+    height: i32,
+    frames: u64,
+}
+
+
+/// Capture a frame every [interval](TimelapseOptions::interval) and assemble them into segments on a background thread.
+///
+/// a failing source (display changed or disconnected, session locked, ...) is created again with `source_factory`
+/// on the next tick, and the frame size changes start new segments, so it can be left running unattended.
+/// # Examples
+/// ```
+/// use dxcapture::{ DxcapMuxer, RawEncoder, TimelapseOptions, TimelapseRecorder };
+///
+/// let recorder = TimelapseRecorder::start(
+///     || {
+///         let device = dxcapture::Device::new_from_displays(None)?;
+///         Ok(dxcapture::Capture::new(&device)?)
+///     },
+///     RawEncoder,
+///     |path| DxcapMuxer::create(path),
+///     TimelapseOptions{
+///         interval: std::time::Duration::from_secs(5),
+///         segment_frames: Some(720),
+///         max_segments: Some(24),
+///         ..Default::default()
+///     },
+/// ).unwrap();
+///
+/// std::thread::sleep(std::time::Duration::from_secs(60));
+/// let progress = recorder.stop().expect("Failed to record");
+/// ```
+pub struct TimelapseRecorder {
+    shared: Arc<TimelapseShared>,
+    thread: Option<JoinHandle<anyhow::Result<TimelapseProgress, CaptureError>>>,
+}
+impl TimelapseRecorder {
+    /// Start capturing.
+    /// ## Parameters
+    /// * source_factory: creates the source on the recording thread, again after it failed.
+    /// * muxer_factory: creates the muxer of a segment at the given path.
+    pub fn start<S, F, E, M, MF>(source_factory: F, encoder: E, muxer_factory: MF, options: TimelapseOptions) -> anyhow::Result<Self, CaptureError>
+    where
+        S: FrameSource,
+        F: FnMut() -> anyhow::Result<S> + Send + 'static,
+        E: Encoder + 'static,
+        M: Muxer + 'static,
+        MF: FnMut(&Path) -> anyhow::Result<M, CaptureError> + Send + 'static,
+    {
+        if options.playback_fps == 0 {
+            return Err(CaptureError::InvalidArgument("playback_fps must be greater than 0.".to_string()));
+        }
+        std::fs::create_dir_all(&options.output_dir).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        let shared = Arc::new(TimelapseShared{
+            stopped: Mutex::new(false),
+            stop_requested: Condvar::new(),
+            progress: Mutex::new(TimelapseProgress::default()),
+        });
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+
+            move || Self::run(&shared, source_factory, encoder, muxer_factory, options)
+        });
+
+        Ok(Self{
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Start capturing, handing each frame to `callback` on the background thread instead of writing segments.
+    ///
+    /// for the frames sent or analyzed as they come. [output_dir](TimelapseOptions::output_dir), the extension
+    /// and the segment options are not used. an error of `callback` stops the capture and is returned by [stop](Self::stop).
+    /// # Examples
+    /// ```
+    /// use dxcapture::{ TimelapseOptions, TimelapseRecorder };
+    ///
+    /// let recorder = TimelapseRecorder::start_with_callback(
+    ///     || {
+    ///         let device = dxcapture::Device::new_from_displays(None)?;
+    ///         Ok(dxcapture::Capture::new(&device)?)
+    ///     },
+    ///     TimelapseOptions{ interval: std::time::Duration::from_secs(5), ..Default::default() },
+    ///     |frame| {
+    ///         println!("{}x{} at {:?}", frame.width, frame.height, frame.info.time);
+    ///         Ok(())
+    ///     },
+    /// ).unwrap();
+    ///
+    /// std::thread::sleep(std::time::Duration::from_secs(60));
+    /// let progress = recorder.stop().expect("Failed to capture");
+    /// ```
+    pub fn start_with_callback<S, F, C>(source_factory: F, options: TimelapseOptions, mut callback: C) -> anyhow::Result<Self, CaptureError>
+    where
+        S: FrameSource,
+        F: FnMut() -> anyhow::Result<S> + Send + 'static,
+        C: FnMut(RawFrameData) -> anyhow::Result<(), CaptureError> + Send + 'static,
+    {
+        let shared = Arc::new(TimelapseShared{
+            stopped: Mutex::new(false),
+            stop_requested: Condvar::new(),
+            progress: Mutex::new(TimelapseProgress::default()),
+        });
+
+        let thread = std::thread::spawn({
+            let shared = shared.clone();
+
+            move || {
+                let mut progress = TimelapseProgress::default();
+                Self::tick(&shared, source_factory, &options, &mut progress, |frame, _| callback(frame))?;
+
+                *shared.progress.lock().unwrap() = progress;
+                Ok(progress)
+            }
+        });
+
+        Ok(Self{
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    fn run<S, F, E, M, MF>(shared: &TimelapseShared, source_factory: F, mut encoder: E, mut muxer_factory: MF, options: TimelapseOptions) -> anyhow::Result<TimelapseProgress, CaptureError>
+    where
+        S: FrameSource,
+        F: FnMut() -> anyhow::Result<S>,
+        E: Encoder,
+        M: Muxer,
+        MF: FnMut(&Path) -> anyhow::Result<M, CaptureError>,
+    {
+        let mut progress = TimelapseProgress::default();
+        let mut segment: Option<Segment<M>> = None;
+        let mut segment_paths = Vec::new();
+
+        Self::tick(shared, source_factory, &options, &mut progress, |frame, progress| {
+            let new_segment = match &segment {
+                Some(current) => {
+                    current.width != frame.width || current.height != frame.height
+                        || options.segment_frames.is_some_and(|max| max <= current.frames)
+                },
+                None => true,
+            };
+            if new_segment {
+                if let Some(current) = segment.take() {
+                    Self::close_segment(current, &mut encoder)?;
+                }
+
+                let mut path = options.output_dir.join(format!("timelapse_{:05}", progress.segments));
+                if !options.extension.is_empty() {
+                    path.set_extension(&options.extension);
+                }
+                segment = Some(Segment{
+                    muxer: muxer_factory(&path)?,
+                    width: frame.width,
+                    height: frame.height,
+                    frames: 0,
+                });
+                segment_paths.push(path);
+                progress.segments += 1;
+
+                if let Some(max_segments) = options.max_segments {
+                    while max_segments.max(1) < segment_paths.len() {
+                        let oldest = segment_paths.remove(0);
+                        let _ = if oldest.is_dir() { std::fs::remove_dir_all(&oldest) } else { std::fs::remove_file(&oldest) };
+                    }
+                }
+            }
+
+            let current = segment.as_mut().unwrap();
+            let timestamp = Duration::from_nanos(current.frames * 1_000_000_000 / options.playback_fps as u64);
+            for packet in encoder.encode(&frame, timestamp)? {
+                current.muxer.write_packet(&packet)?;
+            }
+            current.frames += 1;
+
+            Ok(())
+        })?;
+
+        if let Some(current) = segment.take() {
+            Self::close_segment(current, &mut encoder)?;
+        }
+
+        *shared.progress.lock().unwrap() = progress;
+        Ok(progress)
+    }
+
+    /// Take a frame every interval until stopped, creating the source again after it failed, and pass it to `on_frame`.
+    fn tick<S, F, O>(shared: &TimelapseShared, mut source_factory: F, options: &TimelapseOptions, progress: &mut TimelapseProgress, mut on_frame: O) -> anyhow::Result<(), CaptureError>
+    where
+        S: FrameSource,
+        F: FnMut() -> anyhow::Result<S>,
+        O: FnMut(RawFrameData, &mut TimelapseProgress) -> anyhow::Result<(), CaptureError>,
+    {
+        let _thread = options.thread.map(|thread| thread.apply()).transpose()?;
+        let mut source: Option<S> = None;
+        let mut next_tick = Instant::now();
+
+        loop {
+            // sleep until the next tick, waking up on stop
+            {
+                let mut stopped = shared.stopped.lock().unwrap();
+                while !*stopped {
+                    let wait = match next_tick.checked_duration_since(Instant::now()) {
+                        Some(wait) => wait,
+                        None => break,
+                    };
+                    stopped = shared.stop_requested.wait_timeout(stopped, wait).unwrap().0;
+                }
+                if *stopped {
+                    break;
+                }
+            }
+            next_tick += options.interval;
+
+            if source.is_none() {
+                source = source_factory().ok();
+                if source.is_some() && 0 < progress.frames + progress.skipped {
+                    progress.reconnects += 1;
+                }
+            }
+            let frame = match source.as_ref().map(|source| Self::take_frame(source)) {
+                Some(Ok(frame)) => frame,
+                Some(Err(_)) => {
+                    // create again on the next tick
+                    source = None;
+                    progress.skipped += 1;
+                    *shared.progress.lock().unwrap() = *progress;
+                    continue;
+                },
+                None => {
+                    progress.skipped += 1;
+                    *shared.progress.lock().unwrap() = *progress;
+                    continue;
+                },
+            };
+
+            on_frame(frame, progress)?;
+            progress.frames += 1;
+            *shared.progress.lock().unwrap() = *progress;
+
+            if options.max_frames.is_some_and(|max| max <= progress.frames) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Frame of `source`, waiting a little while the screen is not updated.
+    fn take_frame<S: FrameSource>(source: &S) -> anyhow::Result<RawFrameData, CaptureError> {
+        let start = Instant::now();
+        loop {
+            match source.get_raw_frame() {
+                Err(CaptureError::NoTexture) if start.elapsed() < Duration::from_secs(2) => {
+                    std::thread::sleep(Duration::from_millis(10));
+                },
+                result => return result,
+            }
+        }
+    }
+
+    fn close_segment<E: Encoder, M: Muxer>(mut segment: Segment<M>, encoder: &mut E) -> anyhow::Result<(), CaptureError> {
+        for packet in encoder.flush()? {
+            segment.muxer.write_packet(&packet)?;
+        }
+
+        segment.muxer.finish()
+    }
+
+    pub fn progress(&self) -> TimelapseProgress {
+        *self.shared.progress.lock().unwrap()
+    }
+
+    /// Finish the current segment and wait it to be written.
+    pub fn stop(mut self) -> anyhow::Result<TimelapseProgress, CaptureError> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> anyhow::Result<TimelapseProgress, CaptureError> {
+        *self.shared.stopped.lock().unwrap() = true;
+        self.shared.stop_requested.notify_all();
+
+        match self.thread.take() {
+            Some(thread) => thread.join().unwrap_or(Err(CaptureError::SourceError("Timelapse thread panicked.".to_string()))),
+            None => Ok(self.progress()),
+        }
+    }
+}
+impl Drop for TimelapseRecorder {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+
+/// [Muxer] writing [RawEncoder](crate::RawEncoder) packets as PNG files into a directory,
+/// with a `manifest.csv` of the timestamps like [Capture::save_sequence](crate::Capture::save_sequence).
+///
+/// Required features: *`"img"`*
+#[cfg(feature = "img")]
+#[cfg_attr(feature = "docs-features", doc(cfg(feature = "img")))]
+pub struct ImageSequenceMuxer {
+    dir: PathBuf,
+    index: usize,
+    manifest: String,
+}
+#[cfg(feature = "img")]
+impl ImageSequenceMuxer {
+    pub fn create<P: AsRef<Path>>(dir: P) -> anyhow::Result<Self, CaptureError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        Ok(Self{
+            dir,
+            index: 0,
+            manifest: String::from("file,timestamp_ms\n"),
+        })
+    }
+}
+#[cfg(feature = "img")]
+impl Muxer for ImageSequenceMuxer {
+    fn write_packet(&mut self, packet: &EncodedPacket) -> anyhow::Result<(), CaptureError> {
+        if packet.data.len() != (packet.width * packet.height * 4) as usize {
+            return Err(CaptureError::InvalidArgument("packet is not a raw BGRA frame.".to_string()));
+        }
+
+        let image = ImgFrameData::from_raw(RawFrameData{
+            width: packet.width,
+            height: packet.height,
+            data: packet.data.clone(),
+            ..Default::default()
+        });
+        let name = format!("frame_{:05}.png", self.index);
+        image.data.save(self.dir.join(&name)).map_err(|e| CaptureError::IoError(e.to_string()))?;
+
+        self.manifest.push_str(&format!("{},{}\n", name, packet.timestamp.as_millis()));
+        self.index += 1;
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> anyhow::Result<(), CaptureError> {
+        std::fs::write(self.dir.join("manifest.csv"), &self.manifest).map_err(|e| CaptureError::IoError(e.to_string()))
+    }
+}
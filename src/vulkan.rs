@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use ash::vk;
+use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+
+use crate::capture::{
+    CaptureError,
+    SharedFrame,
+};
+
+
+/// [SharedFrame] imported into a Vulkan device. [Read more](VulkanFrameImporter)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VulkanFrame {
+    /// `B8G8R8A8_UNORM`, optimal tiling, usable for sampling and transfers.
+    pub image: vk::Image,
+    /// timeline semaphore imported from the fence of the frame.
+    pub semaphore: vk::Semaphore,
+    /// wait for it on [semaphore](Self::semaphore) before using [image](Self::image).
+    pub wait_value: u64,
+    pub width: u32,
+    pub height: u32,
+    pub format: vk::Format,
+}
+
+
+#[derive(Debug)]
+struct ImportedImage {
+    image: vk::Image,
+    memory: vk::DeviceMemory,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug)]
+struct ImportedSemaphore {
+    semaphore: vk::Semaphore,
+    /// fence handle it was imported from.
+    fence: isize,
+}
+
+
+/// Import the frames shared by [Capture::share_frames](crate::Capture::share_frames) into a Vulkan device,
+/// as external memory (`VK_KHR_external_memory_win32`) and a timeline semaphore (`VK_KHR_external_semaphore_win32`).
+///
+/// the device must be made on the same adapter as the captured [Device](crate::Device),
+/// with the `VK_KHR_external_memory_win32`, `VK_KHR_external_semaphore_win32` and `VK_KHR_timeline_semaphore`
+/// (core in Vulkan 1.2) extensions enabled.
+/// each shared texture is imported once and kept until the frame size changes.
+///
+/// the images are written outside of Vulkan, so acquire them from `vk::QUEUE_FAMILY_EXTERNAL` on the first use,
+/// and wait for [wait_value](VulkanFrame::wait_value) on the semaphore in each submit reading them.
+///
+/// Required features: *`"vulkan"`*
+/// # Examples
+/// ```
+/// # fn render(instance: &ash::Instance, physical_device: ash::vk::PhysicalDevice, vk_device: &ash::Device) {
+/// let device = dxcapture::Device::new_from_displays(None).unwrap();
+/// let capture = dxcapture::Capture::new(&device).unwrap();
+/// capture.share_frames(3).unwrap();
+///
+/// let mut importer = dxcapture::VulkanFrameImporter::new(instance, physical_device, vk_device);
+/// let frame = importer.import(&capture.wait_shared_frame().unwrap()).expect("Failed to import");
+///
+/// let wait_values = [frame.wait_value];
+/// let mut timeline = ash::vk::TimelineSemaphoreSubmitInfo::builder().wait_semaphore_values(&wait_values);
+/// let wait_semaphores = [frame.semaphore];
+/// let wait_stages = [ash::vk::PipelineStageFlags::FRAGMENT_SHADER];
+/// let submit = ash::vk::SubmitInfo::builder()
+///     .wait_semaphores(&wait_semaphores)
+///     .wait_dst_stage_mask(&wait_stages)
+///     .push_next(&mut timeline);
+/// // .command_buffers(...) sampling frame.image
+/// # }
+/// ```
+pub struct VulkanFrameImporter {
+    instance: ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    device: ash::Device,
+    semaphore_fn: vk::KhrExternalSemaphoreWin32Fn,
+    images: HashMap<u64, ImportedImage>,
+    semaphore: Option<ImportedSemaphore>,
+}
+impl VulkanFrameImporter {
+    pub fn new(instance: &ash::Instance, physical_device: vk::PhysicalDevice, device: &ash::Device) -> Self {
+        let semaphore_fn = vk::KhrExternalSemaphoreWin32Fn::load(|name| unsafe {
+            std::mem::transmute(instance.get_device_proc_addr(device.handle(), name.as_ptr()))
+        });
+
+        Self{
+            instance: instance.clone(),
+            physical_device,
+            device: device.clone(),
+            semaphore_fn,
+            images: HashMap::new(),
+            semaphore: None,
+        }
+    }
+
+    /// Import `frame`, or return the image and the semaphore imported before.
+    /// ## Errors
+    /// * [UnsupportedPixelFormat](CaptureError::UnsupportedPixelFormat): not a `B8G8R8A8_UNORM` frame.
+    /// * [VulkanError](CaptureError::VulkanError): failed to import, as on another adapter or without the extensions.
+    pub fn import(&mut self, frame: &SharedFrame) -> anyhow::Result<VulkanFrame, CaptureError> {
+        if frame.format != DXGI_FORMAT_B8G8R8A8_UNORM {
+            return Err(CaptureError::UnsupportedPixelFormat(frame.format));
+        }
+
+        // the textures are made again on resize
+        if self.images.values().any(|image| image.width != frame.width || image.height != frame.height) {
+            self.clear_images();
+        }
+        if !self.images.contains_key(&frame.texture_id) {
+            let image = self.import_image(frame).map_err(|e| CaptureError::VulkanError(e.to_string()))?;
+            self.images.insert(frame.texture_id, image);
+        }
+
+        // and the fence when shared again
+        if self.semaphore.as_ref().map_or(true, |semaphore| semaphore.fence != frame.fence.0) {
+            self.clear_semaphore();
+            let semaphore = self.import_semaphore(frame).map_err(|e| CaptureError::VulkanError(e.to_string()))?;
+            self.semaphore = Some(ImportedSemaphore{
+                semaphore,
+                fence: frame.fence.0,
+            });
+        }
+
+        Ok(VulkanFrame{
+            image: self.images[&frame.texture_id].image,
+            semaphore: self.semaphore.as_ref().unwrap().semaphore,
+            wait_value: frame.fence_value,
+            width: frame.width,
+            height: frame.height,
+            format: vk::Format::B8G8R8A8_UNORM,
+        })
+    }
+
+    fn import_image(&self, frame: &SharedFrame) -> ash::prelude::VkResult<ImportedImage> {
+        let handle_type = vk::ExternalMemoryHandleTypeFlags::D3D11_TEXTURE;
+
+        let mut external_info = vk::ExternalMemoryImageCreateInfo::builder().handle_types(handle_type);
+        let image_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::TYPE_2D)
+            .format(vk::Format::B8G8R8A8_UNORM)
+            .extent(vk::Extent3D{ width: frame.width, height: frame.height, depth: 1 })
+            .mip_levels(1)
+            .array_layers(1)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .usage(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_SRC)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .push_next(&mut external_info);
+
+        unsafe {
+            let image = self.device.create_image(&image_info, None)?;
+
+            let requirements = self.device.get_image_memory_requirements(image);
+            let memory_properties = self.instance.get_physical_device_memory_properties(self.physical_device);
+            let memory_type = (0..memory_properties.memory_type_count)
+                .find(|&index| {
+                    requirements.memory_type_bits & (1 << index) != 0
+                        && memory_properties.memory_types[index as usize].property_flags.contains(vk::MemoryPropertyFlags::DEVICE_LOCAL)
+                })
+                .or_else(|| (0..memory_properties.memory_type_count).find(|&index| requirements.memory_type_bits & (1 << index) != 0));
+            let memory_type = match memory_type {
+                Some(memory_type) => memory_type,
+                None => {
+                    self.device.destroy_image(image, None);
+                    return Err(vk::Result::ERROR_INVALID_EXTERNAL_HANDLE);
+                },
+            };
+
+            // D3D11 textures are imported dedicated
+            let mut import_info = vk::ImportMemoryWin32HandleInfoKHR::builder()
+                .handle_type(handle_type)
+                .handle(frame.texture.0 as vk::HANDLE);
+            let mut dedicated_info = vk::MemoryDedicatedAllocateInfo::builder().image(image);
+            let allocate_info = vk::MemoryAllocateInfo::builder()
+                .allocation_size(requirements.size)
+                .memory_type_index(memory_type)
+                .push_next(&mut import_info)
+                .push_next(&mut dedicated_info);
+
+            let memory = match self.device.allocate_memory(&allocate_info, None) {
+                Ok(memory) => memory,
+                Err(e) => {
+                    self.device.destroy_image(image, None);
+                    return Err(e);
+                },
+            };
+            if let Err(e) = self.device.bind_image_memory(image, memory, 0) {
+                self.device.destroy_image(image, None);
+                self.device.free_memory(memory, None);
+                return Err(e);
+            }
+
+            Ok(ImportedImage{
+                image,
+                memory,
+                width: frame.width,
+                height: frame.height,
+            })
+        }
+    }
+
+    fn import_semaphore(&self, frame: &SharedFrame) -> ash::prelude::VkResult<vk::Semaphore> {
+        let mut type_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+        let semaphore_info = vk::SemaphoreCreateInfo::builder().push_next(&mut type_info);
+
+        unsafe {
+            let semaphore = self.device.create_semaphore(&semaphore_info, None)?;
+
+            // ID3D11Fence is imported as a D3D12 fence
+            let import_info = vk::ImportSemaphoreWin32HandleInfoKHR::builder()
+                .semaphore(semaphore)
+                .handle_type(vk::ExternalSemaphoreHandleTypeFlags::D3D12_FENCE)
+                .handle(frame.fence.0 as vk::HANDLE);
+            let result = (self.semaphore_fn.import_semaphore_win32_handle_khr)(self.device.handle(), &*import_info);
+            if result != vk::Result::SUCCESS {
+                self.device.destroy_semaphore(semaphore, None);
+                return Err(result);
+            }
+
+            Ok(semaphore)
+        }
+    }
+
+    /// Destroy the imported images. the frames are imported again on the next [import](Self::import).
+    ///
+    /// the device must not be using them.
+    pub fn clear_images(&mut self) {
+        for (_, image) in self.images.drain() {
+            unsafe {
+                self.device.destroy_image(image.image, None);
+                self.device.free_memory(image.memory, None);
+            }
+        }
+    }
+
+    fn clear_semaphore(&mut self) {
+        if let Some(semaphore) = self.semaphore.take() {
+            unsafe {
+                self.device.destroy_semaphore(semaphore.semaphore, None);
+            }
+        }
+    }
+}
+impl Drop for VulkanFrameImporter {
+    // the device must be idle, and destroyed after this
+    fn drop(&mut self) {
+        self.clear_images();
+        self.clear_semaphore();
+    }
+}
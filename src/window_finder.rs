@@ -2,25 +2,45 @@
 
 use winapi::{
     shared::{
-        minwindef::{BOOL, DWORD, LPARAM},
-        windef::HWND,
+        minwindef::{BOOL, DWORD, FALSE, LPARAM},
+        windef::{HMONITOR, HWND, POINT, RECT},
     },
     um::{
         dwmapi::{DwmGetWindowAttribute, DWMWA_CLOAKED, DWM_CLOAKED_SHELL},
-        wincon::{GetConsoleTitleW, SetConsoleTitleW},
+        handleapi::CloseHandle,
+        processthreadsapi::OpenProcess,
+        winbase::QueryFullProcessImageNameW,
+        wincon::GetConsoleWindow,
+        winnt::PROCESS_QUERY_LIMITED_INFORMATION,
         winuser::{
-            EnumWindows, GetAncestor, GetClassNameW, GetShellWindow, GetWindowLongW,
-            GetWindowTextLengthW, GetWindowTextW, IsWindowVisible, GA_ROOT, GWL_EXSTYLE, GWL_STYLE,
-            WS_DISABLED, WS_EX_TOOLWINDOW,
+            ClientToScreen, EnumWindows, GetAncestor, GetClassNameW, GetClientRect, GetCursorPos,
+            GetDpiForWindow, GetForegroundWindow, GetShellWindow, GetWindow, GetWindowLongW,
+            GetWindowRect, GetWindowTextLengthW, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+            IsWindowVisible, MonitorFromWindow, WindowFromPoint, GA_ROOT, GWL_EXSTYLE, GWL_STYLE,
+            GW_OWNER, MONITOR_DEFAULTTONEAREST, WS_DISABLED, WS_EX_TOOLWINDOW,
         },
     },
 };
 
+use crate::capture::Rect;
+
+/// A top level window, as of when it was enumerated.
 #[derive(Debug, Clone)]
 pub struct WindowInfo {
     pub handle: HWND,
     pub title: String,
     pub class_name: String,
+    /// the window with the frame, in the screen coordinates.
+    pub rect: Rect,
+    /// the client area, in the screen coordinates.
+    pub client_rect: Rect,
+    pub process_id: u32,
+    /// the display with the most of the window.
+    pub monitor: HMONITOR,
+    /// DPI of the window, 96 for 100%.
+    pub dpi: u32,
+    pub is_minimized: bool,
+    pub is_foreground: bool,
 }
 
 fn get_shell_window() -> HWND {
@@ -95,43 +115,100 @@ fn is_capturable_window(window: &WindowInfo) -> bool {
     return true;
 }
 
+fn get_window_info(handle: HWND) -> WindowInfo {
+    let window_text = unsafe {
+        let window_text_length = GetWindowTextLengthW(handle) + 1;
+        let mut text_array = vec![0u16; window_text_length as usize];
+        GetWindowTextW(
+            handle,
+            text_array.as_mut_ptr() as *mut _,
+            window_text_length,
+        );
+        std::string::String::from_utf16_lossy(&text_array)
+            .trim_matches(char::from(0))
+            .to_string()
+    };
+    let class_name = unsafe {
+        let class_text_length: i32 = 256;
+        let mut text_array = vec![0u16; class_text_length as usize];
+        GetClassNameW(handle, text_array.as_mut_ptr() as *mut _, class_text_length);
+        std::string::String::from_utf16_lossy(&text_array)
+            .trim_matches(char::from(0))
+            .to_string()
+    };
+
+    let (rect, client_rect) = unsafe {
+        let mut window = RECT::default();
+        GetWindowRect(handle, &mut window);
+
+        let mut client = RECT::default();
+        GetClientRect(handle, &mut client);
+        let mut origin = POINT::default();
+        ClientToScreen(handle, &mut origin);
+
+        (
+            Rect::new(window.left, window.top, window.right - window.left, window.bottom - window.top),
+            Rect::new(origin.x, origin.y, client.right - client.left, client.bottom - client.top),
+        )
+    };
+
+    WindowInfo {
+        handle: handle,
+        title: window_text,
+        class_name: class_name,
+        rect,
+        client_rect,
+        process_id: get_process_id(handle),
+        monitor: unsafe { MonitorFromWindow(handle, MONITOR_DEFAULTTONEAREST) },
+        dpi: unsafe { GetDpiForWindow(handle) },
+        is_minimized: unsafe { IsIconic(handle) != 0 },
+        is_foreground: unsafe { GetForegroundWindow() == handle },
+    }
+}
+
+/// Which windows [get_capturable_windows_with] lists, besides the capturable ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFilter {
+    /// the console window of this process. default is `false`.
+    pub include_own_console: bool,
+    /// the minimized windows, which deliver no frames until restored. default is `true`.
+    pub include_minimized: bool,
+}
+impl Default for WindowFilter {
+    fn default() -> Self {
+        Self {
+            include_own_console: false,
+            include_minimized: true,
+        }
+    }
+}
+
+/// State of an enumeration, passed to [enum_window] through the `LPARAM`.
+struct Enumeration {
+    filter: WindowFilter,
+    /// `GetConsoleWindow`, null without a console.
+    console: HWND,
+    windows: Vec<WindowInfo>,
+}
+
 extern "system" fn enum_window(handle: HWND, lparam: LPARAM) -> BOOL {
+    let enumeration = unsafe { &mut *(lparam as *mut Enumeration) };
+    if !enumeration.filter.include_own_console && !enumeration.console.is_null() && handle == enumeration.console {
+        return 1;
+    }
+
     let window_text_length = unsafe { GetWindowTextLengthW(handle) };
     if window_text_length > 0 {
-        let window_text = unsafe {
-            let window_text_length = window_text_length + 1;
-            let mut text_array = vec![0u16; window_text_length as usize];
-            GetWindowTextW(
-                handle,
-                text_array.as_mut_ptr() as *mut _,
-                window_text_length,
-            );
-            std::string::String::from_utf16_lossy(&text_array)
-                .trim_matches(char::from(0))
-                .to_string()
-        };
-        let class_name = unsafe {
-            let class_text_length: i32 = 256;
-            let mut text_array = vec![0u16; class_text_length as usize];
-            GetClassNameW(handle, text_array.as_mut_ptr() as *mut _, class_text_length);
-            std::string::String::from_utf16_lossy(&text_array)
-                .trim_matches(char::from(0))
-                .to_string()
-        };
-        let info = WindowInfo {
-            handle: handle,
-            title: window_text,
-            class_name: class_name,
-        };
+        let info = get_window_info(handle);
 
         if !is_capturable_window(&info) {
             return 1;
         }
+        if !enumeration.filter.include_minimized && info.is_minimized {
+            return 1;
+        }
 
-        unsafe {
-            let list = std::mem::transmute::<LPARAM, *mut Vec<WindowInfo>>(lparam);
-            (*list).push(info);
-        };
+        enumeration.windows.push(info);
     }
 
     return 1;
@@ -139,50 +216,174 @@ extern "system" fn enum_window(handle: HWND, lparam: LPARAM) -> BOOL {
 
 /// Finds all visible windows and returns them as a Vec.
 pub fn get_capturable_windows() -> Vec<WindowInfo> {
-    // https://support.microsoft.com/en-us/help/124103/how-to-obtain-a-console-window-handle-hwnd
-    let current_console_title = unsafe {
-        let console_title_length: u32 = 256;
-        let mut text_array = vec![0u16; console_title_length as usize];
-        GetConsoleTitleW(text_array.as_mut_ptr() as *mut _, console_title_length);
-        std::string::String::from_utf16_lossy(&text_array)
-            .trim_matches(char::from(0))
-            .to_string()
-    };
+    get_capturable_windows_with(&WindowFilter::default())
+}
 
-    unsafe {
-        let temp_guid = uuid::Uuid::new_v4();
-        let mut new_console_title: Vec<u16> = temp_guid.to_string().encode_utf16().collect();
-        new_console_title.push(0);
-        SetConsoleTitleW(new_console_title.as_mut_ptr() as *mut _);
+/// Finds all visible windows passing `filter`.
+///
+/// the console window of this process is told by `GetConsoleWindow`, so the console is left as it is.
+pub fn get_capturable_windows_with(filter: &WindowFilter) -> Vec<WindowInfo> {
+    let mut enumeration = Enumeration {
+        filter: *filter,
+        console: unsafe { GetConsoleWindow() },
+        windows: Vec::new(),
     };
-    let duration = std::time::Duration::from_millis(40);
-    std::thread::sleep(duration);
-
-    let mut window_list = Vec::<WindowInfo>::new();
-    let result = unsafe { EnumWindows(Some(enum_window), &mut window_list as *mut _ as _) };
+    let result = unsafe { EnumWindows(Some(enum_window), &mut enumeration as *mut _ as _) };
     if result == 0 {
         panic!("EnumWindows failed!");
         // TODO: GetLastError
         // TODO: ErrorCode conversion
     }
 
+    enumeration.windows
+}
+
+pub fn find_window(window_name: &str) -> Vec<WindowInfo> {
+    find_windows_matching(&WindowMatch::Contains(window_name.to_string()))
+}
+
+/// How the title of a window is matched. [Read more](crate::Device::new_from_window_matching)
+#[derive(Debug, Clone)]
+pub enum WindowMatch {
+    /// contains the text, without case. as [new_from_window](crate::Device::new_from_window).
+    Contains(String),
+    /// the whole title, with case.
+    Exact(String),
+    /// starts with the text, without case.
+    Prefix(String),
+    /// Required features: *`"regex"`*
+    #[cfg(feature = "regex")]
+    #[cfg_attr(feature = "docs-features", doc(cfg(feature = "regex")))]
+    Regex(regex::Regex),
+}
+impl WindowMatch {
+    pub fn matches(&self, title: &str) -> bool {
+        match self {
+            Self::Contains(text) => title.to_lowercase().contains(&text.to_lowercase()),
+            Self::Exact(text) => title == text,
+            Self::Prefix(text) => title.to_lowercase().starts_with(&text.to_lowercase()),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.is_match(title),
+        }
+    }
+}
+
+/// The capturable windows with the titles `window_match` matches.
+pub fn find_windows_matching(window_match: &WindowMatch) -> Vec<WindowInfo> {
+    get_capturable_windows()
+        .into_iter()
+        .filter(|window| window_match.matches(&window.title))
+        .collect()
+}
+
+
+/// Process id of the window.
+pub fn get_process_id(window: HWND) -> u32 {
+    let mut process_id: DWORD = 0;
+    unsafe { GetWindowThreadProcessId(window, &mut process_id) };
+    process_id
+}
+
+/// File name of the executable of the process, as `game.exe`. `None` when the process can't be opened.
+pub fn get_process_name(process_id: u32) -> Option<String> {
     unsafe {
-        let mut new_console_title: Vec<u16> = current_console_title.encode_utf16().collect();
-        new_console_title.push(0);
-        SetConsoleTitleW(new_console_title.as_mut_ptr() as *mut _);
-    };
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id);
+        if process.is_null() {
+            return None;
+        }
 
-    window_list
+        let mut path = vec![0u16; 1024];
+        let mut length = path.len() as DWORD;
+        let result = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut length);
+        CloseHandle(process);
+        if result == 0 {
+            return None;
+        }
+
+        let path = std::string::String::from_utf16_lossy(&path[..length as usize]);
+        std::path::Path::new(&path).file_name().map(|name| name.to_string_lossy().to_string())
+    }
 }
 
-pub fn find_window(window_name: &str) -> Vec<WindowInfo> {
-    let window_list = get_capturable_windows();
-    let mut windows: Vec<WindowInfo> = Vec::new();
-    for window_info in &window_list {
-        let title = window_info.title.to_lowercase();
-        if title.contains(&window_name.to_string().to_lowercase()) {
-            windows.push(window_info.clone());
+/// The main window of the processes `is_target` accepts: a window without owner first, then the largest one.
+pub fn find_process_window<F: Fn(u32) -> bool>(is_target: F) -> Option<WindowInfo> {
+    let area = |window: &WindowInfo| window.rect.width.max(0) as i64 * window.rect.height.max(0) as i64;
+
+    get_capturable_windows()
+        .into_iter()
+        .filter(|window| is_target(window.process_id))
+        .max_by_key(|window| (unsafe { GetWindow(window.handle, GW_OWNER) }.is_null(), area(window)))
+}
+
+
+/// The capturable top level window under the mouse cursor. `None` over the desktop, the taskbar and the others not capturable.
+pub fn get_window_under_cursor() -> Option<WindowInfo> {
+    let window = unsafe {
+        let mut cursor = POINT::default();
+        if GetCursorPos(&mut cursor) == 0 {
+            return None;
         }
+        let window = WindowFromPoint(cursor);
+        if window.is_null() {
+            return None;
+        }
+
+        // the child controls are not capture targets
+        GetAncestor(window, GA_ROOT)
+    };
+
+    let info = get_window_info(window);
+    if is_capturable_window(&info) {
+        Some(info)
+    } else {
+        None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_without_case() {
+        let notepad = WindowMatch::Contains("notepad".to_string());
+
+        assert!(notepad.matches("Untitled - Notepad"));
+        assert!(notepad.matches("NOTEPAD"));
+        assert!(!notepad.matches("Note pad"));
+    }
+
+    #[test]
+    fn exact_with_case() {
+        let notepad = WindowMatch::Exact("Untitled - Notepad".to_string());
+
+        assert!(notepad.matches("Untitled - Notepad"));
+        assert!(!notepad.matches("untitled - notepad"));
+        assert!(!notepad.matches("*Untitled - Notepad"));
+    }
+
+    #[test]
+    fn prefix_without_case() {
+        let visual_studio = WindowMatch::Prefix("Visual Studio".to_string());
+
+        assert!(visual_studio.matches("visual studio code"));
+        assert!(!visual_studio.matches("main.rs - Visual Studio Code"));
+    }
+
+    #[test]
+    fn empty_text_matches_all() {
+        assert!(WindowMatch::Contains(String::new()).matches("any"));
+        assert!(WindowMatch::Prefix(String::new()).matches("any"));
+        assert!(!WindowMatch::Exact(String::new()).matches("any"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn regex() {
+        let chrome = WindowMatch::Regex(regex::Regex::new(r"^.* - Google Chrome$").unwrap());
+
+        assert!(chrome.matches("Inbox - Google Chrome"));
+        assert!(!chrome.matches("Google Chrome Helper"));
     }
-    windows
 }